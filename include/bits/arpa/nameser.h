@@ -0,0 +1,93 @@
+#ifndef _BITS_ARPA_NAMESER_H
+#define _BITS_ARPA_NAMESER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The fixed header of a message, in network byte order */
+typedef struct {
+	unsigned id :16;
+#if BYTE_ORDER == BIG_ENDIAN
+	unsigned qr :1;
+	unsigned opcode :4;
+	unsigned aa :1;
+	unsigned tc :1;
+	unsigned rd :1;
+	unsigned ra :1;
+	unsigned unused :1;
+	unsigned ad :1;
+	unsigned cd :1;
+	unsigned rcode :4;
+#else
+	unsigned rd :1;
+	unsigned tc :1;
+	unsigned aa :1;
+	unsigned opcode :4;
+	unsigned qr :1;
+	unsigned rcode :4;
+	unsigned cd :1;
+	unsigned ad :1;
+	unsigned unused :1;
+	unsigned ra :1;
+#endif
+	unsigned qdcount :16;
+	unsigned ancount :16;
+	unsigned nscount :16;
+	unsigned arcount :16;
+} HEADER;
+
+#define ns_msg_id(handle) ((handle)._id + 0)
+#define ns_msg_base(handle) ((handle)._msg + 0)
+#define ns_msg_end(handle) ((handle)._eom + 0)
+#define ns_msg_size(handle) ((handle)._eom - (handle)._msg)
+#define ns_msg_count(handle, section) ((handle)._counts[section] + 0)
+
+#define ns_rr_name(rr) (((rr).name[0] != '\0') ? (rr).name : ".")
+#define ns_rr_type(rr) ((int)((rr).type + 0))
+#define ns_rr_class(rr) ((int)((rr).rr_class + 0))
+#define ns_rr_ttl(rr) ((rr).ttl + 0)
+#define ns_rr_rdlen(rr) ((rr).rdlength + 0)
+#define ns_rr_rdata(rr) ((rr).rdata + 0)
+
+#define NS_GET16(s, cp) do { \
+	const unsigned char *__p = (const unsigned char *)(cp); \
+	(s) = ((uint16_t)__p[0] << 8) | (uint16_t)__p[1]; \
+	(cp) += NS_INT16SZ; \
+} while (0)
+
+#define NS_GET32(l, cp) do { \
+	const unsigned char *__p = (const unsigned char *)(cp); \
+	(l) = ((uint32_t)__p[0] << 24) | ((uint32_t)__p[1] << 16) \
+		| ((uint32_t)__p[2] << 8) | (uint32_t)__p[3]; \
+	(cp) += NS_INT32SZ; \
+} while (0)
+
+#define NS_PUT16(s, cp) do { \
+	uint16_t __v = (uint16_t)(s); \
+	unsigned char *__p = (unsigned char *)(cp); \
+	*__p++ = __v >> 8; \
+	*__p = __v; \
+	(cp) += NS_INT16SZ; \
+} while (0)
+
+#define NS_PUT32(l, cp) do { \
+	uint32_t __v = (uint32_t)(l); \
+	unsigned char *__p = (unsigned char *)(cp); \
+	*__p++ = __v >> 24; \
+	*__p++ = __v >> 16; \
+	*__p++ = __v >> 8; \
+	*__p = __v; \
+	(cp) += NS_INT32SZ; \
+} while (0)
+
+#define GETSHORT NS_GET16
+#define GETLONG NS_GET32
+#define PUTSHORT NS_PUT16
+#define PUTLONG NS_PUT32
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif /* _BITS_ARPA_NAMESER_H */
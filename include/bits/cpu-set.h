@@ -0,0 +1,51 @@
+#ifndef _BITS_CPU_SET_H
+#define _BITS_CPU_SET_H
+
+#include <stddef.h>
+
+#define CPU_SETSIZE 1024
+
+typedef struct {
+	unsigned long __bits[CPU_SETSIZE / 8 / sizeof(long)];
+} cpu_set_t;
+
+#define __CPU_BITS (8 * sizeof(unsigned long))
+#define __CPU_ELT(cpu) ((size_t)(cpu) / __CPU_BITS)
+#define __CPU_MASK(cpu) (1UL << ((size_t)(cpu) % __CPU_BITS))
+
+#define CPU_ALLOC_SIZE(count) \
+	((((size_t)(count) + __CPU_BITS - 1) / __CPU_BITS) * sizeof(unsigned long))
+#define CPU_ALLOC(count) __sched_cpualloc(count)
+#define CPU_FREE(set) __sched_cpufree(set)
+
+#define CPU_ZERO_S(size, set) ((void)__builtin_memset((set), 0, (size)))
+#define CPU_SET_S(cpu, size, set) ((size_t)(cpu) / 8 < (size) \
+	? ((set)->__bits[__CPU_ELT(cpu)] |= __CPU_MASK(cpu)) : 0)
+#define CPU_CLR_S(cpu, size, set) ((size_t)(cpu) / 8 < (size) \
+	? ((set)->__bits[__CPU_ELT(cpu)] &= ~__CPU_MASK(cpu)) : 0)
+#define CPU_ISSET_S(cpu, size, set) ((size_t)(cpu) / 8 < (size) \
+	? ((set)->__bits[__CPU_ELT(cpu)] & __CPU_MASK(cpu)) != 0 : 0)
+#define CPU_COUNT_S(size, set) __sched_cpucount((size), (set))
+#define CPU_EQUAL_S(size, set1, set2) \
+	(__builtin_memcmp((set1), (set2), (size)) == 0)
+
+#define __CPU_OP_S(size, dest, set1, set2, op) do { \
+	size_t __i; \
+	for (__i = 0; __i < (size) / sizeof(unsigned long); __i++) \
+		(dest)->__bits[__i] = (set1)->__bits[__i] op (set2)->__bits[__i]; \
+} while (0)
+#define CPU_AND_S(size, dest, set1, set2) __CPU_OP_S(size, dest, set1, set2, &)
+#define CPU_OR_S(size, dest, set1, set2) __CPU_OP_S(size, dest, set1, set2, |)
+#define CPU_XOR_S(size, dest, set1, set2) __CPU_OP_S(size, dest, set1, set2, ^)
+
+#define CPU_ZERO(set) CPU_ZERO_S(sizeof(cpu_set_t), set)
+#define CPU_SET(cpu, set) CPU_SET_S(cpu, sizeof(cpu_set_t), set)
+#define CPU_CLR(cpu, set) CPU_CLR_S(cpu, sizeof(cpu_set_t), set)
+#define CPU_ISSET(cpu, set) CPU_ISSET_S(cpu, sizeof(cpu_set_t), set)
+#define CPU_COUNT(set) CPU_COUNT_S(sizeof(cpu_set_t), set)
+#define CPU_EQUAL(set1, set2) CPU_EQUAL_S(sizeof(cpu_set_t), set1, set2)
+#define CPU_AND(dest, set1, set2) CPU_AND_S(sizeof(cpu_set_t), dest, set1, set2)
+#define CPU_OR(dest, set1, set2) CPU_OR_S(sizeof(cpu_set_t), dest, set1, set2)
+#define CPU_XOR(dest, set1, set2) CPU_XOR_S(sizeof(cpu_set_t), dest, set1, set2)
+
+#endif
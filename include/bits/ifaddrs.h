@@ -0,0 +1,7 @@
+#ifndef _BITS_IFADDRS_H
+#define _BITS_IFADDRS_H
+
+#define ifa_broadaddr ifa_ifu.ifu_broadaddr
+#define ifa_dstaddr ifa_ifu.ifu_dstaddr
+
+#endif /* _BITS_IFADDRS_H */
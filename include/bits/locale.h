@@ -5,3 +5,5 @@
 #define LC_MONETARY 4
 #define LC_NUMERIC 5
 #define LC_TIME 6
+
+#define LC_GLOBAL_LOCALE ((locale_t)-1)
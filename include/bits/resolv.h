@@ -0,0 +1,14 @@
+#ifndef _BITS_RESOLV_H
+#define _BITS_RESOLV_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define _res (*__res_state())
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif /* _BITS_RESOLV_H */
@@ -5,6 +5,8 @@
 extern "C" {
 #endif
 
+#define MB_CUR_MAX (__ctype_get_mb_cur_max())
+
 long double strtold(const char *nptr, char **endptr);
 
 #ifdef __cplusplus
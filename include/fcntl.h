@@ -17,8 +17,10 @@ extern "C" {
 #define O_TRUNC  01000
 #define O_APPEND 02000
 #define O_NONBLOCK 04000
+#define O_DSYNC 010000
 #define O_DIRECTORY 0200000
 #define O_CLOEXEC 02000000
+#define O_SYNC 04010000
 #define O_PATH 010000000
 
 #define FD_CLOEXEC 1
@@ -0,0 +1,23 @@
+#ifndef _NSS_H
+#define _NSS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// The outcome of a lookup in a module of the name service switch
+enum nss_status {
+    NSS_STATUS_TRYAGAIN = -2,
+    NSS_STATUS_UNAVAIL = -1,
+    NSS_STATUS_NOTFOUND = 0,
+    NSS_STATUS_SUCCESS = 1,
+    NSS_STATUS_RETURN = 2,
+};
+
+int __nss_configure_lookup(const char *db, const char *service_line);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* _NSS_H */
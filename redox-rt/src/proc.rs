@@ -1012,6 +1012,18 @@ pub struct NewChildProc {
     pid: usize,
 }
 
+impl NewChildProc {
+    pub fn proc_fd(&self) -> Option<&FdGuardUpper> {
+        self.proc_fd.as_ref()
+    }
+    pub fn thr_fd(&self) -> &FdGuardUpper {
+        &self.thr_fd
+    }
+    pub fn pid(&self) -> usize {
+        self.pid
+    }
+}
+
 pub fn new_child_process(args: &ForkArgs<'_>) -> Result<NewChildProc> {
     match *args {
         ForkArgs::Managed => {
@@ -1,62 +1,493 @@
+//! `aio.h` implementation.
+//!
+//! Requests are queued and executed by a small pool of worker threads, which are spawned lazily
+//! on the first submission. Completion is published through the private fields of the caller's
+//! `aiocb`, and every completion bumps a global generation counter that `aio_suspend` and
+//! `lio_listio(LIO_WAIT, ...)` wait on.
+//!
+//! See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/aio.h.html>.
+
+use alloc::{boxed::Box, collections::VecDeque, sync::Arc, vec::Vec};
+use core::{
+    ptr, slice,
+    sync::atomic::{AtomicI32, AtomicU32, AtomicUsize, Ordering},
+};
+
 use crate::{
-    header::{signal::sigevent, time::timespec},
-    platform::types::*,
+    error::{Errno, ResultExt},
+    header::{
+        errno::{EAGAIN, EBADF, ECANCELED, EINPROGRESS, EINVAL, EIO},
+        fcntl::{F_GETFD, O_DSYNC, O_SYNC},
+        pthread::{PTHREAD_CREATE_DETACHED, RlctAttr},
+        signal::{SIGEV_NONE, SIGEV_SIGNAL, SIGEV_THREAD, sigevent, sigval},
+        time::{CLOCK_MONOTONIC, timespec},
+    },
+    out::Out,
+    platform::{Pal, PalSignal, Sys, types::*},
+    pthread,
+    sync::{Mutex, Semaphore},
 };
 
+pub const AIO_CANCELED: c_int = 0;
+pub const AIO_NOTCANCELED: c_int = 1;
+pub const AIO_ALLDONE: c_int = 2;
+
+pub const LIO_READ: c_int = 0;
+pub const LIO_WRITE: c_int = 1;
+pub const LIO_NOP: c_int = 2;
+
+pub const LIO_WAIT: c_int = 0;
+pub const LIO_NOWAIT: c_int = 1;
+
+/// Maximum number of requests accepted by a single `lio_listio` call.
+pub const AIO_LISTIO_MAX: c_int = 1024;
+
+/// Upper bound on the number of worker threads servicing the request queue.
+const AIO_MAX_WORKERS: usize = 16;
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/aio.h.html>.
+#[repr(C)]
 pub struct aiocb {
     pub aio_fildes: c_int,
     pub aio_lio_opcode: c_int,
     pub aio_reqprio: c_int,
     pub aio_buf: *mut c_void,
-    pub aio_nbytes: usize,
+    pub aio_nbytes: size_t,
+    pub aio_offset: off_t,
     pub aio_sigevent: sigevent,
+    // Private, written by the worker thread once the request completes.
+    pub __error_code: c_int,
+    pub __return_value: ssize_t,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Read,
+    Write,
+    Sync,
+    DataSync,
+}
+
+/// Shared completion state of a `lio_listio(LIO_NOWAIT, ...)` batch.
+struct ListGroup {
+    remaining: AtomicUsize,
+    sigevent: Option<sigevent>,
+}
+
+struct Request {
+    aiocbp: *mut aiocb,
+    op: Op,
+    group: Option<Arc<ListGroup>>,
+}
+unsafe impl Send for Request {}
+
+struct Queue {
+    pending: VecDeque<Request>,
+    running: Vec<*mut aiocb>,
+    workers: usize,
+    idle: usize,
 }
+unsafe impl Send for Queue {}
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn aio_read(aiocbp: *mut aiocb) -> c_int {
-    unimplemented!();
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    pending: VecDeque::new(),
+    running: Vec::new(),
+    workers: 0,
+    idle: 0,
+});
+static WORK: Semaphore = Semaphore::new(0);
+
+/// Bumped (and woken) after every completed request.
+static COMPLETIONS: AtomicU32 = AtomicU32::new(0);
+
+unsafe fn error_code<'a>(aiocbp: *const aiocb) -> &'a AtomicI32 {
+    unsafe { AtomicI32::from_ptr(ptr::addr_of!((*aiocbp).__error_code).cast_mut()) }
+}
+
+fn submit(aiocbp: *mut aiocb, op: Op, group: Option<Arc<ListGroup>>) -> Result<(), Errno> {
+    if aiocbp.is_null() {
+        return Err(Errno(EINVAL));
+    }
+    let cb = unsafe { &mut *aiocbp };
+    if cb.aio_reqprio < 0 || (matches!(op, Op::Read | Op::Write) && cb.aio_offset < 0) {
+        return Err(Errno(EINVAL));
+    }
+    if Sys::fcntl(cb.aio_fildes, F_GETFD, 0).is_err() {
+        return Err(Errno(EBADF));
+    }
+
+    cb.__return_value = 0;
+    unsafe { error_code(aiocbp) }.store(EINPROGRESS, Ordering::Release);
+
+    let mut queue = QUEUE.lock();
+    queue.pending.push_back(Request { aiocbp, op, group });
+
+    if queue.pending.len() > queue.idle && queue.workers < AIO_MAX_WORKERS {
+        let mut attr = RlctAttr::default();
+        attr.detachstate = PTHREAD_CREATE_DETACHED as _;
+
+        match unsafe { pthread::create(Some(&attr), worker, ptr::null_mut()) } {
+            Ok(_) => queue.workers += 1,
+            // Existing workers will eventually pick the request up.
+            Err(_) if queue.workers > 0 => (),
+            Err(_) => {
+                queue.pending.pop_back();
+                return Err(Errno(EAGAIN));
+            }
+        }
+    }
+    drop(queue);
+
+    WORK.post(1);
+    Ok(())
+}
+
+extern "C" fn worker(_arg: *mut c_void) -> *mut c_void {
+    loop {
+        QUEUE.lock().idle += 1;
+        let _ = WORK.wait(None, CLOCK_MONOTONIC);
+
+        let request = {
+            let mut queue = QUEUE.lock();
+            queue.idle -= 1;
+
+            // The request may have been cancelled before it was dequeued.
+            let Some(request) = queue.pending.pop_front() else {
+                continue;
+            };
+            queue.running.push(request.aiocbp);
+            request
+        };
+
+        let result = unsafe { execute(&*request.aiocbp, request.op) };
+
+        QUEUE.lock().running.retain(|&p| p != request.aiocbp);
+        unsafe { complete(request, result) };
+    }
 }
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn aio_write(aiocbp: *mut aiocb) -> c_int {
-    unimplemented!();
+unsafe fn execute(cb: &aiocb, op: Op) -> Result<ssize_t, Errno> {
+    let len = if cb.aio_buf.is_null() {
+        0
+    } else {
+        cb.aio_nbytes
+    };
+
+    match op {
+        Op::Read => {
+            let buf = unsafe { slice::from_raw_parts_mut(cb.aio_buf.cast::<u8>(), len) };
+            Sys::pread(cb.aio_fildes, buf, cb.aio_offset).map(|n| n as ssize_t)
+        }
+        Op::Write => {
+            let buf = unsafe { slice::from_raw_parts(cb.aio_buf.cast::<u8>(), len) };
+            Sys::pwrite(cb.aio_fildes, buf, cb.aio_offset).map(|n| n as ssize_t)
+        }
+        Op::Sync => Sys::fsync(cb.aio_fildes).map(|()| 0),
+        Op::DataSync => Sys::fdatasync(cb.aio_fildes).map(|()| 0),
+    }
 }
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn lio_listio(
+unsafe fn complete(request: Request, result: Result<ssize_t, Errno>) {
+    let aiocbp = request.aiocbp;
+
+    // The caller may free the aiocb as soon as the error code is published, so the sigevent
+    // must be read beforehand.
+    let sigevent = unsafe { (*aiocbp).aio_sigevent.clone() };
+
+    let (ret, err) = match result {
+        Ok(ret) => (ret, 0),
+        Err(Errno(err)) => (-1, err),
+    };
+    unsafe {
+        (*aiocbp).__return_value = ret;
+        error_code(aiocbp).store(err, Ordering::Release);
+    }
+
+    COMPLETIONS.fetch_add(1, Ordering::Release);
+    crate::sync::futex_wake(&COMPLETIONS, i32::MAX);
+
+    // Requests submitted through lio_listio only use the list-wide notification.
+    match request.group {
+        Some(group) => {
+            if group.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                if let Some(sigevent) = &group.sigevent {
+                    notify(sigevent);
+                }
+            }
+        }
+        None => notify(&sigevent),
+    }
+}
+
+fn notify(sigevent: &sigevent) {
+    match sigevent.sigev_notify {
+        SIGEV_SIGNAL => {
+            let _ = Sys::sigqueue(Sys::getpid(), sigevent.sigev_signo, sigevent.sigev_value);
+        }
+        SIGEV_THREAD => {
+            let Some(function) = sigevent.sigev_notify_function else {
+                return;
+            };
+            let mut attr = unsafe {
+                sigevent
+                    .sigev_notify_attributes
+                    .cast::<RlctAttr>()
+                    .as_ref()
+                    .copied()
+                    .unwrap_or_default()
+            };
+            // Nobody will ever join the notification thread.
+            attr.detachstate = PTHREAD_CREATE_DETACHED as _;
+
+            let arg = Box::into_raw(Box::new((function, sigevent.sigev_value)));
+            if unsafe { pthread::create(Some(&attr), notify_thread, arg.cast()) }.is_err() {
+                drop(unsafe { Box::from_raw(arg) });
+            }
+        }
+        _ => (),
+    }
+}
+
+extern "C" fn notify_thread(arg: *mut c_void) -> *mut c_void {
+    let (function, value) =
+        *unsafe { Box::from_raw(arg.cast::<(extern "C" fn(sigval), sigval)>()) };
+    function(value);
+    ptr::null_mut()
+}
+
+/// Returns the absolute `CLOCK_MONOTONIC` deadline `timeout` from now.
+fn deadline_after(timeout: &timespec) -> Result<timespec, Errno> {
+    let mut now = timespec::default();
+    Sys::clock_gettime(CLOCK_MONOTONIC, Out::from_mut(&mut now))?;
+    timespec::add(now, *timeout).ok_or(Errno(EINVAL))
+}
+
+/// Waits until `done` returns true, or the deadline passes.
+fn wait_completions(done: impl Fn() -> bool, deadline: Option<&timespec>) -> Result<(), Errno> {
+    loop {
+        let generation = COMPLETIONS.load(Ordering::Acquire);
+        if done() {
+            return Ok(());
+        }
+        if crate::pthread::cancellation_point(|| {
+            crate::sync::futex_wait(&COMPLETIONS, generation, deadline)
+        }) == crate::sync::FutexWaitResult::TimedOut
+        {
+            return if done() { Ok(()) } else { Err(Errno(EAGAIN)) };
+        }
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/aio_read.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aio_read(aiocbp: *mut aiocb) -> c_int {
+    submit(aiocbp, Op::Read, None)
+        .map(|()| 0)
+        .or_minus_one_errno()
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/aio_write.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aio_write(aiocbp: *mut aiocb) -> c_int {
+    submit(aiocbp, Op::Write, None)
+        .map(|()| 0)
+        .or_minus_one_errno()
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/lio_listio.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lio_listio(
     mode: c_int,
-    list: *const *const aiocb,
+    list: *const *mut aiocb,
     nent: c_int,
     sig: *mut sigevent,
 ) -> c_int {
-    unimplemented!();
+    if (mode != LIO_WAIT && mode != LIO_NOWAIT) || nent < 0 || nent > AIO_LISTIO_MAX {
+        return Err(Errno(EINVAL)).or_minus_one_errno();
+    }
+    let list = if nent == 0 {
+        &[][..]
+    } else {
+        unsafe { slice::from_raw_parts(list, nent as usize) }
+    };
+
+    let requests = list
+        .iter()
+        .copied()
+        .filter(|cb| !cb.is_null())
+        .filter_map(|cb| {
+            let op = match unsafe { (*cb).aio_lio_opcode } {
+                LIO_READ => Op::Read,
+                LIO_WRITE => Op::Write,
+                _ => return None,
+            };
+            Some((cb, op))
+        })
+        .collect::<Vec<_>>();
+
+    let sigevent = if mode == LIO_NOWAIT {
+        unsafe { sig.as_ref() }
+            .filter(|sig| sig.sigev_notify != SIGEV_NONE)
+            .cloned()
+    } else {
+        None
+    };
+    // One extra reference is held until every request has been submitted, so that the
+    // notification cannot fire early.
+    let group = Arc::new(ListGroup {
+        remaining: AtomicUsize::new(requests.len() + 1),
+        sigevent,
+    });
+
+    let mut failed = false;
+    for &(cb, op) in &requests {
+        if let Err(Errno(err)) = submit(cb, op, Some(Arc::clone(&group))) {
+            unsafe {
+                (*cb).__return_value = -1;
+                error_code(cb).store(err, Ordering::Release);
+            }
+            group.remaining.fetch_sub(1, Ordering::AcqRel);
+            failed = true;
+        }
+    }
+    if group.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+        if let Some(sigevent) = &group.sigevent {
+            notify(sigevent);
+        }
+    }
+
+    if mode == LIO_WAIT {
+        let _ = wait_completions(
+            || {
+                requests
+                    .iter()
+                    .all(|&(cb, _)| unsafe { error_code(cb) }.load(Ordering::Acquire) != EINPROGRESS)
+            },
+            None,
+        );
+        if requests
+            .iter()
+            .any(|&(cb, _)| unsafe { error_code(cb) }.load(Ordering::Acquire) != 0)
+        {
+            return Err(Errno(EIO)).or_minus_one_errno();
+        }
+    } else if failed {
+        return Err(Errno(EAGAIN)).or_minus_one_errno();
+    }
+
+    0
 }
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn aio_error(aiocbp: *const aiocb) -> c_int {
-    unimplemented!();
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/aio_error.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aio_error(aiocbp: *const aiocb) -> c_int {
+    if aiocbp.is_null() {
+        return Err(Errno(EINVAL)).or_minus_one_errno();
+    }
+    unsafe { error_code(aiocbp) }.load(Ordering::Acquire)
 }
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn aio_return(aiocbp: *mut aiocb) -> usize {
-    unimplemented!();
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/aio_return.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aio_return(aiocbp: *mut aiocb) -> ssize_t {
+    if aiocbp.is_null() || unsafe { error_code(aiocbp) }.load(Ordering::Acquire) == EINPROGRESS {
+        return Err(Errno(EINVAL)).or_minus_one_errno();
+    }
+    unsafe { (*aiocbp).__return_value }
 }
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn aio_cancel(fildes: c_int, aiocbp: *mut aiocb) -> c_int {
-    unimplemented!();
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/aio_cancel.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aio_cancel(fildes: c_int, aiocbp: *mut aiocb) -> c_int {
+    if Sys::fcntl(fildes, F_GETFD, 0).is_err() {
+        return Err(Errno(EBADF)).or_minus_one_errno();
+    }
+    if !aiocbp.is_null() && unsafe { (*aiocbp).aio_fildes } != fildes {
+        return Err(Errno(EINVAL)).or_minus_one_errno();
+    }
+    let matches = |cb: *mut aiocb| {
+        if aiocbp.is_null() {
+            unsafe { (*cb).aio_fildes == fildes }
+        } else {
+            cb == aiocbp
+        }
+    };
+
+    let mut cancelled = Vec::new();
+    let not_cancelled = {
+        let mut queue = QUEUE.lock();
+
+        let mut i = 0;
+        while i < queue.pending.len() {
+            if matches(queue.pending[i].aiocbp) {
+                cancelled.extend(queue.pending.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        queue.running.iter().any(|&cb| matches(cb))
+    };
+
+    let any_cancelled = !cancelled.is_empty();
+    for request in cancelled {
+        unsafe { complete(request, Err(Errno(ECANCELED))) };
+    }
+
+    if not_cancelled {
+        AIO_NOTCANCELED
+    } else if any_cancelled {
+        AIO_CANCELED
+    } else {
+        AIO_ALLDONE
+    }
 }
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn aio_suspend(
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/aio_suspend.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aio_suspend(
     list: *const *const aiocb,
     nent: c_int,
     timeout: *const timespec,
 ) -> c_int {
-    unimplemented!();
+    if nent < 0 {
+        return Err(Errno(EINVAL)).or_minus_one_errno();
+    }
+    let list = if nent == 0 {
+        &[][..]
+    } else {
+        unsafe { slice::from_raw_parts(list, nent as usize) }
+    };
+
+    let deadline = match unsafe { timeout.as_ref() } {
+        Some(timeout) => match deadline_after(timeout) {
+            Ok(deadline) => Some(deadline),
+            Err(err) => return Err(err).or_minus_one_errno(),
+        },
+        None => None,
+    };
+
+    wait_completions(
+        || {
+            list.iter().any(|&cb| {
+                !cb.is_null() && unsafe { error_code(cb) }.load(Ordering::Acquire) != EINPROGRESS
+            })
+        },
+        deadline.as_ref(),
+    )
+    .map(|()| 0)
+    .or_minus_one_errno()
 }
 
-// #[unsafe(no_mangle)]
-pub extern "C" fn aio_fsync(operation: c_int, aiocbp: *mut aiocb) -> c_int {
-    unimplemented!();
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/aio_fsync.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aio_fsync(operation: c_int, aiocbp: *mut aiocb) -> c_int {
+    // On Redox, O_DSYNC and O_SYNC are the same flag.
+    let op = if operation == O_DSYNC {
+        Op::DataSync
+    } else if operation == O_SYNC {
+        Op::Sync
+    } else {
+        return Err(Errno(EINVAL)).or_minus_one_errno();
+    };
+    submit(aiocbp, op, None).map(|()| 0).or_minus_one_errno()
 }
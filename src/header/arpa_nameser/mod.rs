@@ -0,0 +1,635 @@
+//! `arpa/nameser.h` implementation.
+//!
+//! Non-POSIX, see <https://man.freebsd.org/cgi/man.cgi?query=resolver&sektion=3>
+//! for the parsing functions.
+
+// TODO: set this for entire crate when possible
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use alloc::vec::Vec;
+use core::{ptr, slice};
+
+use crate::{
+    header::errno::{EMSGSIZE, ENODEV},
+    platform::{self, types::*},
+};
+
+pub const NS_PACKETSZ: c_int = 512;
+pub const NS_MAXDNAME: usize = 1025;
+pub const NS_MAXMSG: c_int = 65535;
+pub const NS_MAXCDNAME: c_int = 255;
+pub const NS_MAXLABEL: c_int = 63;
+pub const NS_HFIXEDSZ: c_int = 12;
+pub const NS_QFIXEDSZ: c_int = 4;
+pub const NS_RRFIXEDSZ: c_int = 10;
+pub const NS_INT32SZ: c_int = 4;
+pub const NS_INT16SZ: c_int = 2;
+pub const NS_INT8SZ: c_int = 1;
+pub const NS_INADDRSZ: c_int = 4;
+pub const NS_IN6ADDRSZ: c_int = 16;
+pub const NS_CMPRSFLGS: c_int = 0xc0;
+pub const NS_DEFAULTPORT: c_int = 53;
+
+pub const PACKETSZ: c_int = NS_PACKETSZ;
+pub const MAXDNAME: c_int = NS_MAXDNAME as c_int;
+pub const MAXCDNAME: c_int = NS_MAXCDNAME;
+pub const MAXLABEL: c_int = NS_MAXLABEL;
+pub const HFIXEDSZ: c_int = NS_HFIXEDSZ;
+pub const QFIXEDSZ: c_int = NS_QFIXEDSZ;
+pub const RRFIXEDSZ: c_int = NS_RRFIXEDSZ;
+pub const INT32SZ: c_int = NS_INT32SZ;
+pub const INT16SZ: c_int = NS_INT16SZ;
+pub const INDIR_MASK: c_int = NS_CMPRSFLGS;
+pub const NAMESERVER_PORT: c_int = NS_DEFAULTPORT;
+
+/// The sections of a message. cbindgen cannot declare an enum whose names
+/// share values, so these are plain constants.
+pub type ns_sect = c_int;
+pub const ns_s_qd: ns_sect = 0;
+pub const ns_s_zn: ns_sect = 0;
+pub const ns_s_an: ns_sect = 1;
+pub const ns_s_pr: ns_sect = 1;
+pub const ns_s_ns: ns_sect = 2;
+pub const ns_s_ud: ns_sect = 2;
+pub const ns_s_ar: ns_sect = 3;
+pub const ns_s_max: ns_sect = 4;
+
+pub type ns_opcode = c_int;
+pub const ns_o_query: ns_opcode = 0;
+pub const ns_o_iquery: ns_opcode = 1;
+pub const ns_o_status: ns_opcode = 2;
+pub const ns_o_notify: ns_opcode = 4;
+pub const ns_o_update: ns_opcode = 5;
+
+pub type ns_rcode = c_int;
+pub const ns_r_noerror: ns_rcode = 0;
+pub const ns_r_formerr: ns_rcode = 1;
+pub const ns_r_servfail: ns_rcode = 2;
+pub const ns_r_nxdomain: ns_rcode = 3;
+pub const ns_r_notimpl: ns_rcode = 4;
+pub const ns_r_refused: ns_rcode = 5;
+
+pub type ns_type = c_int;
+pub const ns_t_invalid: ns_type = 0;
+pub const ns_t_a: ns_type = 1;
+pub const ns_t_ns: ns_type = 2;
+pub const ns_t_cname: ns_type = 5;
+pub const ns_t_soa: ns_type = 6;
+pub const ns_t_ptr: ns_type = 12;
+pub const ns_t_hinfo: ns_type = 13;
+pub const ns_t_mx: ns_type = 15;
+pub const ns_t_txt: ns_type = 16;
+pub const ns_t_aaaa: ns_type = 28;
+pub const ns_t_srv: ns_type = 33;
+pub const ns_t_naptr: ns_type = 35;
+pub const ns_t_opt: ns_type = 41;
+pub const ns_t_ds: ns_type = 43;
+pub const ns_t_rrsig: ns_type = 46;
+pub const ns_t_dnskey: ns_type = 48;
+pub const ns_t_tlsa: ns_type = 52;
+pub const ns_t_svcb: ns_type = 64;
+pub const ns_t_https: ns_type = 65;
+pub const ns_t_axfr: ns_type = 252;
+pub const ns_t_any: ns_type = 255;
+pub const ns_t_caa: ns_type = 257;
+
+pub type ns_class = c_int;
+pub const ns_c_invalid: ns_class = 0;
+pub const ns_c_in: ns_class = 1;
+pub const ns_c_chaos: ns_class = 3;
+pub const ns_c_hs: ns_class = 4;
+pub const ns_c_none: ns_class = 254;
+pub const ns_c_any: ns_class = 255;
+
+// The names of the old <arpa/nameser_compat.h>
+pub const QUERY: c_int = ns_o_query;
+pub const IQUERY: c_int = ns_o_iquery;
+pub const STATUS: c_int = ns_o_status;
+pub const NS_NOTIFY_OP: c_int = ns_o_notify;
+pub const NS_UPDATE_OP: c_int = ns_o_update;
+
+pub const NOERROR: c_int = ns_r_noerror;
+pub const FORMERR: c_int = ns_r_formerr;
+pub const SERVFAIL: c_int = ns_r_servfail;
+pub const NXDOMAIN: c_int = ns_r_nxdomain;
+pub const NOTIMP: c_int = ns_r_notimpl;
+pub const REFUSED: c_int = ns_r_refused;
+
+pub const T_A: c_int = ns_t_a;
+pub const T_NS: c_int = ns_t_ns;
+pub const T_CNAME: c_int = ns_t_cname;
+pub const T_SOA: c_int = ns_t_soa;
+pub const T_PTR: c_int = ns_t_ptr;
+pub const T_HINFO: c_int = ns_t_hinfo;
+pub const T_MX: c_int = ns_t_mx;
+pub const T_TXT: c_int = ns_t_txt;
+pub const T_AAAA: c_int = ns_t_aaaa;
+pub const T_SRV: c_int = ns_t_srv;
+pub const T_NAPTR: c_int = ns_t_naptr;
+pub const T_OPT: c_int = ns_t_opt;
+pub const T_AXFR: c_int = ns_t_axfr;
+pub const T_ANY: c_int = ns_t_any;
+
+pub const C_IN: c_int = ns_c_in;
+pub const C_CHAOS: c_int = ns_c_chaos;
+pub const C_HS: c_int = ns_c_hs;
+pub const C_NONE: c_int = ns_c_none;
+pub const C_ANY: c_int = ns_c_any;
+
+/// A message being parsed by [`ns_parserr`], set up by [`ns_initparse`].
+#[repr(C)]
+pub struct ns_msg {
+    pub _msg: *const c_uchar,
+    pub _eom: *const c_uchar,
+    pub _id: u16,
+    pub _flags: u16,
+    pub _counts: [u16; 4],
+    pub _sections: [*const c_uchar; 4],
+    pub _sect: ns_sect,
+    pub _rrnum: c_int,
+    pub _msg_ptr: *const c_uchar,
+}
+
+/// A resource record, or a question with no TTL and no data.
+#[repr(C)]
+pub struct ns_rr {
+    pub name: [c_char; NS_MAXDNAME],
+    pub r#type: u16,
+    pub rr_class: u16,
+    pub ttl: u32,
+    pub rdlength: u16,
+    pub rdata: *const c_uchar,
+}
+
+/// Characters that are escaped with a backslash in the text form of names.
+const SPECIAL: &[u8] = b"\".;\\()@$";
+
+/// The longest chain of compression pointers that is followed, so that a
+/// loop of them fails instead of hanging.
+const MAX_POINTERS: usize = 127;
+
+/// Reads the possibly compressed name at `pos` of `msg`. Returns each of
+/// its labels along with where the label is in `msg`, and how many bytes the
+/// name takes up at `pos`.
+pub(crate) fn read_name(msg: &[u8], mut pos: usize) -> Option<(Vec<(usize, &[u8])>, usize)> {
+    let start = pos;
+    let mut labels = Vec::new();
+    let mut len = None;
+    let mut total = 0;
+    let mut pointers = 0;
+    loop {
+        let byte = *msg.get(pos)?;
+        match c_int::from(byte) & NS_CMPRSFLGS {
+            NS_CMPRSFLGS => {
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return None;
+                }
+                let low = *msg.get(pos + 1)?;
+                len.get_or_insert(pos + 2 - start);
+                pos = (usize::from(byte & 0x3f) << 8) | usize::from(low);
+            }
+            0 => {
+                let label_len = usize::from(byte);
+                total += label_len + 1;
+                if total > NS_MAXCDNAME as usize {
+                    return None;
+                }
+                if label_len == 0 {
+                    return Some((labels, len.unwrap_or(pos + 1 - start)));
+                }
+                labels.push((pos, msg.get(pos + 1..pos + 1 + label_len)?));
+                pos += label_len + 1;
+            }
+            // Extended and binary labels are long obsolete
+            _ => return None,
+        }
+    }
+}
+
+/// Writes `labels` in text form, with a dot after each label, or a lone dot
+/// for the root.
+pub(crate) fn labels_to_text<'a>(labels: impl Iterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut text = Vec::new();
+    for label in labels {
+        for &byte in label {
+            if SPECIAL.contains(&byte) {
+                text.push(b'\\');
+                text.push(byte);
+            } else if byte <= b' ' || byte >= 0x7f {
+                text.push(b'\\');
+                text.push(b'0' + byte / 100);
+                text.push(b'0' + byte / 10 % 10);
+                text.push(b'0' + byte % 10);
+            } else {
+                text.push(byte);
+            }
+        }
+        text.push(b'.');
+    }
+    if text.is_empty() {
+        text.push(b'.');
+    }
+    text
+}
+
+/// Splits a name in text form into its labels, undoing escapes. The root is
+/// either empty or a lone dot, and any other name may end with a dot.
+pub(crate) fn text_to_labels(text: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut labels = Vec::new();
+    if text == b"." {
+        return Some(labels);
+    }
+    let mut label = Vec::new();
+    let mut total = 1;
+    let mut i = 0;
+    while i < text.len() {
+        let byte = match text[i] {
+            b'\\'
+                if text
+                    .get(i + 1..i + 4)
+                    .is_some_and(|d| d.iter().all(u8::is_ascii_digit)) =>
+            {
+                let digits = &text[i + 1..i + 4];
+                let value = digits
+                    .iter()
+                    .fold(0u32, |value, digit| value * 10 + u32::from(digit - b'0'));
+                i += 4;
+                u8::try_from(value).ok()?
+            }
+            b'\\' => {
+                i += 2;
+                *text.get(i - 1)?
+            }
+            b'.' => {
+                i += 1;
+                if label.is_empty() {
+                    return None;
+                }
+                total += label.len() + 1;
+                labels.push(core::mem::take(&mut label));
+                continue;
+            }
+            byte => {
+                i += 1;
+                byte
+            }
+        };
+        if label.len() == NS_MAXLABEL as usize {
+            return None;
+        }
+        label.push(byte);
+    }
+    if !label.is_empty() {
+        total += label.len() + 1;
+        labels.push(label);
+    }
+    if total > NS_MAXCDNAME as usize {
+        return None;
+    }
+    Some(labels)
+}
+
+/// Encodes `labels` to follow `msg`, pointing to the longest suffix of them
+/// that is already in one of the names at `known` offsets of `msg`. Returns
+/// the encoded name and how many labels were written out in full.
+pub(crate) fn compress(labels: &[Vec<u8>], msg: &[u8], known: &[usize]) -> (Vec<u8>, usize) {
+    let names: Vec<Vec<(usize, &[u8])>> = known
+        .iter()
+        .filter_map(|&offset| read_name(msg, offset).map(|(labels, _)| labels))
+        .collect();
+
+    let mut out = Vec::new();
+    for (i, label) in labels.iter().enumerate() {
+        let suffix = &labels[i..];
+        let found = names.iter().find_map(|name| {
+            (0..name.len()).find_map(|j| {
+                let theirs = &name[j..];
+                let same = theirs.len() == suffix.len()
+                    && theirs
+                        .iter()
+                        .zip(suffix)
+                        .all(|((_, a), b)| a.eq_ignore_ascii_case(b));
+                // Pointers only have 14 bits
+                (same && theirs[0].0 < 0x4000).then_some(theirs[0].0)
+            })
+        });
+        if let Some(offset) = found {
+            out.extend_from_slice(&(0xc000 | offset as u16).to_be_bytes());
+            return (out, i);
+        }
+        out.push(label.len() as u8);
+        out.extend_from_slice(label);
+    }
+    out.push(0);
+    (out, labels.len())
+}
+
+/// Skips the question or resource record at `pos` of `msg`, returning where
+/// the next one starts.
+fn skip_rr(msg: &[u8], pos: usize, question: bool) -> Option<usize> {
+    let (_, len) = read_name(msg, pos)?;
+    let pos = pos + len;
+    let end = if question {
+        pos + NS_QFIXEDSZ as usize
+    } else {
+        let rdlength = msg.get(pos + 8..pos + 10)?;
+        pos + NS_RRFIXEDSZ as usize + usize::from(u16::from_be_bytes([rdlength[0], rdlength[1]]))
+    };
+    (end <= msg.len()).then_some(end)
+}
+
+/// Copies `text` and a nul into `dst` of `dstsiz` bytes, if they fit.
+pub(crate) unsafe fn copy_name(text: &[u8], dst: *mut c_char, dstsiz: usize) -> bool {
+    if text.len() >= dstsiz {
+        platform::ERRNO.set(EMSGSIZE);
+        return false;
+    }
+    unsafe {
+        ptr::copy_nonoverlapping(text.as_ptr() as *const c_char, dst, text.len());
+        *dst.add(text.len()) = 0;
+    }
+    true
+}
+
+/// Reads a 16 bit integer in network byte order.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_get16(src: *const c_uchar) -> c_uint {
+    let bytes = unsafe { slice::from_raw_parts(src, 2) };
+    c_uint::from(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a 32 bit integer in network byte order.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_get32(src: *const c_uchar) -> c_ulong {
+    let bytes = unsafe { slice::from_raw_parts(src, 4) };
+    c_ulong::from(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Writes a 16 bit integer in network byte order.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_put16(src: c_uint, dst: *mut c_uchar) {
+    let bytes = (src as u16).to_be_bytes();
+    unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), dst, 2) };
+}
+
+/// Writes a 32 bit integer in network byte order.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_put32(src: c_ulong, dst: *mut c_uchar) {
+    let bytes = (src as u32).to_be_bytes();
+    unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), dst, 4) };
+}
+
+/// Expands the compressed name at `src` of the message from `msg` to `eom`
+/// into text form in `dst`, returning the length of the compressed name.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_name_uncompress(
+    msg: *const c_uchar,
+    eom: *const c_uchar,
+    src: *const c_uchar,
+    dst: *mut c_char,
+    dstsiz: size_t,
+) -> c_int {
+    let msg = unsafe { slice::from_raw_parts(msg, eom.offset_from(msg) as usize) };
+    let pos = unsafe { src.offset_from(msg.as_ptr()) } as usize;
+    let Some((labels, len)) = read_name(msg, pos) else {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    };
+    let text = labels_to_text(labels.into_iter().map(|(_, label)| label));
+    if !unsafe { copy_name(&text, dst, dstsiz) } {
+        return -1;
+    }
+    len as c_int
+}
+
+/// Skips the resource record at `ptr`, returning its length.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_skiprr(
+    ptr: *const c_uchar,
+    eom: *const c_uchar,
+    section: ns_sect,
+    count: c_int,
+) -> c_int {
+    // Compression pointers are not followed, so the record can be read as
+    // if it started a message
+    let msg = unsafe { slice::from_raw_parts(ptr, eom.offset_from(ptr) as usize) };
+    let mut pos = 0;
+    for _ in 0..count {
+        match skip_rr_unrooted(msg, pos, section == ns_s_qd) {
+            Some(next) => pos = next,
+            None => {
+                platform::ERRNO.set(EMSGSIZE);
+                return -1;
+            }
+        }
+    }
+    pos as c_int
+}
+
+/// Like [`skip_rr`], for a record without the message that it is in.
+fn skip_rr_unrooted(msg: &[u8], mut pos: usize, question: bool) -> Option<usize> {
+    loop {
+        let byte = *msg.get(pos)?;
+        match c_int::from(byte) & NS_CMPRSFLGS {
+            NS_CMPRSFLGS => {
+                pos += 2;
+                break;
+            }
+            0 if byte == 0 => {
+                pos += 1;
+                break;
+            }
+            0 => pos += usize::from(byte) + 1,
+            _ => return None,
+        }
+    }
+    let end = if question {
+        pos + NS_QFIXEDSZ as usize
+    } else {
+        let rdlength = msg.get(pos + 8..pos + 10)?;
+        pos + NS_RRFIXEDSZ as usize + usize::from(u16::from_be_bytes([rdlength[0], rdlength[1]]))
+    };
+    (end <= msg.len()).then_some(end)
+}
+
+/// Sets up `handle` to parse the message of `msglen` bytes at `msg`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_initparse(
+    msg: *const c_uchar,
+    msglen: c_int,
+    handle: *mut ns_msg,
+) -> c_int {
+    let data = unsafe { slice::from_raw_parts(msg, msglen.max(0) as usize) };
+    if data.len() < NS_HFIXEDSZ as usize {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    }
+    let handle = unsafe { &mut *handle };
+    let word = |i: usize| u16::from_be_bytes([data[i * 2], data[i * 2 + 1]]);
+    handle._msg = msg;
+    handle._eom = unsafe { msg.add(data.len()) };
+    handle._id = word(0);
+    handle._flags = word(1);
+
+    let mut pos = NS_HFIXEDSZ as usize;
+    for section in 0..ns_s_max as usize {
+        let count = word(2 + section);
+        handle._counts[section] = count;
+        handle._sections[section] = if count == 0 {
+            ptr::null()
+        } else {
+            unsafe { msg.add(pos) }
+        };
+        for _ in 0..count {
+            match skip_rr(data, pos, section == ns_s_qd as usize) {
+                Some(next) => pos = next,
+                None => {
+                    platform::ERRNO.set(EMSGSIZE);
+                    return -1;
+                }
+            }
+        }
+    }
+    if pos != data.len() {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    }
+
+    handle._sect = ns_s_max;
+    handle._rrnum = -1;
+    handle._msg_ptr = ptr::null();
+    0
+}
+
+/// Parses record `rrnum` of `section` of the message of `handle` into `rr`.
+/// An `rrnum` of -1 is the record after the last one parsed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ns_parserr(
+    handle: *mut ns_msg,
+    section: ns_sect,
+    mut rrnum: c_int,
+    rr: *mut ns_rr,
+) -> c_int {
+    let handle = unsafe { &mut *handle };
+    let rr = unsafe { &mut *rr };
+    if !(0..ns_s_max).contains(&section) {
+        platform::ERRNO.set(ENODEV);
+        return -1;
+    }
+    let index = section as usize;
+    if section != handle._sect {
+        handle._sect = section;
+        handle._rrnum = 0;
+        handle._msg_ptr = handle._sections[index];
+    }
+    if rrnum == -1 {
+        rrnum = handle._rrnum;
+    }
+    if rrnum < 0 || rrnum >= c_int::from(handle._counts[index]) {
+        platform::ERRNO.set(ENODEV);
+        return -1;
+    }
+    if rrnum < handle._rrnum {
+        handle._rrnum = 0;
+        handle._msg_ptr = handle._sections[index];
+    }
+
+    let msg = unsafe {
+        slice::from_raw_parts(handle._msg, handle._eom.offset_from(handle._msg) as usize)
+    };
+    let question = section == ns_s_qd;
+    let mut pos = unsafe { handle._msg_ptr.offset_from(handle._msg) } as usize;
+    while handle._rrnum < rrnum {
+        match skip_rr(msg, pos, question) {
+            Some(next) => pos = next,
+            None => {
+                platform::ERRNO.set(EMSGSIZE);
+                return -1;
+            }
+        }
+        handle._rrnum += 1;
+    }
+
+    let Some((labels, len)) = read_name(msg, pos) else {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    };
+    let mut text = labels_to_text(labels.into_iter().map(|(_, label)| label));
+    // Names in records have no trailing dot, which leaves the root empty
+    // for `ns_rr_name` to show as "."
+    text.pop();
+    if !unsafe { copy_name(&text, rr.name.as_mut_ptr(), NS_MAXDNAME) } {
+        return -1;
+    }
+    pos += len;
+
+    let Some(fixed) = msg.get(pos..pos + if question { 4 } else { 10 }) else {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    };
+    rr.r#type = u16::from_be_bytes([fixed[0], fixed[1]]);
+    rr.rr_class = u16::from_be_bytes([fixed[2], fixed[3]]);
+    if question {
+        rr.ttl = 0;
+        rr.rdlength = 0;
+        rr.rdata = ptr::null();
+        pos += 4;
+    } else {
+        rr.ttl = u32::from_be_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]);
+        rr.rdlength = u16::from_be_bytes([fixed[8], fixed[9]]);
+        rr.rdata = unsafe { handle._msg.add(pos + 10) };
+        pos += 10 + usize::from(rr.rdlength);
+    }
+
+    handle._rrnum += 1;
+    handle._msg_ptr = unsafe { handle._msg.add(pos) };
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, labels_to_text, read_name, text_to_labels};
+
+    #[test]
+    fn text_round_trip() {
+        let labels = text_to_labels(b"a\\.b.ex\\097mple.\\001.").unwrap();
+        assert_eq!(vec![&b"a.b"[..], b"example", b"\x01"], labels);
+        assert_eq!(
+            &b"a\\.b.example.\\001."[..],
+            &labels_to_text(labels.iter().map(|label| &label[..]))[..]
+        );
+        assert_eq!(Some(vec![]), text_to_labels(b"."));
+        assert_eq!(Some(vec![]), text_to_labels(b""));
+        assert_eq!(&b"."[..], &labels_to_text([].into_iter())[..]);
+        assert_eq!(None, text_to_labels(b"a..b"));
+        assert_eq!(None, text_to_labels(&[b'a'; 64]));
+    }
+
+    #[test]
+    fn compression() {
+        let mut msg = vec![0; 12];
+        let first = text_to_labels(b"mail.example.com").unwrap();
+        let (encoded, written) = compress(&first, &msg, &[]);
+        assert_eq!(3, written);
+        msg.extend_from_slice(&encoded);
+
+        let second = text_to_labels(b"www.EXAMPLE.com").unwrap();
+        let (encoded, written) = compress(&second, &msg, &[12]);
+        assert_eq!(1, written);
+        // "www", then a pointer to "example.com" after the 5 bytes of "mail"
+        assert_eq!(&[3, b'w', b'w', b'w', 0xc0, 17][..], &encoded[..]);
+        msg.extend_from_slice(&encoded);
+
+        let (labels, len) = read_name(&msg, 30).unwrap();
+        assert_eq!(6, len);
+        let labels: Vec<&[u8]> = labels.into_iter().map(|(_, label)| label).collect();
+        assert_eq!(vec![&b"www"[..], b"example", b"com"], labels);
+    }
+
+    #[test]
+    fn pointer_loop() {
+        let msg = [0xc0, 0x00];
+        assert_eq!(None, read_name(&msg, 0));
+    }
+}
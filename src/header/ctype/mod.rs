@@ -8,9 +8,41 @@
 // TODO: set this for entire crate when possible
 #![deny(unsafe_op_in_unsafe_fn)]
 
-// TODO: *_l functions
+// Bytes above ASCII only have classes in ISO-8859-1 locales, where they are
+// the code points of the same value, as they are for wide characters in
+// `wchar/single_byte.rs`. Functions with no such characters in their class
+// ignore the locale.
 
-use crate::platform::types::*;
+use crate::{
+    header::locale::{self, __locale_struct, data::Charset, locale_t},
+    platform::types::*,
+};
+
+/// `c` as an ISO-8859-1 byte above ASCII, if `loc` uses that codeset. The
+/// locale is only looked up for such bytes.
+fn latin1(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> Option<u8> {
+    if !(0x80..=0xff).contains(&c) || loc().charset() != Charset::Latin1 {
+        return None;
+    }
+    Some(c as u8)
+}
+
+fn latin1_upper(b: u8) -> bool {
+    matches!(b, 0xc0..=0xde) && b != 0xd7
+}
+
+fn latin1_lower(b: u8) -> bool {
+    (matches!(b, 0xdf..=0xff) && b != 0xf7) || b == 0xb5
+}
+
+fn latin1_alpha(b: u8) -> bool {
+    latin1_upper(b) || latin1_lower(b) || b == 0xaa || b == 0xba
+}
+
+/// The locale passed to a `_l` function.
+fn given(loc: locale_t) -> impl FnOnce() -> &'static __locale_struct {
+    move || unsafe { locale::get(loc) }
+}
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isalnum.html>.
 #[unsafe(no_mangle)]
@@ -18,10 +50,29 @@ pub extern "C" fn isalnum(c: c_int) -> c_int {
     c_int::from(isdigit(c) != 0 || isalpha(c) != 0)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isalnum.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isalnum_l(c: c_int, loc: locale_t) -> c_int {
+    c_int::from(isdigit(c) != 0 || isalpha_l(c, loc) != 0)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isalpha.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn isalpha(c: c_int) -> c_int {
-    c_int::from(islower(c) != 0 || isupper(c) != 0)
+    alpha(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isalpha.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isalpha_l(c: c_int, loc: locale_t) -> c_int {
+    alpha(c, given(loc))
+}
+
+fn alpha(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        Some(b) => c_int::from(latin1_alpha(b)),
+        None => c_int::from(c | 32 >= c_int::from(b'a') && c | 32 <= c_int::from(b'z')),
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9699919799/functions/isascii.html>.
@@ -40,10 +91,29 @@ pub extern "C" fn isblank(c: c_int) -> c_int {
     c_int::from(c == c_int::from(b' ') || c == c_int::from(b'\t'))
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isblank.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isblank_l(c: c_int, _loc: locale_t) -> c_int {
+    isblank(c)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iscntrl.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iscntrl(c: c_int) -> c_int {
-    c_int::from((c >= 0x00 && c <= 0x1f) || c == 0x7f)
+    cntrl(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iscntrl.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iscntrl_l(c: c_int, loc: locale_t) -> c_int {
+    cntrl(c, given(loc))
+}
+
+fn cntrl(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        Some(b) => c_int::from(b < 0xa0),
+        None => c_int::from((c >= 0x00 && c <= 0x1f) || c == 0x7f),
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isdigit.html>.
@@ -52,33 +122,92 @@ pub extern "C" fn isdigit(c: c_int) -> c_int {
     c_int::from(c >= c_int::from(b'0') && c <= c_int::from(b'9'))
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isdigit.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isdigit_l(c: c_int, _loc: locale_t) -> c_int {
+    isdigit(c)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isgraph.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn isgraph(c: c_int) -> c_int {
-    c_int::from(c >= 0x21 && c <= 0x7e)
+    graph(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isgraph.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isgraph_l(c: c_int, loc: locale_t) -> c_int {
+    graph(c, given(loc))
+}
+
+fn graph(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        // Everything from the no-break space on is printable
+        Some(b) => c_int::from(b > 0xa0),
+        None => c_int::from(c >= 0x21 && c <= 0x7e),
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/islower.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn islower(c: c_int) -> c_int {
-    c_int::from(c >= c_int::from(b'a') && c <= c_int::from(b'z'))
+    lower(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/islower.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn islower_l(c: c_int, loc: locale_t) -> c_int {
+    lower(c, given(loc))
+}
+
+fn lower(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        Some(b) => c_int::from(latin1_lower(b)),
+        None => c_int::from(c >= c_int::from(b'a') && c <= c_int::from(b'z')),
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isprint.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn isprint(c: c_int) -> c_int {
-    c_int::from(c >= 0x20 && c < 0x7f)
+    print(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isprint.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isprint_l(c: c_int, loc: locale_t) -> c_int {
+    print(c, given(loc))
+}
+
+fn print(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        Some(b) => c_int::from(b >= 0xa0),
+        None => c_int::from(c >= 0x20 && c < 0x7f),
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/ispunct.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn ispunct(c: c_int) -> c_int {
-    c_int::from(
-        (c >= c_int::from(b'!') && c <= c_int::from(b'/'))
-            || (c >= c_int::from(b':') && c <= c_int::from(b'@'))
-            || (c >= c_int::from(b'[') && c <= c_int::from(b'`'))
-            || (c >= c_int::from(b'{') && c <= c_int::from(b'~')),
-    )
+    punct(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/ispunct.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn ispunct_l(c: c_int, loc: locale_t) -> c_int {
+    punct(c, given(loc))
+}
+
+fn punct(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        Some(b) => c_int::from(b > 0xa0 && !latin1_alpha(b)),
+        None => c_int::from(
+            (c >= c_int::from(b'!') && c <= c_int::from(b'/'))
+                || (c >= c_int::from(b':') && c <= c_int::from(b'@'))
+                || (c >= c_int::from(b'[') && c <= c_int::from(b'`'))
+                || (c >= c_int::from(b'{') && c <= c_int::from(b'~')),
+        ),
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isspace.html>.
@@ -94,10 +223,29 @@ pub extern "C" fn isspace(c: c_int) -> c_int {
     )
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isspace.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isspace_l(c: c_int, _loc: locale_t) -> c_int {
+    isspace(c)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isupper.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn isupper(c: c_int) -> c_int {
-    c_int::from(c >= c_int::from(b'A') && c <= c_int::from(b'Z'))
+    upper(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isupper.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isupper_l(c: c_int, loc: locale_t) -> c_int {
+    upper(c, given(loc))
+}
+
+fn upper(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        Some(b) => c_int::from(latin1_upper(b)),
+        None => c_int::from(c >= c_int::from(b'A') && c <= c_int::from(b'Z')),
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isxdigit.html>.
@@ -106,6 +254,12 @@ pub extern "C" fn isxdigit(c: c_int) -> c_int {
     c_int::from(isdigit(c) != 0 || (c | 32 >= c_int::from(b'a') && c | 32 <= c_int::from(b'f')))
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/isxdigit.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn isxdigit_l(c: c_int, _loc: locale_t) -> c_int {
+    isxdigit(c)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9699919799/functions/toascii.html>.
 ///
 /// The `toascii()` function was marked obsolescent in the Open Group Base
@@ -122,8 +276,30 @@ pub extern "C" fn tolower(c: c_int) -> c_int {
     if isupper(c) != 0 { c | 0x20 } else { c }
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/tolower.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn tolower_l(c: c_int, loc: locale_t) -> c_int {
+    if isupper_l(c, loc) != 0 { c | 0x20 } else { c }
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/toupper.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn toupper(c: c_int) -> c_int {
-    if islower(c) != 0 { c & !0x20 } else { c }
+    upper_case(c, locale::current)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/toupper.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn toupper_l(c: c_int, loc: locale_t) -> c_int {
+    upper_case(c, given(loc))
+}
+
+fn upper_case(c: c_int, loc: impl FnOnce() -> &'static __locale_struct) -> c_int {
+    match latin1(c, loc) {
+        // ß, ÿ and µ have no upper case letter in ISO-8859-1
+        Some(b) if latin1_lower(b) && !matches!(b, 0xdf | 0xff | 0xb5) => c & !0x20,
+        Some(_) => c,
+        None if c >= c_int::from(b'a') && c <= c_int::from(b'z') => c & !0x20,
+        None => c,
+    }
 }
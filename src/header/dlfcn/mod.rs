@@ -6,14 +6,14 @@
 #![warn(warnings, unused_variables)]
 
 use core::{
-    ptr, str,
+    mem, ptr, str,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
 use crate::{
     c_str::CStr,
     ld_so::{
-        linker::{DlError, ObjectHandle, Resolve, ScopeKind},
+        linker::{AddrInfo, DlError, LoadFlags, ObjectHandle, ObjectInfo, Resolve, ScopeKind},
         tcb::Tcb,
     },
     platform::types::*,
@@ -22,12 +22,28 @@ use crate::{
 pub const RTLD_LAZY: c_int = 1 << 0;
 pub const RTLD_NOW: c_int = 1 << 1;
 pub const RTLD_NOLOAD: c_int = 1 << 2;
+pub const RTLD_DEEPBIND: c_int = 1 << 3;
 pub const RTLD_GLOBAL: c_int = 1 << 8;
 pub const RTLD_LOCAL: c_int = 0x0000;
+pub const RTLD_NODELETE: c_int = 1 << 12;
+
+pub const RTLD_DL_SYMENT: c_int = 1;
+pub const RTLD_DL_LINKMAP: c_int = 2;
+
+// Requests of dlinfo()
+pub const RTLD_DI_LMID: c_int = 1;
+pub const RTLD_DI_LINKMAP: c_int = 2;
+pub const RTLD_DI_SERINFO: c_int = 4;
+pub const RTLD_DI_SERINFOSIZE: c_int = 5;
+pub const RTLD_DI_ORIGIN: c_int = 6;
+pub const RTLD_DI_TLS_MODID: c_int = 9;
 
 pub const RTLD_DEFAULT: *mut c_void = 0 as *mut c_void; // XXX: cbindgen doesn't like ptr::null_mut()
 
 static ERROR_NOT_SUPPORTED: &core::ffi::CStr = c"dlfcn not supported";
+static ERROR_INVALID_REQUEST: &core::ffi::CStr = c"unsupported dlinfo request";
+static ERROR_BUFFER_SIZE: &core::ffi::CStr = c"buffer too small for the search path";
+static ERROR_NULL_EXTRA_INFO: &core::ffi::CStr = c"no place to store the extra information";
 
 #[thread_local]
 static ERROR: AtomicUsize = AtomicUsize::new(0);
@@ -45,16 +61,72 @@ pub struct Dl_info {
     dli_saddr: *mut c_void,
 }
 
+/// A directory of the search path returned by [`dlinfo`].
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct Dl_serpath {
+    dls_name: *mut c_char,
+    dls_flags: c_uint,
+}
+
+/// The search path returned by [`dlinfo`], with as many entries in
+/// `dls_serpath` as `dls_cnt` says.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct Dl_serinfo {
+    dls_size: size_t,
+    dls_cnt: c_uint,
+    dls_serpath: [Dl_serpath; 1],
+}
+
+fn addr_info(addr: *const c_void) -> Option<AddrInfo> {
+    let tcb = unsafe { Tcb::current() }?;
+    if tcb.linker_ptr.is_null() {
+        return None;
+    }
+
+    let linker = unsafe { (*tcb.linker_ptr).lock() };
+    let cbs_c = linker.cbs.clone();
+    let cbs = cbs_c.borrow();
+    (cbs.addr_info)(&linker, addr as usize)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/dladdr.html>.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn dladdr(_addr: *mut c_void, info: *mut Dl_info) -> c_int {
-    //TODO
+pub unsafe extern "C" fn dladdr(addr: *const c_void, info: *mut Dl_info) -> c_int {
+    unsafe { dladdr1(addr, info, ptr::null_mut(), 0) }
+}
+
+/// Non-POSIX, see <https://man7.org/linux/man-pages/man3/dladdr.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dladdr1(
+    addr: *const c_void,
+    info: *mut Dl_info,
+    extra_info: *mut *mut c_void,
+    flags: c_int,
+) -> c_int {
+    if matches!(flags, RTLD_DL_SYMENT | RTLD_DL_LINKMAP) && extra_info.is_null() {
+        ERROR.store(ERROR_NULL_EXTRA_INFO.as_ptr() as usize, Ordering::SeqCst);
+        return 0;
+    }
+
+    let Some(found) = addr_info(addr) else {
+        return 0;
+    };
+
     unsafe {
-        (*info).dli_fname = ptr::null();
-        (*info).dli_fbase = ptr::null_mut();
-        (*info).dli_sname = ptr::null();
-        (*info).dli_saddr = ptr::null_mut();
+        (*info).dli_fname = found.fname;
+        (*info).dli_fbase = found.fbase;
+        (*info).dli_sname = found.sname;
+        (*info).dli_saddr = found.saddr;
+
+        match flags {
+            RTLD_DL_SYMENT => *extra_info = found.sym.cast_mut(),
+            RTLD_DL_LINKMAP => *extra_info = found.link_map,
+            _ => {}
+        }
     }
-    0
+    1
 }
 
 #[unsafe(no_mangle)]
@@ -72,7 +144,10 @@ pub unsafe extern "C" fn dlopen(cfilename: *const c_char, flags: c_int) -> *mut
         ScopeKind::Local
     };
 
-    let noload = flags & RTLD_NOLOAD == RTLD_NOLOAD;
+    let mut load_flags = LoadFlags::empty();
+    load_flags.set(LoadFlags::NOLOAD, flags & RTLD_NOLOAD == RTLD_NOLOAD);
+    load_flags.set(LoadFlags::NODELETE, flags & RTLD_NODELETE == RTLD_NODELETE);
+    load_flags.set(LoadFlags::DEEPBIND, flags & RTLD_DEEPBIND == RTLD_DEEPBIND);
 
     let filename = if cfilename.is_null() {
         None
@@ -102,7 +177,7 @@ pub unsafe extern "C" fn dlopen(cfilename: *const c_char, flags: c_int) -> *mut
     let cbs_c = linker.cbs.clone();
     let cbs = cbs_c.borrow();
 
-    match (cbs.load_library)(&mut linker, filename, resolve, scope, noload) {
+    match (cbs.load_library)(&mut linker, filename, resolve, scope, load_flags) {
         Ok(handle) => handle.as_ptr().cast_mut(),
         Err(error) => {
             set_last_error(error);
@@ -113,6 +188,30 @@ pub unsafe extern "C" fn dlopen(cfilename: *const c_char, flags: c_int) -> *mut
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void {
+    unsafe { get_sym(handle, symbol, None) }
+}
+
+/// Non-POSIX, see <https://man7.org/linux/man-pages/man3/dlvsym.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dlvsym(
+    handle: *mut c_void,
+    symbol: *const c_char,
+    version: *const c_char,
+) -> *mut c_void {
+    if version.is_null() {
+        ERROR.store(ERROR_NOT_SUPPORTED.as_ptr() as usize, Ordering::SeqCst);
+        return ptr::null_mut();
+    }
+
+    let version_str = unsafe { str::from_utf8_unchecked(CStr::from_ptr(version).to_bytes()) };
+    unsafe { get_sym(handle, symbol, Some(version_str)) }
+}
+
+unsafe fn get_sym(
+    handle: *mut c_void,
+    symbol: *const c_char,
+    version: Option<&str>,
+) -> *mut c_void {
     let handle = ObjectHandle::from_ptr(handle);
 
     if symbol.is_null() {
@@ -141,7 +240,7 @@ pub unsafe extern "C" fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *m
     let linker = unsafe { (*tcb.linker_ptr).lock() };
     let cbs_c = linker.cbs.clone();
     let cbs = cbs_c.borrow();
-    match (cbs.get_sym)(&linker, handle, symbol_str) {
+    match (cbs.get_sym)(&linker, handle, symbol_str, version) {
         Some(sym) => sym,
         _ => {
             ERROR.store(ERROR_NOT_SUPPORTED.as_ptr() as usize, Ordering::SeqCst);
@@ -177,6 +276,111 @@ pub unsafe extern "C" fn dlclose(handle: *mut c_void) -> c_int {
     0
 }
 
+/// Non-POSIX, see <https://man7.org/linux/man-pages/man3/dlinfo.3.html>.
+///
+/// `RTLD_DI_SERINFO` expects `dls_size` and `dls_cnt` to be filled in by a
+/// previous `RTLD_DI_SERINFOSIZE` request.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dlinfo(handle: *mut c_void, request: c_int, arg: *mut c_void) -> c_int {
+    let tcb = match unsafe { Tcb::current() } {
+        Some(tcb) => tcb,
+        None => {
+            ERROR.store(ERROR_NOT_SUPPORTED.as_ptr() as usize, Ordering::SeqCst);
+            return -1;
+        }
+    };
+
+    if tcb.linker_ptr.is_null() {
+        ERROR.store(ERROR_NOT_SUPPORTED.as_ptr() as usize, Ordering::SeqCst);
+        return -1;
+    };
+
+    let Some(handle) = ObjectHandle::from_ptr(handle) else {
+        set_last_error(DlError::InvalidHandle);
+        return -1;
+    };
+
+    let linker = unsafe { (*tcb.linker_ptr).lock() };
+    let cbs_c = linker.cbs.clone();
+    let cbs = cbs_c.borrow();
+
+    let mut error = None;
+    (cbs.object_info)(&linker, &handle, &mut |info: &ObjectInfo| {
+        error = unsafe { object_info(info, request, arg) }.err();
+    });
+
+    match error {
+        Some(error) => {
+            ERROR.store(error.as_ptr() as usize, Ordering::SeqCst);
+            -1
+        }
+        None => 0,
+    }
+}
+
+unsafe fn object_info(
+    info: &ObjectInfo,
+    request: c_int,
+    arg: *mut c_void,
+) -> Result<(), &'static core::ffi::CStr> {
+    match request {
+        // Only the default namespace is supported.
+        RTLD_DI_LMID => unsafe { *arg.cast::<c_long>() = 0 },
+        RTLD_DI_LINKMAP => unsafe { *arg.cast::<*mut c_void>() = info.link_map },
+        RTLD_DI_TLS_MODID => unsafe { *arg.cast::<size_t>() = info.tls_modid },
+        RTLD_DI_ORIGIN => unsafe {
+            let dst = arg.cast::<c_char>();
+            ptr::copy_nonoverlapping(info.origin.as_ptr().cast(), dst, info.origin.len());
+            *dst.add(info.origin.len()) = 0;
+        },
+        RTLD_DI_SERINFOSIZE | RTLD_DI_SERINFO => {
+            let serinfo = arg.cast::<Dl_serinfo>();
+            let count = info.search_path.len();
+            let names_offset =
+                mem::offset_of!(Dl_serinfo, dls_serpath) + count * mem::size_of::<Dl_serpath>();
+            let size = names_offset
+                + info
+                    .search_path
+                    .iter()
+                    .map(|dir| dir.len() + 1)
+                    .sum::<usize>();
+
+            if request == RTLD_DI_SERINFOSIZE {
+                unsafe {
+                    (*serinfo).dls_size = size;
+                    (*serinfo).dls_cnt = count as c_uint;
+                }
+                return Ok(());
+            }
+
+            if unsafe { (*serinfo).dls_size < size || ((*serinfo).dls_cnt as usize) < count } {
+                return Err(ERROR_BUFFER_SIZE);
+            }
+
+            // The names are stored after the entries.
+            let entries = unsafe { ptr::addr_of_mut!((*serinfo).dls_serpath).cast::<Dl_serpath>() };
+            let mut name = unsafe { serinfo.cast::<c_char>().add(names_offset) };
+            for (i, dir) in info.search_path.iter().enumerate() {
+                unsafe {
+                    ptr::copy_nonoverlapping(dir.as_ptr().cast(), name, dir.len());
+                    *name.add(dir.len()) = 0;
+                    entries.add(i).write(Dl_serpath {
+                        dls_name: name,
+                        dls_flags: 0,
+                    });
+                    name = name.add(dir.len() + 1);
+                }
+            }
+            unsafe {
+                (*serinfo).dls_size = size;
+                (*serinfo).dls_cnt = count as c_uint;
+            }
+        }
+        _ => return Err(ERROR_INVALID_REQUEST),
+    }
+    Ok(())
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn dlerror() -> *mut c_char {
     ERROR.swap(0, Ordering::SeqCst) as *mut c_char
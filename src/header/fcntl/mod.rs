@@ -14,6 +14,7 @@ use crate::{
         Pal, Sys,
         types::{c_char, c_int, c_short, c_ulonglong, mode_t, off_t, pid_t},
     },
+    pthread,
 };
 
 pub use self::sys::*;
@@ -73,7 +74,13 @@ pub unsafe extern "C" fn fcntl(fildes: c_int, cmd: c_int, mut __valist: ...) ->
         _ => 0,
     };
 
-    Sys::fcntl(fildes, cmd, arg).or_minus_one_errno()
+    // Only waiting for a lock blocks.
+    let result = if cmd == F_SETLKW {
+        pthread::cancellation_point(|| Sys::fcntl(fildes, cmd, arg))
+    } else {
+        Sys::fcntl(fildes, cmd, arg)
+    };
+    result.or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/open.html>.
@@ -88,7 +95,7 @@ pub unsafe extern "C" fn open(path: *const c_char, oflag: c_int, mut __valist: .
     };
 
     let path = unsafe { CStr::from_ptr(path) };
-    Sys::open(path, oflag, mode).or_minus_one_errno()
+    pthread::cancellation_point(|| Sys::open(path, oflag, mode)).or_minus_one_errno()
 }
 
 #[unsafe(no_mangle)]
@@ -22,15 +22,16 @@ use alloc::{
 use crate::{
     c_str::CStr,
     fs::File,
-    header::{errno, fcntl, limits, string::strlen, unistd},
+    header::{errno, fcntl, limits, unistd},
     io,
     io::{BufReader, Lines, prelude::*},
+    nss::{self, Database, LookupError, Source, Status},
     platform,
     platform::types::*,
     sync::Mutex,
 };
 
-use super::{errno::*, string::strncmp};
+use super::{errno::*, string::strcmp};
 
 #[cfg(target_os = "linux")]
 const SEPARATOR: char = ':';
@@ -103,8 +104,64 @@ enum Error {
     FromUtf8Error(FromUtf8Error),
     ParseIntError(ParseIntError),
     Other,
+    /// No source has the group.
+    NotFound,
+    /// A module of `nsswitch.conf` failed.
+    Nss(Status),
 }
 
+impl LookupError for Error {
+    fn from_status(status: Status) -> Self {
+        match status {
+            Status::NotFound => Error::NotFound,
+            status => Error::Nss(status),
+        }
+    }
+
+    fn status(&self) -> Status {
+        match self {
+            Error::NotFound => Status::NotFound,
+            Error::BufTooSmall => Status::TryAgain,
+            Error::Nss(status) => *status,
+            _ => Status::Unavail,
+        }
+    }
+}
+
+impl Error {
+    /// The error number that the reentrant functions return.
+    fn errno(&self) -> c_int {
+        match self {
+            Error::BufTooSmall => ERANGE,
+            Error::EOF
+            | Error::SyntaxError
+            | Error::FromUtf8Error(_)
+            | Error::ParseIntError(_)
+            | Error::Other => EINVAL,
+            Error::Misc(io_err) => match io_err.kind() {
+                io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => EINVAL,
+                io::ErrorKind::NotFound => ENOENT,
+                _ => EIO,
+            },
+            Error::NotFound => ENOENT,
+            Error::Nss(Status::TryAgain) if platform::ERRNO.get() == ERANGE => ERANGE,
+            Error::Nss(Status::TryAgain) => EAGAIN,
+            Error::Nss(_) => ENOENT,
+        }
+    }
+}
+
+/// The group that a lookup is for.
+#[derive(Clone, Copy, Debug)]
+enum Key {
+    Name(*const c_char),
+    Gid(gid_t),
+}
+
+type GetgrnamR =
+    unsafe extern "C" fn(*const c_char, *mut group, *mut c_char, size_t, *mut c_int) -> c_int;
+type GetgrgidR = unsafe extern "C" fn(gid_t, *mut group, *mut c_char, size_t, *mut c_int) -> c_int;
+
 #[derive(Debug)]
 struct OwnedGrp {
     buffer: MaybeAllocated,
@@ -248,58 +305,80 @@ fn parse_grp(line: String, destbuf: Option<DestBuffer>) -> Result<OwnedGrp, Erro
     Ok(OwnedGrp { buffer, reference })
 }
 
-// MT-Unsafe race:grgid locale
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn getgrgid(gid: gid_t) -> *mut group {
-    let Ok(db) = File::open(GROUP_FILE.into(), fcntl::O_RDONLY) else {
-        return ptr::null_mut();
-    };
+fn files_lookup(key: Key, destbuf: Option<DestBuffer>) -> Result<OwnedGrp, Error> {
+    let db = File::open(GROUP_FILE.into(), fcntl::O_RDONLY).map_err(Error::Misc)?;
 
     for line in BufReader::new(db).lines() {
-        let Ok(line) = line else {
-            return ptr::null_mut();
-        };
-        let Ok(grp) = parse_grp(line, None) else {
-            return ptr::null_mut();
-        };
+        let grp = parse_grp(line.map_err(Error::Misc)?, destbuf)?;
 
-        if grp.reference.gr_gid == gid {
-            return grp.into_global();
+        let matches = match key {
+            Key::Name(name) => unsafe { strcmp(grp.reference.gr_name, name) == 0 },
+            Key::Gid(gid) => grp.reference.gr_gid == gid,
+        };
+        if matches {
+            return Ok(grp);
         }
     }
-
-    ptr::null_mut()
+    Err(Error::NotFound)
 }
 
-// MT-Unsafe race:grnam locale
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn getgrnam(name: *const c_char) -> *mut group {
-    let Ok(db) = File::open(GROUP_FILE.into(), fcntl::O_RDONLY) else {
-        return ptr::null_mut();
+fn module_lookup(module: &str, key: Key, destbuf: Option<DestBuffer>) -> Result<OwnedGrp, Status> {
+    let mut reference: group = unsafe { mem::zeroed() };
+    let dest = destbuf.map(|dst| unsafe { slice::from_raw_parts_mut(dst.ptr, dst.len) });
+    let function = match key {
+        Key::Name(_) => "getgrnam_r",
+        Key::Gid(_) => "getgrgid_r",
     };
-
-    for line in BufReader::new(db).lines() {
-        let Ok(line) = line else {
-            return ptr::null_mut();
-        };
-
-        let Ok(grp) = parse_grp(line, None) else {
-            return ptr::null_mut();
-        };
-
-        // Attempt to prevent BO vulnerabilities
-        if unsafe {
-            strncmp(
-                grp.reference.gr_name,
+    let buffer = nss::module_lookup(module, function, dest, |symbol, buf, errnop| unsafe {
+        let buf_ptr = buf.as_mut_ptr() as *mut c_char;
+        match key {
+            Key::Name(name) => mem::transmute::<_, GetgrnamR>(symbol)(
                 name,
-                strlen(grp.reference.gr_name).min(strlen(name)),
-            ) == 0
-        } {
-            return grp.into_global();
+                &mut reference,
+                buf_ptr,
+                buf.len(),
+                errnop,
+            ),
+            Key::Gid(gid) => mem::transmute::<_, GetgrgidR>(symbol)(
+                gid,
+                &mut reference,
+                buf_ptr,
+                buf.len(),
+                errnop,
+            ),
         }
-    }
+    })?;
+    // Without a buffer of our own, the entry is in the one of the caller
+    let buffer = match buffer {
+        Some(buffer) => MaybeAllocated::Owned(Box::into_pin(buffer)),
+        None => MaybeAllocated::Borrowed(destbuf.ok_or(Status::Unavail)?),
+    };
+    Ok(OwnedGrp { buffer, reference })
+}
+
+/// Looks up a group in the sources of `group` in `nsswitch.conf`.
+fn grp_lookup(key: Key, destbuf: Option<DestBuffer>) -> Result<OwnedGrp, Error> {
+    nss::dispatch(Database::Group, |source| match source {
+        Source::Files => files_lookup(key, destbuf),
+        Source::Module(module) => module_lookup(module, key, destbuf).map_err(Error::from_status),
+        Source::Dns => Err(Error::Nss(Status::Unavail)),
+    })
+}
 
-    ptr::null_mut()
+// MT-Unsafe race:grgid locale
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getgrgid(gid: gid_t) -> *mut group {
+    grp_lookup(Key::Gid(gid), None)
+        .map(|grp| grp.into_global())
+        .unwrap_or(ptr::null_mut())
+}
+
+// MT-Unsafe race:grnam locale
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getgrnam(name: *const c_char) -> *mut group {
+    grp_lookup(Key::Name(name), None)
+        .map(|grp| grp.into_global())
+        .unwrap_or(ptr::null_mut())
 }
 
 // MT-Safe locale
@@ -316,49 +395,22 @@ pub unsafe extern "C" fn getgrgid_r(
         *result = ptr::null_mut();
     }
 
-    let Ok(db) = File::open(GROUP_FILE.into(), fcntl::O_RDONLY) else {
-        return ENOENT;
-    };
-
-    for line in BufReader::new(db).lines() {
-        let Ok(line) = line else { return EINVAL };
-        let grp = match parse_grp(
-            line,
-            Some(DestBuffer {
-                ptr: buffer as *mut u8,
-                len: buflen,
-            }),
-        ) {
-            Ok(grp) => grp,
-            Err(err) => {
-                return match err {
-                    Error::BufTooSmall => ERANGE,
-                    Error::EOF
-                    | Error::SyntaxError
-                    | Error::FromUtf8Error(_)
-                    | Error::ParseIntError(_)
-                    | Error::Other => EINVAL,
-                    Error::Misc(io_err) => match io_err.kind() {
-                        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => EINVAL,
-                        io::ErrorKind::NotFound => ENOENT,
-                        _ => EIO,
-                    },
-                };
-            }
-        };
-
-        if grp.reference.gr_gid == gid {
+    let destbuf = Some(DestBuffer {
+        ptr: buffer as *mut u8,
+        len: buflen,
+    });
+    match grp_lookup(Key::Gid(gid), destbuf) {
+        Ok(grp) => {
             unsafe {
                 *result_buf = grp.reference;
                 *result = result_buf;
             }
-
-            return 0;
+            0
         }
+        // The requested entry was not found.
+        Err(Error::NotFound) => 0,
+        Err(err) => err.errno(),
     }
-
-    // The requested entry was not found.
-    0
 }
 
 // MT-Safe locale
@@ -370,39 +422,26 @@ pub unsafe extern "C" fn getgrnam_r(
     buflen: usize,
     result: *mut *mut group,
 ) -> c_int {
-    let Ok(db) = File::open(GROUP_FILE.into(), fcntl::O_RDONLY) else {
-        return ENOENT;
-    };
-
-    for line in BufReader::new(db).lines() {
-        let Ok(line) = line else { return EINVAL };
-        let Ok(mut grp) = parse_grp(
-            line,
-            Some(DestBuffer {
-                ptr: buffer as *mut u8,
-                len: buflen,
-            }),
-        ) else {
-            return EINVAL;
-        };
+    unsafe {
+        *result = ptr::null_mut();
+    }
 
-        if unsafe {
-            strncmp(
-                grp.reference.gr_name,
-                name,
-                strlen(grp.reference.gr_name).min(strlen(name)),
-            ) > 0
-        } {
+    let destbuf = Some(DestBuffer {
+        ptr: buffer as *mut u8,
+        len: buflen,
+    });
+    match grp_lookup(Key::Name(name), destbuf) {
+        Ok(grp) => {
             unsafe {
                 *result_buf = grp.reference;
                 *result = result_buf;
             }
-
-            return 0;
+            0
         }
+        // The requested entry was not found.
+        Err(Error::NotFound) => 0,
+        Err(err) => err.errno(),
     }
-
-    ENOENT
 }
 
 // MT-Unsafe race:grent race:grentbuf locale
@@ -0,0 +1,337 @@
+//! The character sets `iconv` knows, and how to decode and encode them.
+
+use core::{mem, ptr};
+
+use crate::{
+    header::wchar::{mbstate_t, utf8},
+    platform::types::*,
+};
+
+use super::tables;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Endian {
+    Big,
+    Little,
+}
+
+#[cfg(target_endian = "big")]
+const NATIVE: Endian = Endian::Big;
+#[cfg(target_endian = "little")]
+const NATIVE: Endian = Endian::Little;
+
+#[derive(Clone, Copy)]
+pub(super) enum Charset {
+    Ascii,
+    Utf8,
+    /// UTF-16 in the given byte order. Without one, input starts with an
+    /// optional byte order mark and output with a mark in native order.
+    Utf16(Option<Endian>),
+    /// UTF-32, with the same byte order rules as UTF-16.
+    Utf32(Option<Endian>),
+    /// ISO-8859-1, where every byte is the code point of the same value.
+    Latin1,
+    /// A set that agrees with ISO-8859-1 below 0xa0 and uses the table
+    /// above it.
+    Iso8859(&'static [u16; 96]),
+    Cp1252,
+}
+
+pub(super) enum DecodeError {
+    /// The input ends in the middle of a character.
+    Incomplete,
+    /// The input is not valid. Skipping the given number of bytes moves on
+    /// to the next character.
+    Invalid(usize),
+}
+
+pub(super) enum EncodeError {
+    /// The character set has no way to represent the character.
+    Unrepresentable,
+    /// The output buffer is too small.
+    TooBig,
+}
+
+/// Names of each character set, compared with [`normalize`] applied.
+static CHARSETS: &[(&[&str], Charset)] = &[
+    (&["UTF8"], Charset::Utf8),
+    (&["UTF16"], Charset::Utf16(None)),
+    (&["UTF16BE"], Charset::Utf16(Some(Endian::Big))),
+    (&["UTF16LE"], Charset::Utf16(Some(Endian::Little))),
+    (&["UTF32", "UCS4"], Charset::Utf32(None)),
+    (&["UTF32BE", "UCS4BE"], Charset::Utf32(Some(Endian::Big))),
+    (&["UTF32LE", "UCS4LE"], Charset::Utf32(Some(Endian::Little))),
+    // wchar_t always holds UTF-32 code points
+    (&["WCHART"], Charset::Utf32(Some(NATIVE))),
+    (&["ASCII", "USASCII", "ANSIX3.41968", "646"], Charset::Ascii),
+    (&["ISO88591", "LATIN1", "L1"], Charset::Latin1),
+    (
+        &["ISO88592", "LATIN2", "L2"],
+        Charset::Iso8859(&tables::ISO_8859_2),
+    ),
+    (
+        &["ISO88593", "LATIN3", "L3"],
+        Charset::Iso8859(&tables::ISO_8859_3),
+    ),
+    (
+        &["ISO88594", "LATIN4", "L4"],
+        Charset::Iso8859(&tables::ISO_8859_4),
+    ),
+    (
+        &["ISO88595", "CYRILLIC"],
+        Charset::Iso8859(&tables::ISO_8859_5),
+    ),
+    (
+        &["ISO88596", "ARABIC"],
+        Charset::Iso8859(&tables::ISO_8859_6),
+    ),
+    (
+        &["ISO88597", "GREEK"],
+        Charset::Iso8859(&tables::ISO_8859_7),
+    ),
+    (
+        &["ISO88598", "HEBREW"],
+        Charset::Iso8859(&tables::ISO_8859_8),
+    ),
+    (
+        &["ISO88599", "LATIN5", "L5"],
+        Charset::Iso8859(&tables::ISO_8859_9),
+    ),
+    (
+        &["ISO885910", "LATIN6", "L6"],
+        Charset::Iso8859(&tables::ISO_8859_10),
+    ),
+    (
+        &["ISO885911", "TIS620"],
+        Charset::Iso8859(&tables::ISO_8859_11),
+    ),
+    (
+        &["ISO885913", "LATIN7", "L7"],
+        Charset::Iso8859(&tables::ISO_8859_13),
+    ),
+    (
+        &["ISO885914", "LATIN8", "L8"],
+        Charset::Iso8859(&tables::ISO_8859_14),
+    ),
+    (
+        &["ISO885915", "LATIN9", "L9"],
+        Charset::Iso8859(&tables::ISO_8859_15),
+    ),
+    (
+        &["ISO885916", "LATIN10", "L10"],
+        Charset::Iso8859(&tables::ISO_8859_16),
+    ),
+    (&["CP1252", "WINDOWS1252"], Charset::Cp1252),
+];
+
+/// Uppercases `name` and drops `-` and `_`, so that `utf-8`, `UTF8` and
+/// `ISO_8859-1` match the names in [`CHARSETS`].
+fn normalize(name: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    name.iter()
+        .filter(|&&b| b != b'-' && b != b'_')
+        .map(u8::to_ascii_uppercase)
+}
+
+impl Charset {
+    pub(super) fn find(name: &[u8]) -> Option<Self> {
+        CHARSETS.iter().find_map(|&(names, charset)| {
+            names
+                .iter()
+                .any(|known| normalize(name).eq(known.bytes()))
+                .then_some(charset)
+        })
+    }
+
+    /// For UTF-16 and UTF-32 without a byte order, picks one based on the
+    /// byte order mark at the start of `input`. Returns the length of the
+    /// mark, which is not part of the text.
+    pub(super) fn detect_order(&mut self, input: &[u8]) -> Result<usize, DecodeError> {
+        let (order, len) = match *self {
+            Charset::Utf16(None) => match input {
+                [0xfe, 0xff, ..] => (Endian::Big, 2),
+                [0xff, 0xfe, ..] => (Endian::Little, 2),
+                [_] => return Err(DecodeError::Incomplete),
+                _ => (Endian::Big, 0),
+            },
+            Charset::Utf32(None) => match input {
+                [0, 0, 0xfe, 0xff, ..] => (Endian::Big, 4),
+                [0xff, 0xfe, 0, 0, ..] => (Endian::Little, 4),
+                [_] | [_, _] | [_, _, _] => return Err(DecodeError::Incomplete),
+                _ => (Endian::Big, 0),
+            },
+            _ => return Ok(0),
+        };
+        self.set_order(order);
+        Ok(len)
+    }
+
+    /// For UTF-16 and UTF-32 without a byte order, picks native order and
+    /// returns the byte order mark that output must start with.
+    pub(super) fn choose_order(&mut self) -> Option<&'static [u8]> {
+        let bom: &'static [u8] = match (*self, NATIVE) {
+            (Charset::Utf16(None), Endian::Big) => &[0xfe, 0xff],
+            (Charset::Utf16(None), Endian::Little) => &[0xff, 0xfe],
+            (Charset::Utf32(None), Endian::Big) => &[0, 0, 0xfe, 0xff],
+            (Charset::Utf32(None), Endian::Little) => &[0xff, 0xfe, 0, 0],
+            _ => return None,
+        };
+        self.set_order(NATIVE);
+        Some(bom)
+    }
+
+    fn set_order(&mut self, order: Endian) {
+        match self {
+            Charset::Utf16(endian) | Charset::Utf32(endian) => *endian = Some(order),
+            _ => (),
+        }
+    }
+
+    /// Decodes the character at the start of `input`, which is not empty.
+    /// Returns it along with the number of bytes it took up.
+    pub(super) fn decode(&self, input: &[u8]) -> Result<(char, usize), DecodeError> {
+        match *self {
+            Charset::Ascii => match input[0] {
+                b @ 0..=0x7f => Ok((char::from(b), 1)),
+                _ => Err(DecodeError::Invalid(1)),
+            },
+            Charset::Utf8 => {
+                let mut state: mbstate_t = unsafe { mem::zeroed() };
+                let mut wc: wchar_t = 0;
+                let len = unsafe {
+                    utf8::mbrtowc(&mut wc, input.as_ptr().cast(), input.len(), &mut state)
+                };
+                match len as isize {
+                    -1 => Err(DecodeError::Invalid(1)),
+                    -2 => Err(DecodeError::Incomplete),
+                    // The nul character
+                    0 => Ok(('\0', 1)),
+                    _ => Ok((char::from_u32(wc as u32).unwrap(), len)),
+                }
+            }
+            Charset::Utf16(endian) => {
+                let endian = endian.unwrap_or(Endian::Big);
+                let unit = |i: usize| -> Option<u32> {
+                    let bytes = [*input.get(i)?, *input.get(i + 1)?];
+                    Some(u32::from(match endian {
+                        Endian::Big => u16::from_be_bytes(bytes),
+                        Endian::Little => u16::from_le_bytes(bytes),
+                    }))
+                };
+
+                let high = unit(0).ok_or(DecodeError::Incomplete)?;
+                match high {
+                    0xd800..=0xdbff => {
+                        let low = unit(2).ok_or(DecodeError::Incomplete)?;
+                        if !(0xdc00..=0xdfff).contains(&low) {
+                            return Err(DecodeError::Invalid(2));
+                        }
+                        let c = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                        Ok((char::from_u32(c).unwrap(), 4))
+                    }
+                    0xdc00..=0xdfff => Err(DecodeError::Invalid(2)),
+                    c => Ok((char::from_u32(c).unwrap(), 2)),
+                }
+            }
+            Charset::Utf32(endian) => {
+                let bytes: [u8; 4] = input
+                    .get(..4)
+                    .ok_or(DecodeError::Incomplete)?
+                    .try_into()
+                    .unwrap();
+                let c = match endian.unwrap_or(Endian::Big) {
+                    Endian::Big => u32::from_be_bytes(bytes),
+                    Endian::Little => u32::from_le_bytes(bytes),
+                };
+                char::from_u32(c)
+                    .map(|c| (c, 4))
+                    .ok_or(DecodeError::Invalid(4))
+            }
+            Charset::Latin1 => Ok((char::from(input[0]), 1)),
+            Charset::Iso8859(high) => match input[0] {
+                b @ 0..=0x9f => Ok((char::from(b), 1)),
+                b => match high[usize::from(b - 0xa0)] {
+                    0 => Err(DecodeError::Invalid(1)),
+                    c => Ok((char::from_u32(u32::from(c)).unwrap(), 1)),
+                },
+            },
+            Charset::Cp1252 => match input[0] {
+                b @ 0x80..=0x9f => match tables::CP1252[usize::from(b - 0x80)] {
+                    0 => Err(DecodeError::Invalid(1)),
+                    c => Ok((char::from_u32(u32::from(c)).unwrap(), 1)),
+                },
+                b => Ok((char::from(b), 1)),
+            },
+        }
+    }
+
+    /// Encodes `c` at the start of `output`, returning the number of bytes
+    /// written.
+    pub(super) fn encode(&self, c: char, output: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = [0; 4];
+        let bytes: &[u8] = match *self {
+            Charset::Ascii => match u8::try_from(c) {
+                Ok(b @ 0..=0x7f) => {
+                    buf[0] = b;
+                    &buf[..1]
+                }
+                _ => return Err(EncodeError::Unrepresentable),
+            },
+            Charset::Utf8 => {
+                let len = unsafe {
+                    utf8::wcrtomb(buf.as_mut_ptr().cast(), c as wchar_t, ptr::null_mut())
+                };
+                &buf[..len]
+            }
+            Charset::Utf16(endian) => {
+                let mut units = [0; 2];
+                let units = c.encode_utf16(&mut units);
+                for (i, unit) in units.iter().enumerate() {
+                    buf[i * 2..i * 2 + 2].copy_from_slice(&match endian.unwrap_or(NATIVE) {
+                        Endian::Big => unit.to_be_bytes(),
+                        Endian::Little => unit.to_le_bytes(),
+                    });
+                }
+                &buf[..units.len() * 2]
+            }
+            Charset::Utf32(endian) => {
+                buf = match endian.unwrap_or(NATIVE) {
+                    Endian::Big => u32::from(c).to_be_bytes(),
+                    Endian::Little => u32::from(c).to_le_bytes(),
+                };
+                &buf
+            }
+            Charset::Latin1 => {
+                buf[0] = u8::try_from(c).map_err(|_| EncodeError::Unrepresentable)?;
+                &buf[..1]
+            }
+            Charset::Iso8859(high) => {
+                buf[0] = match u32::from(c) {
+                    c @ 0..=0x9f => c as u8,
+                    c => high
+                        .iter()
+                        .position(|&h| u32::from(h) == c)
+                        .map(|i| 0xa0 + i as u8)
+                        .ok_or(EncodeError::Unrepresentable)?,
+                };
+                &buf[..1]
+            }
+            Charset::Cp1252 => {
+                buf[0] = match u32::from(c) {
+                    c @ (0..=0x7f | 0xa0..=0xff) => c as u8,
+                    c => tables::CP1252
+                        .iter()
+                        .position(|&h| h != 0 && u32::from(h) == c)
+                        .map(|i| 0x80 + i as u8)
+                        .ok_or(EncodeError::Unrepresentable)?,
+                };
+                &buf[..1]
+            }
+        };
+
+        output
+            .get_mut(..bytes.len())
+            .ok_or(EncodeError::TooBig)?
+            .copy_from_slice(bytes);
+        Ok(bytes.len())
+    }
+}
@@ -0,0 +1,282 @@
+//! `iconv.h` implementation.
+//!
+//! See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/iconv.h.html>.
+//!
+//! Every conversion decodes one character from the source set and encodes it
+//! in the target set. The target name may end in `//TRANSLIT`, to replace
+//! characters it cannot represent with look-alikes or `?`, and `//IGNORE`, to
+//! skip those characters and invalid input instead. Both count towards the
+//! number of irreversible conversions `iconv` returns.
+
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use alloc::boxed::Box;
+use core::{ffi::CStr, slice, str};
+
+use crate::{
+    header::{
+        errno::{E2BIG, EBADF, EILSEQ, EINVAL},
+        locale,
+    },
+    platform::{self, types::*},
+};
+
+use self::charset::{Charset, DecodeError, EncodeError};
+
+mod charset;
+mod tables;
+
+/// Conversion descriptor returned by [`iconv_open`].
+#[allow(non_camel_case_types)]
+pub type iconv_t = *mut c_void;
+
+/// Replacements for `//TRANSLIT` beyond the accented letters in
+/// [`tables::LATIN_BASE`].
+static TRANSLIT: &[(char, &str)] = &[
+    ('\u{a0}', " "),
+    ('©', "(C)"),
+    ('«', "<<"),
+    ('®', "(R)"),
+    ('»', ">>"),
+    ('Æ', "AE"),
+    ('×', "x"),
+    ('Ø', "O"),
+    ('ß', "ss"),
+    ('æ', "ae"),
+    ('ø', "o"),
+    ('Đ', "D"),
+    ('đ', "d"),
+    ('Ł', "L"),
+    ('ł', "l"),
+    ('Œ', "OE"),
+    ('œ', "oe"),
+    ('\u{2010}', "-"),
+    ('\u{2013}', "-"),
+    ('\u{2014}', "-"),
+    ('‘', "'"),
+    ('’', "'"),
+    ('‚', "'"),
+    ('“', "\""),
+    ('”', "\""),
+    ('„', "\""),
+    ('•', "o"),
+    ('…', "..."),
+    ('‹', "<"),
+    ('›', ">"),
+    ('€', "EUR"),
+    ('™', "(TM)"),
+];
+
+/// Finds a replacement for `c` made of more common characters, using `buf`
+/// for single letters.
+fn transliterate(c: char, buf: &mut [u8; 1]) -> Option<&str> {
+    if let Some(&(_, replacement)) = TRANSLIT.iter().find(|&&(from, _)| from == c) {
+        return Some(replacement);
+    }
+    buf[0] = *tables::LATIN_BASE.get((c as usize).checked_sub(0xc0)?)?;
+    if buf[0] == 0 {
+        return None;
+    }
+    str::from_utf8(buf).ok()
+}
+
+struct Converter {
+    from: Charset,
+    to: Charset,
+    /// The character sets as opened, to return to on reset.
+    initial: (Charset, Charset),
+    translit: bool,
+    ignore: bool,
+}
+
+/// How far a call to [`Converter::convert`] got.
+#[derive(Default)]
+struct Progress {
+    read: usize,
+    written: usize,
+    irreversible: usize,
+}
+
+impl Converter {
+    fn convert(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        progress: &mut Progress,
+    ) -> Result<(), c_int> {
+        while progress.read < input.len() {
+            let rest = &input[progress.read..];
+            match self.from.detect_order(rest) {
+                Ok(0) => (),
+                Ok(bom) => {
+                    progress.read += bom;
+                    continue;
+                }
+                Err(_) => return Err(EINVAL),
+            }
+
+            let (c, len) = match self.from.decode(rest) {
+                Ok(decoded) => decoded,
+                Err(DecodeError::Incomplete) => return Err(EINVAL),
+                Err(DecodeError::Invalid(skip)) if self.ignore => {
+                    progress.read += skip.min(rest.len());
+                    progress.irreversible += 1;
+                    continue;
+                }
+                Err(DecodeError::Invalid(_)) => return Err(EILSEQ),
+            };
+
+            let mut to = self.to;
+            let bom = to.choose_order().unwrap_or(&[]);
+            let out = &mut output[progress.written..];
+            if out.len() < bom.len() {
+                return Err(E2BIG);
+            }
+            out[..bom.len()].copy_from_slice(bom);
+            let out = &mut out[bom.len()..];
+
+            let written = match to.encode(c, out) {
+                Ok(written) => written,
+                Err(EncodeError::TooBig) => return Err(E2BIG),
+                Err(EncodeError::Unrepresentable) => {
+                    let written = self.substitute(to, c, out)?;
+                    progress.irreversible += 1;
+                    written
+                }
+            };
+            self.to = to;
+            progress.written += bom.len() + written;
+            progress.read += len;
+        }
+        Ok(())
+    }
+
+    /// Handles a character that `to` cannot represent, as allowed by
+    /// `//TRANSLIT` and `//IGNORE`. Returns the number of bytes written.
+    fn substitute(&self, to: Charset, c: char, output: &mut [u8]) -> Result<usize, c_int> {
+        if self.translit {
+            let mut buf = [0];
+            let replacements = [transliterate(c, &mut buf), Some("?")];
+            'replacements: for replacement in replacements.into_iter().flatten() {
+                let mut written = 0;
+                for c in replacement.chars() {
+                    match to.encode(c, &mut output[written..]) {
+                        Ok(len) => written += len,
+                        Err(EncodeError::TooBig) => return Err(E2BIG),
+                        Err(EncodeError::Unrepresentable) => continue 'replacements,
+                    }
+                }
+                return Ok(written);
+            }
+        }
+        if self.ignore { Ok(0) } else { Err(EILSEQ) }
+    }
+}
+
+/// Looks up a character set name, where the empty name stands for the
+/// codeset of the current locale.
+fn find_charset(name: &[u8]) -> Option<Charset> {
+    if name.is_empty() || name.eq_ignore_ascii_case(b"char") {
+        return Charset::find(locale::current().codeset().to_bytes());
+    }
+    Charset::find(name)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iconv.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iconv(
+    cd: iconv_t,
+    inbuf: *mut *mut c_char,
+    inbytesleft: *mut size_t,
+    outbuf: *mut *mut c_char,
+    outbytesleft: *mut size_t,
+) -> size_t {
+    if cd.is_null() || cd == usize::MAX as iconv_t {
+        platform::ERRNO.set(EBADF);
+        return usize::MAX;
+    }
+    let conv = unsafe { &mut *(cd as *mut Converter) };
+
+    // Return to the initial state. None of our character sets need a shift
+    // sequence to get there.
+    if inbuf.is_null() || unsafe { *inbuf }.is_null() {
+        (conv.from, conv.to) = conv.initial;
+        return 0;
+    }
+
+    let input = unsafe { slice::from_raw_parts(*inbuf as *const u8, *inbytesleft) };
+    let output = if outbuf.is_null() || unsafe { *outbuf }.is_null() {
+        <&mut [u8]>::default()
+    } else {
+        unsafe { slice::from_raw_parts_mut(*outbuf as *mut u8, *outbytesleft) }
+    };
+
+    let mut progress = Progress::default();
+    let result = conv.convert(input, output, &mut progress);
+
+    unsafe {
+        *inbuf = (*inbuf).add(progress.read);
+        *inbytesleft -= progress.read;
+        if !output.is_empty() {
+            *outbuf = (*outbuf).add(progress.written);
+            *outbytesleft -= progress.written;
+        }
+    }
+
+    match result {
+        Ok(()) => progress.irreversible,
+        Err(err) => {
+            platform::ERRNO.set(err);
+            usize::MAX
+        }
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iconv_close.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iconv_close(cd: iconv_t) -> c_int {
+    if cd.is_null() || cd == usize::MAX as iconv_t {
+        platform::ERRNO.set(EBADF);
+        return -1;
+    }
+    drop(unsafe { Box::from_raw(cd as *mut Converter) });
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iconv_open.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iconv_open(tocode: *const c_char, fromcode: *const c_char) -> iconv_t {
+    let tocode = unsafe { CStr::from_ptr(tocode) }.to_bytes();
+    let fromcode = unsafe { CStr::from_ptr(fromcode) }.to_bytes();
+
+    // Suffixes on the source name are accepted, but mean nothing
+    let mut to_parts = tocode.split(|&b| b == b'/').filter(|part| !part.is_empty());
+    let to_name = if tocode.starts_with(b"/") {
+        &[][..]
+    } else {
+        to_parts.next().unwrap_or(&[])
+    };
+    let from_name = fromcode.split(|&b| b == b'/').next().unwrap_or(&[]);
+
+    let (Some(to), Some(from)) = (find_charset(to_name), find_charset(from_name)) else {
+        platform::ERRNO.set(EINVAL);
+        return usize::MAX as iconv_t;
+    };
+
+    let mut conv = Converter {
+        from,
+        to,
+        initial: (from, to),
+        translit: false,
+        ignore: false,
+    };
+    for suffix in to_parts {
+        if suffix.eq_ignore_ascii_case(b"TRANSLIT") {
+            conv.translit = true;
+        } else if suffix.eq_ignore_ascii_case(b"IGNORE") {
+            conv.ignore = true;
+        }
+    }
+
+    Box::into_raw(Box::new(conv)) as iconv_t
+}
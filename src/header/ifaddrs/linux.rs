@@ -0,0 +1,287 @@
+//! Interfaces from a netlink dump of the links and addresses of the kernel,
+//! see <https://man7.org/linux/man-pages/man7/rtnetlink.7.html>.
+
+use alloc::vec::Vec;
+use core::{iter, net::IpAddr, ptr};
+
+use crate::{
+    error::{Errno, Result},
+    header::{
+        errno::EIO,
+        sys_socket::constants::{AF_INET, AF_INET6, AF_NETLINK, AF_UNSPEC, SOCK_CLOEXEC, SOCK_RAW},
+    },
+    platform::{Pal, Sys, types::*},
+};
+
+use super::{Address, Interface, Link};
+
+const NETLINK_ROUTE: c_int = 0;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const RTM_NEWLINK: u16 = 16;
+const RTM_GETLINK: u16 = 18;
+const RTM_NEWADDR: u16 = 20;
+const RTM_GETADDR: u16 = 22;
+
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+
+const IFLA_ADDRESS: u16 = 1;
+const IFLA_BROADCAST: u16 = 2;
+const IFLA_IFNAME: u16 = 3;
+
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+const IFA_BROADCAST: u16 = 4;
+
+/// The size of `struct nlmsghdr`.
+const NLMSG_HDRLEN: usize = 16;
+/// The size of `struct ifinfomsg`.
+const IFINFOMSG_LEN: usize = 16;
+/// The size of `struct ifaddrmsg`.
+const IFADDRMSG_LEN: usize = 8;
+
+/// Messages and attributes start at multiples of 4 bytes.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn u16_at(data: &[u8], at: usize) -> u16 {
+    u16::from_ne_bytes([data[at], data[at + 1]])
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_ne_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// Closes the socket when dropped.
+struct Socket(c_int);
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        let _ = Sys::close(self.0);
+    }
+}
+
+/// Asks for a dump of `kind`, and passes the type and payload of each
+/// message of the answer to `handle`.
+fn dump(sock: &Socket, kind: u16, seq: u32, mut handle: impl FnMut(u16, &[u8])) -> Result<()> {
+    // A header, and a `struct rtgenmsg` for all families
+    let mut request = [0u8; NLMSG_HDRLEN + 4];
+    request[0..4].copy_from_slice(&(NLMSG_HDRLEN as u32 + 4).to_ne_bytes());
+    request[4..6].copy_from_slice(&kind.to_ne_bytes());
+    request[6..8].copy_from_slice(&(NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    request[8..12].copy_from_slice(&seq.to_ne_bytes());
+    request[NLMSG_HDRLEN] = AF_UNSPEC as u8;
+    // Without an address, the request goes to the kernel
+    unsafe {
+        Sys::sendto(
+            sock.0,
+            request.as_ptr() as *const c_void,
+            request.len(),
+            0,
+            ptr::null(),
+            0,
+        )
+    }?;
+
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let len = unsafe {
+            Sys::recvfrom(
+                sock.0,
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len(),
+                0,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        }?;
+        if len == 0 {
+            return Err(Errno(EIO));
+        }
+        let mut data = &buf[..len];
+        while data.len() >= NLMSG_HDRLEN {
+            let msg_len = u32_at(data, 0) as usize;
+            if msg_len < NLMSG_HDRLEN || msg_len > data.len() {
+                return Err(Errno(EIO));
+            }
+            let payload = &data[NLMSG_HDRLEN..msg_len];
+            if u32_at(data, 8) == seq {
+                match u16_at(data, 4) {
+                    NLMSG_DONE => return Ok(()),
+                    NLMSG_ERROR => {
+                        // A negative errno, or 0 for an acknowledgement
+                        return match payload.get(..4).map(|err| u32_at(err, 0) as i32) {
+                            Some(0) => Ok(()),
+                            Some(err) => Err(Errno(-err)),
+                            None => Err(Errno(EIO)),
+                        };
+                    }
+                    msg_type => handle(msg_type, payload),
+                }
+            }
+            data = &data[align(msg_len).min(data.len())..];
+        }
+    }
+}
+
+/// The type and payload of each attribute in `data`.
+fn attributes(mut data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    iter::from_fn(move || {
+        if data.len() < 4 {
+            return None;
+        }
+        let len = u16_at(data, 0) as usize;
+        if len < 4 || len > data.len() {
+            return None;
+        }
+        // Without the nested and byte order flags
+        let attribute = (u16_at(data, 2) & 0x3fff, &data[4..len]);
+        data = &data[align(len).min(data.len())..];
+        Some(attribute)
+    })
+}
+
+/// Strings in attributes end with a nul.
+fn attribute_string(value: &[u8]) -> Vec<u8> {
+    value.split(|&c| c == 0).next().unwrap_or(&[]).to_vec()
+}
+
+/// Reads the payload of an `RTM_NEWLINK` message.
+fn parse_link(payload: &[u8]) -> Option<Interface> {
+    if payload.len() < IFINFOMSG_LEN {
+        return None;
+    }
+    let mut name = None;
+    let mut address = Vec::new();
+    let mut broadcast = None;
+    for (kind, value) in attributes(&payload[IFINFOMSG_LEN..]) {
+        match kind {
+            IFLA_IFNAME => name = Some(attribute_string(value)),
+            IFLA_ADDRESS => address = value.to_vec(),
+            IFLA_BROADCAST => broadcast = Some(value.to_vec()),
+            _ => (),
+        }
+    }
+    Some(Interface {
+        index: u32_at(payload, 4),
+        name: name?,
+        flags: u32_at(payload, 8),
+        link: Some(Link {
+            hatype: u16_at(payload, 2),
+            address,
+            broadcast,
+        }),
+        addrs: Vec::new(),
+    })
+}
+
+/// Reads the payload of an `RTM_NEWADDR` message, returning the index of
+/// the interface with the address.
+fn parse_address(payload: &[u8]) -> Option<(c_uint, Address)> {
+    if payload.len() < IFADDRMSG_LEN {
+        return None;
+    }
+    let family = c_int::from(payload[0]);
+    let ip = |value: &[u8]| match family {
+        AF_INET => <[u8; 4]>::try_from(value).ok().map(IpAddr::from),
+        AF_INET6 => <[u8; 16]>::try_from(value).ok().map(IpAddr::from),
+        _ => None,
+    };
+
+    let mut address = None;
+    let mut local = None;
+    let mut broadcast = None;
+    let mut label = None;
+    for (kind, value) in attributes(&payload[IFADDRMSG_LEN..]) {
+        match kind {
+            IFA_ADDRESS => address = ip(value),
+            IFA_LOCAL => local = ip(value),
+            IFA_BROADCAST => broadcast = ip(value),
+            IFA_LABEL => label = Some(attribute_string(value)),
+            _ => (),
+        }
+    }
+    let addr = local.or(address)?;
+    // With both, the other one is the other end of a point-to-point link
+    let destination = address.filter(|&address| local.is_some() && address != addr);
+    let address = Address {
+        addr,
+        prefix_len: payload[1],
+        broadcast,
+        destination,
+        label,
+    };
+    Some((u32_at(payload, 4), address))
+}
+
+pub fn interfaces() -> Result<Vec<Interface>> {
+    let sock = Socket(unsafe { Sys::socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE) }?);
+
+    let mut interfaces = Vec::new();
+    dump(&sock, RTM_GETLINK, 1, |msg_type, payload| {
+        if msg_type == RTM_NEWLINK {
+            interfaces.extend(parse_link(payload));
+        }
+    })?;
+    dump(&sock, RTM_GETADDR, 2, |msg_type, payload| {
+        if msg_type != RTM_NEWADDR {
+            return;
+        }
+        if let Some((index, address)) = parse_address(payload) {
+            let interface = interfaces
+                .iter_mut()
+                .find(|interface| interface.index == index);
+            if let Some(interface) = interface {
+                // Labels that are just the name of the interface say nothing
+                let address = match &address.label {
+                    Some(label) if *label == interface.name => Address {
+                        label: None,
+                        ..address
+                    },
+                    _ => address,
+                };
+                interface.addrs.push(address);
+            }
+        }
+    })?;
+    interfaces.sort_by_key(|interface| interface.index);
+    Ok(interfaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::IpAddr;
+
+    use super::{IFADDRMSG_LEN, parse_address};
+
+    fn attribute(kind: u16, value: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(4 + value.len() as u16).to_ne_bytes());
+        data.extend_from_slice(&kind.to_ne_bytes());
+        data.extend_from_slice(value);
+        data.resize((data.len() + 3) & !3, 0);
+        data
+    }
+
+    #[test]
+    fn point_to_point() {
+        // AF_INET, /32, on interface 3
+        let mut payload = vec![2, 32, 0, 0];
+        payload.extend_from_slice(&3u32.to_ne_bytes());
+        assert_eq!(IFADDRMSG_LEN, payload.len());
+        payload.extend(attribute(1, &[10, 0, 0, 2]));
+        payload.extend(attribute(2, &[10, 0, 0, 1]));
+        payload.extend(attribute(3, b"tun0\0"));
+
+        let (index, address) = parse_address(&payload).unwrap();
+        assert_eq!(3, index);
+        assert_eq!("10.0.0.1".parse::<IpAddr>().unwrap(), address.addr);
+        assert_eq!(Some("10.0.0.2".parse().unwrap()), address.destination);
+        assert_eq!(32, address.prefix_len);
+        assert_eq!(Some(b"tun0".to_vec()), address.label);
+    }
+}
@@ -0,0 +1,272 @@
+//! `ifaddrs.h` implementation.
+//!
+//! Non-POSIX, see <https://man7.org/linux/man-pages/man3/getifaddrs.3.html>.
+
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ptr,
+};
+
+use crate::{
+    error::Result,
+    header::{
+        net_if::IF_NAMESIZE,
+        netdb::SockAddr,
+        netinet_in::sockaddr_in6,
+        sys_socket::{constants::AF_PACKET, sa_family_t, sockaddr, sockaddr_storage},
+    },
+    platform::types::*,
+};
+
+#[cfg(target_os = "linux")]
+#[path = "linux.rs"]
+mod sys;
+
+#[cfg(target_os = "redox")]
+#[path = "redox.rs"]
+mod sys;
+
+/// The link layer of an interface.
+pub(crate) struct Link {
+    /// An `ARPHRD_*` hardware type.
+    pub hatype: u16,
+    pub address: Vec<u8>,
+    pub broadcast: Option<Vec<u8>>,
+}
+
+/// An address of an interface.
+pub(crate) struct Address {
+    pub addr: IpAddr,
+    pub prefix_len: u8,
+    pub broadcast: Option<IpAddr>,
+    /// The other end of a point-to-point link.
+    pub destination: Option<IpAddr>,
+    /// The name that the address goes by, if it is not the one of the
+    /// interface, as for the aliases of Linux.
+    pub label: Option<Vec<u8>>,
+}
+
+/// A network interface, with the addresses on it.
+pub(crate) struct Interface {
+    pub index: c_uint,
+    pub name: Vec<u8>,
+    /// `IFF_*` flags.
+    pub flags: c_uint,
+    pub link: Option<Link>,
+    pub addrs: Vec<Address>,
+}
+
+/// Lists the network interfaces of the system, in the order of their
+/// indexes.
+pub(crate) fn interfaces() -> Result<Vec<Interface>> {
+    sys::interfaces()
+}
+
+/// See <https://man7.org/linux/man-pages/man3/getifaddrs.3.html>.
+#[repr(C)]
+pub struct ifaddrs {
+    pub ifa_next: *mut ifaddrs,
+    pub ifa_name: *mut c_char,
+    pub ifa_flags: c_uint,
+    pub ifa_addr: *mut sockaddr,
+    pub ifa_netmask: *mut sockaddr,
+    pub ifa_ifu: __ifa_ifu,
+    pub ifa_data: *mut c_void,
+}
+
+/// The broadcast address of an entry of [`ifaddrs`] with `IFF_BROADCAST`,
+/// or else the destination address of one with `IFF_POINTOPOINT`.
+#[repr(C)]
+pub union __ifa_ifu {
+    pub ifu_broadaddr: *mut sockaddr,
+    pub ifu_dstaddr: *mut sockaddr,
+}
+
+/// The link-layer address of an `AF_PACKET` entry, as in
+/// `<netpacket/packet.h>`.
+#[repr(C)]
+struct sockaddr_ll {
+    sll_family: c_ushort,
+    sll_protocol: c_ushort,
+    sll_ifindex: c_int,
+    sll_hatype: c_ushort,
+    sll_pkttype: c_uchar,
+    sll_halen: c_uchar,
+    sll_addr: [c_uchar; 8],
+}
+
+/// An entry of the list with room for its name and addresses, so that each
+/// one is a single allocation. `ifaddrs` comes first, for the pointers to
+/// it to be pointers to the entry.
+#[repr(C)]
+struct Entry {
+    ifaddrs: ifaddrs,
+    name: [c_char; IF_NAMESIZE],
+    addr: sockaddr_storage,
+    netmask: sockaddr_storage,
+    ifu: sockaddr_storage,
+}
+
+impl Entry {
+    fn new(name: &[u8], flags: c_uint) -> Box<Self> {
+        // All null pointers and empty addresses
+        let mut entry: Box<Self> = Box::new(unsafe { mem::zeroed() });
+        // Truncated to fit, with the nul left in place
+        let name = &name[..name.len().min(IF_NAMESIZE - 1)];
+        for (dst, src) in entry.name.iter_mut().zip(name) {
+            *dst = *src as c_char;
+        }
+        entry.ifaddrs.ifa_name = entry.name.as_mut_ptr();
+        entry.ifaddrs.ifa_flags = flags;
+        entry
+    }
+
+    fn link(interface: &Interface, link: &Link) -> Box<Self> {
+        let mut entry = Self::new(&interface.name, interface.flags);
+        entry.ifaddrs.ifa_addr = store_link(&mut entry.addr, interface.index, link, &link.address);
+        if let Some(broadcast) = &link.broadcast {
+            entry.ifaddrs.ifa_ifu.ifu_broadaddr =
+                store_link(&mut entry.ifu, interface.index, link, broadcast);
+        }
+        entry
+    }
+
+    fn address(interface: &Interface, address: &Address) -> Box<Self> {
+        let name = address.label.as_deref().unwrap_or(&interface.name);
+        let mut entry = Self::new(name, interface.flags);
+        let scope_id = interface.index;
+        entry.ifaddrs.ifa_addr = store_ip(&mut entry.addr, address.addr, scope_id);
+        let netmask = netmask(address.addr, address.prefix_len);
+        entry.ifaddrs.ifa_netmask = store_ip(&mut entry.netmask, netmask, 0);
+        if let Some(ifu) = address.destination.or(address.broadcast) {
+            entry.ifaddrs.ifa_ifu.ifu_broadaddr = store_ip(&mut entry.ifu, ifu, scope_id);
+        }
+        entry
+    }
+}
+
+/// The netmask of a prefix of `prefix_len` bits, for addresses like `addr`.
+fn netmask(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(_) => {
+            let bits = u32::MAX.checked_shl(32 - u32::from(prefix_len.min(32)));
+            IpAddr::V4(Ipv4Addr::from(bits.unwrap_or(0)))
+        }
+        IpAddr::V6(_) => {
+            let bits = u128::MAX.checked_shl(128 - u32::from(prefix_len.min(128)));
+            IpAddr::V6(Ipv6Addr::from(bits.unwrap_or(0)))
+        }
+    }
+}
+
+/// Writes `addr` into `storage`, with the scope of link-local IPv6
+/// addresses set to `scope_id`.
+fn store_ip(storage: &mut sockaddr_storage, addr: IpAddr, scope_id: c_uint) -> *mut sockaddr {
+    let sockaddr = SockAddr::new(addr, 0);
+    let dst = storage as *mut sockaddr_storage as *mut sockaddr;
+    unsafe {
+        ptr::copy_nonoverlapping(
+            sockaddr.as_ptr() as *const u8,
+            dst as *mut u8,
+            sockaddr.len() as usize,
+        );
+    }
+    if let IpAddr::V6(addr) = addr {
+        if addr.is_unicast_link_local() {
+            unsafe { (*(dst as *mut sockaddr_in6)).sin6_scope_id = scope_id };
+        }
+    }
+    dst
+}
+
+/// Writes the link-layer `address` of `link` into `storage`.
+fn store_link(
+    storage: &mut sockaddr_storage,
+    index: c_uint,
+    link: &Link,
+    address: &[u8],
+) -> *mut sockaddr {
+    let dst = storage as *mut sockaddr_storage as *mut sockaddr_ll;
+    let sll = unsafe { &mut *dst };
+    sll.sll_family = AF_PACKET as sa_family_t;
+    sll.sll_ifindex = index as c_int;
+    sll.sll_hatype = link.hatype;
+    let len = address.len().min(sll.sll_addr.len());
+    sll.sll_halen = len as c_uchar;
+    sll.sll_addr[..len].copy_from_slice(&address[..len]);
+    dst as *mut sockaddr
+}
+
+/// See <https://man7.org/linux/man-pages/man3/getifaddrs.3.html>.
+///
+/// Lists an `AF_PACKET` entry for the link layer of each interface that has
+/// one, followed by an entry for each of its addresses. `ifa_data` is always
+/// null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getifaddrs(ifap: *mut *mut ifaddrs) -> c_int {
+    let interfaces = match interfaces() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            err.sync();
+            return -1;
+        }
+    };
+
+    let mut entries = Vec::new();
+    for interface in &interfaces {
+        if let Some(link) = &interface.link {
+            entries.push(Entry::link(interface, link));
+        }
+        for address in &interface.addrs {
+            entries.push(Entry::address(interface, address));
+        }
+    }
+
+    // Linked from the back, so that each entry points to the next one
+    let mut next: *mut ifaddrs = ptr::null_mut();
+    for mut entry in entries.into_iter().rev() {
+        entry.ifaddrs.ifa_next = next;
+        next = Box::into_raw(entry) as *mut ifaddrs;
+    }
+    unsafe { *ifap = next };
+    0
+}
+
+/// See <https://man7.org/linux/man-pages/man3/freeifaddrs.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freeifaddrs(ifa: *mut ifaddrs) {
+    let mut ifa = ifa;
+    while !ifa.is_null() {
+        // SAFETY: every entry was allocated as an `Entry` by `getifaddrs`
+        let entry = unsafe { Box::from_raw(ifa as *mut Entry) };
+        ifa = entry.ifaddrs.ifa_next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::IpAddr;
+
+    use super::netmask;
+
+    #[test]
+    fn netmasks() {
+        let v4: IpAddr = "10.1.2.3".parse().unwrap();
+        assert_eq!("255.255.255.0".parse::<IpAddr>().unwrap(), netmask(v4, 24));
+        assert_eq!("0.0.0.0".parse::<IpAddr>().unwrap(), netmask(v4, 0));
+        assert_eq!(
+            "255.255.255.255".parse::<IpAddr>().unwrap(),
+            netmask(v4, 32)
+        );
+        let v6: IpAddr = "fe80::1".parse().unwrap();
+        assert_eq!(
+            "ffff:ffff:ffff:ffff::".parse::<IpAddr>().unwrap(),
+            netmask(v6, 64)
+        );
+        assert_eq!("::".parse::<IpAddr>().unwrap(), netmask(v6, 0));
+    }
+}
@@ -0,0 +1,115 @@
+//! Interfaces from the `netcfg` scheme of the network stack, where
+//! `ifaces/<name>/mac` holds the MAC address of an interface and
+//! `ifaces/<name>/addr/list` its addresses, one `<address>/<prefix>` a line.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::net::{IpAddr, Ipv4Addr};
+
+use crate::{
+    c_str::{CStr, CString},
+    error::{Errno, Result},
+    fs::File,
+    header::{
+        dirent::{closedir, opendir, readdir},
+        fcntl::O_RDONLY,
+        net_if::{IFF_BROADCAST, IFF_LOOPBACK, IFF_MULTICAST, IFF_RUNNING, IFF_UP},
+    },
+    io::Read,
+    platform::{self, types::*},
+};
+
+use super::{Address, Interface, Link};
+
+/// The `ARPHRD_*` hardware type of Ethernet.
+const ARPHRD_ETHER: u16 = 1;
+
+/// Reads `file` in the directory of the interface `name`.
+fn read(name: &str, file: &str) -> Option<String> {
+    let path = CString::new(format!("/scheme/netcfg/ifaces/{}/{}", name, file)).ok()?;
+    let mut file = File::open(CStr::borrow(&path), O_RDONLY).ok()?;
+    let mut string = String::new();
+    file.read_to_string(&mut string).ok()?;
+    Some(string)
+}
+
+/// Parses a line of `addr/list`.
+fn parse_address(line: &str) -> Option<Address> {
+    let (addr, prefix_len) = line.trim().split_once('/')?;
+    let addr: IpAddr = addr.parse().ok()?;
+    let prefix_len: u8 = prefix_len.parse().ok()?;
+    let broadcast = match addr {
+        IpAddr::V4(v4) if !v4.is_loopback() && prefix_len < 32 => {
+            let host_bits = u32::MAX.checked_shr(u32::from(prefix_len)).unwrap_or(0);
+            Some(IpAddr::V4(Ipv4Addr::from(u32::from(v4) | host_bits)))
+        }
+        _ => None,
+    };
+    Some(Address {
+        addr,
+        prefix_len,
+        broadcast,
+        destination: None,
+        label: None,
+    })
+}
+
+fn interface(index: c_uint, name: String) -> Interface {
+    // Written as in `52-54-00-12-34-56`
+    let mac: Option<Vec<u8>> = read(&name, "mac").and_then(|mac| {
+        mac.trim()
+            .split(['-', ':'])
+            .map(|byte| u8::from_str_radix(byte, 16).ok())
+            .collect()
+    });
+    let addrs: Vec<Address> = read(&name, "addr/list")
+        .map(|list| list.lines().filter_map(parse_address).collect())
+        .unwrap_or_default();
+
+    // The network stack has no notion of an interface that is down
+    let mut flags = (IFF_UP | IFF_RUNNING) as c_uint;
+    if addrs.iter().any(|address| address.addr.is_loopback()) {
+        flags |= IFF_LOOPBACK as c_uint;
+    } else {
+        flags |= (IFF_BROADCAST | IFF_MULTICAST) as c_uint;
+    }
+    let link = mac.map(|address| Link {
+        hatype: ARPHRD_ETHER,
+        broadcast: Some(vec![0xff; address.len()]),
+        address,
+    });
+    Interface {
+        index,
+        name: name.into_bytes(),
+        flags,
+        link,
+        addrs,
+    }
+}
+
+/// Interfaces get indexes in the order of the directory, as the scheme has
+/// none of its own.
+pub fn interfaces() -> Result<Vec<Interface>> {
+    let dir = unsafe { opendir(c"/scheme/netcfg/ifaces".as_ptr()) };
+    if dir.is_null() {
+        return Err(Errno(platform::ERRNO.get()));
+    }
+    let mut names = Vec::new();
+    loop {
+        let entry = readdir(unsafe { &mut *dir });
+        if entry.is_null() {
+            break;
+        }
+        let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+        match name.to_str() {
+            Ok(".") | Ok("..") | Err(_) => (),
+            Ok(name) => names.push(String::from(name)),
+        }
+    }
+    closedir(unsafe { Box::from_raw(dir) });
+
+    Ok(names
+        .into_iter()
+        .zip(1..)
+        .map(|(name, index)| interface(index, name))
+        .collect())
+}
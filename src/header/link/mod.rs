@@ -0,0 +1,170 @@
+//! `link.h` implementation.
+//!
+//! Non-POSIX, see <https://www.man7.org/linux/man-pages/man3/dl_iterate_phdr.3.html>.
+
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use alloc::vec::Vec;
+use core::{mem, ptr};
+
+use crate::{
+    header::elf,
+    ld_so::{linker::PhdrInfo, tcb::Tcb},
+    platform::{self, auxv_defs, types::*},
+};
+
+#[cfg(target_pointer_width = "32")]
+type Phdr = elf::Elf32_Phdr;
+#[cfg(target_pointer_width = "64")]
+type Phdr = elf::Elf64_Phdr;
+
+/// Entry in the dynamic linker's list of loaded objects, shared with
+/// debuggers through `_r_debug`. This is the same format used in SVR4.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct link_map {
+    /// Difference between the address in the ELF file and the address in
+    /// memory.
+    pub l_addr: usize,
+    /// Absolute file name the object was found in.
+    pub l_name: *const c_char,
+    /// Dynamic section of the shared object.
+    pub l_ld: usize,
+    pub l_next: *mut link_map,
+    pub l_prev: *mut link_map,
+}
+
+#[cfg(target_pointer_width = "32")]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct dl_phdr_info {
+    pub dlpi_addr: elf::Elf32_Addr,
+    pub dlpi_name: *const c_char,
+    pub dlpi_phdr: *const elf::Elf32_Phdr,
+    pub dlpi_phnum: elf::Elf32_Half,
+    pub dlpi_adds: c_ulonglong,
+    pub dlpi_subs: c_ulonglong,
+    pub dlpi_tls_modid: size_t,
+    pub dlpi_tls_data: *mut c_void,
+}
+
+#[cfg(target_pointer_width = "64")]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct dl_phdr_info {
+    pub dlpi_addr: elf::Elf64_Addr,
+    pub dlpi_name: *const c_char,
+    pub dlpi_phdr: *const elf::Elf64_Phdr,
+    pub dlpi_phnum: elf::Elf64_Half,
+    pub dlpi_adds: c_ulonglong,
+    pub dlpi_subs: c_ulonglong,
+    pub dlpi_tls_modid: size_t,
+    pub dlpi_tls_data: *mut c_void,
+}
+
+impl From<PhdrInfo> for dl_phdr_info {
+    fn from(info: PhdrInfo) -> Self {
+        Self {
+            dlpi_addr: info.addr as _,
+            dlpi_name: info.name,
+            dlpi_phdr: info.phdr.cast(),
+            dlpi_phnum: info.phnum as _,
+            dlpi_adds: info.adds,
+            dlpi_subs: info.subs,
+            dlpi_tls_modid: info.tls_modid,
+            dlpi_tls_data: tls_data(info.tls_modid),
+        }
+    }
+}
+
+/// Returns the calling thread's TLS block for module `modid`, or null if the
+/// thread has not allocated it yet.
+fn tls_data(modid: usize) -> *mut c_void {
+    let Some(tcb) = (unsafe { Tcb::current() }) else {
+        return ptr::null_mut();
+    };
+    let Some(masters) = tcb.masters() else {
+        return ptr::null_mut();
+    };
+    if modid == 0 || modid > masters.len() {
+        return ptr::null_mut();
+    }
+
+    if tcb.dtv_mut().len() < masters.len() {
+        tcb.setup_dtv(masters.len());
+    }
+    tcb.dtv_mut()[modid - 1].cast()
+}
+
+/// Describes the main program of a statically linked executable, using the
+/// program headers the kernel passed in the auxiliary vector.
+fn static_phdr_info() -> Option<PhdrInfo> {
+    let auxvs = unsafe { platform::AUXVS.unsafe_ref() }.as_ref()?;
+    let phdr = platform::get_auxv(auxvs, auxv_defs::AT_PHDR)?;
+    let phnum = platform::get_auxv(auxvs, auxv_defs::AT_PHNUM)?;
+
+    let headers = unsafe { core::slice::from_raw_parts(phdr as *const Phdr, phnum) };
+
+    // A static PIE is loaded at an arbitrary address, which PT_PHDR lets us
+    // recover.
+    let addr = headers
+        .iter()
+        .find(|ph| ph.p_type as usize == elf::PT_PHDR)
+        .map_or(0, |ph| phdr - ph.p_vaddr as usize);
+    let tls_modid = headers.iter().any(|ph| ph.p_type as usize == elf::PT_TLS) as usize;
+
+    Some(PhdrInfo {
+        addr,
+        name: c"".as_ptr(),
+        phdr: phdr as *const c_void,
+        phnum,
+        adds: 1,
+        subs: 0,
+        tls_modid,
+    })
+}
+
+/// See <https://www.man7.org/linux/man-pages/man3/dl_iterate_phdr.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dl_iterate_phdr(
+    callback: Option<unsafe extern "C" fn(*mut dl_phdr_info, size_t, *mut c_void) -> c_int>,
+    data: *mut c_void,
+) -> c_int {
+    let Some(callback) = callback else {
+        return 0;
+    };
+
+    let mut objects = Vec::new();
+    let linker_ptr = unsafe { Tcb::current() }
+        .map(|tcb| tcb.linker_ptr)
+        .filter(|linker_ptr| !linker_ptr.is_null());
+    match linker_ptr {
+        Some(linker_ptr) => {
+            let mut linker = unsafe { (*linker_ptr).lock() };
+            let cbs_c = linker.cbs.clone();
+            let cbs = cbs_c.borrow();
+            // Take a snapshot so the linker is not locked while the callback
+            // runs, as it may well call dladdr() or dlopen(). Objects closed
+            // meanwhile stay loaded until end_iterate_phdr.
+            (cbs.iterate_phdr)(&mut linker, &mut |info| objects.push(info));
+        }
+        None => objects.extend(static_phdr_info()),
+    }
+
+    let mut ret = 0;
+    for info in objects {
+        let mut info = dl_phdr_info::from(info);
+        ret = unsafe { callback(&mut info, mem::size_of::<dl_phdr_info>(), data) };
+        if ret != 0 {
+            break;
+        }
+    }
+
+    if let Some(linker_ptr) = linker_ptr {
+        let mut linker = unsafe { (*linker_ptr).lock() };
+        let cbs_c = linker.cbs.clone();
+        let cbs = cbs_c.borrow();
+        (cbs.end_iterate_phdr)(&mut linker);
+    }
+    ret
+}
@@ -0,0 +1,535 @@
+//! Locale definitions, either built in or loaded from disk.
+//!
+//! A locale called `name` is read from `$LOCPATH/name`, or from
+//! `/usr/share/relibc/locale/name` when `LOCPATH` is not set. Files use a
+//! subset of the POSIX `localedef` source format (see XBD 7.3):
+//!
+//! ```text
+//! # comment
+//! LC_NUMERIC
+//! decimal_point ","
+//! thousands_sep "."
+//! grouping      3;3
+//! END LC_NUMERIC
+//!
+//! LC_TIME
+//! abday "So";"Mo";"Di";"Mi";"Do";"Fr";"Sa"
+//! END LC_TIME
+//! ```
+//!
+//! Strings may use `<Uxxxx>` to name a Unicode code point, and `\` to escape
+//! the next character. Lines ending in `\` continue on the next line.
+//! Categories or keywords that are left out keep their "C" locale values, and
+//! unknown keywords are ignored. `LC_CTYPE` only understands `codeset`, which
+//! is UTF-8, ASCII or ISO-8859-1. Strings are stored in that codeset, and a
+//! locale using characters it cannot represent is rejected.
+
+use alloc::{boxed::Box, ffi::CString, string::String, vec::Vec};
+use core::{ffi::CStr, str};
+
+use crate::{
+    c_str,
+    fs::File,
+    header::{
+        errno::{EINVAL, ENOENT},
+        fcntl::{O_CLOEXEC, O_RDONLY},
+        limits::NAME_MAX,
+        stdlib::getenv,
+    },
+    io::Read,
+    platform::types::*,
+    sync::Mutex,
+};
+
+const DEFAULT_LOCPATH: &str = "/usr/share/relibc/locale";
+
+/// `CHAR_MAX`, meaning "not available in this locale" in `lconv`.
+const UNSET: c_char = c_char::max_value();
+
+/// The codesets a locale can use.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Charset {
+    Utf8,
+    Ascii,
+    /// ISO-8859-1, where each byte is the code point of the same value.
+    Latin1,
+}
+
+impl Charset {
+    /// Finds a codeset by name, ignoring case, `-` and `_`.
+    fn find(name: &[u8]) -> Option<Self> {
+        let name: Vec<u8> = name
+            .iter()
+            .filter(|&&b| b != b'-' && b != b'_')
+            .map(u8::to_ascii_uppercase)
+            .collect();
+        match name.as_slice() {
+            b"UTF8" => Some(Charset::Utf8),
+            b"ASCII" | b"USASCII" | b"ANSIX3.41968" => Some(Charset::Ascii),
+            b"ISO88591" | b"LATIN1" => Some(Charset::Latin1),
+            _ => None,
+        }
+    }
+
+    /// Appends `c` to `out` in this codeset, unless it cannot be represented.
+    fn encode(self, c: char, out: &mut Vec<u8>) -> Option<()> {
+        match self {
+            Charset::Utf8 => out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            Charset::Ascii => out.push(u8::try_from(c).ok().filter(u8::is_ascii)?),
+            Charset::Latin1 => out.push(u8::try_from(c).ok()?),
+        }
+        Some(())
+    }
+}
+
+pub(crate) struct Ctype {
+    pub codeset: &'static CStr,
+    pub charset: Charset,
+}
+
+pub(crate) struct Numeric {
+    pub decimal_point: &'static CStr,
+    pub thousands_sep: &'static CStr,
+    pub grouping: &'static CStr,
+}
+
+pub(crate) struct Monetary {
+    pub int_curr_symbol: &'static CStr,
+    pub currency_symbol: &'static CStr,
+    pub mon_decimal_point: &'static CStr,
+    pub mon_thousands_sep: &'static CStr,
+    pub mon_grouping: &'static CStr,
+    pub positive_sign: &'static CStr,
+    pub negative_sign: &'static CStr,
+    pub int_frac_digits: c_char,
+    pub frac_digits: c_char,
+    pub p_cs_precedes: c_char,
+    pub p_sep_by_space: c_char,
+    pub n_cs_precedes: c_char,
+    pub n_sep_by_space: c_char,
+    pub p_sign_posn: c_char,
+    pub n_sign_posn: c_char,
+    /// `CRNCYSTR` for `nl_langinfo`, derived from the fields above.
+    pub crncystr: &'static CStr,
+}
+
+pub(crate) struct Time {
+    pub abday: [&'static CStr; 7],
+    pub day: [&'static CStr; 7],
+    pub abmon: [&'static CStr; 12],
+    pub mon: [&'static CStr; 12],
+    pub am_pm: [&'static CStr; 2],
+    pub d_t_fmt: &'static CStr,
+    pub d_fmt: &'static CStr,
+    pub t_fmt: &'static CStr,
+    pub t_fmt_ampm: &'static CStr,
+    pub era: &'static CStr,
+    pub era_d_fmt: &'static CStr,
+    pub era_d_t_fmt: &'static CStr,
+    pub era_t_fmt: &'static CStr,
+    pub alt_digits: &'static CStr,
+}
+
+pub(crate) struct Messages {
+    pub yesexpr: &'static CStr,
+    pub noexpr: &'static CStr,
+    pub yesstr: &'static CStr,
+    pub nostr: &'static CStr,
+}
+
+/// Everything a locale defines. Each category of a `locale_t` points to one
+/// of these, so a locale object can mix categories from different locales.
+pub(crate) struct LocaleData {
+    pub name: &'static CStr,
+    pub ctype: Ctype,
+    pub numeric: Numeric,
+    pub monetary: Monetary,
+    pub time: Time,
+    pub messages: Messages,
+}
+
+const C: LocaleData = LocaleData {
+    name: c"C",
+    // The C locale has always used UTF-8 in relibc.
+    ctype: Ctype {
+        codeset: c"UTF-8",
+        charset: Charset::Utf8,
+    },
+    numeric: Numeric {
+        decimal_point: c".",
+        thousands_sep: c"",
+        grouping: c"",
+    },
+    monetary: Monetary {
+        int_curr_symbol: c"",
+        currency_symbol: c"",
+        mon_decimal_point: c"",
+        mon_thousands_sep: c"",
+        mon_grouping: c"",
+        positive_sign: c"",
+        negative_sign: c"",
+        int_frac_digits: UNSET,
+        frac_digits: UNSET,
+        p_cs_precedes: UNSET,
+        p_sep_by_space: UNSET,
+        n_cs_precedes: UNSET,
+        n_sep_by_space: UNSET,
+        p_sign_posn: UNSET,
+        n_sign_posn: UNSET,
+        crncystr: c"-",
+    },
+    time: Time {
+        abday: [c"Sun", c"Mon", c"Tue", c"Wed", c"Thu", c"Fri", c"Sat"],
+        day: [
+            c"Sunday",
+            c"Monday",
+            c"Tuesday",
+            c"Wednesday",
+            c"Thursday",
+            c"Friday",
+            c"Saturday",
+        ],
+        abmon: [
+            c"Jan", c"Feb", c"Mar", c"Apr", c"May", c"Jun", c"Jul", c"Aug", c"Sep", c"Oct", c"Nov",
+            c"Dec",
+        ],
+        mon: [
+            c"January",
+            c"February",
+            c"March",
+            c"April",
+            c"May",
+            c"June",
+            c"July",
+            c"August",
+            c"September",
+            c"October",
+            c"November",
+            c"December",
+        ],
+        am_pm: [c"AM", c"PM"],
+        d_t_fmt: c"%a %b %e %H:%M:%S %Y",
+        d_fmt: c"%m/%d/%y",
+        t_fmt: c"%H:%M:%S",
+        t_fmt_ampm: c"%I:%M:%S %p",
+        era: c"",
+        era_d_fmt: c"",
+        era_d_t_fmt: c"",
+        era_t_fmt: c"",
+        alt_digits: c"",
+    },
+    messages: Messages {
+        yesexpr: c"^[yY]",
+        noexpr: c"^[nN]",
+        yesstr: c"yes",
+        nostr: c"no",
+    },
+};
+
+pub(crate) static C_LOCALE: LocaleData = C;
+static C_UTF8_LOCALE: LocaleData = LocaleData {
+    name: c"C.UTF-8",
+    ..C
+};
+
+/// Locales loaded so far. They are never freed, as any number of `locale_t`
+/// objects and `setlocale` results may point into them.
+static LOADED: Mutex<Vec<&'static LocaleData>> = Mutex::new(Vec::new());
+
+/// Finds the locale called `name`, loading it from disk if needed.
+pub(crate) fn find(name: &[u8]) -> Result<&'static LocaleData, c_int> {
+    match name {
+        b"C" | b"POSIX" => return Ok(&C_LOCALE),
+        b"C.UTF-8" | b"C.utf8" => return Ok(&C_UTF8_LOCALE),
+        _ => (),
+    }
+    if name.is_empty()
+        || name.len() > NAME_MAX
+        || name.contains(&b'/')
+        || name == b"."
+        || name == b".."
+    {
+        return Err(ENOENT);
+    }
+
+    let mut loaded = LOADED.lock();
+    if let Some(data) = loaded.iter().find(|data| data.name.to_bytes() == name) {
+        return Ok(data);
+    }
+    let data: &'static LocaleData = Box::leak(Box::new(load(name)?));
+    loaded.push(data);
+    Ok(data)
+}
+
+fn load(name: &[u8]) -> Result<LocaleData, c_int> {
+    let dir = unsafe { getenv(c"LOCPATH".as_ptr()) };
+    let mut path = if dir.is_null() {
+        DEFAULT_LOCPATH.as_bytes().to_vec()
+    } else {
+        unsafe { CStr::from_ptr(dir) }.to_bytes().to_vec()
+    };
+    path.push(b'/');
+    path.extend_from_slice(name);
+    let path = CString::new(path).map_err(|_| ENOENT)?;
+
+    let mut file =
+        File::open(c_str::CStr::from(path.as_c_str()), O_RDONLY | O_CLOEXEC).map_err(|_| ENOENT)?;
+    let mut text = Vec::new();
+    file.read_to_end(&mut text).map_err(|_| ENOENT)?;
+    let text = str::from_utf8(&text).map_err(|_| EINVAL)?;
+
+    parse(leak(name.to_vec()), text).ok_or(EINVAL)
+}
+
+/// Turns `bytes` into a string that lives as long as the locale data.
+fn leak(bytes: Vec<u8>) -> &'static CStr {
+    // Interior nuls would be cut off by C anyway
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let mut bytes = bytes;
+    bytes.truncate(len);
+    Box::leak(CString::new(bytes).unwrap().into_boxed_c_str())
+}
+
+enum Value {
+    Str(Vec<u8>),
+    Int(i64),
+}
+
+/// Parses the `;` separated operands of a keyword, with strings in `charset`.
+fn parse_values(mut rest: &str, charset: Charset) -> Option<Vec<Value>> {
+    let mut values = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = Vec::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                let (i, c) = chars.next()?;
+                match c {
+                    '"' => break i + 1,
+                    '\\' => {
+                        let (_, escaped) = chars.next()?;
+                        charset.encode(escaped, &mut value)?;
+                    }
+                    '<' if quoted[i..].starts_with("<U") => {
+                        let len = quoted[i..].find('>')?;
+                        let code = u32::from_str_radix(&quoted[i + 2..i + len], 16).ok()?;
+                        charset.encode(char::from_u32(code)?, &mut value)?;
+                        for _ in 0..len {
+                            chars.next();
+                        }
+                    }
+                    c => charset.encode(c, &mut value)?,
+                }
+            };
+            values.push(Value::Str(value));
+            rest = &quoted[end..];
+        } else {
+            let end = rest
+                .find(|c: char| c == ';' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            values.push(Value::Int(rest[..end].parse().ok()?));
+            rest = &rest[end..];
+        }
+
+        rest = rest.trim_start();
+        match rest.strip_prefix(';') {
+            Some(next) => rest = next,
+            None if rest.is_empty() => return Some(values),
+            None => return None,
+        }
+    }
+}
+
+fn string(values: Vec<Value>) -> Option<&'static CStr> {
+    let [value] = strings(values)?;
+    Some(value)
+}
+
+fn strings<const N: usize>(values: Vec<Value>) -> Option<[&'static CStr; N]> {
+    if values.len() != N {
+        return None;
+    }
+    let mut strings = [c""; N];
+    for (string, value) in strings.iter_mut().zip(values) {
+        match value {
+            Value::Str(bytes) => *string = leak(bytes),
+            Value::Int(_) => return None,
+        }
+    }
+    Some(strings)
+}
+
+/// Joins a list of strings with `;`, which is how `nl_langinfo` returns
+/// `ERA` and `ALT_DIGITS`.
+fn list(values: Vec<Value>) -> Option<&'static CStr> {
+    let mut list = Vec::new();
+    for value in values {
+        let Value::Str(bytes) = value else {
+            return None;
+        };
+        if !list.is_empty() {
+            list.push(b';');
+        }
+        list.extend_from_slice(&bytes);
+    }
+    Some(leak(list))
+}
+
+fn int(values: Vec<Value>) -> Option<c_char> {
+    match values.as_slice() {
+        [Value::Int(-1)] => Some(UNSET),
+        [Value::Int(n)] => c_char::try_from(*n).ok(),
+        _ => None,
+    }
+}
+
+/// Converts `3;2;-1` to the `lconv` representation of group sizes.
+fn grouping(values: Vec<Value>) -> Option<&'static CStr> {
+    let mut sizes = Vec::with_capacity(values.len());
+    for value in values {
+        match value {
+            Value::Int(-1) => sizes.push(UNSET as u8),
+            Value::Int(n @ 1..=126) => sizes.push(n as u8),
+            _ => return None,
+        }
+    }
+    Some(leak(sizes))
+}
+
+/// Calls `f` with the category, keyword and operands of each line of `text`
+/// that is in a category we know, stopping at the first failure.
+fn for_each_keyword(text: &str, mut f: impl FnMut(&str, &str, &str) -> Option<()>) -> Option<()> {
+    const SECTIONS: [&str; 5] = [
+        "LC_CTYPE",
+        "LC_NUMERIC",
+        "LC_MONETARY",
+        "LC_TIME",
+        "LC_MESSAGES",
+    ];
+    let mut category: Option<&str> = None;
+
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let mut line = String::from(line);
+        while line.ends_with('\\') {
+            line.pop();
+            line.push_str(lines.next().unwrap_or(""));
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        if keyword.starts_with("LC_") && rest.is_empty() {
+            // Sections we have no use for, like LC_COLLATE, are skipped
+            category = Some(SECTIONS.into_iter().find(|&s| s == keyword).unwrap_or(""));
+            continue;
+        }
+        if keyword == "END" {
+            category = None;
+            continue;
+        }
+        // Operands are only parsed for keywords we know, as others may use
+        // syntax we do not understand. The same goes for directives such as
+        // comment_char that come before the first category.
+        let Some(category) = category else {
+            continue;
+        };
+        f(category, keyword, rest)?;
+    }
+    Some(())
+}
+
+fn parse(name: &'static CStr, text: &str) -> Option<LocaleData> {
+    let mut data = LocaleData { name, ..C };
+
+    // Strings are stored in the codeset, wherever it is set in the file
+    for_each_keyword(text, |category, keyword, rest| {
+        if (category, keyword) == ("LC_CTYPE", "codeset") {
+            let codeset = string(parse_values(rest, Charset::Ascii)?)?;
+            data.ctype = Ctype {
+                codeset,
+                charset: Charset::find(codeset.to_bytes())?,
+            };
+        }
+        Some(())
+    })?;
+    let charset = data.ctype.charset;
+
+    for_each_keyword(text, |category, keyword, rest| {
+        let values = || parse_values(rest, charset);
+
+        match (category, keyword) {
+            // Already set above
+            ("LC_CTYPE", "codeset") => (),
+
+            ("LC_NUMERIC", "decimal_point") => data.numeric.decimal_point = string(values()?)?,
+            ("LC_NUMERIC", "thousands_sep") => data.numeric.thousands_sep = string(values()?)?,
+            ("LC_NUMERIC", "grouping") => data.numeric.grouping = grouping(values()?)?,
+
+            ("LC_MONETARY", "int_curr_symbol") => {
+                data.monetary.int_curr_symbol = string(values()?)?
+            }
+            ("LC_MONETARY", "currency_symbol") => {
+                data.monetary.currency_symbol = string(values()?)?
+            }
+            ("LC_MONETARY", "mon_decimal_point") => {
+                data.monetary.mon_decimal_point = string(values()?)?
+            }
+            ("LC_MONETARY", "mon_thousands_sep") => {
+                data.monetary.mon_thousands_sep = string(values()?)?
+            }
+            ("LC_MONETARY", "mon_grouping") => data.monetary.mon_grouping = grouping(values()?)?,
+            ("LC_MONETARY", "positive_sign") => data.monetary.positive_sign = string(values()?)?,
+            ("LC_MONETARY", "negative_sign") => data.monetary.negative_sign = string(values()?)?,
+            ("LC_MONETARY", "int_frac_digits") => data.monetary.int_frac_digits = int(values()?)?,
+            ("LC_MONETARY", "frac_digits") => data.monetary.frac_digits = int(values()?)?,
+            ("LC_MONETARY", "p_cs_precedes") => data.monetary.p_cs_precedes = int(values()?)?,
+            ("LC_MONETARY", "p_sep_by_space") => data.monetary.p_sep_by_space = int(values()?)?,
+            ("LC_MONETARY", "n_cs_precedes") => data.monetary.n_cs_precedes = int(values()?)?,
+            ("LC_MONETARY", "n_sep_by_space") => data.monetary.n_sep_by_space = int(values()?)?,
+            ("LC_MONETARY", "p_sign_posn") => data.monetary.p_sign_posn = int(values()?)?,
+            ("LC_MONETARY", "n_sign_posn") => data.monetary.n_sign_posn = int(values()?)?,
+
+            ("LC_TIME", "abday") => data.time.abday = strings(values()?)?,
+            ("LC_TIME", "day") => data.time.day = strings(values()?)?,
+            ("LC_TIME", "abmon") => data.time.abmon = strings(values()?)?,
+            ("LC_TIME", "mon") => data.time.mon = strings(values()?)?,
+            ("LC_TIME", "am_pm") => data.time.am_pm = strings(values()?)?,
+            ("LC_TIME", "d_t_fmt") => data.time.d_t_fmt = string(values()?)?,
+            ("LC_TIME", "d_fmt") => data.time.d_fmt = string(values()?)?,
+            ("LC_TIME", "t_fmt") => data.time.t_fmt = string(values()?)?,
+            ("LC_TIME", "t_fmt_ampm") => data.time.t_fmt_ampm = string(values()?)?,
+            ("LC_TIME", "era") => data.time.era = list(values()?)?,
+            ("LC_TIME", "era_d_fmt") => data.time.era_d_fmt = string(values()?)?,
+            ("LC_TIME", "era_d_t_fmt") => data.time.era_d_t_fmt = string(values()?)?,
+            ("LC_TIME", "era_t_fmt") => data.time.era_t_fmt = string(values()?)?,
+            ("LC_TIME", "alt_digits") => data.time.alt_digits = list(values()?)?,
+
+            ("LC_MESSAGES", "yesexpr") => data.messages.yesexpr = string(values()?)?,
+            ("LC_MESSAGES", "noexpr") => data.messages.noexpr = string(values()?)?,
+            ("LC_MESSAGES", "yesstr") => data.messages.yesstr = string(values()?)?,
+            ("LC_MESSAGES", "nostr") => data.messages.nostr = string(values()?)?,
+
+            _ => (),
+        }
+        Some(())
+    })?;
+
+    // The currency symbol, preceded by '-' if it goes before the value and
+    // '+' if it goes after
+    let monetary = &mut data.monetary;
+    let mut crncystr = Vec::new();
+    crncystr.push(if monetary.p_cs_precedes == 0 {
+        b'+'
+    } else {
+        b'-'
+    });
+    crncystr.extend_from_slice(monetary.currency_symbol.to_bytes());
+    monetary.crncystr = leak(crncystr);
+
+    Some(data)
+}
@@ -1,17 +1,61 @@
 //! locale implementation for Redox, following http://pubs.opengroup.org/onlinepubs/7908799/xsh/locale.h.html
 
-use core::ptr;
+use alloc::{boxed::Box, vec::Vec};
+use core::{cell::Cell, ffi::CStr, ptr};
 
-use crate::platform::types::*;
+use self::data::{Charset, LocaleData, Messages, Monetary, Numeric, Time};
+use crate::{
+    header::{errno::EINVAL, stdlib::getenv},
+    platform::{self, types::*},
+    raw_cell::RawCell,
+};
+
+pub(crate) mod data;
+
+// Categories, matching bits/locale.h
+pub(crate) const LC_ALL: c_int = 0;
+pub(crate) const LC_COLLATE: c_int = 1;
+pub(crate) const LC_CTYPE: c_int = 2;
+pub(crate) const LC_MESSAGES: c_int = 3;
+pub(crate) const LC_MONETARY: c_int = 4;
+pub(crate) const LC_NUMERIC: c_int = 5;
+pub(crate) const LC_TIME: c_int = 6;
+
+const CATEGORY_COUNT: usize = LC_TIME as usize + 1;
 
-const EMPTY_PTR: *const c_char = "\0" as *const _ as *const c_char;
-// Can't use &str because of the mutability
-static mut C_LOCALE: [c_char; 2] = [b'C' as c_char, 0];
+/// Names of the categories, as used for environment variables and in the
+/// composite name `setlocale(LC_ALL, NULL)` returns.
+const CATEGORY_NAMES: [&str; CATEGORY_COUNT] = [
+    "LC_ALL",
+    "LC_COLLATE",
+    "LC_CTYPE",
+    "LC_MESSAGES",
+    "LC_MONETARY",
+    "LC_NUMERIC",
+    "LC_TIME",
+];
+
+pub const LC_COLLATE_MASK: c_int = 1 << LC_COLLATE;
+pub const LC_CTYPE_MASK: c_int = 1 << LC_CTYPE;
+pub const LC_MESSAGES_MASK: c_int = 1 << LC_MESSAGES;
+pub const LC_MONETARY_MASK: c_int = 1 << LC_MONETARY;
+pub const LC_NUMERIC_MASK: c_int = 1 << LC_NUMERIC;
+pub const LC_TIME_MASK: c_int = 1 << LC_TIME;
+pub const LC_ALL_MASK: c_int = LC_COLLATE_MASK
+    | LC_CTYPE_MASK
+    | LC_MESSAGES_MASK
+    | LC_MONETARY_MASK
+    | LC_NUMERIC_MASK
+    | LC_TIME_MASK;
+
+/// Defined as a macro in bits/locale.h, since cbindgen can't express it.
+pub(crate) const LC_GLOBAL_LOCALE: locale_t = -1isize as locale_t;
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct lconv {
     currency_symbol: *const c_char,
-    decimal_point: *const c_char,
+    pub(crate) decimal_point: *const c_char,
     frac_digits: c_char,
     grouping: *const c_char,
     int_curr_symbol: *const c_char,
@@ -27,40 +71,309 @@ pub struct lconv {
     p_cs_precedes: c_char,
     p_sep_by_space: c_char,
     p_sign_posn: c_char,
-    thousands_sep: *const c_char,
+    pub(crate) thousands_sep: *const c_char,
 }
 unsafe impl Sync for lconv {}
 
-// Mutable because POSIX demands a mutable pointer, even though it warns
-// against mutating it
-static mut CURRENT_LOCALE: lconv = lconv {
-    currency_symbol: EMPTY_PTR,
-    decimal_point: ".\0" as *const _ as *const c_char,
-    frac_digits: c_char::max_value(),
-    grouping: EMPTY_PTR,
-    int_curr_symbol: EMPTY_PTR,
-    int_frac_digits: c_char::max_value(),
-    mon_decimal_point: EMPTY_PTR,
-    mon_grouping: EMPTY_PTR,
-    mon_thousands_sep: EMPTY_PTR,
-    negative_sign: EMPTY_PTR,
-    n_cs_precedes: c_char::max_value(),
-    n_sep_by_space: c_char::max_value(),
-    n_sign_posn: c_char::max_value(),
-    positive_sign: EMPTY_PTR,
-    p_cs_precedes: c_char::max_value(),
-    p_sep_by_space: c_char::max_value(),
-    p_sign_posn: c_char::max_value(),
-    thousands_sep: EMPTY_PTR,
-};
+impl lconv {
+    const fn new(numeric: &Numeric, monetary: &Monetary) -> Self {
+        Self {
+            currency_symbol: monetary.currency_symbol.as_ptr(),
+            decimal_point: numeric.decimal_point.as_ptr(),
+            frac_digits: monetary.frac_digits,
+            grouping: numeric.grouping.as_ptr(),
+            int_curr_symbol: monetary.int_curr_symbol.as_ptr(),
+            int_frac_digits: monetary.int_frac_digits,
+            mon_decimal_point: monetary.mon_decimal_point.as_ptr(),
+            mon_grouping: monetary.mon_grouping.as_ptr(),
+            mon_thousands_sep: monetary.mon_thousands_sep.as_ptr(),
+            negative_sign: monetary.negative_sign.as_ptr(),
+            n_cs_precedes: monetary.n_cs_precedes,
+            n_sep_by_space: monetary.n_sep_by_space,
+            n_sign_posn: monetary.n_sign_posn,
+            positive_sign: monetary.positive_sign.as_ptr(),
+            p_cs_precedes: monetary.p_cs_precedes,
+            p_sep_by_space: monetary.p_sep_by_space,
+            p_sign_posn: monetary.p_sign_posn,
+            thousands_sep: numeric.thousands_sep.as_ptr(),
+        }
+    }
+}
+
+/// The object behind a `locale_t`. Opaque to C.
+#[derive(Clone)]
+pub struct __locale_struct {
+    /// The locale each category was loaded from, indexed by `LC_*`. The
+    /// `LC_ALL` slot is unused.
+    categories: [&'static LocaleData; CATEGORY_COUNT],
+    pub(crate) lconv: lconv,
+}
+
+#[allow(non_camel_case_types)]
+pub type locale_t = *mut __locale_struct;
+
+impl __locale_struct {
+    const fn new(categories: [&'static LocaleData; CATEGORY_COUNT]) -> Self {
+        Self {
+            categories,
+            lconv: lconv::new(
+                &categories[LC_NUMERIC as usize].numeric,
+                &categories[LC_MONETARY as usize].monetary,
+            ),
+        }
+    }
+
+    /// Returns a copy of this locale with the categories in `mask` replaced
+    /// by the locale called `name`, which may be empty to consult the
+    /// environment, or a composite name as returned by `setlocale`.
+    fn with(&self, mask: c_int, name: &[u8]) -> Result<Self, c_int> {
+        let mut categories = self.categories;
+
+        if mask == LC_ALL_MASK && name.contains(&b'=') {
+            for part in name.split(|&b| b == b';') {
+                let mut split = part.splitn(2, |&b| b == b'=');
+                let (Some(category), Some(name)) = (split.next(), split.next()) else {
+                    return Err(EINVAL);
+                };
+                let Some(category) = (LC_COLLATE..=LC_TIME)
+                    .find(|&c| CATEGORY_NAMES[c as usize].as_bytes() == category)
+                else {
+                    return Err(EINVAL);
+                };
+                categories[category as usize] = data::find(name)?;
+            }
+        } else {
+            for category in LC_COLLATE..=LC_TIME {
+                if mask & (1 << category) == 0 {
+                    continue;
+                }
+                categories[category as usize] = if name.is_empty() {
+                    data::find(name_from_env(category))?
+                } else {
+                    data::find(name)?
+                };
+            }
+        }
+
+        Ok(Self::new(categories))
+    }
+
+    pub(crate) fn name(&self, category: c_int) -> &'static CStr {
+        self.categories[category as usize].name
+    }
+
+    /// Whether `LC_CTYPE` uses UTF-8 for multibyte characters. Otherwise,
+    /// each byte is a character of its own.
+    pub(crate) fn is_utf8(&self) -> bool {
+        self.charset() == Charset::Utf8
+    }
+
+    pub(crate) fn charset(&self) -> Charset {
+        self.categories[LC_CTYPE as usize].ctype.charset
+    }
+
+    pub(crate) fn codeset(&self) -> &'static CStr {
+        self.categories[LC_CTYPE as usize].ctype.codeset
+    }
+
+    pub(crate) fn messages(&self) -> &'static Messages {
+        &self.categories[LC_MESSAGES as usize].messages
+    }
+
+    pub(crate) fn monetary(&self) -> &'static Monetary {
+        &self.categories[LC_MONETARY as usize].monetary
+    }
+
+    pub(crate) fn numeric(&self) -> &'static Numeric {
+        &self.categories[LC_NUMERIC as usize].numeric
+    }
+
+    pub(crate) fn time(&self) -> &'static Time {
+        &self.categories[LC_TIME as usize].time
+    }
+}
+
+const C_LOCALE_STRUCT: __locale_struct = __locale_struct::new([&data::C_LOCALE; CATEGORY_COUNT]);
+
+/// The locale used by threads that have not called `uselocale`, as set by
+/// `setlocale`.
+static GLOBAL_LOCALE: RawCell<__locale_struct> = RawCell::new(C_LOCALE_STRUCT);
+
+/// Backing storage for composite names returned by `setlocale(LC_ALL, ...)`.
+static GLOBAL_NAME: RawCell<Vec<u8>> = RawCell::new(Vec::new());
+
+/// The locale installed by `uselocale`, or null to follow the global locale.
+#[thread_local]
+static THREAD_LOCALE: Cell<locale_t> = Cell::new(ptr::null_mut());
+
+/// Returns the locale in effect for the calling thread.
+pub(crate) fn current() -> &'static __locale_struct {
+    unsafe { get(THREAD_LOCALE.get()) }
+}
+
+/// Returns the locale `loc` refers to, where either null or
+/// `LC_GLOBAL_LOCALE` mean the global locale.
+///
+/// # Safety
+/// `loc` must otherwise be a live object returned by `newlocale` or
+/// `duplocale`.
+pub(crate) unsafe fn get(loc: locale_t) -> &'static __locale_struct {
+    if loc.is_null() || loc == LC_GLOBAL_LOCALE {
+        unsafe { GLOBAL_LOCALE.unsafe_ref() }
+    } else {
+        unsafe { &*loc }
+    }
+}
+
+/// Resolves the empty locale name for `category` from the environment, as
+/// described in XBD 8.2.
+fn name_from_env(category: c_int) -> &'static [u8] {
+    let mut var = [0; 16];
+    for name in [
+        CATEGORY_NAMES[LC_ALL as usize],
+        CATEGORY_NAMES[category as usize],
+        "LANG",
+    ] {
+        var[..name.len()].copy_from_slice(name.as_bytes());
+        var[name.len()] = 0;
+        let value = unsafe { getenv(var.as_ptr() as *const c_char) };
+        if !value.is_null() {
+            let value = unsafe { CStr::from_ptr(value) }.to_bytes();
+            if !value.is_empty() {
+                return value;
+            }
+        }
+    }
+    b"C"
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/duplocale.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn duplocale(loc: locale_t) -> locale_t {
+    if loc.is_null() {
+        platform::ERRNO.set(EINVAL);
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(unsafe { get(loc) }.clone()))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/freelocale.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn freelocale(loc: locale_t) {
+    if !loc.is_null() && loc != LC_GLOBAL_LOCALE {
+        drop(unsafe { Box::from_raw(loc) });
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/getlocalename_l.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getlocalename_l(category: c_int, loc: locale_t) -> *const c_char {
+    if category <= LC_ALL || category > LC_TIME || loc.is_null() {
+        return ptr::null();
+    }
+    unsafe { get(loc) }.name(category).as_ptr()
+}
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn localeconv() -> *mut lconv {
-    &raw mut CURRENT_LOCALE as *mut _
+    // Mutable because POSIX demands a mutable pointer, even though it warns
+    // against mutating it
+    &current().lconv as *const lconv as *mut lconv
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/newlocale.html>.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn setlocale(_option: c_int, _val: *const c_char) -> *mut c_char {
-    // TODO actually implement
-    &raw mut C_LOCALE as *mut c_char
+pub unsafe extern "C" fn newlocale(
+    category_mask: c_int,
+    locale: *const c_char,
+    base: locale_t,
+) -> locale_t {
+    if locale.is_null() || category_mask & !LC_ALL_MASK != 0 || base == LC_GLOBAL_LOCALE {
+        platform::ERRNO.set(EINVAL);
+        return ptr::null_mut();
+    }
+    let locale = unsafe { CStr::from_ptr(locale) }.to_bytes();
+
+    let old = if base.is_null() {
+        &C_LOCALE_STRUCT
+    } else {
+        unsafe { &*base }
+    };
+    // base must remain valid when we fail
+    let new = match old.with(category_mask, locale) {
+        Ok(new) => new,
+        Err(err) => {
+            platform::ERRNO.set(err);
+            return ptr::null_mut();
+        }
+    };
+
+    if base.is_null() {
+        Box::into_raw(Box::new(new))
+    } else {
+        unsafe { *base = new };
+        base
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/setlocale.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn setlocale(category: c_int, locale: *const c_char) -> *mut c_char {
+    if category < LC_ALL || category > LC_TIME {
+        platform::ERRNO.set(EINVAL);
+        return ptr::null_mut();
+    }
+    let global = unsafe { GLOBAL_LOCALE.unsafe_mut() };
+
+    if !locale.is_null() {
+        let mask = if category == LC_ALL {
+            LC_ALL_MASK
+        } else {
+            1 << category
+        };
+        match global.with(mask, unsafe { CStr::from_ptr(locale) }.to_bytes()) {
+            Ok(new) => *global = new,
+            Err(err) => {
+                platform::ERRNO.set(err);
+                return ptr::null_mut();
+            }
+        }
+    }
+
+    if category != LC_ALL {
+        return global.name(category).as_ptr() as *mut c_char;
+    }
+
+    let first = global.name(LC_COLLATE);
+    let name = unsafe { GLOBAL_NAME.unsafe_mut() };
+    name.clear();
+    if (LC_COLLATE..=LC_TIME).all(|category| global.name(category) == first) {
+        name.extend_from_slice(first.to_bytes());
+    } else {
+        for category in LC_COLLATE..=LC_TIME {
+            if category != LC_COLLATE {
+                name.push(b';');
+            }
+            name.extend_from_slice(CATEGORY_NAMES[category as usize].as_bytes());
+            name.push(b'=');
+            name.extend_from_slice(global.name(category).to_bytes());
+        }
+    }
+    name.push(0);
+    name.as_mut_ptr() as *mut c_char
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/uselocale.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uselocale(newloc: locale_t) -> locale_t {
+    let old = THREAD_LOCALE.get();
+    if !newloc.is_null() {
+        THREAD_LOCALE.set(if newloc == LC_GLOBAL_LOCALE {
+            ptr::null_mut()
+        } else {
+            newloc
+        });
+    }
+
+    if old.is_null() { LC_GLOBAL_LOCALE } else { old }
 }
@@ -3,6 +3,7 @@
 pub mod _aio;
 pub mod _fenv;
 pub mod arpa_inet;
+pub mod arpa_nameser;
 pub mod assert;
 pub mod bits_pthread;
 pub mod bits_sched;
@@ -28,13 +29,15 @@ pub mod fnmatch;
 pub mod getopt;
 pub mod glob;
 pub mod grp;
-// TODO: iconv.h
+pub mod iconv;
+pub mod ifaddrs;
 pub mod inttypes;
 // iso646.h implemented in C
 pub mod langinfo;
 // TODO: libintl.h
 pub mod libgen;
 pub mod limits;
+pub mod link;
 pub mod locale;
 pub mod malloc;
 // math.h implemented in C
@@ -56,6 +59,7 @@ pub mod pwd;
 // TODO: re_comp.h (deprecated)
 pub mod regex;
 // TODO: regexp.h (deprecated)
+pub mod resolv;
 pub mod sched;
 // TODO: search.h
 pub mod semaphore;
@@ -63,7 +67,7 @@ pub mod setjmp;
 pub mod sgtty;
 pub mod shadow;
 pub mod signal;
-// TODO: spawn.h
+pub mod spawn;
 // TODO: stdalign.h (likely C implementation)
 // stdarg.h implemented in C
 // stdatomic.h implemented in C
@@ -145,7 +149,7 @@ pub mod tar;
 // TODO: term.h (deprecated)
 pub mod termios;
 // TODO: tgmath.h (likely C implementation)
-// TODO: threads.h
+pub mod threads;
 pub mod time;
 // TODO: uchar.h
 // TODO: ucontext.h (deprecated)
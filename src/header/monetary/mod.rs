@@ -1,13 +1,15 @@
 /// monetary.h implementation for Redox, following the POSIX standard.
 /// Following https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/monetary.h.html
 ///
-/// We should provide a strfmon() implementation that formats a monetary value,
-/// according to the current locale (TODO).
+/// strfmon() formats monetary values according to the LC_MONETARY category of
+/// the current locale.
 use alloc::string::{String, ToString};
 use core::{ffi::CStr, ptr, slice, str};
 
 use libm::{fabs, floor, pow, round, trunc};
 
+use crate::{header::locale, platform::types::c_char};
+
 extern crate alloc;
 
 mod strfmon;
@@ -31,23 +33,55 @@ struct LocaleMonetaryInfo {
     n_sign_posn: u8,
 }
 
-const DEFAULT_MONETARY: LocaleMonetaryInfo = LocaleMonetaryInfo {
-    int_curr_symbol: "USD ",
-    currency_symbol: "$",
-    mon_decimal_point: ".",
-    mon_thousands_sep: ",",
-    mon_grouping: &[3, 3],
-    positive_sign: "",
-    negative_sign: "-",
-    int_frac_digits: 2,
-    frac_digits: 2,
-    p_cs_precedes: true,
-    p_sep_by_space: false,
-    p_sign_posn: 1,
-    n_cs_precedes: true,
-    n_sep_by_space: false,
-    n_sign_posn: 1,
-};
+impl LocaleMonetaryInfo {
+    /// Takes the rules from the current locale. Values the locale leaves
+    /// unspecified, as the "C" locale does, fall back to plain number
+    /// formatting with two fractional digits.
+    fn current() -> Self {
+        let monetary = locale::current().monetary();
+        let string = |s: &'static CStr| s.to_str().unwrap_or("");
+        let number = |n: c_char, default: u8| {
+            if n == c_char::max_value() {
+                default
+            } else {
+                n as u8
+            }
+        };
+        let flag = |n: c_char, default: bool| {
+            if n == c_char::max_value() {
+                default
+            } else {
+                n != 0
+            }
+        };
+
+        let mon_decimal_point = match string(monetary.mon_decimal_point) {
+            "" => ".",
+            point => point,
+        };
+
+        LocaleMonetaryInfo {
+            int_curr_symbol: string(monetary.int_curr_symbol),
+            currency_symbol: string(monetary.currency_symbol),
+            mon_decimal_point,
+            mon_thousands_sep: string(monetary.mon_thousands_sep),
+            mon_grouping: monetary.mon_grouping.to_bytes(),
+            positive_sign: string(monetary.positive_sign),
+            negative_sign: match string(monetary.negative_sign) {
+                "" => "-",
+                sign => sign,
+            },
+            int_frac_digits: number(monetary.int_frac_digits, 2),
+            frac_digits: number(monetary.frac_digits, 2),
+            p_cs_precedes: flag(monetary.p_cs_precedes, true),
+            p_sep_by_space: flag(monetary.p_sep_by_space, false),
+            p_sign_posn: number(monetary.p_sign_posn, 1),
+            n_cs_precedes: flag(monetary.n_cs_precedes, true),
+            n_sep_by_space: flag(monetary.n_sep_by_space, false),
+            n_sign_posn: number(monetary.n_sign_posn, 1),
+        }
+    }
+}
 
 #[derive(Default)]
 struct FormatFlags {
@@ -69,6 +103,9 @@ fn apply_grouping(int_str: &str, monetary: &LocaleMonetaryInfo) -> String {
     let mut group_idx = 0;
     let current_grouping = &monetary.mon_grouping;
     let separator = monetary.mon_thousands_sep;
+    if current_grouping.is_empty() {
+        return int_str.to_string();
+    }
 
     for c in int_str.chars() {
         if count > 0 {
@@ -1,6 +1,6 @@
 use crate::platform::types::c_char;
 
-use super::{DEFAULT_MONETARY, FormatFlags, LocaleMonetaryInfo, apply_grouping};
+use super::{FormatFlags, LocaleMonetaryInfo, apply_grouping};
 use alloc::string::{String, ToString};
 use core::{ffi::CStr, ptr, result, slice, str};
 use libm::{fabs, floor, pow, round, trunc};
@@ -12,6 +12,7 @@ use libm::{fabs, floor, pow, round, trunc};
 /// Returns:
 /// - The number of characters written (excluding the null terminator), or -1 if
 /// an error occurs (e.g., invalid input, buffer overflow)
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn strfmon(
     s: *mut c_char,        // Output buffer
     maxsize: usize,        // Maximum size of the buffer
@@ -29,6 +30,8 @@ pub unsafe extern "C" fn strfmon(
         Err(_) => return -1, // Invalid format string
     };
 
+    let monetary = LocaleMonetaryInfo::current();
+
     // Create a mutable slice for the output buffer
     let buffer = unsafe { slice::from_raw_parts_mut(s as *mut u8, maxsize) };
     let mut pos = 0;
@@ -120,8 +123,7 @@ pub unsafe extern "C" fn strfmon(
                 // International formatting
                 flags.international = true;
                 let value = unsafe { args.arg::<f64>() }; // Get the argument as f64
-                if let Some(written) =
-                    format_monetary(&mut buffer[pos..], value, &DEFAULT_MONETARY, &flags)
+                if let Some(written) = format_monetary(&mut buffer[pos..], value, &monetary, &flags)
                 {
                     pos += written; // Update the position
                 } else {
@@ -131,8 +133,7 @@ pub unsafe extern "C" fn strfmon(
             Some('n') => {
                 // Locale-specific formatting
                 let value = unsafe { args.arg::<f64>() }; // Get the argument as f64
-                if let Some(written) =
-                    format_monetary(&mut buffer[pos..], value, &DEFAULT_MONETARY, &flags)
+                if let Some(written) = format_monetary(&mut buffer[pos..], value, &monetary, &flags)
                 {
                     pos += written; // Update the position
                 } else {
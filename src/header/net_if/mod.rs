@@ -2,12 +2,13 @@
 //!
 //! See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/net_if.h.html>.
 
-use core::ptr::null;
+use core::ptr;
 
-use alloc::ffi::CString;
+use alloc::{boxed::Box, ffi::CString, vec::Vec};
 
 use crate::{
     c_str::CStr,
+    header::ifaddrs::{Interface, interfaces},
     platform::{ERRNO, types::*},
 };
 
@@ -17,70 +18,120 @@ use super::errno::ENXIO;
 #[repr(C)]
 pub struct if_nameindex {
     if_index: c_uint,
-    if_name: *const c_char,
+    if_name: *mut c_char,
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/net_if.h.html>.
 pub const IF_NAMESIZE: usize = 16;
 
-const IF_STUB_INTERFACE: *const c_char = (c"stub").as_ptr();
+// Flags of interfaces, as in `ifa_flags` of `getifaddrs`
+pub const IFF_UP: c_int = 0x1;
+pub const IFF_BROADCAST: c_int = 0x2;
+pub const IFF_DEBUG: c_int = 0x4;
+pub const IFF_LOOPBACK: c_int = 0x8;
+pub const IFF_POINTOPOINT: c_int = 0x10;
+pub const IFF_NOTRAILERS: c_int = 0x20;
+pub const IFF_RUNNING: c_int = 0x40;
+pub const IFF_NOARP: c_int = 0x80;
+pub const IFF_PROMISC: c_int = 0x100;
+pub const IFF_ALLMULTI: c_int = 0x200;
+pub const IFF_MASTER: c_int = 0x400;
+pub const IFF_SLAVE: c_int = 0x800;
+pub const IFF_MULTICAST: c_int = 0x1000;
+pub const IFF_PORTSEL: c_int = 0x2000;
+pub const IFF_AUTOMEDIA: c_int = 0x4000;
+pub const IFF_DYNAMIC: c_int = 0x8000;
 
-const INTERFACES: &[if_nameindex] = &[
-    if_nameindex {
-        if_index: 1,
-        if_name: IF_STUB_INTERFACE,
-    },
-    if_nameindex {
-        if_index: 0,
-        if_name: null::<c_char>(),
-    },
-];
+/// Finds the interface that `matches`, setting `ENXIO` if there is none.
+fn find_interface(matches: impl Fn(&Interface) -> bool) -> Option<Interface> {
+    let interface = interfaces()
+        .ok()
+        .and_then(|interfaces| interfaces.into_iter().find(|interface| matches(interface)));
+    if interface.is_none() {
+        ERRNO.set(ENXIO);
+    }
+    interface
+}
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/if_freenameindex.html>.
 ///
 /// # Safety
-/// this is a no-op: the list returned by if_nameindex() is a ref to a constant
+/// `s` must be a list returned by [`if_nameindex`], which is freed along
+/// with its names.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn if_freenameindex(s: *mut if_nameindex) {}
+pub unsafe extern "C" fn if_freenameindex(s: *mut if_nameindex) {
+    if s.is_null() {
+        return;
+    }
+    let mut len = 0;
+    while !(*s.add(len)).if_name.is_null() {
+        drop(CString::from_raw((*s.add(len)).if_name));
+        len += 1;
+    }
+    // With the entry at the end
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(s, len + 1)));
+}
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/if_indextoname.html>.
 ///
 /// # Safety
-/// Returns only static lifetime references to const names, does not reuse the buf pointer.
-/// Returns NULL in case of not found + ERRNO being set to ENXIO.
-/// Currently only checks against inteface index 1.
+/// `buf` must have room for [`IF_NAMESIZE`] bytes. Returns `buf`, or NULL with
+/// `errno` set to `ENXIO` if there is no interface with the index.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn if_indextoname(idx: c_uint, buf: *mut c_char) -> *const c_char {
-    if idx == 1 {
-        return IF_STUB_INTERFACE;
-    }
-    ERRNO.set(ENXIO);
-    null::<c_char>()
+pub unsafe extern "C" fn if_indextoname(idx: c_uint, buf: *mut c_char) -> *mut c_char {
+    let Some(interface) = find_interface(|interface| interface.index == idx) else {
+        return ptr::null_mut();
+    };
+    let name = &interface.name[..interface.name.len().min(IF_NAMESIZE - 1)];
+    ptr::copy_nonoverlapping(name.as_ptr() as *const c_char, buf, name.len());
+    *buf.add(name.len()) = 0;
+    buf
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/if_nameindex.html>.
 ///
 /// # Safety
-/// Returns a constant pointer to a pre defined const stub list
-/// The end of the list is determined by an if_nameindex struct having if_index 0 and if_name NULL
+/// Returns a list to free with [`if_freenameindex`], or NULL if the
+/// interfaces could not be listed. The end of the list is determined by an
+/// if_nameindex struct having if_index 0 and if_name NULL.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn if_nameindex() -> *const if_nameindex {
-    &INTERFACES[0] as *const if_nameindex
+pub unsafe extern "C" fn if_nameindex() -> *mut if_nameindex {
+    let interfaces = match interfaces() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            err.sync();
+            return ptr::null_mut();
+        }
+    };
+    let list: Vec<if_nameindex> = interfaces
+        .into_iter()
+        .filter_map(|interface| {
+            CString::new(interface.name)
+                .ok()
+                .map(|name| (interface.index, name))
+        })
+        .map(|(if_index, name)| if_nameindex {
+            if_index,
+            if_name: name.into_raw(),
+        })
+        .chain(Some(if_nameindex {
+            if_index: 0,
+            if_name: ptr::null_mut(),
+        }))
+        .collect();
+    Box::into_raw(list.into_boxed_slice()) as *mut if_nameindex
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/if_nametoindex.html>.
 ///
 /// # Safety
-/// Compares the name to a constant string and only returns an int as a result.
-/// An invalid name string will return an index of 0
+/// `name` must be NULL or a valid string. Returns 0 if there is no interface
+/// with the name.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn if_nametoindex(name: *const c_char) -> c_uint {
-    if name == null::<c_char>() {
+    let Some(name) = CStr::from_nullable_ptr(name) else {
         return 0;
-    }
-    let name = CStr::from_ptr(name).to_str().unwrap_or("");
-    if name.eq("stub") {
-        return 1;
-    }
-    0
+    };
+    find_interface(|interface| interface.name == name.to_bytes())
+        .map_or(0, |interface| interface.index)
 }
@@ -15,6 +15,21 @@ use alloc::{string::String, vec::Vec};
 mod answer;
 mod query;
 
+pub const TYPE_A: u16 = 0x0001;
+pub const TYPE_PTR: u16 = 0x000C;
+pub const TYPE_AAAA: u16 = 0x001C;
+pub const TYPE_OPT: u16 = 0x0029;
+
+pub const CLASS_IN: u16 = 0x0001;
+
+pub const RCODE_SERVFAIL: u16 = 2;
+pub const RCODE_NXDOMAIN: u16 = 3;
+pub const RCODE_NOTIMP: u16 = 4;
+pub const RCODE_REFUSED: u16 = 5;
+
+/// Set in the flags of an answer that did not fit in a UDP datagram.
+pub const FLAG_TC: u16 = 0x0200;
+
 #[derive(Clone, Debug)]
 pub struct Dns {
     pub transaction_id: u16,
@@ -24,6 +39,11 @@ pub struct Dns {
 }
 
 impl Dns {
+    /// The response code that the server put in the flags.
+    pub fn rcode(&self) -> u16 {
+        self.flags & 0x000F
+    }
+
     pub fn compile(&self) -> Vec<u8> {
         let mut data = Vec::new();
 
@@ -47,7 +67,8 @@ impl Dns {
         push_n16!(0);
 
         for query in self.queries.iter() {
-            for part in query.name.split('.') {
+            // The root has no labels, and absolute names end with a dot
+            for part in query.name.split('.').filter(|part| !part.is_empty()) {
                 push_u8!(part.len() as u8);
                 data.extend_from_slice(part.as_bytes());
             }
@@ -58,6 +79,21 @@ impl Dns {
         data
     }
 
+    /// Compiles the packet with an OPT record, which tells the server that
+    /// answers of up to `payload_size` bytes fit in a UDP datagram. See
+    /// <https://www.rfc-editor.org/rfc/rfc6891#section-6>.
+    pub fn compile_edns(&self, payload_size: u16) -> Vec<u8> {
+        let mut data = self.compile();
+        // One additional record
+        data[10..12].copy_from_slice(&1u16.to_be_bytes());
+        // For the root domain, with no extended flags or options
+        data.push(0);
+        data.extend_from_slice(&TYPE_OPT.to_be_bytes());
+        data.extend_from_slice(&payload_size.to_be_bytes());
+        data.extend_from_slice(&[0; 6]);
+        data
+    }
+
     pub fn parse(data: &[u8]) -> Result<Self, String> {
         let name_ind = 0b1100_0000;
         let mut i = 0;
@@ -1,13 +1,32 @@
-use alloc::{boxed::Box, str::SplitWhitespace, vec::Vec};
-use core::{mem, ptr};
+use alloc::{
+    boxed::Box,
+    str::SplitWhitespace,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ptr, slice,
+};
 
 use crate::{
-    c_str::CString,
+    c_str::{CStr, CString},
     error::ResultExt,
+    fs::File,
     header::{
-        arpa_inet::inet_aton, fcntl::O_RDONLY, netinet_in::in_addr, sys_socket::constants::AF_INET,
+        arpa_inet::inet_aton,
+        errno::{EAGAIN, ECONNREFUSED, EIO, ENOENT, ETIMEDOUT},
+        fcntl::O_RDONLY,
+        netinet_in::in_addr,
+        sys_socket::{
+            constants::{AF_INET, AF_INET6},
+            socklen_t,
+        },
         unistd::SEEK_SET,
     },
+    io::{BufReader, prelude::*},
+    nss::{self, Database, Source, Status},
     platform::{
         Pal, Sys,
         rlb::{Line, RawLineBuffer},
@@ -16,7 +35,11 @@ use crate::{
     raw_cell::RawCell,
 };
 
-use super::{bytes_to_box_str, hostent};
+use super::{
+    H_ERRNO, HOST_NOT_FOUND, NO_DATA, TRY_AGAIN, bytes_to_box_str, hostent,
+    lookup::{LookupHost, in_addr_to_ip, lookup_host, parse_ip_string},
+    module_entry,
+};
 
 static mut HOSTDB: c_int = -1;
 pub static mut HOST_ENTRY: hostent = hostent {
@@ -29,11 +52,51 @@ pub static mut HOST_ENTRY: hostent = hostent {
 pub static HOST_NAME: RawCell<Option<Vec<u8>>> = RawCell::new(None);
 pub static HOST_ALIASES: RawCell<Option<Vec<Vec<u8>>>> = RawCell::new(None);
 static _HOST_ALIASES: RawCell<Option<Vec<*mut c_char>>> = RawCell::new(None);
-pub static mut HOST_ADDR: Option<in_addr> = None;
+pub static mut HOST_ADDR: Option<IpAddr> = None;
 pub static mut HOST_ADDR_LIST: [*mut c_char; 2] = [ptr::null_mut(); 2];
-pub static mut _HOST_ADDR_LIST: [u8; 4] = [0u8; 4];
+pub static mut _HOST_ADDR_LIST: [u8; 16] = [0u8; 16];
 static mut H_POS: usize = 0;
 pub static mut HOST_STAYOPEN: c_int = 0;
+// The strings of entries from modules of `nsswitch.conf`
+static HOST_BUF: RawCell<Option<Box<[u8]>>> = RawCell::new(None);
+
+type GethostbynameR = unsafe extern "C" fn(
+    *const c_char,
+    c_int,
+    *mut hostent,
+    *mut c_char,
+    size_t,
+    *mut c_int,
+    *mut c_int,
+) -> c_int;
+type GethostbyaddrR = unsafe extern "C" fn(
+    *const c_void,
+    socklen_t,
+    c_int,
+    *mut hostent,
+    *mut c_char,
+    size_t,
+    *mut c_int,
+    *mut c_int,
+) -> c_int;
+
+/// Makes `addr` the address of [`HOST_ENTRY`], returning its `h_addrtype`
+/// and `h_length`.
+pub unsafe fn set_host_addr(addr: IpAddr) -> (c_int, c_int) {
+    let (family, len) = match addr {
+        IpAddr::V4(v4) => {
+            _HOST_ADDR_LIST[..4].copy_from_slice(&v4.octets());
+            (AF_INET, 4)
+        }
+        IpAddr::V6(v6) => {
+            _HOST_ADDR_LIST = v6.octets();
+            (AF_INET6, 16)
+        }
+    };
+    HOST_ADDR_LIST = [&raw mut _HOST_ADDR_LIST as *mut c_char, ptr::null_mut()];
+    HOST_ADDR = Some(addr);
+    (family, len)
+}
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn endhostent() {
@@ -79,16 +142,18 @@ pub unsafe extern "C" fn gethostent() -> *mut hostent {
 
     let mut iter: SplitWhitespace = r.split_whitespace();
 
-    let addr_vec: Vec<u8> = iter.next().unwrap().bytes().chain(Some(b'\0')).collect();
-    let addr_cstr = addr_vec.as_slice().as_ptr() as *const c_char;
-    let mut addr = mem::MaybeUninit::uninit();
-    inet_aton(addr_cstr, addr.as_mut_ptr());
-    let addr = addr.assume_init();
-
-    _HOST_ADDR_LIST = addr.s_addr.to_ne_bytes();
-    HOST_ADDR_LIST = [&raw mut _HOST_ADDR_LIST as *mut c_char, ptr::null_mut()];
-
-    HOST_ADDR = Some(addr);
+    let addr_str = iter.next().unwrap();
+    let addr = match addr_str.parse::<Ipv6Addr>() {
+        Ok(addr) => IpAddr::V6(addr),
+        Err(_) => {
+            let addr_vec: Vec<u8> = addr_str.bytes().chain(Some(b'\0')).collect();
+            let addr_cstr = addr_vec.as_slice().as_ptr() as *const c_char;
+            let mut addr = in_addr::default();
+            inet_aton(addr_cstr, &mut addr);
+            IpAddr::V4(in_addr_to_ip(addr))
+        }
+    };
+    let (h_addrtype, h_length) = set_host_addr(addr);
 
     let host_name = iter.next().unwrap().bytes().chain(Some(b'\0')).collect();
 
@@ -111,8 +176,8 @@ pub unsafe extern "C" fn gethostent() -> *mut hostent {
     HOST_ENTRY = hostent {
         h_name: HOST_NAME.unsafe_mut().as_mut().unwrap().as_mut_ptr() as *mut c_char,
         h_aliases: host_aliases.as_mut_slice().as_mut_ptr(),
-        h_addrtype: AF_INET,
-        h_length: 4,
+        h_addrtype,
+        h_length,
         h_addr_list: &raw mut HOST_ADDR_LIST as *mut _,
     };
     _HOST_ALIASES.unsafe_set(Some(host_aliases));
@@ -121,3 +186,191 @@ pub unsafe extern "C" fn gethostent() -> *mut hostent {
     }
     &raw mut HOST_ENTRY as *mut hostent
 }
+
+/// Sets `h_errno` for a lookup that failed, returning what that means to the
+/// name service switch.
+pub fn host_error(h_errno: c_int) -> Status {
+    H_ERRNO.set(h_errno);
+    match h_errno {
+        HOST_NOT_FOUND | NO_DATA => Status::NotFound,
+        TRY_AGAIN => Status::TryAgain,
+        _ => Status::Unavail,
+    }
+}
+
+/// Looks up `name` with `_nss_<module>_gethostbyname2_r`, into
+/// [`HOST_ENTRY`].
+pub unsafe fn module_host_by_name(
+    module: &str,
+    name: *const c_char,
+    af: c_int,
+) -> Result<*mut hostent, Status> {
+    // Left alone if the module is never called
+    let mut h_errno = H_ERRNO.get();
+    module_entry(
+        module,
+        "gethostbyname2_r",
+        &raw mut HOST_ENTRY,
+        &HOST_BUF,
+        |symbol, entry, buf, errnop| {
+            mem::transmute::<_, GethostbynameR>(symbol)(
+                name,
+                af,
+                entry,
+                buf.as_mut_ptr() as *mut c_char,
+                buf.len(),
+                errnop,
+                &mut h_errno,
+            )
+        },
+    )
+    .map_err(|status| {
+        H_ERRNO.set(h_errno);
+        status
+    })
+}
+
+/// Looks up the address at `addr` with `_nss_<module>_gethostbyaddr_r`,
+/// into [`HOST_ENTRY`].
+pub unsafe fn module_host_by_addr(
+    module: &str,
+    addr: *const c_void,
+    len: socklen_t,
+    af: c_int,
+) -> Result<*mut hostent, Status> {
+    let mut h_errno = H_ERRNO.get();
+    module_entry(
+        module,
+        "gethostbyaddr_r",
+        &raw mut HOST_ENTRY,
+        &HOST_BUF,
+        |symbol, entry, buf, errnop| {
+            mem::transmute::<_, GethostbyaddrR>(symbol)(
+                addr,
+                len,
+                af,
+                entry,
+                buf.as_mut_ptr() as *mut c_char,
+                buf.len(),
+                errnop,
+                &mut h_errno,
+            )
+        },
+    )
+    .map_err(|status| {
+        H_ERRNO.set(h_errno);
+        status
+    })
+}
+
+/// Whether addresses like `addr` are asked for with `family`.
+fn wanted(addr: &IpAddr, family: c_int) -> bool {
+    match addr {
+        IpAddr::V4(_) => family != AF_INET6,
+        IpAddr::V6(_) => family != AF_INET,
+    }
+}
+
+/// Finds the addresses of `host` in `/etc/hosts`. Unlike [`gethostent`],
+/// this keeps no state, so that [`getaddrinfo`](super::getaddrinfo) stays
+/// reentrant.
+fn files_resolve(host: &str, family: c_int) -> Result<LookupHost, Status> {
+    let Ok(file) = File::open(c"/etc/hosts".into(), O_RDONLY) else {
+        return Err(Status::Unavail);
+    };
+
+    let mut addrs = Vec::new();
+    let mut canonical_name = None;
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else { continue };
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        let Some(addr) = fields.next().and_then(parse_ip_string) else {
+            continue;
+        };
+        let names: Vec<&str> = fields.collect();
+        if wanted(&addr, family) && names.iter().any(|name| name.eq_ignore_ascii_case(host)) {
+            // The first name of a line is the canonical one
+            canonical_name.get_or_insert_with(|| names[0].to_string());
+            addrs.push(addr);
+        }
+    }
+    if addrs.is_empty() {
+        return Err(Status::NotFound);
+    }
+    Ok(LookupHost::new(addrs, canonical_name))
+}
+
+/// Finds the addresses of `host` with `_nss_<module>_gethostbyname2_r`, once
+/// for each family asked for.
+unsafe fn module_resolve(module: &str, host: &str, family: c_int) -> Result<LookupHost, Status> {
+    let name = CString::new(host).map_err(|_| Status::NotFound)?;
+    let families: &[c_int] = match family {
+        AF_INET => &[AF_INET],
+        AF_INET6 => &[AF_INET6],
+        _ => &[AF_INET, AF_INET6],
+    };
+
+    let mut addrs = Vec::new();
+    let mut canonical_name = None;
+    let mut error = Status::NotFound;
+    for &af in families {
+        let mut entry: hostent = mem::zeroed();
+        let mut h_errno = 0;
+        // The entry points into the buffer, so it must outlive the reads
+        let buffer = nss::module_lookup(module, "gethostbyname2_r", None, |symbol, buf, errnop| {
+            mem::transmute::<_, GethostbynameR>(symbol)(
+                name.as_ptr(),
+                af,
+                &mut entry,
+                buf.as_mut_ptr() as *mut c_char,
+                buf.len(),
+                errnop,
+                &mut h_errno,
+            )
+        });
+        let _buffer = match buffer {
+            Ok(buffer) => buffer,
+            Err(status) => {
+                error = status;
+                continue;
+            }
+        };
+
+        if canonical_name.is_none() && !entry.h_name.is_null() {
+            canonical_name = CStr::from_ptr(entry.h_name).to_str().ok().map(String::from);
+        }
+        let mut cp = entry.h_addr_list;
+        while !cp.is_null() && !(*cp).is_null() {
+            let bytes = slice::from_raw_parts(*cp as *const u8, entry.h_length as usize);
+            if let Ok(octets) = <[u8; 4]>::try_from(bytes) {
+                addrs.push(IpAddr::V4(Ipv4Addr::from(octets)));
+            } else if let Ok(octets) = <[u8; 16]>::try_from(bytes) {
+                addrs.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            cp = cp.offset(1);
+        }
+    }
+    if addrs.is_empty() {
+        return Err(error);
+    }
+    Ok(LookupHost::new(addrs, canonical_name))
+}
+
+/// Looks up the addresses of `host` in the sources of `hosts` in
+/// `nsswitch.conf`, with the families and errors of [`lookup_host`].
+pub fn resolve_host(host: &str, family: c_int) -> Result<LookupHost, c_int> {
+    let host = nss::dispatch(Database::Hosts, |source| match source {
+        Source::Files => files_resolve(host, family),
+        Source::Dns => lookup_host(host, family).map_err(|err| match err {
+            ENOENT => Status::NotFound,
+            EAGAIN | EIO | ETIMEDOUT => Status::TryAgain,
+            _ => Status::Unavail,
+        }),
+        Source::Module(module) => unsafe { module_resolve(module, host, family) },
+    });
+    host.map_err(|status| match status {
+        Status::NotFound => ENOENT,
+        Status::TryAgain => EAGAIN,
+        _ => ECONNREFUSED,
+    })
+}
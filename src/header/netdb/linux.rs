@@ -1,20 +1,13 @@
-use crate::{
-    c_str::CString,
-    error::Errno,
-    fs::File,
-    header::{errno, fcntl},
-    io::{BufRead, BufReader},
-};
+use crate::{fs::File, header::fcntl, io::Read};
 use alloc::string::String;
 
-pub fn get_dns_server() -> Result<String, Errno> {
-    let file = File::open(c"/etc/resolv.conf".into(), fcntl::O_RDONLY).map(BufReader::new)?;
+use super::resolv_conf::ResolvConf;
 
-    for line in file.lines().map_while(Result::ok) {
-        if let Some(dns) = line.strip_prefix("nameserver ") {
-            return Ok(dns.into());
-        }
+pub fn get_resolv_conf() -> ResolvConf {
+    let mut string = String::new();
+    // Without the file, the defaults are used
+    if let Ok(mut file) = File::open(c"/etc/resolv.conf".into(), fcntl::O_RDONLY) {
+        let _ = file.read_to_string(&mut string);
     }
-
-    Err(Errno(errno::EIO).sync())
+    ResolvConf::parse(&string)
 }
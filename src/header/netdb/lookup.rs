@@ -3,243 +3,570 @@ use alloc::{
     string::{String, ToString},
     vec::{IntoIter, Vec},
 };
-use core::mem;
+use core::{
+    fmt::Write,
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use crate::{
     out::Out,
-    platform::{Pal, Sys, types::*},
+    platform::{self, Pal, Sys, types::*},
 };
 
 use crate::header::{
     errno::*,
-    netinet_in::{IPPROTO_UDP, htons, in_addr, sockaddr_in},
+    netinet_in::{
+        IPPROTO_TCP, IPPROTO_UDP, htons, in_addr, in6_addr, ntohs, sockaddr_in, sockaddr_in6,
+    },
+    poll::{POLLIN, poll, pollfd},
+    pthread::{PTHREAD_CANCEL_DISABLE, PTHREAD_CANCEL_ENABLE, pthread_setcancelstate},
     sys_socket::{
         self,
-        constants::{AF_INET, SOCK_DGRAM},
-        sockaddr, socklen_t,
+        constants::{AF_INET, AF_INET6, SOCK_CLOEXEC, SOCK_DGRAM, SOCK_STREAM},
+        sa_family_t, sockaddr, sockaddr_storage, socklen_t,
     },
     time::{self, timespec},
 };
 
 use super::{
-    dns::{Dns, DnsQuery},
-    sys::get_dns_server,
+    dns::{
+        CLASS_IN, Dns, DnsQuery, FLAG_TC, RCODE_NOTIMP, RCODE_NXDOMAIN, RCODE_REFUSED,
+        RCODE_SERVFAIL, TYPE_A, TYPE_AAAA, TYPE_PTR,
+    },
+    resolv_conf::{MAXNS, ResolvConf},
+    sys::get_resolv_conf,
 };
 
-pub struct LookupHost(IntoIter<in_addr>);
+pub struct LookupHost {
+    addrs: IntoIter<IpAddr>,
+    canonical_name: Option<String>,
+}
+
+impl LookupHost {
+    pub fn new(addrs: Vec<IpAddr>, canonical_name: Option<String>) -> Self {
+        LookupHost {
+            addrs: addrs.into_iter(),
+            canonical_name,
+        }
+    }
+
+    /// The name that the addresses were found under at the end of any
+    /// aliases, if they came from DNS.
+    pub fn canonical_name(&self) -> Option<&str> {
+        self.canonical_name.as_deref()
+    }
+}
 
 impl Iterator for LookupHost {
-    type Item = in_addr;
+    type Item = IpAddr;
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        self.addrs.next()
     }
 }
 
-impl From<u32> for LookupHost {
-    /// from ipv4 address
-    fn from(s_addr: u32) -> Self {
-        LookupHost(vec![in_addr { s_addr }].into_iter())
+impl From<IpAddr> for LookupHost {
+    /// from an address that needs no lookup
+    fn from(addr: IpAddr) -> Self {
+        LookupHost {
+            addrs: vec![addr].into_iter(),
+            canonical_name: None,
+        }
     }
 }
 
-pub fn lookup_host(host: &str) -> Result<LookupHost, c_int> {
-    if let Some(host_direct_addr) = parse_ipv4_string(host) {
-        // already an ip address
-        return Ok(host_direct_addr.into());
+/// A socket address of either IP version, laid out for the socket API.
+pub enum SockAddr {
+    V4(sockaddr_in),
+    V6(sockaddr_in6),
+}
+
+impl SockAddr {
+    /// `port` is in host byte order.
+    pub fn new(addr: IpAddr, port: u16) -> Self {
+        match addr {
+            IpAddr::V4(addr) => SockAddr::V4(sockaddr_in {
+                sin_family: AF_INET as sa_family_t,
+                sin_port: htons(port),
+                sin_addr: ipv4_to_in_addr(addr),
+                ..Default::default()
+            }),
+            IpAddr::V6(addr) => SockAddr::V6(sockaddr_in6 {
+                sin6_family: AF_INET6 as sa_family_t,
+                sin6_port: htons(port),
+                sin6_flowinfo: 0,
+                sin6_addr: in6_addr {
+                    s6_addr: addr.octets(),
+                },
+                sin6_scope_id: 0,
+            }),
+        }
     }
 
-    let dns_string = get_dns_server().map_err(|e| e.0)?;
+    /// Reads the address and the port in host byte order out of a
+    /// `sockaddr` of `len` bytes.
+    pub unsafe fn read(addr: *const sockaddr, len: socklen_t) -> Option<(IpAddr, u16)> {
+        let len = len as usize;
+        if addr.is_null() || len < mem::size_of::<sa_family_t>() {
+            return None;
+        }
+        match c_int::from(unsafe { (*addr).sa_family }) {
+            AF_INET if len >= mem::size_of::<sockaddr_in>() => {
+                let sin = unsafe { &*(addr as *const sockaddr_in) };
+                Some((in_addr_to_ip(sin.sin_addr).into(), ntohs(sin.sin_port)))
+            }
+            AF_INET6 if len >= mem::size_of::<sockaddr_in6>() => {
+                let sin6 = unsafe { &*(addr as *const sockaddr_in6) };
+                let addr = Ipv6Addr::from(sin6.sin6_addr.s6_addr);
+                Some((addr.into(), ntohs(sin6.sin6_port)))
+            }
+            _ => None,
+        }
+    }
 
-    if let Some(dns_addr) = parse_ipv4_string(&dns_string) {
-        let mut timespec = timespec::default();
-        unsafe {
-            Sys::clock_gettime(
-                time::constants::CLOCK_REALTIME,
-                Out::from_mut(&mut timespec),
-            );
+    pub fn family(&self) -> c_int {
+        match self {
+            SockAddr::V4(_) => AF_INET,
+            SockAddr::V6(_) => AF_INET6,
         }
-        let tid = (timespec.tv_nsec >> 16) as u16;
-
-        let packet = Dns {
-            transaction_id: tid,
-            flags: 0x0100,
-            queries: vec![DnsQuery {
-                name: host.to_string(),
-                q_type: 0x0001,
-                q_class: 0x0001,
-            }],
-            answers: vec![],
-        };
+    }
 
-        let packet_data = packet.compile();
-        let packet_data_len = packet_data.len();
+    pub fn as_ptr(&self) -> *const sockaddr {
+        match self {
+            SockAddr::V4(sin) => sin as *const _ as *const sockaddr,
+            SockAddr::V6(sin6) => sin6 as *const _ as *const sockaddr,
+        }
+    }
 
-        let packet_data_box = packet_data.into_boxed_slice();
-        let packet_data_ptr = Box::into_raw(packet_data_box) as *mut _ as *mut c_void;
+    pub fn len(&self) -> socklen_t {
+        match self {
+            SockAddr::V4(sin) => mem::size_of_val(sin) as socklen_t,
+            SockAddr::V6(sin6) => mem::size_of_val(sin6) as socklen_t,
+        }
+    }
 
-        let dest = sockaddr_in {
-            sin_family: AF_INET as u16,
-            sin_port: htons(53),
-            sin_addr: in_addr { s_addr: dns_addr },
-            ..Default::default()
-        };
-        let dest_ptr = &dest as *const _ as *const sockaddr;
+    /// Moves the address to the heap, to be freed according to its length
+    /// as `freeaddrinfo` does.
+    pub fn into_raw(self) -> *mut sockaddr {
+        match self {
+            SockAddr::V4(sin) => Box::into_raw(Box::new(sin)) as *mut sockaddr,
+            SockAddr::V6(sin6) => Box::into_raw(Box::new(sin6)) as *mut sockaddr,
+        }
+    }
+}
+
+pub fn ipv4_to_in_addr(addr: Ipv4Addr) -> in_addr {
+    in_addr {
+        s_addr: u32::from_ne_bytes(addr.octets()),
+    }
+}
+
+pub fn in_addr_to_ip(addr: in_addr) -> Ipv4Addr {
+    Ipv4Addr::from(addr.s_addr.to_ne_bytes())
+}
+
+/// Finds the local address that the system would send from to reach `dst`,
+/// or `None` if there is no route there.
+pub fn source_address(dst: IpAddr) -> Option<IpAddr> {
+    // Connecting a datagram socket sends nothing, it only picks a route
+    let sock = Socket::connect(&SockAddr::new(dst, 65535), SOCK_DGRAM, IPPROTO_UDP).ok()?;
+    unsafe {
+        let mut src: sockaddr_storage = mem::zeroed();
+        let mut src_len = mem::size_of::<sockaddr_storage>() as socklen_t;
+        if sys_socket::getsockname(sock.fd, &mut src as *mut _ as *mut sockaddr, &mut src_len) < 0 {
+            return None;
+        }
+        SockAddr::read(&src as *const _ as *const sockaddr, src_len).map(|(addr, _)| addr)
+    }
+}
+
+/// Parses an IPv4 or IPv6 address in text form.
+pub fn parse_ip_string(ip_string: &str) -> Option<IpAddr> {
+    if let Ok(addr) = ip_string.trim().parse::<Ipv6Addr>() {
+        return Some(addr.into());
+    }
+    parse_ipv4_string(ip_string).map(|s_addr| in_addr_to_ip(in_addr { s_addr }).into())
+}
+
+/// The largest answer that we take over UDP with `edns0`.
+pub const EDNS_PAYLOAD_SIZE: u16 = 1232;
+
+/// Counts the queries, so that `rotate` spreads them over the nameservers.
+static NEXT_NAMESERVER: AtomicUsize = AtomicUsize::new(0);
+
+/// Closes the socket when dropped.
+///
+/// Cancellation is disabled for as long as the socket is open, as a thread
+/// cancelled in one of the calls on it would never close it.
+struct Socket {
+    fd: c_int,
+    cancel_state: c_int,
+}
+
+impl Socket {
+    fn connect(addr: &SockAddr, kind: c_int, protocol: u8) -> Result<Self, c_int> {
+        let fd =
+            unsafe { sys_socket::socket(addr.family(), kind | SOCK_CLOEXEC, protocol as c_int) };
+        if fd < 0 {
+            return Err(EIO);
+        }
+        let mut cancel_state = PTHREAD_CANCEL_ENABLE;
+        unsafe { pthread_setcancelstate(PTHREAD_CANCEL_DISABLE, &mut cancel_state) };
+        let sock = Socket { fd, cancel_state };
+        if unsafe { sys_socket::connect(sock.fd, addr.as_ptr(), addr.len()) } < 0 {
+            return Err(EIO);
+        }
+        Ok(sock)
+    }
 
-        let sock = unsafe {
-            let sock = sys_socket::socket(AF_INET, SOCK_DGRAM, IPPROTO_UDP as i32);
-            if sys_socket::connect(sock, dest_ptr, mem::size_of_val(&dest) as socklen_t) < 0 {
+    fn send_all(&self, mut data: &[u8]) -> Result<(), c_int> {
+        while !data.is_empty() {
+            let count =
+                unsafe { sys_socket::send(self.fd, data.as_ptr() as *const c_void, data.len(), 0) };
+            if count < 0 {
                 return Err(EIO);
             }
-            if sys_socket::send(sock, packet_data_ptr, packet_data_len, 0) < 0 {
-                Box::from_raw(packet_data_ptr);
+            data = &data[count as usize..];
+        }
+        Ok(())
+    }
+
+    /// Receives into `buf`, waiting until `deadline` at the latest.
+    fn recv(&self, buf: &mut [u8], deadline: i64) -> Result<usize, c_int> {
+        loop {
+            let timeout = deadline - monotonic_ms();
+            if timeout <= 0 {
+                return Err(ETIMEDOUT);
+            }
+            let mut fd = pollfd {
+                fd: self.fd,
+                events: POLLIN,
+                revents: 0,
+            };
+            match unsafe { poll(&mut fd, 1, timeout as c_int) } {
+                0 => return Err(ETIMEDOUT),
+                n if n < 0 && platform::ERRNO.get() == EINTR => continue,
+                n if n < 0 => return Err(EIO),
+                _ => (),
+            }
+            let count =
+                unsafe { sys_socket::recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+            if count < 0 {
                 return Err(EIO);
             }
-            sock
-        };
+            return Ok(count as usize);
+        }
+    }
 
-        unsafe {
-            Box::from_raw(packet_data_ptr);
+    /// Fills all of `buf`, waiting until `deadline` at the latest.
+    fn recv_exact(&self, buf: &mut [u8], deadline: i64) -> Result<(), c_int> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.recv(&mut buf[filled..], deadline)? {
+                // Closed before the end
+                0 => return Err(EIO),
+                count => filled += count,
+            }
         }
+        Ok(())
+    }
+}
 
-        let i = 0 as socklen_t;
-        let mut buf = vec![0u8; 65536];
-        let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+impl Drop for Socket {
+    fn drop(&mut self) {
+        Sys::close(self.fd);
+        unsafe { pthread_setcancelstate(self.cancel_state, ptr::null_mut()) };
+    }
+}
 
-        let count = unsafe { sys_socket::recv(sock, buf_ptr, 65536, 0) };
-        if count < 0 {
-            return Err(EIO);
+fn monotonic_ms() -> i64 {
+    let mut timespec = timespec::default();
+    unsafe {
+        Sys::clock_gettime(
+            time::constants::CLOCK_MONOTONIC,
+            Out::from_mut(&mut timespec),
+        );
+    }
+    timespec.tv_sec as i64 * 1000 + timespec.tv_nsec as i64 / 1_000_000
+}
+
+/// Sends `packet` to `server` over UDP, and returns the answer to it.
+fn send_udp(packet: &[u8], server: IpAddr, timeout: u32) -> Result<Vec<u8>, c_int> {
+    let sock = Socket::connect(&SockAddr::new(server, 53), SOCK_DGRAM, IPPROTO_UDP)?;
+    sock.send_all(packet)?;
+
+    let deadline = monotonic_ms() + i64::from(timeout) * 1000;
+    let mut buf = vec![0u8; 65536];
+    loop {
+        let count = sock.recv(&mut buf, deadline)?;
+        // Anything else is a stray answer to an earlier query
+        if count >= 12 && buf[..2] == packet[..2] {
+            buf.truncate(count);
+            return Ok(buf);
         }
+    }
+}
 
-        match Dns::parse(&buf[..count as usize]) {
-            Ok(response) => {
-                let addrs: Vec<_> = response
-                    .answers
-                    .into_iter()
-                    .filter_map(|answer| {
-                        if answer.a_type == 0x0001
-                            && answer.a_class == 0x0001
-                            && answer.data.len() == 4
-                        {
-                            let addr = in_addr {
-                                s_addr: u32::from_ne_bytes([
-                                    answer.data[0],
-                                    answer.data[1],
-                                    answer.data[2],
-                                    answer.data[3],
-                                ]),
-                            };
-                            Some(addr)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                Ok(LookupHost(addrs.into_iter()))
+/// Sends `packet` to `server` over TCP, for answers too large for UDP.
+fn send_tcp(packet: &[u8], server: IpAddr, timeout: u32) -> Result<Vec<u8>, c_int> {
+    let sock = Socket::connect(&SockAddr::new(server, 53), SOCK_STREAM, IPPROTO_TCP)?;
+    // Messages are preceded by their length
+    let mut data = (packet.len() as u16).to_be_bytes().to_vec();
+    data.extend_from_slice(packet);
+    sock.send_all(&data)?;
+
+    let deadline = monotonic_ms() + i64::from(timeout) * 1000;
+    let mut len = [0; 2];
+    sock.recv_exact(&mut len, deadline)?;
+    let mut buf = vec![0u8; u16::from_be_bytes(len) as usize];
+    sock.recv_exact(&mut buf, deadline)?;
+    if buf.len() < 12 || buf[..2] != packet[..2] {
+        return Err(EIO);
+    }
+    Ok(buf)
+}
+
+/// Sends the query `packet` to the nameservers of `conf` in turn until one
+/// of them answers, and returns the answer.
+pub fn send_query(packet: &[u8], conf: &ResolvConf) -> Result<Vec<u8>, c_int> {
+    let servers = &conf.nameservers[..conf.nameservers.len().min(MAXNS)];
+    let first = if conf.rotate {
+        NEXT_NAMESERVER.fetch_add(1, Ordering::Relaxed)
+    } else {
+        0
+    };
+
+    let mut error = ETIMEDOUT;
+    let mut failure = None;
+    for _attempt in 0..conf.attempts.max(1) {
+        for i in 0..servers.len() {
+            let server = servers[(first + i) % servers.len()];
+            let answer = if conf.use_vc {
+                send_tcp(packet, server, conf.timeout)
+            } else {
+                match send_udp(packet, server, conf.timeout) {
+                    Ok(answer) if u16::from_be_bytes([answer[2], answer[3]]) & FLAG_TC != 0 => {
+                        send_tcp(packet, server, conf.timeout)
+                    }
+                    answer => answer,
+                }
+            };
+            match answer {
+                // Another nameserver may well do better
+                Ok(answer)
+                    if matches!(
+                        u16::from(answer[3] & 0x0F),
+                        RCODE_SERVFAIL | RCODE_NOTIMP | RCODE_REFUSED
+                    ) =>
+                {
+                    failure = Some(answer);
+                }
+                Ok(answer) => return Ok(answer),
+                Err(err) => error = err,
             }
-            Err(_err) => Err(EINVAL),
         }
-    } else {
-        Err(EINVAL)
     }
+    failure.ok_or(error)
 }
 
-pub fn lookup_addr(addr: in_addr) -> Result<Vec<Vec<u8>>, c_int> {
-    let dns_string = get_dns_server().map_err(|e| e.0)?;
-
-    if let Some(dns_addr) = parse_ipv4_string(&dns_string) {
-        let addr: [u8; 4] = addr.s_addr.to_ne_bytes();
-        // Address intentionally backwards for reverse lookup
-        let name = format!(
-            "{}.{}.{}.{}.in-addr.arpa",
-            addr[3], addr[2], addr[1], addr[0]
+/// A transaction id for a new query.
+pub fn query_id() -> u16 {
+    let mut timespec = timespec::default();
+    unsafe {
+        Sys::clock_gettime(
+            time::constants::CLOCK_REALTIME,
+            Out::from_mut(&mut timespec),
         );
+    }
+    (timespec.tv_nsec >> 16) as u16
+}
 
-        let mut timespec = timespec::default();
-        unsafe {
-            Sys::clock_gettime(
-                time::constants::CLOCK_REALTIME,
-                Out::from_mut(&mut timespec),
-            )
-        };
-        let tid = (timespec.tv_nsec >> 16) as u16;
-
-        let packet = Dns {
-            transaction_id: tid,
-            flags: 0x0100,
-            queries: vec![DnsQuery {
-                name,
-                q_type: 0x000C,
-                q_class: 0x0001,
-            }],
-            answers: vec![],
-        };
+/// Asks the nameservers of `conf` for records of `q_type` for `name`.
+fn dns_query(name: String, q_type: u16, conf: &ResolvConf) -> Result<Dns, c_int> {
+    let packet = Dns {
+        transaction_id: query_id(),
+        flags: 0x0100,
+        queries: vec![DnsQuery {
+            name,
+            q_type,
+            q_class: CLASS_IN,
+        }],
+        answers: vec![],
+    };
+    let packet_data = if conf.edns0 {
+        packet.compile_edns(EDNS_PAYLOAD_SIZE)
+    } else {
+        packet.compile()
+    };
 
-        let packet_data = packet.compile();
-        let packet_data_len = packet_data.len();
-        let packet_data_box = packet_data.into_boxed_slice();
-        let packet_data_ptr = Box::into_raw(packet_data_box) as *mut _ as *mut c_void;
+    let answer = send_query(&packet_data, conf)?;
+    Dns::parse(&answer).map_err(|_err| EINVAL)
+}
 
-        let dest = sockaddr_in {
-            sin_family: AF_INET as u16,
-            sin_port: htons(53),
-            sin_addr: in_addr { s_addr: dns_addr },
-            ..Default::default()
-        };
+/// The names to try in turn for `host`, after the domains of the search
+/// list.
+pub fn search_names(host: &str, conf: &ResolvConf) -> Vec<String> {
+    // A trailing dot makes the name absolute
+    if let Some(host) = host.strip_suffix('.') {
+        return vec![host.to_string()];
+    }
+    let searched = conf
+        .search
+        .iter()
+        .map(|domain| format!("{}.{}", host, domain));
+    // Names with enough dots are more likely to be complete already
+    if host.matches('.').count() as u32 >= conf.ndots {
+        Some(host.to_string()).into_iter().chain(searched).collect()
+    } else {
+        searched.chain(Some(host.to_string())).collect()
+    }
+}
 
-        let dest_ptr = &dest as *const _ as *const sockaddr;
+/// Queries the addresses of `name` of one record type, along with the name
+/// that they belong to.
+///
+/// A name that does not exist fails with `ENOENT`, and a server that could
+/// not answer with `EAGAIN`.
+fn query_records(
+    name: String,
+    q_type: u16,
+    conf: &ResolvConf,
+) -> Result<(Vec<IpAddr>, Option<String>), c_int> {
+    let response = dns_query(name, q_type, conf)?;
+    match response.rcode() {
+        0 => (),
+        RCODE_NXDOMAIN => return Err(ENOENT),
+        RCODE_SERVFAIL => return Err(EAGAIN),
+        _ => return Err(ECONNREFUSED),
+    }
 
-        let sock = unsafe {
-            let sock = sys_socket::socket(AF_INET, SOCK_DGRAM, IPPROTO_UDP as i32);
-            if sys_socket::connect(sock, dest_ptr, mem::size_of_val(&dest) as socklen_t) < 0 {
-                return Err(EIO);
-            }
-            sock
+    // Any CNAME records come first, and the addresses are under the name
+    // that they lead to
+    let mut canonical_name = None;
+    let mut addrs = Vec::new();
+    for answer in response.answers {
+        if answer.a_type != q_type || answer.a_class != CLASS_IN {
+            continue;
+        }
+        let addr = match q_type {
+            TYPE_A => <[u8; 4]>::try_from(&answer.data[..])
+                .ok()
+                .map(|octets| Ipv4Addr::from(octets).into()),
+            TYPE_AAAA => <[u8; 16]>::try_from(&answer.data[..])
+                .ok()
+                .map(|octets| Ipv6Addr::from(octets).into()),
+            _ => None,
         };
+        if let Some(addr) = addr {
+            canonical_name.get_or_insert(answer.name);
+            addrs.push(addr);
+        }
+    }
+    Ok((addrs, canonical_name))
+}
 
-        unsafe {
-            if sys_socket::send(sock, packet_data_ptr, packet_data_len, 0) < 0 {
-                return Err(EIO);
+/// Queries the addresses of `host` of one record type under each name of
+/// the search list, until one of them has some.
+fn lookup_records(
+    host: &str,
+    q_type: u16,
+    conf: &ResolvConf,
+) -> Result<(Vec<IpAddr>, Option<String>), c_int> {
+    let mut result = Err(ENOENT);
+    for name in search_names(host, conf) {
+        match query_records(name, q_type, conf) {
+            Ok((addrs, canonical_name)) if !addrs.is_empty() => {
+                return Ok((addrs, canonical_name));
             }
+            Ok(none) => result = Ok(none),
+            Err(ENOENT) => (),
+            Err(err) => return Err(err),
         }
+    }
+    result
+}
 
-        unsafe {
-            Box::from_raw(packet_data_ptr);
-        }
+/// Looks up the addresses of `host`: IPv4 ones for `AF_INET`, IPv6 ones
+/// for `AF_INET6`, and both for `AF_UNSPEC`. Addresses in text form are
+/// returned as they are, whatever the family.
+pub fn lookup_host(host: &str, family: c_int) -> Result<LookupHost, c_int> {
+    if let Some(addr) = parse_ip_string(host) {
+        // already an ip address
+        return Ok(addr.into());
+    }
 
-        let i = mem::size_of::<sockaddr_in>() as socklen_t;
-        let mut buf = [0u8; 65536];
-        let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+    let mut q_types = Vec::new();
+    if family != AF_INET6 {
+        q_types.push(TYPE_A);
+    }
+    if family != AF_INET {
+        q_types.push(TYPE_AAAA);
+    }
 
-        let count = unsafe { sys_socket::recv(sock, buf_ptr, 65536, 0) };
-        if count < 0 {
-            return Err(EIO);
+    // A name may well have records of only one type, so one failed query
+    // is no reason to give up on the other
+    let conf = get_resolv_conf();
+    let mut addrs = Vec::new();
+    let mut canonical_name = None;
+    let mut error = None;
+    for q_type in q_types {
+        match lookup_records(host, q_type, &conf) {
+            Ok((found, name)) => {
+                addrs.extend(found);
+                canonical_name = canonical_name.or(name);
+            }
+            Err(err) => error = Some(err),
         }
+    }
+    match error {
+        Some(err) if addrs.is_empty() => Err(err),
+        _ => Ok(LookupHost {
+            addrs: addrs.into_iter(),
+            canonical_name,
+        }),
+    }
+}
 
-        match Dns::parse(&buf[..count as usize]) {
-            Ok(response) => {
-                let names = response
-                    .answers
-                    .into_iter()
-                    .filter_map(|answer| {
-                        if answer.a_type == 0x000C && answer.a_class == 0x0001 {
-                            // answer.data is encoded kinda weird.
-                            // Basically length-prefixed strings for each
-                            // subsection of the domain.
-                            // We need to parse this to insert periods where
-                            // they belong (ie at the end of each string)
-                            Some(parse_revdns_answer(&answer.data))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                Ok(names)
+pub fn lookup_addr(addr: IpAddr) -> Result<Vec<Vec<u8>>, c_int> {
+    // Address intentionally backwards for reverse lookup
+    let mut name = String::new();
+    match addr {
+        IpAddr::V4(addr) => {
+            let addr = addr.octets();
+            let _ = write!(
+                name,
+                "{}.{}.{}.{}.in-addr.arpa",
+                addr[3], addr[2], addr[1], addr[0]
+            );
+        }
+        IpAddr::V6(addr) => {
+            for byte in addr.octets().iter().rev() {
+                let _ = write!(name, "{:x}.{:x}.", byte & 0xf, byte >> 4);
             }
-            Err(_err) => Err(EINVAL),
+            name.push_str("ip6.arpa");
         }
-    } else {
-        Err(EINVAL)
     }
+
+    let response = dns_query(name, TYPE_PTR, &get_resolv_conf())?;
+    let names = response
+        .answers
+        .into_iter()
+        .filter_map(|answer| {
+            if answer.a_type == TYPE_PTR && answer.a_class == CLASS_IN {
+                // answer.data is encoded kinda weird.
+                // Basically length-prefixed strings for each
+                // subsection of the domain.
+                // We need to parse this to insert periods where
+                // they belong (ie at the end of each string)
+                Some(parse_revdns_answer(&answer.data))
+            } else {
+                None
+            }
+        })
+        .collect();
+    Ok(names)
 }
 
 fn parse_revdns_answer(data: &[u8]) -> Vec<u8> {
@@ -275,32 +602,19 @@ fn parse_revdns_answer(data: &[u8]) -> Vec<u8> {
     output
 }
 
+/// Parses a dotted-decimal IPv4 address into an `s_addr`, rejecting anything
+/// that is not one, such as a host name with four labels.
 pub fn parse_ipv4_string(ip_string: &str) -> Option<u32> {
-    let dns_vec: Vec<u8> = ip_string
-        .trim()
-        .split('.')
-        .map(|octet| octet.parse::<u8>().unwrap_or(0))
-        .collect();
-
-    if dns_vec.len() != 4 {
-        return None;
-    }
-
-    let mut dns_arr = [0u8; 4];
-
-    for (i, octet) in dns_vec.iter().enumerate() {
-        dns_arr[i] = *octet;
-    }
-
-    Some(u32::from_ne_bytes(dns_arr))
+    let addr = ip_string.trim().parse::<Ipv4Addr>().ok()?;
+    Some(u32::from_ne_bytes(addr.octets()))
 }
 
 #[cfg(test)]
 mod tests {
-    use alloc::str;
+    use alloc::{str, string::ToString};
     use core::ffi::CStr;
 
-    use super::parse_revdns_answer;
+    use super::{ResolvConf, parse_ip_string, parse_revdns_answer, search_names};
 
     // Actual response from a query
     const DNS_GOOGLE: &[u8] = &[3, 100, 110, 115, 6, 103, 111, 111, 103, 108, 101, 0];
@@ -381,4 +695,34 @@ mod tests {
             .expect("Valid UTF-8 bytes to CStr to Rust str should be valid");
         assert_eq!(EXPECTED_EMPTY_RESPONSE_RT, response_cstr_str);
     }
+
+    #[test]
+    fn search_list() {
+        let conf = ResolvConf {
+            search: vec!["a.example".to_string(), "b.example".to_string()],
+            ndots: 1,
+            ..ResolvConf::default()
+        };
+        assert_eq!(
+            vec!["host.a.example", "host.b.example", "host"],
+            search_names("host", &conf)
+        );
+        assert_eq!(
+            vec!["www.host", "www.host.a.example", "www.host.b.example"],
+            search_names("www.host", &conf)
+        );
+        assert_eq!(vec!["host"], search_names("host.", &conf));
+    }
+
+    #[test]
+    fn ip_literals() {
+        assert_eq!(
+            Some("192.0.2.1".parse().unwrap()),
+            parse_ip_string("192.0.2.1")
+        );
+        assert_eq!(Some("::1".parse().unwrap()), parse_ip_string("::1"));
+        assert_eq!(None, parse_ip_string("www.mail.example.com"));
+        assert_eq!(None, parse_ip_string("192.0.2.256"));
+        assert_eq!(None, parse_ip_string("192.0.2"));
+    }
 }
@@ -1,31 +1,48 @@
 //! netdb implementation for Redox, following http://pubs.opengroup.org/onlinepubs/7908799/xns/netdb.h.html
 
-mod dns;
+pub(crate) mod dns;
+pub(crate) mod resolv_conf;
+mod rfc6724;
 
 use core::{
     cell::Cell,
     fmt::Write,
     mem,
-    net::Ipv4Addr,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     ptr, slice,
     str::{self, FromStr},
 };
 
-use alloc::{borrow::ToOwned, boxed::Box, str::SplitWhitespace, string::ToString, vec::Vec};
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    str::SplitWhitespace,
+    string::{String, ToString},
+    vec::Vec,
+};
 
 use crate::{
     c_str::{CStr, CString},
     error::ResultExt,
+    fs::File,
     header::{
         arpa_inet::inet_aton,
         errno::*,
         fcntl::O_RDONLY,
-        netinet_in::{htons, in_addr, ntohl, sockaddr_in, sockaddr_in6},
+        ifaddrs,
+        netinet_in::{
+            IPPROTO_TCP, IPPROTO_UDP, htons, in_addr, ntohl, ntohs, sockaddr_in, sockaddr_in6,
+        },
         stdlib::atoi,
         strings::strcasecmp,
-        sys_socket::{constants::AF_INET, sa_family_t, sockaddr, socklen_t},
+        sys_socket::{
+            constants::{AF_INET, AF_INET6, AF_UNSPEC, SOCK_DGRAM, SOCK_RAW, SOCK_STREAM},
+            sa_family_t, sockaddr, socklen_t,
+        },
         unistd::SEEK_SET,
     },
+    io::{BufReader, prelude::*},
+    nss::{self, Database, Source, Status},
     platform::{
         self, Pal, Sys,
         rlb::{Line, RawLineBuffer},
@@ -174,6 +191,48 @@ static SERV_PROTO: RawCell<Option<Vec<u8>>> = RawCell::new(None);
 static mut S_POS: usize = 0;
 static mut SERV_STAYOPEN: c_int = 0;
 
+// The strings of entries from modules of `nsswitch.conf`
+static PROTO_BUF: RawCell<Option<Box<[u8]>>> = RawCell::new(None);
+static SERV_BUF: RawCell<Option<Box<[u8]>>> = RawCell::new(None);
+
+type GetprotobynameR =
+    unsafe extern "C" fn(*const c_char, *mut protoent, *mut c_char, size_t, *mut c_int) -> c_int;
+type GetprotobynumberR =
+    unsafe extern "C" fn(c_int, *mut protoent, *mut c_char, size_t, *mut c_int) -> c_int;
+type GetservbynameR = unsafe extern "C" fn(
+    *const c_char,
+    *const c_char,
+    *mut servent,
+    *mut c_char,
+    size_t,
+    *mut c_int,
+) -> c_int;
+type GetservbyportR = unsafe extern "C" fn(
+    c_int,
+    *const c_char,
+    *mut servent,
+    *mut c_char,
+    size_t,
+    *mut c_int,
+) -> c_int;
+
+/// Looks up an entry with `_nss_<module>_<function>`, which `call` casts to
+/// its type and calls with `entry`, a buffer for the strings of the entry
+/// and `errnop`. The buffer is kept in `buffer` until the next lookup.
+unsafe fn module_entry<T>(
+    module: &str,
+    function: &str,
+    entry: *mut T,
+    buffer: &RawCell<Option<Box<[u8]>>>,
+    mut call: impl FnMut(*mut c_void, *mut T, &mut [u8], &mut c_int) -> c_int,
+) -> Result<*mut T, Status> {
+    let found = nss::module_lookup(module, function, None, |symbol, buf, errnop| {
+        call(symbol, entry, buf, errnop)
+    })?;
+    buffer.unsafe_set(found);
+    Ok(entry)
+}
+
 fn bytes_to_box_str(bytes: &[u8]) -> Box<str> {
     Box::from(core::str::from_utf8(bytes).unwrap_or(""))
 }
@@ -199,7 +258,7 @@ pub unsafe extern "C" fn endservent() {
 /// Resolve a host name from a given network address.
 ///
 /// # Arguments
-/// * `v` - Address to resolve as a non-null [`in_addr`]
+/// * `v` - Address to resolve as a non-null [`in_addr`] or `in6_addr`
 /// * `length` -
 /// * `format` - AF_INET or AF_INET6
 ///
@@ -225,24 +284,35 @@ pub unsafe extern "C" fn gethostbyaddr(
         !v.is_null(),
         "`gethostbyaddr()` called with null `v` (in_addr)"
     );
-    // Uncomment if optional IPv6 support is added
-    // if length != SOCKLEN_AF_INET4 || length != SOCKLEN_AF_INET6 {
-    //     H_ERRNO.set(NO_RECOVERY);
-    //     return ptr::null_mut();
-    // }
-    if length != SOCKLEN_AF_INET4 {
-        H_ERRNO.set(NO_RECOVERY);
-        return ptr::null_mut();
-    }
-    let addr: in_addr = *(v as *mut in_addr);
+    let addr = match (format, length) {
+        (AF_INET, SOCKLEN_AF_INET4) => IpAddr::V4(in_addr_to_ip(*(v as *const in_addr))),
+        (AF_INET6, SOCKLEN_AF_INET6) => IpAddr::V6(Ipv6Addr::from(*(v as *const [u8; 16]))),
+        _ => {
+            H_ERRNO.set(NO_RECOVERY);
+            return ptr::null_mut();
+        }
+    };
+    let addr_bytes = slice::from_raw_parts(v as *const c_char, length as usize);
 
-    // check the hosts file first
+    let entry = nss::dispatch(Database::Hosts, |source| match source {
+        Source::Files => files_host_by_addr(addr_bytes, format),
+        Source::Dns => dns_host_by_addr(addr),
+        Source::Module(module) => module_host_by_addr(module, v, length, format),
+    });
+    entry.unwrap_or(ptr::null_mut())
+}
+
+unsafe fn files_host_by_addr(addr_bytes: &[c_char], format: c_int) -> Result<*mut hostent, Status> {
+    let length = addr_bytes.len();
     let mut p: *mut hostent;
     sethostent(HOST_STAYOPEN);
     while {
         p = gethostent();
         !p.is_null()
     } {
+        if (*p).h_addrtype != format {
+            continue;
+        }
         let mut cp = (*p).h_addr_list;
         loop {
             if cp.is_null() {
@@ -251,17 +321,17 @@ pub unsafe extern "C" fn gethostbyaddr(
             if (*cp).is_null() {
                 break;
             }
-            let mut cp_slice: [c_char; 4] = [0; 4];
-            (*cp).copy_to(cp_slice.as_mut_ptr(), 4);
-            let cp_s_addr = mem::transmute::<[c_char; 4], u32>(cp_slice);
-            if cp_s_addr == addr.s_addr {
+            if slice::from_raw_parts(*cp, length) == addr_bytes {
                 sethostent(HOST_STAYOPEN);
-                return p;
+                return Ok(p);
             }
             cp = cp.offset(1);
         }
     }
+    Err(host_error(HOST_NOT_FOUND))
+}
 
+unsafe fn dns_host_by_addr(addr: IpAddr) -> Result<*mut hostent, Status> {
     //TODO actually get aliases
     let mut _host_aliases: Vec<Vec<u8>> = Vec::new();
     _host_aliases.push(vec![b'\0']);
@@ -271,29 +341,22 @@ pub unsafe extern "C" fn gethostbyaddr(
 
     match lookup_addr(addr).map(|host_names| host_names.into_iter().next()) {
         Ok(Some(host_name)) => {
-            _HOST_ADDR_LIST = addr.s_addr.to_ne_bytes();
-            HOST_ADDR_LIST = [&raw mut _HOST_ADDR_LIST as *mut c_char, ptr::null_mut()];
+            let (h_addrtype, h_length) = set_host_addr(addr);
             HOST_NAME.unsafe_set(Some(host_name));
             HOST_ENTRY = hostent {
                 h_name: HOST_NAME.unsafe_mut().as_mut().unwrap().as_mut_ptr() as *mut c_char,
                 h_aliases: host_aliases.as_mut_slice().as_mut_ptr(),
-                h_addrtype: format,
-                h_length: length as i32,
+                h_addrtype,
+                h_length,
                 h_addr_list: &raw mut HOST_ADDR_LIST as *mut _,
             };
-            &raw mut HOST_ENTRY
+            Ok(&raw mut HOST_ENTRY)
         }
         // `glibc` sets errno if an address doesn't have a host name
         // `musl` uses the address as the host name in said case
-        Ok(None) => {
-            H_ERRNO.set(HOST_NOT_FOUND);
-            ptr::null_mut()
-        }
-        Err(e) => {
-            // TODO: Better error separation in lookup_addr
-            H_ERRNO.set(NO_RECOVERY);
-            ptr::null_mut()
-        }
+        Ok(None) => Err(host_error(HOST_NOT_FOUND)),
+        // TODO: Better error separation in lookup_addr
+        Err(e) => Err(host_error(NO_RECOVERY)),
     }
 }
 
@@ -315,8 +378,35 @@ pub unsafe extern "C" fn gethostbyaddr(
 #[unsafe(no_mangle)]
 #[deprecated]
 pub unsafe extern "C" fn gethostbyname(name: *const c_char) -> *mut hostent {
+    gethostbyname2(name, AF_INET)
+}
+
+/// Resolve host information by name or IP address, for addresses of the
+/// family `af`.
+///
+/// # Arguments
+/// * `name` - Host name or IP address.
+/// * `af` - AF_INET or AF_INET6
+///
+/// # Safety
+/// `name` must be a valid string.
+/// This function is not reentrant and may modify static data.
+///
+/// # Panics
+/// Panics if `name` is a null pointer.
+///
+/// # Deprecation
+/// A GNU extension, new code should use [`getaddrinfo`] instead.
+#[unsafe(no_mangle)]
+#[deprecated]
+pub unsafe extern "C" fn gethostbyname2(name: *const c_char, af: c_int) -> *mut hostent {
     let name_cstr =
-        CStr::from_nullable_ptr(name).expect("gethostbyname() called with a NULL pointer");
+        CStr::from_nullable_ptr(name).expect("gethostbyname2() called with a NULL pointer");
+    if af != AF_INET && af != AF_INET6 {
+        platform::ERRNO.set(EAFNOSUPPORT);
+        H_ERRNO.set(NO_RECOVERY);
+        return ptr::null_mut();
+    }
     let Ok(name_str) = str::from_utf8(name_cstr.to_bytes()) else {
         H_ERRNO.set(NO_RECOVERY);
         return ptr::null_mut();
@@ -325,21 +415,43 @@ pub unsafe extern "C" fn gethostbyname(name: *const c_char) -> *mut hostent {
     // Addresses and hostnames are both valid, so we'll check addresses first
     // The standard doesn't define what to do when called with addresses
     // Some implementations just skip resolution and copy the address to h_name
-    if let Some(s_addr) = parse_ipv4_string(name_str) {
-        let addr = in_addr { s_addr };
-        return gethostbyaddr(&addr as *const _ as *const c_void, 4, AF_INET);
+    match parse_ip_string(name_str) {
+        Some(IpAddr::V4(addr)) if af == AF_INET => {
+            let addr = ipv4_to_in_addr(addr);
+            return gethostbyaddr(&addr as *const _ as *const c_void, SOCKLEN_AF_INET4, af);
+        }
+        Some(IpAddr::V6(addr)) if af == AF_INET6 => {
+            let addr = addr.octets();
+            return gethostbyaddr(addr.as_ptr() as *const c_void, SOCKLEN_AF_INET6, af);
+        }
+        Some(_) => {
+            H_ERRNO.set(HOST_NOT_FOUND);
+            return ptr::null_mut();
+        }
+        None => (),
     }
 
-    // check the hosts file first
+    let entry = nss::dispatch(Database::Hosts, |source| match source {
+        Source::Files => files_host_by_name(name, af),
+        Source::Dns => dns_host_by_name(name_cstr, name_str, af),
+        Source::Module(module) => module_host_by_name(module, name, af),
+    });
+    entry.unwrap_or(ptr::null_mut())
+}
+
+unsafe fn files_host_by_name(name: *const c_char, af: c_int) -> Result<*mut hostent, Status> {
     let mut p: *mut hostent;
     sethostent(HOST_STAYOPEN);
     while {
         p = gethostent();
         !p.is_null()
     } {
+        if (*p).h_addrtype != af {
+            continue;
+        }
         if strcasecmp((*p).h_name, name) == 0 {
             sethostent(HOST_STAYOPEN);
-            return p;
+            return Ok(p);
         }
         let mut cp = (*p).h_aliases;
         loop {
@@ -351,32 +463,37 @@ pub unsafe extern "C" fn gethostbyname(name: *const c_char) -> *mut hostent {
             }
             if strcasecmp(*cp, name) == 0 {
                 sethostent(HOST_STAYOPEN);
-                return p;
+                return Ok(p);
             }
             cp = cp.offset(1);
         }
     }
+    Err(host_error(HOST_NOT_FOUND))
+}
 
-    let mut host = match lookup_host(name_str) {
+unsafe fn dns_host_by_name(
+    name_cstr: CStr,
+    name_str: &str,
+    af: c_int,
+) -> Result<*mut hostent, Status> {
+    let mut host = match lookup_host(name_str, af) {
         Ok(lookuphost) => lookuphost,
         Err(e) => {
-            H_ERRNO.set(NO_RECOVERY);
-            return ptr::null_mut();
+            return Err(host_error(match e {
+                ENOENT => HOST_NOT_FOUND,
+                EAGAIN | ETIMEDOUT => TRY_AGAIN,
+                _ => NO_RECOVERY,
+            }));
         }
     };
     let host_addr = match host.next() {
         Some(result) => result,
-        None => {
-            H_ERRNO.set(HOST_NOT_FOUND);
-            return ptr::null_mut();
-        }
+        None => return Err(host_error(HOST_NOT_FOUND)),
     };
 
     let host_name: Vec<u8> = name_cstr.to_bytes().to_vec();
     HOST_NAME.unsafe_set(Some(host_name));
-    _HOST_ADDR_LIST = host_addr.s_addr.to_ne_bytes();
-    HOST_ADDR_LIST = [&raw mut _HOST_ADDR_LIST as *mut c_char, ptr::null_mut()];
-    HOST_ADDR = Some(host_addr);
+    let (h_addrtype, h_length) = set_host_addr(host_addr);
 
     //TODO actually get aliases
     let mut _host_aliases: Vec<Vec<u8>> = Vec::new();
@@ -389,12 +506,12 @@ pub unsafe extern "C" fn gethostbyname(name: *const c_char) -> *mut hostent {
     HOST_ENTRY = hostent {
         h_name: HOST_NAME.unsafe_mut().as_mut().unwrap().as_mut_ptr() as *mut c_char,
         h_aliases: host_aliases.as_mut_slice().as_mut_ptr(),
-        h_addrtype: AF_INET,
-        h_length: 4,
+        h_addrtype,
+        h_length,
         h_addr_list: &raw mut HOST_ADDR_LIST as *mut _,
     };
     sethostent(HOST_STAYOPEN);
-    &raw mut HOST_ENTRY as *mut hostent
+    Ok(&raw mut HOST_ENTRY as *mut hostent)
 }
 
 pub unsafe extern "C" fn getnetbyaddr(net: u32, net_type: c_int) -> *mut netent {
@@ -479,6 +596,32 @@ pub unsafe extern "C" fn getnetent() -> *mut netent {
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn getprotobyname(name: *const c_char) -> *mut protoent {
+    let entry = nss::dispatch(Database::Protocols, |source| match source {
+        Source::Files => files_protobyname(name),
+        Source::Module(module) => module_entry(
+            module,
+            "getprotobyname_r",
+            &raw mut PROTO_ENTRY,
+            &PROTO_BUF,
+            |symbol, entry, buf, errnop| {
+                mem::transmute::<_, GetprotobynameR>(symbol)(
+                    name,
+                    entry,
+                    buf.as_mut_ptr() as *mut c_char,
+                    buf.len(),
+                    errnop,
+                )
+            },
+        ),
+        Source::Dns => Err(Status::Unavail),
+    });
+    entry.unwrap_or_else(|_| {
+        platform::ERRNO.set(ENOENT);
+        ptr::null_mut()
+    })
+}
+
+unsafe fn files_protobyname(name: *const c_char) -> Result<*mut protoent, Status> {
     let mut p: *mut protoent;
     setprotoent(PROTO_STAYOPEN);
     while {
@@ -487,7 +630,7 @@ pub unsafe extern "C" fn getprotobyname(name: *const c_char) -> *mut protoent {
     } {
         if strcasecmp((*p).p_name, name) == 0 {
             setprotoent(PROTO_STAYOPEN);
-            return p;
+            return Ok(p);
         }
 
         let mut cp = (*p).p_aliases;
@@ -502,19 +645,43 @@ pub unsafe extern "C" fn getprotobyname(name: *const c_char) -> *mut protoent {
             }
             if strcasecmp(*cp, name) == 0 {
                 setprotoent(PROTO_STAYOPEN);
-                return p;
+                return Ok(p);
             }
             cp = cp.offset(1);
         }
     }
     setprotoent(PROTO_STAYOPEN);
-
-    platform::ERRNO.set(ENOENT);
-    ptr::null_mut() as *mut protoent
+    Err(Status::NotFound)
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn getprotobynumber(number: c_int) -> *mut protoent {
+    let entry = nss::dispatch(Database::Protocols, |source| match source {
+        Source::Files => files_protobynumber(number),
+        Source::Module(module) => module_entry(
+            module,
+            "getprotobynumber_r",
+            &raw mut PROTO_ENTRY,
+            &PROTO_BUF,
+            |symbol, entry, buf, errnop| {
+                mem::transmute::<_, GetprotobynumberR>(symbol)(
+                    number,
+                    entry,
+                    buf.as_mut_ptr() as *mut c_char,
+                    buf.len(),
+                    errnop,
+                )
+            },
+        ),
+        Source::Dns => Err(Status::Unavail),
+    });
+    entry.unwrap_or_else(|_| {
+        platform::ERRNO.set(ENOENT);
+        ptr::null_mut()
+    })
+}
+
+unsafe fn files_protobynumber(number: c_int) -> Result<*mut protoent, Status> {
     setprotoent(PROTO_STAYOPEN);
     let mut p: *mut protoent;
     while {
@@ -523,12 +690,11 @@ pub unsafe extern "C" fn getprotobynumber(number: c_int) -> *mut protoent {
     } {
         if (*p).p_proto == number {
             setprotoent(PROTO_STAYOPEN);
-            return p;
+            return Ok(p);
         }
     }
     setprotoent(PROTO_STAYOPEN);
-    platform::ERRNO.set(ENOENT);
-    ptr::null_mut() as *mut protoent
+    Err(Status::NotFound)
 }
 
 #[unsafe(no_mangle)]
@@ -594,6 +760,36 @@ pub unsafe extern "C" fn getprotoent() -> *mut protoent {
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn getservbyname(name: *const c_char, proto: *const c_char) -> *mut servent {
+    let entry = nss::dispatch(Database::Services, |source| match source {
+        Source::Files => files_servbyname(name, proto),
+        Source::Module(module) => module_entry(
+            module,
+            "getservbyname_r",
+            &raw mut SERV_ENTRY,
+            &SERV_BUF,
+            |symbol, entry, buf, errnop| {
+                mem::transmute::<_, GetservbynameR>(symbol)(
+                    name,
+                    proto,
+                    entry,
+                    buf.as_mut_ptr() as *mut c_char,
+                    buf.len(),
+                    errnop,
+                )
+            },
+        ),
+        Source::Dns => Err(Status::Unavail),
+    });
+    entry.unwrap_or_else(|_| {
+        platform::ERRNO.set(ENOENT);
+        ptr::null_mut()
+    })
+}
+
+unsafe fn files_servbyname(
+    name: *const c_char,
+    proto: *const c_char,
+) -> Result<*mut servent, Status> {
     setservent(SERV_STAYOPEN);
     let mut p: *mut servent;
     if proto.is_null() {
@@ -603,7 +799,7 @@ pub unsafe extern "C" fn getservbyname(name: *const c_char, proto: *const c_char
         } {
             if strcasecmp((*p).s_name, name) == 0 {
                 setservent(SERV_STAYOPEN);
-                return p;
+                return Ok(p);
             }
         }
     } else {
@@ -613,17 +809,43 @@ pub unsafe extern "C" fn getservbyname(name: *const c_char, proto: *const c_char
         } {
             if strcasecmp((*p).s_name, name) == 0 && strcasecmp((*p).s_proto, proto) == 0 {
                 setservent(SERV_STAYOPEN);
-                return p;
+                return Ok(p);
             }
         }
     }
     setservent(SERV_STAYOPEN);
-    platform::ERRNO.set(ENOENT);
-    ptr::null_mut() as *mut servent
+    Err(Status::NotFound)
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn getservbyport(port: c_int, proto: *const c_char) -> *mut servent {
+    let entry = nss::dispatch(Database::Services, |source| match source {
+        Source::Files => files_servbyport(port, proto),
+        Source::Module(module) => module_entry(
+            module,
+            "getservbyport_r",
+            &raw mut SERV_ENTRY,
+            &SERV_BUF,
+            |symbol, entry, buf, errnop| {
+                mem::transmute::<_, GetservbyportR>(symbol)(
+                    port,
+                    proto,
+                    entry,
+                    buf.as_mut_ptr() as *mut c_char,
+                    buf.len(),
+                    errnop,
+                )
+            },
+        ),
+        Source::Dns => Err(Status::Unavail),
+    });
+    entry.unwrap_or_else(|_| {
+        platform::ERRNO.set(ENOENT);
+        ptr::null_mut()
+    })
+}
+
+unsafe fn files_servbyport(port: c_int, proto: *const c_char) -> Result<*mut servent, Status> {
     setservent(SERV_STAYOPEN);
     let mut p: *mut servent;
     if proto.is_null() {
@@ -633,7 +855,7 @@ pub unsafe extern "C" fn getservbyport(port: c_int, proto: *const c_char) -> *mu
         } {
             if (*p).s_port == port {
                 setservent(SERV_STAYOPEN);
-                return p;
+                return Ok(p);
             }
         }
     } else {
@@ -643,13 +865,12 @@ pub unsafe extern "C" fn getservbyport(port: c_int, proto: *const c_char) -> *mu
         } {
             if (*p).s_port == port && strcasecmp((*p).s_proto, proto) == 0 {
                 setservent(SERV_STAYOPEN);
-                return p;
+                return Ok(p);
             }
         }
     }
     setservent(SERV_STAYOPEN);
-    platform::ERRNO.set(ENOENT);
-    ptr::null_mut()
+    Err(Status::NotFound)
 }
 
 #[unsafe(no_mangle)]
@@ -779,6 +1000,193 @@ pub unsafe extern "C" fn setservent(stayopen: c_int) {
     }
 }
 
+/// The socket types that `getaddrinfo` gives addresses for when it is not
+/// told which, each with its protocol and the name of that protocol in
+/// `/etc/services`. Raw sockets come with whatever protocol was asked for.
+const GAI_SOCKTYPES: [(c_int, c_int, Option<&core::ffi::CStr>); 3] = [
+    (SOCK_STREAM, IPPROTO_TCP as c_int, Some(c"tcp")),
+    (SOCK_DGRAM, IPPROTO_UDP as c_int, Some(c"udp")),
+    (SOCK_RAW, 0, None),
+];
+
+const AI_MASK: c_int = AI_PASSIVE
+    | AI_CANONNAME
+    | AI_NUMERICHOST
+    | AI_V4MAPPED
+    | AI_ALL
+    | AI_ADDRCONFIG
+    | AI_NUMERICSERV;
+
+/// Finds the socket types that `getaddrinfo` should give addresses for, each
+/// with its protocol and the port of `service` in host byte order.
+unsafe fn gai_services(
+    service: Option<CStr>,
+    socktype: c_int,
+    protocol: c_int,
+    flags: c_int,
+) -> Result<Vec<(c_int, c_int, u16)>, c_int> {
+    if !matches!(socktype, 0 | SOCK_STREAM | SOCK_DGRAM | SOCK_RAW) {
+        return Err(EAI_SOCKTYPE);
+    }
+    // No service is port 0, and a service by name has a port per protocol
+    let port = match service {
+        Some(service) => str::from_utf8(service.to_bytes())
+            .ok()
+            .and_then(|service| service.parse::<u16>().ok()),
+        None => Some(0),
+    };
+    if port.is_none() && flags & AI_NUMERICSERV > 0 {
+        return Err(EAI_NONAME);
+    }
+
+    let candidates: Vec<(c_int, c_int, Option<&core::ffi::CStr>)> = GAI_SOCKTYPES
+        .iter()
+        .filter(|&&(ty, proto, _)| {
+            if ty == SOCK_RAW {
+                // Raw sockets are not in /etc/services, so they are only
+                // given for services by name if asked for
+                socktype == SOCK_RAW
+                    || (socktype == 0
+                        && port.is_some()
+                        && protocol != IPPROTO_TCP as c_int
+                        && protocol != IPPROTO_UDP as c_int)
+            } else {
+                (socktype == 0 || socktype == ty) && (protocol == 0 || protocol == proto)
+            }
+        })
+        .map(|&(ty, proto, name)| (ty, if ty == SOCK_RAW { protocol } else { proto }, name))
+        .collect();
+    if candidates.is_empty() {
+        return Err(EAI_SOCKTYPE);
+    }
+
+    let found: Vec<(c_int, c_int, u16)> = candidates
+        .into_iter()
+        .filter_map(|(ty, proto, name)| {
+            if let Some(port) = port {
+                return Some((ty, proto, port));
+            }
+            let service = str::from_utf8(service?.to_bytes()).ok()?;
+            let (port, _) = service_by_name(service, Some(name?.to_str().ok()?))?;
+            Some((ty, proto, port))
+        })
+        .collect();
+    if found.is_empty() {
+        return Err(EAI_SERVICE);
+    }
+    Ok(found)
+}
+
+/// Finds the port of the service `name` in host byte order, with the protocol
+/// that it is for, in the sources of `services` in `nsswitch.conf`. Unlike
+/// [`getservbyname`], this keeps no state, so that [`getaddrinfo`] stays
+/// reentrant.
+unsafe fn service_by_name(name: &str, proto: Option<&str>) -> Option<(u16, String)> {
+    nss::dispatch(Database::Services, |source| match source {
+        Source::Files => files_service_by_name(name, proto),
+        Source::Module(module) => module_service_by_name(module, name, proto),
+        Source::Dns => Err(Status::Unavail),
+    })
+    .ok()
+}
+
+/// Finds the service `name` in `/etc/services`, by its name or an alias.
+fn files_service_by_name(name: &str, proto: Option<&str>) -> Result<(u16, String), Status> {
+    let Ok(file) = File::open(c"/etc/services".into(), O_RDONLY) else {
+        return Err(Status::Unavail);
+    };
+
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else { continue };
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        let (Some(service), Some(port_proto)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some((port, service_proto)) = port_proto.split_once('/') else {
+            continue;
+        };
+        let Ok(port) = port.parse::<u16>() else {
+            continue;
+        };
+        if proto.is_some_and(|proto| !proto.eq_ignore_ascii_case(service_proto)) {
+            continue;
+        }
+        let mut names = Some(service).into_iter().chain(fields);
+        if names.any(|service| service.eq_ignore_ascii_case(name)) {
+            return Ok((port, service_proto.to_string()));
+        }
+    }
+    Err(Status::NotFound)
+}
+
+/// Finds the service `name` with `_nss_<module>_getservbyname_r`.
+unsafe fn module_service_by_name(
+    module: &str,
+    name: &str,
+    proto: Option<&str>,
+) -> Result<(u16, String), Status> {
+    let name = CString::new(name).map_err(|_| Status::NotFound)?;
+    let proto = match proto {
+        Some(proto) => Some(CString::new(proto).map_err(|_| Status::NotFound)?),
+        None => None,
+    };
+    let mut entry: servent = mem::zeroed();
+    // The entry points into the buffer, so it must outlive the reads
+    let _buffer = nss::module_lookup(module, "getservbyname_r", None, |symbol, buf, errnop| {
+        mem::transmute::<_, GetservbynameR>(symbol)(
+            name.as_ptr(),
+            proto.as_ref().map_or(ptr::null(), |proto| proto.as_ptr()),
+            &mut entry,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            errnop,
+        )
+    })?;
+
+    let service_proto = if entry.s_proto.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(entry.s_proto)
+            .to_str()
+            .map_or_else(|_| String::new(), String::from)
+    };
+    Ok((ntohs(entry.s_port as u16), service_proto))
+}
+
+/// Whether an IPv4 and an IPv6 address are configured, for `AI_ADDRCONFIG`. Loopback and
+/// link-local IPv6 addresses do not count, as nothing outside this host could be reached
+/// from them. Both families count if the interfaces cannot be listed.
+fn configured_families() -> (bool, bool) {
+    let Ok(interfaces) = ifaddrs::interfaces() else {
+        return (true, true);
+    };
+    let addrs = interfaces
+        .iter()
+        .flat_map(|interface| &interface.addrs)
+        .map(|address| address.addr);
+    let (mut has_v4, mut has_v6) = (false, false);
+    for addr in addrs {
+        match addr {
+            IpAddr::V4(addr) => has_v4 |= !addr.is_loopback(),
+            IpAddr::V6(addr) => has_v6 |= !addr.is_loopback() && !addr.is_unicast_link_local(),
+        }
+    }
+    (has_v4, has_v6)
+}
+
+/// The `EAI_*` code for an error of [`resolve_host`].
+fn gai_lookup_error(err: c_int) -> c_int {
+    match err {
+        ENOENT => EAI_NONAME,
+        EAGAIN | EIO | ETIMEDOUT => EAI_AGAIN,
+        EINVAL | ECONNREFUSED => EAI_FAIL,
+        _ => {
+            platform::ERRNO.set(err);
+            EAI_SYSTEM
+        }
+    }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn getaddrinfo(
     node: *const c_char,
@@ -798,87 +1206,130 @@ pub unsafe extern "C" fn getaddrinfo(
         hints_opt
     );
 
-    //TODO: Use hints
-    let mut ai_flags = hints_opt.map_or(0, |hints| hints.ai_flags);
-    let mut ai_family; // = hints_opt.map_or(AF_UNSPEC, |hints| hints.ai_family);
+    let ai_flags = hints_opt.map_or(0, |hints| hints.ai_flags);
+    let mut ai_family = hints_opt.map_or(AF_UNSPEC, |hints| hints.ai_family);
     let ai_socktype = hints_opt.map_or(0, |hints| hints.ai_socktype);
-    let mut ai_protocol; // = hints_opt.map_or(0, |hints| hints.ai_protocol);
+    let ai_protocol = hints_opt.map_or(0, |hints| hints.ai_protocol);
 
     *res = ptr::null_mut();
 
-    let mut port = 0;
-    if let Some(service) = service_opt {
-        //TODO: Support other service definitions as well as AI_NUMERICSERV
-        match str::from_utf8_unchecked(service.to_bytes()).parse::<u16>() {
-            Ok(ok) => port = ok,
-            Err(_err) => (),
-        }
+    if ai_flags & !AI_MASK != 0 || (ai_flags & AI_CANONNAME > 0 && node_opt.is_none()) {
+        return EAI_BADFLAGS;
     }
-    let node = node_opt.unwrap_or_else(|| {
-        //TODO: Optimize by bypassing string parsing
-        if ai_flags & AI_PASSIVE > 0 {
-            c"0.0.0.0".into()
-        } else {
-            c"127.0.0.1".into()
+    if node_opt.is_none() && service_opt.is_none() {
+        return EAI_NONAME;
+    }
+    if ai_family != AF_UNSPEC && ai_family != AF_INET && ai_family != AF_INET6 {
+        return EAI_FAMILY;
+    }
+
+    let services = match gai_services(service_opt, ai_socktype, ai_protocol, ai_flags) {
+        Ok(services) => services,
+        Err(err) => return err,
+    };
+
+    // Only ask for the families that we could reach
+    if ai_flags & AI_ADDRCONFIG > 0 {
+        let (has_v4, has_v6) = configured_families();
+        match (ai_family, has_v4, has_v6) {
+            (AF_UNSPEC, true, false) => ai_family = AF_INET,
+            (AF_UNSPEC, false, true) => ai_family = AF_INET6,
+            (AF_INET, false, _) | (AF_INET6, _, false) => return EAI_NONAME,
+            _ => (),
         }
-    });
+    }
+    let v4mapped = ai_family == AF_INET6 && ai_flags & AI_V4MAPPED > 0;
 
-    let lookuphost = if ai_flags & AI_NUMERICHOST > 0 {
-        match parse_ipv4_string(str::from_utf8_unchecked(node.to_bytes())) {
-            Some(s_addr) => s_addr.into(),
-            None => {
-                return EAI_NONAME;
+    let mut canonname = None;
+    let mut numeric = true;
+    let addrs: Vec<IpAddr> = match node_opt {
+        None => {
+            let (v4, v6) = if ai_flags & AI_PASSIVE > 0 {
+                (Ipv4Addr::UNSPECIFIED, Ipv6Addr::UNSPECIFIED)
+            } else {
+                (Ipv4Addr::LOCALHOST, Ipv6Addr::LOCALHOST)
+            };
+            match ai_family {
+                AF_INET => vec![v4.into()],
+                AF_INET6 => vec![v6.into()],
+                _ => vec![v4.into(), v6.into()],
             }
         }
-    } else {
-        match lookup_host(str::from_utf8_unchecked(node.to_bytes())) {
-            Ok(lookuphost) => lookuphost,
-            Err(e) => {
-                platform::ERRNO.set(e);
-                return EAI_SYSTEM;
+        Some(node) => {
+            let name = str::from_utf8_unchecked(node.to_bytes());
+            if let Some(addr) = parse_ip_string(name) {
+                vec![addr]
+            } else if ai_flags & AI_NUMERICHOST > 0 {
+                return EAI_NONAME;
+            } else {
+                numeric = false;
+                // IPv4 addresses are needed to map them
+                let family = if v4mapped { AF_UNSPEC } else { ai_family };
+                match resolve_host(name, family) {
+                    Ok(lookuphost) => {
+                        canonname = lookuphost.canonical_name().map(|name| name.to_owned());
+                        lookuphost.collect()
+                    }
+                    Err(e) => return gai_lookup_error(e),
+                }
             }
         }
     };
 
-    for in_addr in lookuphost {
-        ai_family = AF_INET;
-        ai_protocol = 0;
-
-        let ai_addr = Box::into_raw(Box::new(sockaddr_in {
-            sin_family: ai_family as sa_family_t,
-            sin_port: htons(port),
-            sin_addr: in_addr,
-            sin_zero: [0; 8],
-        })) as *mut sockaddr;
-
-        let ai_addrlen = mem::size_of::<sockaddr_in>() as socklen_t;
-
-        let ai_canonname = if ai_flags & AI_CANONNAME > 0 {
-            if node_opt.is_none() {
-                return EAI_BADFLAGS;
+    let mut addrs: Vec<IpAddr> = match ai_family {
+        AF_INET => addrs.into_iter().filter(IpAddr::is_ipv4).collect(),
+        AF_INET6 => {
+            let (mut v6, v4): (Vec<IpAddr>, Vec<IpAddr>) =
+                addrs.into_iter().partition(IpAddr::is_ipv6);
+            // Mapped addresses are a fallback, unless all of them are asked for
+            if v4mapped && (v6.is_empty() || ai_flags & AI_ALL > 0) {
+                v6.extend(v4.into_iter().filter_map(|addr| match addr {
+                    IpAddr::V4(addr) => Some(IpAddr::V6(addr.to_ipv6_mapped())),
+                    IpAddr::V6(_) => None,
+                }));
             }
-            ai_flags &= !AI_CANONNAME;
-            node.to_owned_cstring().into_raw()
-        } else {
-            ptr::null_mut()
+            v6
+        }
+        _ => addrs,
+    };
+    if addrs.is_empty() {
+        // The name exists, but has no addresses of the family
+        return if numeric { EAI_ADDRFAMILY } else { EAI_NODATA };
+    }
+    rfc6724::sort(&mut addrs);
+
+    // The canonical name goes in the first entry only
+    let mut ai_canonname = if ai_flags & AI_CANONNAME > 0 {
+        let name = match canonname {
+            Some(name) => CString::new(name).ok(),
+            None => node_opt.map(|node| node.to_owned_cstring()),
         };
+        match name {
+            Some(name) => name.into_raw(),
+            None => return EAI_FAIL,
+        }
+    } else {
+        ptr::null_mut()
+    };
 
-        let addrinfo = Box::new(addrinfo {
-            ai_flags: 0,
-            ai_family,
-            ai_socktype,
-            ai_protocol,
-            ai_addrlen,
-            ai_canonname,
-            ai_addr,
-            ai_next: ptr::null_mut(),
-        });
-
-        let mut indirect = res;
-        while !(*indirect).is_null() {
+    let mut indirect = res;
+    for addr in addrs {
+        for &(ai_socktype, ai_protocol, port) in &services {
+            let sockaddr = SockAddr::new(addr, port);
+            let addrinfo = Box::new(addrinfo {
+                ai_flags: 0,
+                ai_family: sockaddr.family(),
+                ai_socktype,
+                ai_protocol,
+                ai_addrlen: sockaddr.len(),
+                ai_canonname: mem::replace(&mut ai_canonname, ptr::null_mut()),
+                ai_addr: sockaddr.into_raw(),
+                ai_next: ptr::null_mut(),
+            });
+
+            *indirect = Box::into_raw(addrinfo);
             indirect = &mut (**indirect).ai_next;
         }
-        *indirect = Box::into_raw(addrinfo);
     }
 
     0
@@ -894,15 +1345,23 @@ pub unsafe extern "C" fn getnameinfo(
     servlen: socklen_t,
     flags: c_int,
 ) -> c_int {
-    if addr.is_null() || addrlen as usize != mem::size_of::<sockaddr_in>() {
+    let Some((ip_addr, port)) = SockAddr::read(addr, addrlen) else {
         return EAI_FAMILY;
-    }
-
-    let sa = &*(addr as *const sockaddr_in);
+    };
+    let ip_str = match ip_addr {
+        // Link-local addresses are only meaningful along with their interface
+        IpAddr::V6(v6) if v6.is_unicast_link_local() => {
+            match (*(addr as *const sockaddr_in6)).sin6_scope_id {
+                0 => v6.to_string(),
+                scope_id => format!("{}%{}", v6, scope_id),
+            }
+        }
+        _ => ip_addr.to_string(),
+    };
 
     if !serv.is_null() && servlen > 0 {
         if flags & NI_NUMERICSERV != 0 {
-            let port_str = sa.sin_port.to_be().to_string();
+            let port_str = port.to_string();
             let port_bytes = port_str.as_bytes();
             if (servlen as usize) <= port_bytes.len() {
                 return EAI_MEMORY; // Buffer too small
@@ -917,8 +1376,6 @@ pub unsafe extern "C" fn getnameinfo(
 
     if !host.is_null() && hostlen > 0 {
         if flags & NI_NUMERICHOST != 0 {
-            let ip_addr = Ipv4Addr::from(sa.sin_addr.s_addr.to_be());
-            let ip_str = ip_addr.to_string();
             let ip_bytes = ip_str.as_bytes();
             if (hostlen as usize) <= ip_bytes.len() {
                 return EAI_MEMORY; // Buffer too small
@@ -926,7 +1383,7 @@ pub unsafe extern "C" fn getnameinfo(
             ptr::copy_nonoverlapping(ip_bytes.as_ptr() as *const c_char, host, ip_bytes.len());
             *host.add(ip_bytes.len()) = 0;
         } else {
-            match lookup_addr(sa.sin_addr.clone()).map(|host_names| host_names.into_iter().next()) {
+            match lookup_addr(ip_addr).map(|host_names| host_names.into_iter().next()) {
                 Ok(Some(hostname)) => {
                     if (hostlen as usize) <= hostname.len() {
                         return EAI_MEMORY; // Buffer too small
@@ -949,8 +1406,6 @@ pub unsafe extern "C" fn getnameinfo(
                     if flags & NI_NAMEREQD != 0 {
                         return EAI_NONAME;
                     }
-                    let ip_addr = Ipv4Addr::from(sa.sin_addr.s_addr.to_be());
-                    let ip_str = ip_addr.to_string();
                     let ip_bytes = ip_str.as_bytes();
                     if (hostlen as usize) <= ip_bytes.len() {
                         return EAI_MEMORY;
@@ -999,7 +1454,7 @@ pub const extern "C" fn gai_strerror(errcode: c_int) -> *const c_char {
         EAI_NONAME => c"Name does not resolve",
         EAI_AGAIN => c"Try again",
         EAI_FAIL => c"Non-recoverable error",
-        EAI_NODATA => c"Unknown error",
+        EAI_NODATA => c"No address associated with hostname",
         EAI_FAMILY => c"Unrecognized address family or invalid length",
         EAI_SOCKTYPE => c"Unrecognized socket type",
         EAI_SERVICE => c"Unrecognized service",
@@ -1,16 +1,13 @@
-use crate::{
-    error::Errno,
-    fs::File,
-    header::{errno, fcntl},
-    io::Read,
-};
+use crate::{fs::File, header::fcntl, io::Read};
 use alloc::string::String;
 
-pub fn get_dns_server() -> Result<String, Errno> {
-    let mut string = String::new();
-    let mut file = File::open(c"/etc/net/dns".into(), fcntl::O_RDONLY)?;
-    file.read_to_string(&mut string)
-        .map_err(|_| Errno(errno::EIO).sync())?;
+use super::resolv_conf::ResolvConf;
 
-    Ok(string)
+pub fn get_resolv_conf() -> ResolvConf {
+    // There is one nameserver, configured by netcfg
+    let mut string = String::new();
+    if let Ok(mut file) = File::open(c"/etc/net/dns".into(), fcntl::O_RDONLY) {
+        let _ = file.read_to_string(&mut string);
+    }
+    ResolvConf::parse(&format!("nameserver {}", string.trim()))
 }
@@ -0,0 +1,166 @@
+//! The resolver configuration, as in `/etc/resolv.conf`.
+//!
+//! See <https://man7.org/linux/man-pages/man5/resolv.conf.5.html>.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::net::{IpAddr, Ipv4Addr};
+
+pub use crate::header::resolv::{MAXDNSRCH, MAXNS};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvConf {
+    /// Never empty: without any, the local nameserver is asked.
+    pub nameservers: Vec<IpAddr>,
+    /// Domains to try names with fewer than `ndots` dots in first.
+    pub search: Vec<String>,
+    pub ndots: u32,
+    /// Seconds to wait for each answer.
+    pub timeout: u32,
+    /// How many times to go through the nameservers.
+    pub attempts: u32,
+    /// Whether to start at a different nameserver for each query.
+    pub rotate: bool,
+    /// Whether to ask for answers larger than 512 bytes over UDP.
+    pub edns0: bool,
+    /// Whether to query over TCP from the start.
+    pub use_vc: bool,
+}
+
+impl Default for ResolvConf {
+    fn default() -> Self {
+        ResolvConf {
+            nameservers: vec![IpAddr::V4(Ipv4Addr::LOCALHOST)],
+            search: Vec::new(),
+            ndots: 1,
+            timeout: 5,
+            attempts: 2,
+            rotate: false,
+            edns0: false,
+            use_vc: false,
+        }
+    }
+}
+
+impl ResolvConf {
+    pub fn parse(text: &str) -> Self {
+        let mut conf = ResolvConf::default();
+        let mut nameservers = Vec::new();
+
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("nameserver") if nameservers.len() < MAXNS => {
+                    // Link-local addresses may come with an interface, which
+                    // is of no use without scope ids
+                    let addr = words.next().and_then(|addr| addr.split('%').next());
+                    if let Some(addr) = addr.and_then(|addr| addr.parse().ok()) {
+                        nameservers.push(addr);
+                    }
+                }
+                // The last of `domain` and `search` wins
+                Some("domain") => {
+                    conf.search = words
+                        .next()
+                        .map(|domain| domain.to_string())
+                        .into_iter()
+                        .collect();
+                }
+                Some("search") => {
+                    conf.search = words
+                        .take(MAXDNSRCH)
+                        .map(|domain| domain.to_string())
+                        .collect();
+                }
+                Some("options") => {
+                    for option in words {
+                        conf.option(option);
+                    }
+                }
+                // Comments and anything unknown
+                _ => (),
+            }
+        }
+
+        if !nameservers.is_empty() {
+            conf.nameservers = nameservers;
+        }
+        conf
+    }
+
+    fn option(&mut self, option: &str) {
+        let (name, value) = match option.split_once(':') {
+            Some((name, value)) => (name, value.parse::<u32>().ok()),
+            None => (option, None),
+        };
+        match (name, value) {
+            ("ndots", Some(ndots)) => self.ndots = ndots.min(15),
+            ("timeout", Some(timeout)) => self.timeout = timeout.clamp(1, 30),
+            ("attempts", Some(attempts)) => self.attempts = attempts.clamp(1, 5),
+            ("rotate", _) => self.rotate = true,
+            ("edns0", _) => self.edns0 = true,
+            ("use-vc", _) => self.use_vc = true,
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::{MAXNS, ResolvConf};
+
+    #[test]
+    fn empty() {
+        assert_eq!(ResolvConf::default(), ResolvConf::parse(""));
+        assert_eq!(
+            vec![IpAddr::V4(Ipv4Addr::LOCALHOST)],
+            ResolvConf::parse("# nothing here\n").nameservers
+        );
+    }
+
+    #[test]
+    fn nameservers() {
+        let conf = ResolvConf::parse(
+            "nameserver 192.0.2.1\n\
+             nameserver not-an-address\n\
+             nameserver fe80::1%eth0\n\
+             nameserver 192.0.2.2\n\
+             nameserver 192.0.2.3\n",
+        );
+        assert_eq!(MAXNS, conf.nameservers.len());
+        assert_eq!(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), conf.nameservers[0]);
+        assert_eq!(
+            IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+            conf.nameservers[1]
+        );
+        assert_eq!(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)), conf.nameservers[2]);
+    }
+
+    #[test]
+    fn search() {
+        assert_eq!(
+            vec!["b.example", "c.example"],
+            ResolvConf::parse("domain a.example\nsearch b.example c.example\n").search
+        );
+        assert_eq!(
+            vec!["a.example"],
+            ResolvConf::parse("search b.example c.example\ndomain a.example\n").search
+        );
+    }
+
+    #[test]
+    fn options() {
+        let conf =
+            ResolvConf::parse("options ndots:2 timeout:0 attempts:9 rotate edns0 use-vc bogus\n");
+        assert_eq!(2, conf.ndots);
+        assert_eq!(1, conf.timeout);
+        assert_eq!(5, conf.attempts);
+        assert!(conf.rotate);
+        assert!(conf.edns0);
+        assert!(conf.use_vc);
+    }
+}
@@ -0,0 +1,226 @@
+//! Destination address selection of RFC 6724, which orders the addresses of
+//! a host so that the ones most likely to work come first.
+//!
+//! See <https://www.rfc-editor.org/rfc/rfc6724#section-6>.
+
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv6Addr};
+
+use super::lookup::source_address;
+
+/// An entry of the default policy table, see
+/// <https://www.rfc-editor.org/rfc/rfc6724#section-2.1>.
+struct Policy {
+    prefix: [u8; 16],
+    len: u32,
+    precedence: u32,
+    label: u32,
+}
+
+const fn prefix(head: &[u8]) -> [u8; 16] {
+    let mut prefix = [0; 16];
+    let mut i = 0;
+    while i < head.len() {
+        prefix[i] = head[i];
+        i += 1;
+    }
+    prefix
+}
+
+/// Longest prefixes first, so that the first match is the best one.
+static POLICIES: [Policy; 9] = [
+    // ::1/128
+    Policy {
+        prefix: prefix(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+        len: 128,
+        precedence: 50,
+        label: 0,
+    },
+    // ::ffff:0:0/96, IPv4-mapped
+    Policy {
+        prefix: prefix(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff]),
+        len: 96,
+        precedence: 35,
+        label: 4,
+    },
+    // ::/96, IPv4-compatible
+    Policy {
+        prefix: prefix(&[]),
+        len: 96,
+        precedence: 1,
+        label: 3,
+    },
+    // 2001::/32, Teredo
+    Policy {
+        prefix: prefix(&[0x20, 0x01, 0, 0]),
+        len: 32,
+        precedence: 5,
+        label: 5,
+    },
+    // 2002::/16, 6to4
+    Policy {
+        prefix: prefix(&[0x20, 0x02]),
+        len: 16,
+        precedence: 30,
+        label: 2,
+    },
+    // 3ffe::/16, 6bone
+    Policy {
+        prefix: prefix(&[0x3f, 0xfe]),
+        len: 16,
+        precedence: 1,
+        label: 12,
+    },
+    // fec0::/10, site-local
+    Policy {
+        prefix: prefix(&[0xfe, 0xc0]),
+        len: 10,
+        precedence: 1,
+        label: 11,
+    },
+    // fc00::/7, unique local
+    Policy {
+        prefix: prefix(&[0xfc]),
+        len: 7,
+        precedence: 3,
+        label: 13,
+    },
+    // ::/0
+    Policy {
+        prefix: prefix(&[]),
+        len: 0,
+        precedence: 40,
+        label: 1,
+    },
+];
+
+/// The number of leading bits that `a` and `b` have in common.
+fn common_prefix_len(a: &Ipv6Addr, b: &Ipv6Addr) -> u32 {
+    (u128::from(*a) ^ u128::from(*b)).leading_zeros()
+}
+
+fn policy(addr: &Ipv6Addr) -> &'static Policy {
+    POLICIES
+        .iter()
+        .find(|policy| common_prefix_len(addr, &Ipv6Addr::from(policy.prefix)) >= policy.len)
+        .unwrap_or(&POLICIES[POLICIES.len() - 1])
+}
+
+/// The scope of an address, see
+/// <https://www.rfc-editor.org/rfc/rfc6724#section-3.1>.
+fn scope(addr: &Ipv6Addr) -> u32 {
+    if let Some(addr) = addr.to_ipv4_mapped() {
+        // Loopback and link-local IPv4 addresses count as link-local
+        return if addr.is_loopback() || addr.is_link_local() {
+            2
+        } else {
+            14
+        };
+    }
+    let octets = addr.octets();
+    if octets[0] == 0xff {
+        u32::from(octets[1] & 0xf)
+    } else if addr.is_loopback() || addr.is_unicast_link_local() {
+        2
+    } else if octets[0] == 0xfe && octets[1] & 0xc0 == 0xc0 {
+        5
+    } else {
+        14
+    }
+}
+
+/// IPv4 addresses take part as IPv4-mapped IPv6 addresses.
+fn as_ipv6(addr: IpAddr) -> Ipv6Addr {
+    match addr {
+        IpAddr::V4(addr) => addr.to_ipv6_mapped(),
+        IpAddr::V6(addr) => addr,
+    }
+}
+
+// The rules that apply without knowing more than the source address we
+// would use, from the most significant bit of the key down
+const USABLE: u32 = 1 << 30; // Rule 1
+const MATCHING_SCOPE: u32 = 1 << 29; // Rule 2
+const MATCHING_LABEL: u32 = 1 << 28; // Rule 5
+const PRECEDENCE_SHIFT: u32 = 20; // Rule 6
+const SCOPE_SHIFT: u32 = 16; // Rule 8
+const PREFIX_SHIFT: u32 = 8; // Rule 9
+
+/// How preferable `dst` is, where higher is better.
+fn key(dst: IpAddr) -> u32 {
+    let dst6 = as_ipv6(dst);
+    let dst_policy = policy(&dst6);
+    let dst_scope = scope(&dst6);
+
+    let mut key = 0;
+    if let Some(src) = source_address(dst) {
+        let src6 = as_ipv6(src);
+        key |= USABLE;
+        if scope(&src6) == dst_scope {
+            key |= MATCHING_SCOPE;
+        }
+        if policy(&src6).label == dst_policy.label {
+            key |= MATCHING_LABEL;
+        }
+        if dst.is_ipv6() {
+            key |= common_prefix_len(&src6, &dst6) << PREFIX_SHIFT;
+        }
+    }
+    key |= dst_policy.precedence << PRECEDENCE_SHIFT;
+    key |= (15 - dst_scope) << SCOPE_SHIFT;
+    key
+}
+
+/// Sorts `addrs` from the most to the least preferable. Addresses that are
+/// equally good keep their order.
+pub fn sort(addrs: &mut Vec<IpAddr>) {
+    // Every IPv4 address ranks the same, so they are left as the DNS server
+    // sent them
+    if !addrs.iter().any(IpAddr::is_ipv6) {
+        return;
+    }
+    let mut keyed: Vec<(u32, IpAddr)> = addrs.iter().map(|&addr| (key(addr), addr)).collect();
+    keyed.sort_by(|a, b| b.0.cmp(&a.0));
+    addrs.clear();
+    addrs.extend(keyed.into_iter().map(|(_, addr)| addr));
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::{common_prefix_len, policy, scope};
+
+    #[test]
+    fn policy_table() {
+        let precedence = |addr: &str| policy(&addr.parse::<Ipv6Addr>().unwrap()).precedence;
+        assert_eq!(50, precedence("::1"));
+        assert_eq!(35, precedence("::ffff:192.0.2.1"));
+        assert_eq!(40, precedence("2001:db8::1"));
+        assert_eq!(5, precedence("2001:0:4136:e378::1"));
+        assert_eq!(30, precedence("2002:c000:0204::1"));
+        assert_eq!(3, precedence("fd00::1"));
+        assert_eq!(1, precedence("fec0::1"));
+    }
+
+    #[test]
+    fn scopes() {
+        let scope_of = |addr: &str| scope(&addr.parse::<Ipv6Addr>().unwrap());
+        assert_eq!(2, scope_of("::1"));
+        assert_eq!(2, scope_of("fe80::1"));
+        assert_eq!(5, scope_of("fec0::1"));
+        assert_eq!(14, scope_of("2001:db8::1"));
+        assert_eq!(5, scope_of("ff05::2"));
+        assert_eq!(2, scope(&Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped()));
+        assert_eq!(2, scope(&Ipv4Addr::new(169, 254, 1, 1).to_ipv6_mapped()));
+        assert_eq!(14, scope(&Ipv4Addr::new(192, 0, 2, 1).to_ipv6_mapped()));
+    }
+
+    #[test]
+    fn prefix_len() {
+        let a = "2001:db8::1".parse().unwrap();
+        assert_eq!(128, common_prefix_len(&a, &a));
+        assert_eq!(127, common_prefix_len(&a, &"2001:db8::".parse().unwrap()));
+        assert_eq!(0, common_prefix_len(&a, &"fe80::1".parse().unwrap()));
+    }
+}
@@ -9,11 +9,12 @@ use crate::{
     error::ResultExt,
     fs::File,
     header::{
+        pthread::with_cleanup,
         signal::sigset_t,
         sys_epoll::{
             EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLLERR, EPOLLHUP, EPOLLIN, EPOLLNVAL, EPOLLOUT,
-            EPOLLPRI, EPOLLRDBAND, EPOLLRDNORM, EPOLLWRBAND, EPOLLWRNORM, epoll_create1, epoll_ctl,
-            epoll_data, epoll_event, epoll_pwait,
+            EPOLLPRI, EPOLLRDBAND, EPOLLRDNORM, EPOLLWRBAND, EPOLLWRNORM, close_epoll,
+            epoll_create1, epoll_ctl, epoll_data, epoll_event, epoll_pwait,
         },
         time::timespec,
     },
@@ -91,7 +92,7 @@ pub fn poll_epoll(fds: &mut [pollfd], timeout: c_int, sigmask: *const sigset_t)
     }
 
     let mut events: [epoll_event; 32] = unsafe { mem::zeroed() };
-    let res = unsafe {
+    let res = with_cleanup(close_epoll, *ep as *mut c_void, || unsafe {
         epoll_pwait(
             *ep,
             events.as_mut_ptr(),
@@ -99,7 +100,7 @@ pub fn poll_epoll(fds: &mut [pollfd], timeout: c_int, sigmask: *const sigset_t)
             timeout,
             sigmask,
         )
-    };
+    });
     if res < 0 {
         return -1;
     }
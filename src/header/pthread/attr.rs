@@ -14,9 +14,11 @@ impl Default for RlctAttr {
             detachstate: PTHREAD_CREATE_JOINABLE as _,
             // Default according to POSIX.
             inheritsched: PTHREAD_INHERIT_SCHED as _,
-            // TODO: Linux
             // Redox uses a round-robin scheduler
+            #[cfg(target_os = "redox")]
             schedpolicy: SCHED_RR as _,
+            #[cfg(target_os = "linux")]
+            schedpolicy: SCHED_OTHER as _,
             // TODO: Linux uses this one.
             scope: PTHREAD_SCOPE_SYSTEM as _,
             guardsize: Sys::getpagesize(),
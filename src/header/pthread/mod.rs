@@ -5,11 +5,15 @@
 //! primitives.
 
 use alloc::collections::LinkedList;
-use core::{cell::Cell, ptr::NonNull};
+use core::{
+    cell::Cell,
+    ptr::{self, NonNull},
+};
 
 use crate::{
     error::Errno,
     header::{sched::*, time::timespec},
+    out::Out,
     platform::{Pal, Sys, types::*},
     pthread,
 };
@@ -59,11 +63,9 @@ pub const PTHREAD_MUTEX_RECURSIVE: c_int = 3;
 pub const PTHREAD_MUTEX_ROBUST: c_int = 0;
 pub const PTHREAD_MUTEX_STALLED: c_int = 1;
 
-pub const PTHREAD_PRIO_INHERIT: c_int = 0;
-
 pub const PTHREAD_PRIO_NONE: c_int = 0;
-
-pub const PTHREAD_PRIO_PROTECT: c_int = 0;
+pub const PTHREAD_PRIO_INHERIT: c_int = 1;
+pub const PTHREAD_PRIO_PROTECT: c_int = 2;
 
 pub const PTHREAD_PROCESS_SHARED: c_int = 0;
 pub const PTHREAD_PROCESS_PRIVATE: c_int = 1;
@@ -172,6 +174,17 @@ pub unsafe extern "C" fn pthread_getcpuclockid(
     }
 }
 
+/// See <https://man7.org/linux/man-pages/man3/pthread_getaffinity_np.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_getaffinity_np(
+    thread: pthread_t,
+    cpusetsize: size_t,
+    cpuset: *mut cpu_set_t,
+) -> c_int {
+    let mask = Out::from_raw_parts(cpuset.cast::<u8>(), cpusetsize);
+    e(pthread::get_affinity(&*thread.cast(), mask))
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pthread_getschedparam(
     thread: pthread_t,
@@ -253,6 +266,17 @@ pub extern "C" fn pthread_setconcurrency(concurrency: c_int) -> c_int {
     0
 }
 
+/// See <https://man7.org/linux/man-pages/man3/pthread_setaffinity_np.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_setaffinity_np(
+    thread: pthread_t,
+    cpusetsize: size_t,
+    cpuset: *const cpu_set_t,
+) -> c_int {
+    let mask = core::slice::from_raw_parts(cpuset.cast::<u8>(), cpusetsize);
+    e(pthread::set_affinity(&*thread.cast(), mask))
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pthread_setschedparam(
     thread: pthread_t,
@@ -306,12 +330,28 @@ pub unsafe extern "C" fn __relibc_internal_pthread_cleanup_pop(execute: c_int) {
 }
 
 pub(crate) unsafe fn run_destructor_stack() {
-    let mut ptr = CLEANUP_LL_HEAD.get();
-
-    while !ptr.is_null() {
-        let entry = ptr.read();
-        ptr = entry.prev.cast();
-
-        (entry.routine)(entry.arg);
+    // Each entry is popped before it runs, in case it calls pthread_exit itself.
+    while !CLEANUP_LL_HEAD.get().is_null() {
+        __relibc_internal_pthread_cleanup_pop(1);
     }
 }
+
+/// Runs `f` with `routine(arg)` pushed as a cleanup handler, for undoing what the cancellation
+/// points within `f` would otherwise leave behind if the thread is cancelled.
+pub(crate) fn with_cleanup<T>(
+    routine: extern "C" fn(*mut c_void),
+    arg: *mut c_void,
+    f: impl FnOnce() -> T,
+) -> T {
+    let mut entry = CleanupLinkedListEntry {
+        routine,
+        arg,
+        prev: ptr::null(),
+    };
+    unsafe { __relibc_internal_pthread_cleanup_push(ptr::addr_of_mut!(entry).cast()) };
+
+    let ret = f();
+
+    unsafe { __relibc_internal_pthread_cleanup_pop(0) };
+    ret
+}
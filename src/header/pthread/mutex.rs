@@ -1,6 +1,6 @@
 use super::*;
 
-use crate::error::Errno;
+use crate::{error::Errno, header::errno::EINVAL};
 
 // PTHREAD_MUTEX_INITIALIZER is defined in bits_pthread/cbindgen.toml
 
@@ -155,6 +155,12 @@ pub unsafe extern "C" fn pthread_mutexattr_setprotocol(
     attr: *mut pthread_mutexattr_t,
     protocol: c_int,
 ) -> c_int {
+    if !matches!(
+        protocol,
+        PTHREAD_PRIO_NONE | PTHREAD_PRIO_INHERIT | PTHREAD_PRIO_PROTECT
+    ) {
+        return EINVAL;
+    }
     (*attr.cast::<RlctMutexAttr>()).protocol = protocol;
     0
 }
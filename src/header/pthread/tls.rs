@@ -12,6 +12,7 @@ use crate::{header::errno::EINVAL, sync::Mutex};
 
 // TODO: What should this limit be?
 pub const PTHREAD_KEYS_MAX: u32 = 4096 * 32;
+pub const PTHREAD_DESTRUCTOR_ITERATIONS: c_int = 4;
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pthread_getspecific(key: pthread_key_t) -> *mut c_void {
@@ -104,14 +105,27 @@ struct Record {
 static NEXTKEY: AtomicUsize = AtomicUsize::new(1);
 
 pub(crate) unsafe fn run_all_destructors() {
-    for (key, Record { data }) in VALUES.take() {
-        let Some(&Dtor {
-            destructor: Some(dtor),
-        }) = KEYS.lock().get(&key)
-        else {
-            continue;
-        };
-
-        dtor(data);
+    // Destructors may set values again, which are destroyed in turn a limited number of times.
+    for _ in 0..PTHREAD_DESTRUCTOR_ITERATIONS {
+        let mut ran_any = false;
+
+        for (key, Record { data }) in VALUES.take() {
+            if data.is_null() {
+                continue;
+            }
+            let Some(&Dtor {
+                destructor: Some(dtor),
+            }) = KEYS.lock().get(&key)
+            else {
+                continue;
+            };
+
+            ran_any = true;
+            dtor(data);
+        }
+
+        if !ran_any {
+            break;
+        }
     }
 }
@@ -4,15 +4,17 @@
 
 use alloc::{boxed::Box, vec::Vec};
 use core::{
+    mem,
     ops::{Deref, DerefMut},
     pin::Pin,
-    ptr,
+    ptr, slice,
 };
 
 use crate::{
     fs::File,
     header::{errno, fcntl, string::strcmp},
     io::{BufReader, SeekFrom, prelude::*},
+    nss::{self, Database, LookupError, Source, Status},
     platform::{self, types::*},
     raw_cell::RawCell,
 };
@@ -116,6 +118,34 @@ enum Cause {
     Other,
 }
 
+impl LookupError for Cause {
+    fn from_status(status: Status) -> Self {
+        match status {
+            Status::NotFound => Cause::Eof,
+            _ => Cause::Other,
+        }
+    }
+
+    fn status(&self) -> Status {
+        match self {
+            Cause::Eof => Status::NotFound,
+            Cause::Other if platform::ERRNO.get() == errno::ERANGE => Status::TryAgain,
+            Cause::Other => Status::Unavail,
+        }
+    }
+}
+
+/// The user that a lookup is for.
+#[derive(Clone, Copy, Debug)]
+enum Key {
+    Name(*const c_char),
+    Uid(uid_t),
+}
+
+type GetpwnamR =
+    unsafe extern "C" fn(*const c_char, *mut passwd, *mut c_char, size_t, *mut c_int) -> c_int;
+type GetpwuidR = unsafe extern "C" fn(uid_t, *mut passwd, *mut c_char, size_t, *mut c_int) -> c_int;
+
 static READER: RawCell<Option<BufReader<File>>> = RawCell::new(None);
 
 fn parsed<I, O>(buf: Option<I>) -> Option<O>
@@ -178,10 +208,7 @@ fn getpwent_r(
     })
 }
 
-fn pwd_lookup<F>(mut matches: F, destination: Option<DestBuffer>) -> Result<OwnedPwd, Cause>
-where
-    F: FnMut(&passwd) -> bool,
-{
+fn files_lookup(key: Key, destination: Option<DestBuffer>) -> Result<OwnedPwd, Cause> {
     let file = match File::open(c"/etc/passwd".into(), fcntl::O_RDONLY) {
         Ok(file) => file,
         Err(_) => return Err(Cause::Other),
@@ -192,12 +219,65 @@ where
     loop {
         let entry = getpwent_r(&mut reader, destination)?;
 
-        if matches(&entry.reference) {
+        let matches = match key {
+            Key::Name(name) => unsafe { strcmp(entry.reference.pw_name, name) == 0 },
+            Key::Uid(uid) => entry.reference.pw_uid == uid,
+        };
+        if matches {
             return Ok(entry);
         }
     }
 }
 
+fn module_lookup(
+    module: &str,
+    key: Key,
+    destination: Option<DestBuffer>,
+) -> Result<OwnedPwd, Status> {
+    let mut reference: passwd = unsafe { mem::zeroed() };
+    let dest = destination.map(|dst| unsafe { slice::from_raw_parts_mut(dst.ptr, dst.len) });
+    let function = match key {
+        Key::Name(_) => "getpwnam_r",
+        Key::Uid(_) => "getpwuid_r",
+    };
+    let buffer = nss::module_lookup(module, function, dest, |symbol, buf, errnop| unsafe {
+        let buf_ptr = buf.as_mut_ptr() as *mut c_char;
+        match key {
+            Key::Name(name) => mem::transmute::<_, GetpwnamR>(symbol)(
+                name,
+                &mut reference,
+                buf_ptr,
+                buf.len(),
+                errnop,
+            ),
+            Key::Uid(uid) => mem::transmute::<_, GetpwuidR>(symbol)(
+                uid,
+                &mut reference,
+                buf_ptr,
+                buf.len(),
+                errnop,
+            ),
+        }
+    })?;
+    // Without a buffer of our own, the entry is in the one of the caller
+    let buffer = match buffer {
+        Some(buffer) => MaybeAllocated::Owned(Box::into_pin(buffer)),
+        None => MaybeAllocated::Borrowed(destination.ok_or(Status::Unavail)?),
+    };
+    Ok(OwnedPwd { buffer, reference })
+}
+
+/// Looks up a user in the sources of `passwd` in `nsswitch.conf`.
+fn pwd_lookup(key: Key, destination: Option<DestBuffer>) -> Result<OwnedPwd, Cause> {
+    nss::dispatch(Database::Passwd, |source| match source {
+        Source::Files => files_lookup(key, destination),
+        Source::Module(module) => {
+            module_lookup(module, key, destination).map_err(Cause::from_status)
+        }
+        Source::Dns => Err(Cause::from_status(Status::Unavail)),
+    })
+}
+
 unsafe fn mux(
     status: Result<OwnedPwd, Cause>,
     out: *mut passwd,
@@ -253,7 +333,7 @@ pub extern "C" fn getpwent() -> *mut passwd {
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/getpwnam.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn getpwnam(name: *const c_char) -> *mut passwd {
-    pwd_lookup(|parts| unsafe { strcmp(parts.pw_name, name) } == 0, None)
+    pwd_lookup(Key::Name(name), None)
         .map(|res| res.into_global())
         .unwrap_or(ptr::null_mut())
 }
@@ -269,7 +349,7 @@ pub unsafe extern "C" fn getpwnam_r(
 ) -> c_int {
     mux(
         pwd_lookup(
-            |parts| strcmp(parts.pw_name, name) == 0,
+            Key::Name(name),
             Some(DestBuffer {
                 ptr: buf as *mut u8,
                 len: size,
@@ -283,7 +363,7 @@ pub unsafe extern "C" fn getpwnam_r(
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/getpwuid.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn getpwuid(uid: uid_t) -> *mut passwd {
-    pwd_lookup(|parts| parts.pw_uid == uid, None)
+    pwd_lookup(Key::Uid(uid), None)
         .map(|res| res.into_global())
         .unwrap_or(ptr::null_mut())
 }
@@ -300,7 +380,7 @@ pub unsafe extern "C" fn getpwuid_r(
     let slice = core::slice::from_raw_parts_mut(buf as *mut u8, size);
     mux(
         pwd_lookup(
-            |part| part.pw_uid == uid,
+            Key::Uid(uid),
             Some(DestBuffer {
                 ptr: buf as *mut u8,
                 len: size,
@@ -0,0 +1,663 @@
+//! `resolv.h` implementation.
+//!
+//! Non-POSIX, see <https://man7.org/linux/man-pages/man3/resolver.3.html>.
+
+// TODO: set this for entire crate when possible
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    mem,
+    net::{IpAddr, Ipv6Addr},
+    ptr, slice, str,
+};
+
+use crate::{
+    c_str::CStr,
+    header::{
+        arpa_nameser::{
+            NS_HFIXEDSZ, NS_MAXDNAME, QUERY, compress, copy_name, labels_to_text, ns_r_noerror,
+            ns_r_nxdomain, ns_r_servfail, read_name, text_to_labels,
+        },
+        errno::{EINVAL, EMSGSIZE},
+        netdb::{
+            self, H_ERRNO, HOST_NOT_FOUND, NO_DATA, NO_RECOVERY, TRY_AGAIN,
+            dns::{Dns, DnsQuery, FLAG_TC},
+            lookup::{self, in_addr_to_ip, ipv4_to_in_addr},
+            resolv_conf::ResolvConf,
+        },
+        netinet_in::{htons, in6_addr, sockaddr_in, sockaddr_in6},
+        sys_socket::{
+            constants::{AF_INET, AF_INET6},
+            sa_family_t,
+        },
+    },
+    platform::{self, types::*},
+    raw_cell::RawCell,
+};
+
+/// The most nameservers that are used.
+pub const MAXNS: usize = 3;
+/// The most domains in the search list.
+pub const MAXDNSRCH: usize = 6;
+pub const MAXRESOLVSORT: c_int = 10;
+pub const RES_MAXNDOTS: c_int = 15;
+pub const RES_MAXRETRANS: c_int = 30;
+pub const RES_MAXRETRY: c_int = 5;
+pub const RES_DFLRETRY: c_int = 2;
+pub const RES_TIMEOUT: c_int = 5;
+
+pub const RES_INIT: c_ulong = 0x0000_0001;
+pub const RES_DEBUG: c_ulong = 0x0000_0002;
+pub const RES_USEVC: c_ulong = 0x0000_0008;
+pub const RES_IGNTC: c_ulong = 0x0000_0020;
+pub const RES_RECURSE: c_ulong = 0x0000_0040;
+pub const RES_DEFNAMES: c_ulong = 0x0000_0080;
+pub const RES_STAYOPEN: c_ulong = 0x0000_0100;
+pub const RES_DNSRCH: c_ulong = 0x0000_0200;
+pub const RES_NOALIASES: c_ulong = 0x0000_1000;
+pub const RES_ROTATE: c_ulong = 0x0000_4000;
+pub const RES_USE_EDNS0: c_ulong = 0x0010_0000;
+pub const RES_DEFAULT: c_ulong = RES_RECURSE | RES_DEFNAMES | RES_DNSRCH;
+
+/// The resolver state, set up from `/etc/resolv.conf` by [`res_ninit`] and
+/// open to changes before queries.
+#[repr(C)]
+pub struct __res_state {
+    /// Seconds to wait for each answer.
+    pub retrans: c_int,
+    /// How many times to go through the nameservers.
+    pub retry: c_int,
+    pub options: c_ulong,
+    pub nscount: c_int,
+    /// The IPv4 nameservers. The family of the IPv6 ones is 0 here, and they
+    /// are at the same index of `_nsaddrs6`.
+    pub nsaddr_list: [sockaddr_in; MAXNS],
+    pub id: c_ushort,
+    /// The search list, ending with a null pointer.
+    pub dnsrch: [*mut c_char; MAXDNSRCH + 1],
+    /// The first domain of the search list, followed by the others.
+    pub defdname: [c_char; 256],
+    pub pfcode: c_ulong,
+    pub ndots: c_uint,
+    pub res_h_errno: c_int,
+    pub _nsaddrs6: [sockaddr_in6; MAXNS],
+}
+
+pub type res_state = *mut __res_state;
+
+#[thread_local]
+static STATE: RawCell<__res_state> = RawCell::new(unsafe { mem::zeroed() });
+
+/// Provide a pointer to the resolver state of the thread, which `_res`
+/// expands to.
+#[unsafe(no_mangle)]
+pub extern "C" fn __res_state() -> res_state {
+    STATE.as_mut_ptr()
+}
+
+/// Sets `h_errno` both for the thread and in `statp`.
+fn set_h_errno(statp: &mut __res_state, err: c_int) {
+    statp.res_h_errno = err;
+    H_ERRNO.set(err);
+}
+
+/// The configuration that queries made with `statp` follow.
+fn conf(statp: &__res_state) -> ResolvConf {
+    let mut conf = ResolvConf::default();
+
+    let nscount = (statp.nscount.max(0) as usize).min(MAXNS);
+    let nameservers: Vec<IpAddr> = (0..nscount)
+        .filter_map(|i| match c_int::from(statp.nsaddr_list[i].sin_family) {
+            AF_INET => Some(in_addr_to_ip(statp.nsaddr_list[i].sin_addr).into()),
+            _ if c_int::from(statp._nsaddrs6[i].sin6_family) == AF_INET6 => {
+                Some(Ipv6Addr::from(statp._nsaddrs6[i].sin6_addr.s6_addr).into())
+            }
+            _ => None,
+        })
+        .collect();
+    if !nameservers.is_empty() {
+        conf.nameservers = nameservers;
+    }
+
+    conf.search = statp
+        .dnsrch
+        .iter()
+        .take_while(|domain| !domain.is_null())
+        .filter_map(|&domain| {
+            let domain = unsafe { CStr::from_ptr(domain) };
+            domain.to_str().ok().map(String::from)
+        })
+        .collect();
+    conf.ndots = statp.ndots;
+    conf.timeout = statp.retrans.max(1) as u32;
+    conf.attempts = statp.retry.max(1) as u32;
+    conf.rotate = statp.options & RES_ROTATE != 0;
+    conf.edns0 = statp.options & RES_USE_EDNS0 != 0;
+    conf.use_vc = statp.options & RES_USEVC != 0;
+    conf
+}
+
+/// Makes sure that the state of the thread is set up, for the functions
+/// that use it implicitly.
+unsafe fn implicit_state() -> Option<&'static mut __res_state> {
+    let statp = unsafe { &mut *__res_state() };
+    if statp.options & RES_INIT == 0 && unsafe { res_ninit(statp) } < 0 {
+        return None;
+    }
+    Some(statp)
+}
+
+/// Sets up `statp` from `/etc/resolv.conf`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_ninit(statp: res_state) -> c_int {
+    let statp = unsafe { &mut *statp };
+    let conf = netdb::sys::get_resolv_conf();
+
+    *statp = unsafe { mem::zeroed() };
+    statp.retrans = conf.timeout as c_int;
+    statp.retry = conf.attempts as c_int;
+    statp.ndots = conf.ndots;
+    statp.id = lookup::query_id();
+    statp.options = RES_INIT | RES_DEFAULT;
+    if conf.rotate {
+        statp.options |= RES_ROTATE;
+    }
+    if conf.edns0 {
+        statp.options |= RES_USE_EDNS0;
+    }
+    if conf.use_vc {
+        statp.options |= RES_USEVC;
+    }
+
+    for (i, &addr) in conf.nameservers.iter().take(MAXNS).enumerate() {
+        match addr {
+            IpAddr::V4(addr) => {
+                statp.nsaddr_list[i].sin_family = AF_INET as sa_family_t;
+                statp.nsaddr_list[i].sin_port = htons(53);
+                statp.nsaddr_list[i].sin_addr = ipv4_to_in_addr(addr);
+            }
+            IpAddr::V6(addr) => {
+                statp._nsaddrs6[i].sin6_family = AF_INET6 as sa_family_t;
+                statp._nsaddrs6[i].sin6_port = htons(53);
+                statp._nsaddrs6[i].sin6_addr = in6_addr {
+                    s6_addr: addr.octets(),
+                };
+            }
+        }
+        statp.nscount += 1;
+    }
+
+    // The domains are stored one after the other, as many as fit
+    let mut offset = 0;
+    for (i, domain) in conf.search.iter().take(MAXDNSRCH).enumerate() {
+        if offset + domain.len() >= statp.defdname.len() {
+            break;
+        }
+        let dst = unsafe { statp.defdname.as_mut_ptr().add(offset) };
+        unsafe { copy_name(domain.as_bytes(), dst, domain.len() + 1) };
+        statp.dnsrch[i] = dst;
+        offset += domain.len() + 1;
+    }
+    0
+}
+
+/// Sets up the state of the thread from `/etc/resolv.conf`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_init() -> c_int {
+    unsafe { res_ninit(__res_state()) }
+}
+
+/// Releases what `statp` holds. Sockets are not kept open between queries,
+/// so there is nothing to release.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_nclose(statp: res_state) {}
+
+/// Like [`res_nclose`], for the state of the thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn res_close() {}
+
+/// Builds a standard query for records of `type_` and `class_` for
+/// `dname`, with an OPT record if `edns` is set.
+unsafe fn make_query(
+    statp: &mut __res_state,
+    dname: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    edns: bool,
+) -> Result<Vec<u8>, c_int> {
+    if dname.is_null() {
+        return Err(EINVAL);
+    }
+
+    // The codec takes names as dotted strings, which leaves no way to put a
+    // dot inside of a label
+    let dname = unsafe { CStr::from_ptr(dname) };
+    let name = text_to_labels(dname.to_bytes())
+        .filter(|labels| labels.iter().all(|label| !label.contains(&b'.')))
+        .and_then(|labels| {
+            let labels: Vec<&str> = labels
+                .iter()
+                .map(|label| str::from_utf8(label).ok())
+                .collect::<Option<_>>()?;
+            Some(labels.join("."))
+        })
+        .ok_or(EINVAL)?;
+
+    statp.id = statp.id.wrapping_add(1);
+    let packet = Dns {
+        transaction_id: statp.id,
+        flags: if statp.options & RES_RECURSE != 0 {
+            0x0100
+        } else {
+            0
+        },
+        queries: vec![DnsQuery {
+            name,
+            q_type: type_ as u16,
+            q_class: class_ as u16,
+        }],
+        answers: vec![],
+    };
+    Ok(if edns {
+        packet.compile_edns(lookup::EDNS_PAYLOAD_SIZE)
+    } else {
+        packet.compile()
+    })
+}
+
+/// Builds a query for records of `type_` and `class_` for `dname` in `buf`,
+/// returning its length. Only standard queries are supported, so `data` and
+/// `newrr` are unused.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_nmkquery(
+    statp: res_state,
+    op: c_int,
+    dname: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    data: *const c_uchar,
+    datalen: c_int,
+    newrr: *const c_uchar,
+    buf: *mut c_uchar,
+    buflen: c_int,
+) -> c_int {
+    if op != QUERY {
+        platform::ERRNO.set(EINVAL);
+        return -1;
+    }
+    let packet = match unsafe { make_query(&mut *statp, dname, class_, type_, false) } {
+        Ok(packet) => packet,
+        Err(err) => {
+            platform::ERRNO.set(err);
+            return -1;
+        }
+    };
+    if packet.len() > buflen.max(0) as usize {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    }
+    unsafe { ptr::copy_nonoverlapping(packet.as_ptr(), buf, packet.len()) };
+    packet.len() as c_int
+}
+
+/// Sends the query `msg` to the nameservers of `statp`, and puts the answer
+/// in `answer`. Returns the length stored, which is at most `anslen`. An
+/// answer cut short has the TC bit set, as a truncated one from a nameserver
+/// would.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_nsend(
+    statp: res_state,
+    msg: *const c_uchar,
+    msglen: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    let statp = unsafe { &mut *statp };
+    if msglen < NS_HFIXEDSZ {
+        platform::ERRNO.set(EINVAL);
+        return -1;
+    }
+    let msg = unsafe { slice::from_raw_parts(msg, msglen as usize) };
+    match lookup::send_query(msg, &conf(statp)) {
+        Ok(reply) => {
+            let len = reply.len().min(anslen.max(0) as usize);
+            unsafe { ptr::copy_nonoverlapping(reply.as_ptr(), answer, len) };
+            if len < reply.len() && len > 2 {
+                unsafe { *answer.add(2) |= (FLAG_TC >> 8) as u8 };
+            }
+            len as c_int
+        }
+        Err(err) => {
+            platform::ERRNO.set(err);
+            -1
+        }
+    }
+}
+
+/// Queries records of `type_` and `class_` for exactly `dname`, putting the
+/// answer in `answer`. Returns the length of the answer, or -1 with
+/// `h_errno` set if it has no records.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_nquery(
+    statp: res_state,
+    dname: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    let statp = unsafe { &mut *statp };
+    // Unlike the queries of `res_nmkquery`, these may ask for larger answers
+    let edns = statp.options & RES_USE_EDNS0 != 0;
+    let Ok(query) = (unsafe { make_query(statp, dname, class_, type_, edns) }) else {
+        set_h_errno(statp, NO_RECOVERY);
+        return -1;
+    };
+
+    let len = unsafe { res_nsend(statp, query.as_ptr(), query.len() as c_int, answer, anslen) };
+    if len < 0 {
+        set_h_errno(statp, TRY_AGAIN);
+        return -1;
+    }
+    if len < NS_HFIXEDSZ || anslen < NS_HFIXEDSZ {
+        set_h_errno(statp, NO_RECOVERY);
+        return -1;
+    }
+
+    let header = unsafe { slice::from_raw_parts(answer, NS_HFIXEDSZ as usize) };
+    let rcode = c_int::from(header[3] & 0x0F);
+    let ancount = u16::from_be_bytes([header[6], header[7]]);
+    if rcode != ns_r_noerror || ancount == 0 {
+        let err = match rcode {
+            ns_r_nxdomain => HOST_NOT_FOUND,
+            ns_r_servfail => TRY_AGAIN,
+            ns_r_noerror => NO_DATA,
+            _ => NO_RECOVERY,
+        };
+        set_h_errno(statp, err);
+        return -1;
+    }
+    len
+}
+
+/// Like [`res_nquery`], for `name` in `domain`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_nquerydomain(
+    statp: res_state,
+    name: *const c_char,
+    domain: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    if domain.is_null() {
+        return unsafe { res_nquery(statp, name, class_, type_, answer, anslen) };
+    }
+    let name = unsafe { CStr::from_ptr(name) }.to_bytes();
+    let domain = unsafe { CStr::from_ptr(domain) }.to_bytes();
+
+    let mut full = Vec::with_capacity(name.len() + domain.len() + 2);
+    full.extend_from_slice(name);
+    full.push(b'.');
+    full.extend_from_slice(domain);
+    full.push(0);
+    if full.len() > NS_MAXDNAME {
+        let statp = unsafe { &mut *statp };
+        set_h_errno(statp, NO_RECOVERY);
+        return -1;
+    }
+    unsafe {
+        res_nquery(
+            statp,
+            full.as_ptr() as *const c_char,
+            class_,
+            type_,
+            answer,
+            anslen,
+        )
+    }
+}
+
+/// Like [`res_nquery`], trying `dname` under the domains of the search list
+/// as `/etc/resolv.conf` describes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_nsearch(
+    statp: res_state,
+    dname: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    let Ok(host) = unsafe { CStr::from_ptr(dname) }.to_str() else {
+        return unsafe { res_nquery(statp, dname, class_, type_, answer, anslen) };
+    };
+
+    let names = {
+        let statp = unsafe { &*statp };
+        let mut conf = conf(statp);
+        if statp.options & RES_DNSRCH == 0 {
+            if statp.options & RES_DEFNAMES == 0 {
+                conf.search.clear();
+            } else {
+                conf.search.truncate(1);
+            }
+        }
+        lookup::search_names(host, &conf)
+    };
+
+    // A name that exists without records of the type is worth reporting
+    // over one that does not exist at all
+    let mut no_data = false;
+    let mut err = HOST_NOT_FOUND;
+    for name in names {
+        let mut name = name.into_bytes();
+        name.push(0);
+        let len = unsafe {
+            res_nquery(
+                statp,
+                name.as_ptr() as *const c_char,
+                class_,
+                type_,
+                answer,
+                anslen,
+            )
+        };
+        if len >= 0 {
+            return len;
+        }
+        match unsafe { (*statp).res_h_errno } {
+            NO_DATA => no_data = true,
+            HOST_NOT_FOUND => (),
+            TRY_AGAIN => err = TRY_AGAIN,
+            other => {
+                err = other;
+                break;
+            }
+        }
+    }
+
+    let statp = unsafe { &mut *statp };
+    set_h_errno(statp, if no_data { NO_DATA } else { err });
+    -1
+}
+
+/// Like [`res_nmkquery`], with the state of the thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_mkquery(
+    op: c_int,
+    dname: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    data: *const c_uchar,
+    datalen: c_int,
+    newrr: *const c_uchar,
+    buf: *mut c_uchar,
+    buflen: c_int,
+) -> c_int {
+    let Some(statp) = (unsafe { implicit_state() }) else {
+        return -1;
+    };
+    unsafe {
+        res_nmkquery(
+            statp, op, dname, class_, type_, data, datalen, newrr, buf, buflen,
+        )
+    }
+}
+
+/// Like [`res_nsend`], with the state of the thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_send(
+    msg: *const c_uchar,
+    msglen: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    let Some(statp) = (unsafe { implicit_state() }) else {
+        return -1;
+    };
+    unsafe { res_nsend(statp, msg, msglen, answer, anslen) }
+}
+
+/// Like [`res_nquery`], with the state of the thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_query(
+    dname: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    let Some(statp) = (unsafe { implicit_state() }) else {
+        return -1;
+    };
+    unsafe { res_nquery(statp, dname, class_, type_, answer, anslen) }
+}
+
+/// Like [`res_nquerydomain`], with the state of the thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_querydomain(
+    name: *const c_char,
+    domain: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    let Some(statp) = (unsafe { implicit_state() }) else {
+        return -1;
+    };
+    unsafe { res_nquerydomain(statp, name, domain, class_, type_, answer, anslen) }
+}
+
+/// Like [`res_nsearch`], with the state of the thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn res_search(
+    dname: *const c_char,
+    class_: c_int,
+    type_: c_int,
+    answer: *mut c_uchar,
+    anslen: c_int,
+) -> c_int {
+    let Some(statp) = (unsafe { implicit_state() }) else {
+        return -1;
+    };
+    unsafe { res_nsearch(statp, dname, class_, type_, answer, anslen) }
+}
+
+/// Compresses the name `exp_dn` into `comp_dn` of `length` bytes, returning
+/// the length of the compressed name.
+///
+/// If `dnptrs` is not null, it lists the names already in the message,
+/// starting with the message itself and ending with a null pointer before
+/// `lastdnptr`. The name is compressed against them, and added to them.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dn_comp(
+    exp_dn: *const c_char,
+    comp_dn: *mut c_uchar,
+    length: c_int,
+    dnptrs: *mut *mut c_uchar,
+    lastdnptr: *mut *mut c_uchar,
+) -> c_int {
+    let text = unsafe { CStr::from_ptr(exp_dn) }.to_bytes();
+    let Some(labels) = text_to_labels(text) else {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    };
+
+    // The names of the message, each as its offset from the start
+    let mut msg: &[u8] = &[];
+    let mut known = Vec::new();
+    let mut end = ptr::null_mut();
+    if !dnptrs.is_null() && unsafe { !(*dnptrs).is_null() } {
+        let base = unsafe { *dnptrs };
+        end = unsafe { dnptrs.add(1) };
+        while unsafe { !(*end).is_null() } {
+            known.push(unsafe { (*end).offset_from(base) } as usize);
+            end = unsafe { end.add(1) };
+        }
+        // Everything up to the new name is already in the message
+        msg = unsafe { slice::from_raw_parts(base, comp_dn.offset_from(base) as usize) };
+    }
+
+    let (encoded, written) = compress(&labels, msg, &known);
+    if encoded.len() > length.max(0) as usize {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    }
+    unsafe { ptr::copy_nonoverlapping(encoded.as_ptr(), comp_dn, encoded.len()) };
+
+    // Later names can point to any suffix of this one that was written out
+    if !end.is_null() && written > 0 && (lastdnptr.is_null() || end < lastdnptr) {
+        unsafe {
+            *end = comp_dn;
+            *end.add(1) = ptr::null_mut();
+        }
+    }
+    encoded.len() as c_int
+}
+
+/// Expands the compressed name at `comp_dn` of the message from `msg` to
+/// `eomorig` into text form in `exp_dn` of `length` bytes, returning the
+/// length of the compressed name.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dn_expand(
+    msg: *const c_uchar,
+    eomorig: *const c_uchar,
+    comp_dn: *const c_uchar,
+    exp_dn: *mut c_char,
+    length: c_int,
+) -> c_int {
+    let data = unsafe { slice::from_raw_parts(msg, eomorig.offset_from(msg) as usize) };
+    let pos = unsafe { comp_dn.offset_from(msg) } as usize;
+    let Some((labels, len)) = read_name(data, pos) else {
+        platform::ERRNO.set(EMSGSIZE);
+        return -1;
+    };
+    // Without the trailing dot, which leaves the root empty
+    let mut text = labels_to_text(labels.into_iter().map(|(_, label)| label));
+    text.pop();
+    if !unsafe { copy_name(&text, exp_dn, length.max(0) as usize) } {
+        return -1;
+    }
+    len as c_int
+}
+
+/// Skips the compressed name at `comp_dn`, returning its length.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dn_skipname(comp_dn: *const c_uchar, eom: *const c_uchar) -> c_int {
+    let data = unsafe { slice::from_raw_parts(comp_dn, eom.offset_from(comp_dn) as usize) };
+    let mut pos = 0;
+    while let Some(&len) = data.get(pos) {
+        match len & 0xc0 {
+            0xc0 => return pos as c_int + 2,
+            0 if len == 0 => return pos as c_int + 1,
+            0 => pos += usize::from(len) + 1,
+            _ => break,
+        }
+    }
+    platform::ERRNO.set(EMSGSIZE);
+    -1
+}
@@ -1,47 +1,145 @@
 //! sched.h implementation for Redox, following https://pubs.opengroup.org/onlinepubs/7908799/xsh/sched.h.html
 
+use core::{mem, slice};
+
 use crate::{
-    error::ResultExt,
-    header::time::timespec,
+    error::{Errno, ResultExt},
+    header::{stdlib, time::timespec},
+    out::Out,
     platform::{Pal, Sys, types::*},
 };
 
+#[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct sched_param {
     pub sched_priority: c_int,
 }
 
+#[cfg(target_os = "linux")]
+pub const SCHED_OTHER: c_int = 0;
+#[cfg(target_os = "linux")]
+pub const SCHED_FIFO: c_int = 1;
+#[cfg(target_os = "linux")]
+pub const SCHED_RR: c_int = 2;
+#[cfg(target_os = "linux")]
+pub const SCHED_BATCH: c_int = 3;
+#[cfg(target_os = "linux")]
+pub const SCHED_IDLE: c_int = 5;
+
+#[cfg(target_os = "redox")]
 pub const SCHED_FIFO: c_int = 0;
+#[cfg(target_os = "redox")]
 pub const SCHED_RR: c_int = 1;
+#[cfg(target_os = "redox")]
 pub const SCHED_OTHER: c_int = 2;
 
-// #[unsafe(no_mangle)]
+// cpu_set_t is also defined in C because cbindgen is incompatible with mem::size_of
+pub const CPU_SETSIZE: usize = 1024;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct cpu_set_t {
+    pub __bits: [c_ulong; CPU_SETSIZE / (8 * mem::size_of::<c_ulong>())],
+}
+
+/// Backs `CPU_ALLOC`.
+#[unsafe(no_mangle)]
+pub extern "C" fn __sched_cpualloc(count: size_t) -> *mut cpu_set_t {
+    let size = count.div_ceil(c_ulong::BITS as usize) * mem::size_of::<c_ulong>();
+    unsafe { stdlib::malloc(size).cast() }
+}
+
+/// Backs `CPU_COUNT` and `CPU_COUNT_S`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn __sched_cpucount(setsize: size_t, set: *const cpu_set_t) -> c_int {
+    slice::from_raw_parts(set.cast::<u8>(), setsize)
+        .iter()
+        .map(|byte| byte.count_ones() as c_int)
+        .sum()
+}
+
+/// Backs `CPU_FREE`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn __sched_cpufree(set: *mut cpu_set_t) {
+    stdlib::free(set.cast());
+}
+
+#[unsafe(no_mangle)]
 pub extern "C" fn sched_get_priority_max(policy: c_int) -> c_int {
-    todo!()
+    Sys::sched_get_priority_max(policy).or_minus_one_errno()
 }
-// #[unsafe(no_mangle)]
+#[unsafe(no_mangle)]
 pub extern "C" fn sched_get_priority_min(policy: c_int) -> c_int {
-    todo!()
+    Sys::sched_get_priority_min(policy).or_minus_one_errno()
+}
+
+/// Fills `mask` with the CPUs thread `pid` may run on, clearing those the kernel knows nothing
+/// about.
+pub(crate) fn getaffinity(pid: pid_t, mut mask: Out<[u8]>) -> Result<(), Errno> {
+    let len = mask.len();
+    let written = Sys::sched_getaffinity(pid, mask.subslice(0, len))?;
+    mask.subslice(written, len).zero();
+    Ok(())
 }
-// #[unsafe(no_mangle)]
+
+/// See <https://man7.org/linux/man-pages/man2/sched_getaffinity.2.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sched_getaffinity(
+    pid: pid_t,
+    cpusetsize: size_t,
+    mask: *mut cpu_set_t,
+) -> c_int {
+    getaffinity(pid, Out::from_raw_parts(mask.cast::<u8>(), cpusetsize))
+        .map(|()| 0)
+        .or_minus_one_errno()
+}
+/// See <https://man7.org/linux/man-pages/man3/sched_getcpu.3.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn sched_getcpu() -> c_int {
+    Sys::sched_getcpu().or_minus_one_errno()
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn sched_getparam(pid: pid_t, param: *mut sched_param) -> c_int {
-    todo!()
+    Sys::sched_getparam(pid, Out::nonnull(param))
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
-// #[unsafe(no_mangle)]
-pub extern "C" fn sched_rr_get_interval(pid: pid_t, time: *const timespec) -> c_int {
-    todo!()
+#[unsafe(no_mangle)]
+pub extern "C" fn sched_getscheduler(pid: pid_t) -> c_int {
+    Sys::sched_getscheduler(pid).or_minus_one_errno()
 }
-// #[unsafe(no_mangle)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sched_rr_get_interval(pid: pid_t, time: *mut timespec) -> c_int {
+    Sys::sched_rr_get_interval(pid, Out::nonnull(time))
+        .map(|()| 0)
+        .or_minus_one_errno()
+}
+/// See <https://man7.org/linux/man-pages/man2/sched_setaffinity.2.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sched_setaffinity(
+    pid: pid_t,
+    cpusetsize: size_t,
+    mask: *const cpu_set_t,
+) -> c_int {
+    Sys::sched_setaffinity(pid, slice::from_raw_parts(mask.cast::<u8>(), cpusetsize))
+        .map(|()| 0)
+        .or_minus_one_errno()
+}
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn sched_setparam(pid: pid_t, param: *const sched_param) -> c_int {
-    todo!()
+    Sys::sched_setparam(pid, &*param)
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
-// #[unsafe(no_mangle)]
-pub extern "C" fn sched_setscheduler(
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sched_setscheduler(
     pid: pid_t,
     policy: c_int,
     param: *const sched_param,
 ) -> c_int {
-    todo!()
+    Sys::sched_setscheduler(pid, policy, &*param)
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
 #[unsafe(no_mangle)]
 pub extern "C" fn sched_yield() -> c_int {
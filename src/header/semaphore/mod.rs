@@ -2,9 +2,23 @@
 //!
 //! See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/semaphore.h.html>.
 
+use alloc::collections::BTreeMap;
+use core::{mem, ptr};
+
 use crate::{
-    header::time::{CLOCK_MONOTONIC, CLOCK_REALTIME, timespec},
-    platform::types::*,
+    c_str::{CStr, CString},
+    error::{Errno, ResultExt},
+    header::{
+        errno::{EEXIST, EINVAL, ENAMETOOLONG, ENOENT},
+        fcntl::{O_CLOEXEC, O_CREAT, O_EXCL, O_RDWR},
+        limits::{NAME_MAX, SEM_VALUE_MAX},
+        sys_mman::{self, MAP_SHARED, PROT_READ, PROT_WRITE},
+        sys_stat::stat,
+        time::{CLOCK_MONOTONIC, CLOCK_REALTIME, timespec},
+    },
+    out::Out,
+    platform::{ERRNO, Pal, Sys, types::*},
+    sync::Mutex,
 };
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/semaphore.h.html>.
@@ -17,10 +31,132 @@ pub union sem_t {
 }
 pub type RlctSempahore = crate::sync::Semaphore;
 
+/// A named semaphore mapped into this process, shared by every `sem_open` of the same object.
+struct NamedSemaphore {
+    sem: *mut sem_t,
+    refs: usize,
+}
+unsafe impl Send for NamedSemaphore {}
+
+// Keyed by (st_dev, st_ino), so that opening the same object twice returns the same address.
+static NAMED_SEMAPHORES: Mutex<BTreeMap<(dev_t, ino_t), NamedSemaphore>> =
+    Mutex::new(BTreeMap::new());
+
+/// Returns the shared memory path backing the named semaphore `name`.
+unsafe fn sem_path(name: *const c_char) -> Result<CString, Errno> {
+    if name.is_null() {
+        return Err(Errno(EINVAL));
+    }
+    let name = unsafe { CStr::from_ptr(name) }.to_bytes();
+    let name = name.strip_prefix(b"/").unwrap_or(name);
+
+    if name.is_empty() || name.contains(&b'/') {
+        return Err(Errno(EINVAL));
+    }
+    // Leave room for the "sem." prefix.
+    if name.len() > NAME_MAX - 4 {
+        return Err(Errno(ENAMETOOLONG));
+    }
+
+    let mut shm_name = b"sem.".to_vec();
+    shm_name.extend_from_slice(name);
+    shm_name.push(0);
+
+    Ok(unsafe { sys_mman::shm_path(shm_name.as_ptr().cast()) })
+}
+
+unsafe fn open_named(
+    name: *const c_char,
+    oflag: c_int,
+    mode: mode_t,
+    value: c_uint,
+) -> Result<*mut sem_t, Errno> {
+    if oflag & O_CREAT == O_CREAT && value as usize > SEM_VALUE_MAX {
+        return Err(Errno(EINVAL));
+    }
+    let path = unsafe { sem_path(name) }?;
+    let path = CStr::borrow(&path);
+
+    let (fd, created) = loop {
+        if oflag & O_CREAT == O_CREAT {
+            match Sys::open(path, O_RDWR | O_CREAT | O_EXCL | O_CLOEXEC, mode) {
+                Ok(fd) => break (fd, true),
+                Err(Errno(EEXIST)) if oflag & O_EXCL != O_EXCL => (),
+                Err(err) => return Err(err),
+            }
+        }
+        match Sys::open(path, O_RDWR | O_CLOEXEC, 0) {
+            Ok(fd) => break (fd, false),
+            // Unlinked between the two opens, try to create it again.
+            Err(Errno(ENOENT)) if oflag & O_CREAT == O_CREAT => continue,
+            Err(err) => return Err(err),
+        }
+    };
+
+    let result = unsafe { map_named(fd, created, value) };
+    let _ = Sys::close(fd);
+
+    if result.is_err() && created {
+        let _ = Sys::unlink(path);
+    }
+    result
+}
+
+unsafe fn map_named(fd: c_int, created: bool, value: c_uint) -> Result<*mut sem_t, Errno> {
+    let mut st = stat::default();
+    Sys::fstat(fd, Out::from_mut(&mut st))?;
+
+    let mut named = NAMED_SEMAPHORES.lock();
+    if let Some(entry) = named.get_mut(&(st.st_dev, st.st_ino)) {
+        entry.refs += 1;
+        return Ok(entry.sem);
+    }
+
+    // Every opener extends the object, so that a concurrent sem_open racing with the creator
+    // never maps past the end of the file. The zero-filled contents are a valid semaphore with
+    // a count of zero, and the creator then posts the initial value.
+    let size = mem::size_of::<sem_t>();
+    if (st.st_size as usize) < size {
+        Sys::ftruncate(fd, size as off_t)?;
+    }
+    let sem = unsafe {
+        Sys::mmap(
+            ptr::null_mut(),
+            size,
+            PROT_READ | PROT_WRITE,
+            MAP_SHARED,
+            fd,
+            0,
+        )
+    }?
+    .cast::<sem_t>();
+
+    if created && value > 0 {
+        unsafe { get(sem) }.post(value);
+    }
+
+    named.insert((st.st_dev, st.st_ino), NamedSemaphore { sem, refs: 1 });
+    Ok(sem)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/sem_close.html>.
-// #[unsafe(no_mangle)]
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn sem_close(sem: *mut sem_t) -> c_int {
-    todo!("named semaphores")
+    let mut named = NAMED_SEMAPHORES.lock();
+
+    let Some((&key, entry)) = named.iter_mut().find(|(_, entry)| entry.sem == sem) else {
+        return Err(Errno(EINVAL)).or_minus_one_errno();
+    };
+    entry.refs -= 1;
+    if entry.refs > 0 {
+        return 0;
+    }
+    named.remove(&key);
+    drop(named);
+
+    unsafe { Sys::munmap(sem.cast(), mem::size_of::<sem_t>()) }
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/sem_destroy.html>.
@@ -47,13 +183,26 @@ pub unsafe extern "C" fn sem_init(sem: *mut sem_t, _pshared: c_int, value: c_uin
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/sem_open.html>.
-// TODO: va_list
-// #[unsafe(no_mangle)]
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn sem_open(
     name: *const c_char,
-    oflag: c_int, /* (va_list) value: c_uint */
+    oflag: c_int,
+    mut __valist: ...
 ) -> *mut sem_t {
-    todo!("named semaphores")
+    let (mode, value) = if oflag & O_CREAT == O_CREAT {
+        unsafe { (__valist.arg::<mode_t>(), __valist.arg::<c_uint>()) }
+    } else {
+        (0, 0)
+    };
+
+    match unsafe { open_named(name, oflag, mode, value) } {
+        Ok(sem) => sem,
+        Err(Errno(errno)) => {
+            ERRNO.set(errno);
+            // SEM_FAILED
+            ptr::null_mut()
+        }
+    }
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/sem_post.html>.
@@ -73,15 +222,21 @@ pub unsafe extern "C" fn sem_trywait(sem: *mut sem_t) -> c_int {
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/sem_unlink.html>.
-// #[unsafe(no_mangle)]
+///
+/// The name is removed immediately, while processes that still have the semaphore open keep
+/// using it until they call `sem_close`.
+#[unsafe(no_mangle)]
 pub unsafe extern "C" fn sem_unlink(name: *const c_char) -> c_int {
-    todo!("named semaphores")
+    unsafe { sem_path(name) }
+        .and_then(|path| Sys::unlink(CStr::borrow(&path)))
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/sem_trywait.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sem_wait(sem: *mut sem_t) -> c_int {
-    get(sem).wait(None, CLOCK_MONOTONIC);
+    get(sem).wait_cancelable(None, CLOCK_MONOTONIC);
 
     0
 }
@@ -93,7 +248,7 @@ pub unsafe extern "C" fn sem_clockwait(
     clock_id: clockid_t,
     abstime: *const timespec,
 ) -> c_int {
-    get(sem).wait(Some(&*abstime), clock_id);
+    get(sem).wait_cancelable(Some(&*abstime), clock_id);
 
     0
 }
@@ -101,7 +256,7 @@ pub unsafe extern "C" fn sem_clockwait(
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/sem_timedwait.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sem_timedwait(sem: *mut sem_t, abstime: *const timespec) -> c_int {
-    get(sem).wait(Some(&*abstime), CLOCK_REALTIME);
+    get(sem).wait_cancelable(Some(&*abstime), CLOCK_REALTIME);
 
     0
 }
@@ -6,7 +6,7 @@ use core::{
     mem,
     ops::{Deref, DerefMut},
     pin::Pin,
-    ptr,
+    ptr, slice,
     str::FromStr,
 };
 
@@ -17,6 +17,7 @@ use crate::{
     fs::File,
     header::{errno, fcntl, string::strlen},
     io::{BufReader, Lines, prelude::*},
+    nss::{self, Database, LookupError, Source, Status},
     platform,
     platform::types::*,
 };
@@ -99,8 +100,33 @@ enum Error {
     EOF,
     BufTooSmall,
     Syntax,
+    /// No source has the entry.
+    NotFound,
+    /// A module of `nsswitch.conf` failed.
+    Nss(Status),
 }
 
+impl LookupError for Error {
+    fn from_status(status: Status) -> Self {
+        match status {
+            Status::NotFound => Error::NotFound,
+            status => Error::Nss(status),
+        }
+    }
+
+    fn status(&self) -> Status {
+        match self {
+            Error::NotFound => Status::NotFound,
+            Error::BufTooSmall => Status::TryAgain,
+            Error::Nss(status) => *status,
+            _ => Status::Unavail,
+        }
+    }
+}
+
+type GetspnamR =
+    unsafe extern "C" fn(*const c_char, *mut spwd, *mut c_char, size_t, *mut c_int) -> c_int;
+
 #[derive(Debug)]
 struct OwnedSpwd {
     buffer: MaybeAllocated,
@@ -181,22 +207,60 @@ fn parse_spwd(line: String, destbuf: Option<DestBuffer>) -> Result<OwnedSpwd, Er
     Ok(OwnedSpwd { buffer, reference })
 }
 
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn getspnam(name: *const c_char) -> *mut spwd {
+fn files_lookup(name: &[u8], destbuf: Option<DestBuffer>) -> Result<OwnedSpwd, Error> {
     let Ok(db) = File::open(SHADOW_FILE.into(), fcntl::O_RDONLY) else {
-        return ptr::null_mut();
+        return Err(Error::Nss(Status::Unavail));
     };
-    let c_name = unsafe { CStr::from_ptr(name) };
 
     for line in BufReader::new(db).lines() {
         let Ok(line) = line else { continue };
-        if line.starts_with(c_name.to_str().unwrap_or("\0")) {
-            if let Ok(pwd) = parse_spwd(line, None) {
-                return pwd.into_global();
-            }
+        if line.split(SEPARATOR).next().map(str::as_bytes) == Some(name) {
+            return parse_spwd(line, destbuf);
         }
     }
-    ptr::null_mut()
+    Err(Error::NotFound)
+}
+
+fn module_lookup(
+    module: &str,
+    name: *const c_char,
+    destbuf: Option<DestBuffer>,
+) -> Result<OwnedSpwd, Status> {
+    let mut reference: spwd = unsafe { mem::zeroed() };
+    let dest = destbuf.map(|dst| unsafe { slice::from_raw_parts_mut(dst.ptr, dst.len) });
+    let buffer = nss::module_lookup(module, "getspnam_r", dest, |symbol, buf, errnop| unsafe {
+        mem::transmute::<_, GetspnamR>(symbol)(
+            name,
+            &mut reference,
+            buf.as_mut_ptr() as *mut c_char,
+            buf.len(),
+            errnop,
+        )
+    })?;
+    // Without a buffer of our own, the entry is in the one of the caller
+    let buffer = match buffer {
+        Some(buffer) => MaybeAllocated::Owned(Box::into_pin(buffer)),
+        None => MaybeAllocated::Borrowed(destbuf.ok_or(Status::Unavail)?),
+    };
+    Ok(OwnedSpwd { buffer, reference })
+}
+
+/// Looks up the entry of a user in the sources of `shadow` in
+/// `nsswitch.conf`.
+fn spwd_lookup(name: *const c_char, destbuf: Option<DestBuffer>) -> Result<OwnedSpwd, Error> {
+    let c_name = unsafe { CStr::from_ptr(name) };
+    nss::dispatch(Database::Shadow, |source| match source {
+        Source::Files => files_lookup(c_name.to_bytes(), destbuf),
+        Source::Module(module) => module_lookup(module, name, destbuf).map_err(Error::from_status),
+        Source::Dns => Err(Error::Nss(Status::Unavail)),
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getspnam(name: *const c_char) -> *mut spwd {
+    spwd_lookup(name, None)
+        .map(|sp| sp.into_global())
+        .unwrap_or(ptr::null_mut())
 }
 
 #[unsafe(no_mangle)]
@@ -209,32 +273,22 @@ pub unsafe extern "C" fn getspnam_r(
 ) -> c_int {
     unsafe { *result = ptr::null_mut() };
 
-    let Ok(db) = File::open(SHADOW_FILE.into(), fcntl::O_RDONLY) else {
-        return ENOENT;
-    };
-    let c_name = unsafe { CStr::from_ptr(name).to_str().unwrap_or("\0") };
-
-    for line in BufReader::new(db).lines() {
-        let Ok(line) = line else { continue };
-        if line.starts_with(c_name) {
-            let dest_buf = Some(DestBuffer {
-                ptr: buffer as *mut u8,
-                len: buflen,
-            });
-            return match parse_spwd(line, dest_buf) {
-                Ok(sp) => {
-                    unsafe {
-                        *result_buf = sp.reference;
-                        *result = result_buf;
-                    }
-                    0
-                }
-                Err(Error::BufTooSmall) => ERANGE,
-                _ => ENOENT,
-            };
+    let dest_buf = Some(DestBuffer {
+        ptr: buffer as *mut u8,
+        len: buflen,
+    });
+    match spwd_lookup(name, dest_buf) {
+        Ok(sp) => {
+            unsafe {
+                *result_buf = sp.reference;
+                *result = result_buf;
+            }
+            0
         }
+        Err(Error::BufTooSmall) => ERANGE,
+        Err(Error::Nss(Status::TryAgain)) if platform::ERRNO.get() == ERANGE => ERANGE,
+        Err(_) => ENOENT,
     }
-    ENOENT
 }
 
 #[unsafe(no_mangle)]
@@ -424,7 +424,10 @@ pub unsafe extern "C" fn sigset(
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sigsuspend(sigmask: *const sigset_t) -> c_int {
-    Err(Sys::sigsuspend(&*sigmask)).or_minus_one_errno()
+    Err(crate::pthread::cancellation_point(|| {
+        Sys::sigsuspend(&*sigmask)
+    }))
+    .or_minus_one_errno()
 }
 
 #[unsafe(no_mangle)]
@@ -447,7 +450,7 @@ pub unsafe extern "C" fn sigtimedwait(
     // to differentiate between sigtimedwait and sigwaitinfo internally
     tp: *const timespec,
 ) -> c_int {
-    Sys::sigtimedwait(&*set, sig.as_mut(), tp.as_ref())
+    crate::pthread::cancellation_point(|| Sys::sigtimedwait(&*set, sig.as_mut(), tp.as_ref()))
         .map(|()| 0)
         .or_minus_one_errno()
 }
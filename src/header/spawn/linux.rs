@@ -0,0 +1,73 @@
+use core::{
+    mem, ptr,
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+use super::Child;
+use crate::{
+    error::{Errno, Result},
+    header::sys_mman::{MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ, PROT_WRITE},
+    platform::{Pal, Sys, types::*},
+};
+
+/// Stack used by the child until it calls `execve`.
+const CHILD_STACK_SIZE: usize = 64 * 1024;
+
+struct VforkChild<'a> {
+    child: &'a Child<'a>,
+    error: AtomicI32,
+}
+
+extern "C" fn child_entry(arg: *mut c_void) -> ! {
+    // The child shares our memory, so its error is visible to us once it has exited.
+    let vfork_child = unsafe { &*(arg as *const VforkChild) };
+    let Errno(err) = unsafe { vfork_child.child.run() };
+    vfork_child.error.store(err, Ordering::Release);
+    Sys::exit(127)
+}
+
+/// Creates the child with `CLONE_VM | CLONE_VFORK`, so that the parent's memory is not copied
+/// and the calling thread only resumes once the child has called `execve` or exited.
+pub(super) unsafe fn spawn(child: &Child) -> Result<pid_t> {
+    let vfork_child = VforkChild {
+        child,
+        error: AtomicI32::new(0),
+    };
+
+    let stack_base = unsafe {
+        Sys::mmap(
+            ptr::null_mut(),
+            CHILD_STACK_SIZE,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | MAP_ANONYMOUS,
+            -1,
+            0,
+        )?
+    };
+
+    let mut stack = unsafe {
+        stack_base
+            .cast::<usize>()
+            .add(CHILD_STACK_SIZE / mem::size_of::<usize>())
+    };
+    {
+        let mut push = |value: usize| unsafe {
+            stack = stack.sub(1);
+            stack.write(value);
+        };
+        push(&raw const vfork_child as usize);
+        push(child_entry as usize);
+    }
+
+    let result = unsafe { crate::platform::sys::clone_vfork(stack) };
+    let _ = unsafe { Sys::munmap(stack_base, CHILD_STACK_SIZE) };
+    let pid = result?;
+
+    match vfork_child.error.load(Ordering::Acquire) {
+        0 => Ok(pid),
+        err => {
+            let _ = Sys::waitpid(pid, None, 0);
+            Err(Errno(err))
+        }
+    }
+}
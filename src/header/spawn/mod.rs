@@ -0,0 +1,630 @@
+//! `spawn.h` implementation.
+//!
+//! The child applies the spawn attributes and file actions and then calls `execve`. If any of
+//! that fails, the error is reported back to the parent, which returns it from `posix_spawn`.
+//!
+//! See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/spawn.h.html>.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{mem, ptr};
+
+use crate::{
+    c_str::{CStr, CString},
+    error::{Errno, Result},
+    header::{
+        errno::{EACCES, EBADF, EINVAL, ENOENT, ENOMEM, ENOTDIR},
+        fcntl::{F_GETFD, F_SETFD, FD_CLOEXEC},
+        sched::sched_param,
+        signal::{SIG_BLOCK, SIG_DFL, SIG_IGN, SIG_SETMASK, SIGRTMAX, sigaction, sigset_t},
+        stdlib::getenv,
+        sys_resource::{RLIMIT_NOFILE, rlim_t, rlimit},
+    },
+    out::Out,
+    platform::{self, Pal, PalSignal, Sys, types::*},
+};
+
+#[cfg(target_os = "linux")]
+#[path = "linux.rs"]
+mod sys;
+
+#[cfg(target_os = "redox")]
+#[path = "redox.rs"]
+mod sys;
+
+pub const POSIX_SPAWN_RESETIDS: c_short = 0x01;
+pub const POSIX_SPAWN_SETPGROUP: c_short = 0x02;
+pub const POSIX_SPAWN_SETSIGDEF: c_short = 0x04;
+pub const POSIX_SPAWN_SETSIGMASK: c_short = 0x08;
+pub const POSIX_SPAWN_SETSCHEDPARAM: c_short = 0x10;
+pub const POSIX_SPAWN_SETSCHEDULER: c_short = 0x20;
+pub const POSIX_SPAWN_USEVFORK: c_short = 0x40;
+pub const POSIX_SPAWN_SETSID: c_short = 0x80;
+
+const POSIX_SPAWN_ALL: c_short = POSIX_SPAWN_RESETIDS
+    | POSIX_SPAWN_SETPGROUP
+    | POSIX_SPAWN_SETSIGDEF
+    | POSIX_SPAWN_SETSIGMASK
+    | POSIX_SPAWN_SETSCHEDPARAM
+    | POSIX_SPAWN_SETSCHEDULER
+    | POSIX_SPAWN_USEVFORK
+    | POSIX_SPAWN_SETSID;
+
+/// Search path used by `posix_spawnp` when `PATH` is unset.
+const DEFAULT_PATH: &[u8] = b"/usr/local/bin:/bin:/usr/bin";
+const PATH_SEPARATOR: u8 = b':';
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/spawn.h.html>.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct posix_spawnattr_t {
+    __flags: c_short,
+    __pgroup: pid_t,
+    __sigdefault: sigset_t,
+    __sigmask: sigset_t,
+    __schedparam: sched_param,
+    __schedpolicy: c_int,
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/spawn.h.html>.
+#[repr(C)]
+pub struct posix_spawn_file_actions_t {
+    // Boxed `Vec<FileAction>`, or null when no actions have been added yet.
+    __actions: *mut c_void,
+}
+
+enum FileAction {
+    Open {
+        fd: c_int,
+        path: CString,
+        oflag: c_int,
+        mode: mode_t,
+    },
+    Close(c_int),
+    Dup2 {
+        fd: c_int,
+        newfd: c_int,
+    },
+    Chdir(CString),
+    Fchdir(c_int),
+    Closefrom(c_int),
+}
+
+impl posix_spawn_file_actions_t {
+    unsafe fn actions(&self) -> &[FileAction] {
+        match unsafe { (self.__actions as *const Vec<FileAction>).as_ref() } {
+            Some(actions) => actions,
+            None => &[],
+        }
+    }
+
+    fn push(&mut self, action: FileAction) -> c_int {
+        if self.__actions.is_null() {
+            self.__actions = Box::into_raw(Box::new(Vec::<FileAction>::new())).cast();
+        }
+        let actions = unsafe { &mut *(self.__actions as *mut Vec<FileAction>) };
+        if actions.try_reserve(1).is_err() {
+            return ENOMEM;
+        }
+        actions.push(action);
+        0
+    }
+}
+
+/// Everything the child needs, prepared by the parent so that the child does not have to
+/// allocate.
+struct Child<'a> {
+    /// Candidate paths to execute, tried in order.
+    paths: &'a [CString],
+    argv: *const *mut c_char,
+    envp: *const *mut c_char,
+    actions: &'a [FileAction],
+    attr: Option<&'a posix_spawnattr_t>,
+    /// Signal mask of the calling thread, restored in the child unless overridden.
+    sigmask: sigset_t,
+}
+
+impl Child<'_> {
+    /// Sets up the process and executes the program. Only returns on failure.
+    unsafe fn run(&self) -> Errno {
+        match unsafe { self.setup() } {
+            Ok(()) => unsafe { self.exec() },
+            Err(err) => err,
+        }
+    }
+
+    unsafe fn setup(&self) -> Result<()> {
+        let flags = self.attr.map_or(0, |attr| attr.__flags);
+        let sigdefault = match self.attr {
+            Some(attr) if flags & POSIX_SPAWN_SETSIGDEF != 0 => attr.__sigdefault,
+            _ => 0,
+        };
+
+        // Caught signals must not run the parent's handlers in the child before exec, and
+        // SETSIGDEF resets ignored ones as well.
+        for sig in 1..=SIGRTMAX as c_int {
+            let mut old = unsafe { mem::zeroed::<sigaction>() };
+            if Sys::sigaction(sig, None, Some(&mut old)).is_err() {
+                continue;
+            }
+            let handler = old.sa_handler.map_or(SIG_DFL, |f| f as usize);
+            let reset = sigdefault & (1 << (sig - 1)) != 0;
+            if handler != SIG_DFL && (reset || handler != SIG_IGN) {
+                let mut act = unsafe { mem::zeroed::<sigaction>() };
+                act.sa_handler = None;
+                let _ = Sys::sigaction(sig, Some(&act), None);
+            }
+        }
+
+        if let Some(attr) = self.attr {
+            if flags & POSIX_SPAWN_SETSID != 0 {
+                Sys::setsid()?;
+            }
+            if flags & POSIX_SPAWN_SETPGROUP != 0 {
+                Sys::setpgid(0, attr.__pgroup)?;
+            }
+            if flags & POSIX_SPAWN_SETSCHEDULER != 0 {
+                Sys::sched_setscheduler(0, attr.__schedpolicy, &attr.__schedparam)?;
+            } else if flags & POSIX_SPAWN_SETSCHEDPARAM != 0 {
+                Sys::sched_setparam(0, &attr.__schedparam)?;
+            }
+            if flags & POSIX_SPAWN_RESETIDS != 0 {
+                Sys::setresgid(!0, Sys::getgid(), !0)?;
+                Sys::setresuid(!0, Sys::getuid(), !0)?;
+            }
+        }
+
+        self.apply_file_actions()?;
+
+        let sigmask = match self.attr {
+            Some(attr) if flags & POSIX_SPAWN_SETSIGMASK != 0 => attr.__sigmask,
+            _ => self.sigmask,
+        };
+        Sys::sigprocmask(SIG_SETMASK, Some(&sigmask), None)?;
+
+        Ok(())
+    }
+
+    fn apply_file_actions(&self) -> Result<()> {
+        for action in self.actions {
+            match *action {
+                FileAction::Open {
+                    fd,
+                    ref path,
+                    oflag,
+                    mode,
+                } => {
+                    let opened = Sys::open(CStr::borrow(path), oflag, mode)?;
+                    if opened != fd {
+                        Sys::dup2(opened, fd)?;
+                        Sys::close(opened)?;
+                    }
+                }
+                FileAction::Close(fd) => {
+                    // Closing a descriptor that is not open is not an error.
+                    match Sys::close(fd) {
+                        Ok(()) | Err(Errno(EBADF)) => (),
+                        Err(err) => return Err(err),
+                    }
+                }
+                FileAction::Dup2 { fd, newfd } => {
+                    if fd == newfd {
+                        // The descriptor must survive exec, as with a real dup2.
+                        let fdflags = Sys::fcntl(fd, F_GETFD, 0)?;
+                        Sys::fcntl(fd, F_SETFD, (fdflags & !FD_CLOEXEC) as c_ulonglong)?;
+                    } else {
+                        Sys::dup2(fd, newfd)?;
+                    }
+                }
+                FileAction::Chdir(ref path) => Sys::chdir(CStr::borrow(path))?,
+                FileAction::Fchdir(fd) => Sys::fchdir(fd)?,
+                FileAction::Closefrom(lowfd) => {
+                    let mut limit = rlimit {
+                        rlim_cur: 0,
+                        rlim_max: 0,
+                    };
+                    Sys::getrlimit(RLIMIT_NOFILE, Out::from_mut(&mut limit))?;
+                    for fd in lowfd..limit.rlim_cur.min(c_int::MAX as rlim_t) as c_int {
+                        let _ = Sys::close(fd);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn exec(&self) -> Errno {
+        let mut error = Errno(ENOENT);
+        for path in self.paths {
+            let err = match unsafe { Sys::execve(CStr::borrow(path), self.argv, self.envp) } {
+                Ok(()) => unreachable!(),
+                Err(err) => err,
+            };
+            match err.0 {
+                ENOENT | ENOTDIR => (),
+                EACCES => error = err,
+                _ => return err,
+            }
+        }
+        error
+    }
+}
+
+/// Builds the list of paths `posix_spawnp` tries, following `execvp`.
+unsafe fn search_path(file: CStr) -> Result<Vec<CString>> {
+    let file = file.to_bytes();
+    if file.is_empty() {
+        return Err(Errno(ENOENT));
+    }
+    if file.contains(&b'/') || (cfg!(target_os = "redox") && file.contains(&b':')) {
+        return Ok(vec![CString::new(file).map_err(|_| Errno(EINVAL))?]);
+    }
+
+    let path_env = unsafe { getenv(c"PATH".as_ptr()) };
+    let path_env = if path_env.is_null() {
+        DEFAULT_PATH
+    } else {
+        unsafe { CStr::from_ptr(path_env) }.to_bytes()
+    };
+
+    let mut paths = Vec::new();
+    for dir in path_env.split(|&b| b == PATH_SEPARATOR) {
+        let mut program = Vec::with_capacity(dir.len() + file.len() + 2);
+        if dir.is_empty() {
+            // An empty entry means the current directory.
+            program.push(b'.');
+        } else {
+            program.extend_from_slice(dir);
+        }
+        program.push(b'/');
+        program.extend_from_slice(file);
+        paths.push(CString::new(program).map_err(|_| Errno(EINVAL))?);
+    }
+    Ok(paths)
+}
+
+unsafe fn spawn(
+    pid: *mut pid_t,
+    paths: &[CString],
+    file_actions: *const posix_spawn_file_actions_t,
+    attrp: *const posix_spawnattr_t,
+    argv: *const *mut c_char,
+    envp: *const *mut c_char,
+) -> c_int {
+    let mut child = Child {
+        paths,
+        argv,
+        envp: if envp.is_null() {
+            unsafe { platform::environ }.cast_const()
+        } else {
+            envp
+        },
+        actions: match unsafe { file_actions.as_ref() } {
+            Some(file_actions) => unsafe { file_actions.actions() },
+            None => &[],
+        },
+        attr: unsafe { attrp.as_ref() },
+        sigmask: 0,
+    };
+
+    // Keep signal handlers from running in the child until it has reset them.
+    let all: sigset_t = !0;
+    if let Err(Errno(err)) = Sys::sigprocmask(SIG_BLOCK, Some(&all), Some(&mut child.sigmask)) {
+        return err;
+    }
+
+    let result = unsafe { sys::spawn(&child) };
+
+    let _ = Sys::sigprocmask(SIG_SETMASK, Some(&child.sigmask), None);
+
+    match result {
+        Ok(child_pid) => {
+            if let Some(pid) = unsafe { pid.as_mut() } {
+                *pid = child_pid;
+            }
+            0
+        }
+        Err(Errno(err)) => err,
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn(
+    pid: *mut pid_t,
+    path: *const c_char,
+    file_actions: *const posix_spawn_file_actions_t,
+    attrp: *const posix_spawnattr_t,
+    argv: *const *mut c_char,
+    envp: *const *mut c_char,
+) -> c_int {
+    let path = unsafe { CStr::from_ptr(path) }.to_owned_cstring();
+    unsafe { spawn(pid, &[path], file_actions, attrp, argv, envp) }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnp.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnp(
+    pid: *mut pid_t,
+    file: *const c_char,
+    file_actions: *const posix_spawn_file_actions_t,
+    attrp: *const posix_spawnattr_t,
+    argv: *const *mut c_char,
+    envp: *const *mut c_char,
+) -> c_int {
+    match unsafe { search_path(CStr::from_ptr(file)) } {
+        Ok(paths) => unsafe { spawn(pid, &paths, file_actions, attrp, argv, envp) },
+        Err(Errno(err)) => err,
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn_file_actions_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_init(
+    file_actions: *mut posix_spawn_file_actions_t,
+) -> c_int {
+    unsafe {
+        file_actions.write(posix_spawn_file_actions_t {
+            __actions: ptr::null_mut(),
+        })
+    };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn_file_actions_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_destroy(
+    file_actions: *mut posix_spawn_file_actions_t,
+) -> c_int {
+    let file_actions = unsafe { &mut *file_actions };
+    if !file_actions.__actions.is_null() {
+        drop(unsafe { Box::from_raw(file_actions.__actions as *mut Vec<FileAction>) });
+        file_actions.__actions = ptr::null_mut();
+    }
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn_file_actions_addclose.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_addopen(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fildes: c_int,
+    path: *const c_char,
+    oflag: c_int,
+    mode: mode_t,
+) -> c_int {
+    if fildes < 0 {
+        return EBADF;
+    }
+    let path = unsafe { CStr::from_ptr(path) }.to_owned_cstring();
+    unsafe { &mut *file_actions }.push(FileAction::Open {
+        fd: fildes,
+        path,
+        oflag,
+        mode,
+    })
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn_file_actions_addclose.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_addclose(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fildes: c_int,
+) -> c_int {
+    if fildes < 0 {
+        return EBADF;
+    }
+    unsafe { &mut *file_actions }.push(FileAction::Close(fildes))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn_file_actions_adddup2.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_adddup2(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fildes: c_int,
+    newfildes: c_int,
+) -> c_int {
+    if fildes < 0 || newfildes < 0 {
+        return EBADF;
+    }
+    unsafe { &mut *file_actions }.push(FileAction::Dup2 {
+        fd: fildes,
+        newfd: newfildes,
+    })
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn_file_actions_addchdir.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_addchdir(
+    file_actions: *mut posix_spawn_file_actions_t,
+    path: *const c_char,
+) -> c_int {
+    let path = unsafe { CStr::from_ptr(path) }.to_owned_cstring();
+    unsafe { &mut *file_actions }.push(FileAction::Chdir(path))
+}
+
+/// Non-POSIX, see <https://man7.org/linux/man-pages/man3/posix_spawn_file_actions_addchdir_np.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_addchdir_np(
+    file_actions: *mut posix_spawn_file_actions_t,
+    path: *const c_char,
+) -> c_int {
+    unsafe { posix_spawn_file_actions_addchdir(file_actions, path) }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawn_file_actions_addchdir.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_addfchdir(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fildes: c_int,
+) -> c_int {
+    if fildes < 0 {
+        return EBADF;
+    }
+    unsafe { &mut *file_actions }.push(FileAction::Fchdir(fildes))
+}
+
+/// Non-POSIX, see <https://man7.org/linux/man-pages/man3/posix_spawn_file_actions_addchdir_np.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_addfchdir_np(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fildes: c_int,
+) -> c_int {
+    unsafe { posix_spawn_file_actions_addfchdir(file_actions, fildes) }
+}
+
+/// Non-POSIX, closes every file descriptor from `fildes` upwards in the child.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawn_file_actions_addclosefrom_np(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fildes: c_int,
+) -> c_int {
+    if fildes < 0 {
+        return EBADF;
+    }
+    unsafe { &mut *file_actions }.push(FileAction::Closefrom(fildes))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_init(attr: *mut posix_spawnattr_t) -> c_int {
+    unsafe {
+        attr.write(posix_spawnattr_t {
+            __flags: 0,
+            __pgroup: 0,
+            __sigdefault: 0,
+            __sigmask: 0,
+            __schedparam: sched_param { sched_priority: 0 },
+            __schedpolicy: 0,
+        })
+    };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_destroy(_attr: *mut posix_spawnattr_t) -> c_int {
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getflags.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_getflags(
+    attr: *const posix_spawnattr_t,
+    flags: *mut c_short,
+) -> c_int {
+    unsafe { *flags = (*attr).__flags };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getflags.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_setflags(
+    attr: *mut posix_spawnattr_t,
+    flags: c_short,
+) -> c_int {
+    if flags & !POSIX_SPAWN_ALL != 0 {
+        return EINVAL;
+    }
+    unsafe { (*attr).__flags = flags };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getpgroup.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_getpgroup(
+    attr: *const posix_spawnattr_t,
+    pgroup: *mut pid_t,
+) -> c_int {
+    unsafe { *pgroup = (*attr).__pgroup };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getpgroup.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_setpgroup(
+    attr: *mut posix_spawnattr_t,
+    pgroup: pid_t,
+) -> c_int {
+    unsafe { (*attr).__pgroup = pgroup };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getschedparam.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_getschedparam(
+    attr: *const posix_spawnattr_t,
+    schedparam: *mut sched_param,
+) -> c_int {
+    unsafe { *schedparam = (*attr).__schedparam };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getschedparam.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_setschedparam(
+    attr: *mut posix_spawnattr_t,
+    schedparam: *const sched_param,
+) -> c_int {
+    unsafe { (*attr).__schedparam = *schedparam };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getschedpolicy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_getschedpolicy(
+    attr: *const posix_spawnattr_t,
+    schedpolicy: *mut c_int,
+) -> c_int {
+    unsafe { *schedpolicy = (*attr).__schedpolicy };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getschedpolicy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_setschedpolicy(
+    attr: *mut posix_spawnattr_t,
+    schedpolicy: c_int,
+) -> c_int {
+    unsafe { (*attr).__schedpolicy = schedpolicy };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getsigdefault.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_getsigdefault(
+    attr: *const posix_spawnattr_t,
+    sigdefault: *mut sigset_t,
+) -> c_int {
+    unsafe { *sigdefault = (*attr).__sigdefault };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getsigdefault.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_setsigdefault(
+    attr: *mut posix_spawnattr_t,
+    sigdefault: *const sigset_t,
+) -> c_int {
+    unsafe { (*attr).__sigdefault = *sigdefault };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getsigmask.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_getsigmask(
+    attr: *const posix_spawnattr_t,
+    sigmask: *mut sigset_t,
+) -> c_int {
+    unsafe { *sigmask = (*attr).__sigmask };
+    0
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/posix_spawnattr_getsigmask.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn posix_spawnattr_setsigmask(
+    attr: *mut posix_spawnattr_t,
+    sigmask: *const sigset_t,
+) -> c_int {
+    unsafe { (*attr).__sigmask = *sigmask };
+    0
+}
@@ -0,0 +1,204 @@
+use core::mem;
+
+use redox_rt::{
+    RtTcb,
+    proc::{FdGuardUpper, ForkArgs, new_child_process},
+};
+
+use super::{
+    Child, FileAction, POSIX_SPAWN_RESETIDS, POSIX_SPAWN_SETPGROUP, POSIX_SPAWN_SETSCHEDPARAM,
+    POSIX_SPAWN_SETSCHEDULER, POSIX_SPAWN_SETSID, POSIX_SPAWN_SETSIGDEF, POSIX_SPAWN_SETSIGMASK,
+};
+
+use crate::{
+    c_str::{CStr, CString},
+    error::{Errno, Result},
+    fs::File,
+    header::{
+        errno::{EACCES, EINTR, ENOENT, ENOTDIR},
+        fcntl::{O_CLOEXEC, O_RDONLY},
+        signal::SIGKILL,
+    },
+    out::Out,
+    platform::{
+        Pal, Sys,
+        sys::exec::{self, ArgEnv, ExecTarget, Executable},
+        types::*,
+    },
+};
+
+/// Creates the child as a new process that has not been started yet, and loads the image into
+/// it from here with the same loader as the exec family, in `platform/redox/exec.rs`. Our
+/// address space is never copied.
+///
+/// Becoming a session leader, dropping privileges and changing directory can only be done by
+/// the child itself, so these requests still fork.
+pub(super) unsafe fn spawn(child: &Child) -> Result<pid_t> {
+    let flags = child.attr.map_or(0, |attr| attr.__flags);
+    let changes_dir = child
+        .actions
+        .iter()
+        .any(|action| matches!(action, FileAction::Chdir(_) | FileAction::Fchdir(_)));
+    if flags & (POSIX_SPAWN_SETSID | POSIX_SPAWN_RESETIDS) != 0 || changes_dir {
+        return unsafe { spawn_forked(child) };
+    }
+
+    // Look the image up first, as posix_spawnp may try many paths.
+    let (image, path) = open_image(child.paths)?;
+
+    let new = new_child_process(&ForkArgs::Managed)?;
+    let pid = new.pid() as pid_t;
+    let Some(proc_fd) = new.proc_fd() else {
+        unreachable!("managed child processes always have a process handle");
+    };
+
+    let result = unsafe { setup(child, pid) }.and_then(|()| {
+        with_child_file_table(new.thr_fd(), || {
+            child.apply_file_actions()?;
+            let target = ExecTarget {
+                thread_fd: new.thr_fd(),
+                proc_fd,
+                sigprocmask: match child.attr {
+                    Some(attr) if flags & POSIX_SPAWN_SETSIGMASK != 0 => attr.__sigmask,
+                    _ => child.sigmask,
+                },
+                sigignmask: match child.attr {
+                    Some(attr) if flags & POSIX_SPAWN_SETSIGDEF != 0 => {
+                        redox_rt::signal::get_sigignmask_to_inherit() & !attr.__sigdefault
+                    }
+                    _ => redox_rt::signal::get_sigignmask_to_inherit(),
+                },
+            };
+            exec::load(
+                Executable::InFd {
+                    // Our descriptor is closed once back in our own file table.
+                    file: unsafe { image.get_ref() },
+                    arg0: path.to_bytes(),
+                },
+                ArgEnv::C {
+                    argv: child.argv,
+                    envp: child.envp,
+                },
+                None,
+                &target,
+            )?;
+            Ok(())
+        })
+    });
+
+    if let Err(err) = result {
+        // The child has not run anything yet, so it dies as soon as it starts.
+        let _ = Sys::kill(pid, SIGKILL);
+        let _ = new
+            .thr_fd()
+            .dup(b"start")
+            .and_then(|start| start.write(&[0]));
+        let _ = Sys::waitpid(pid, None, 0);
+        return Err(err);
+    }
+
+    new.thr_fd().dup(b"start")?.write(&[0])?;
+    Ok(pid)
+}
+
+/// Creates the child by forking, for requests only the child itself can carry out. The child
+/// reports failures through a close-on-exec pipe, which is closed without any data being
+/// written if `execve` succeeds.
+unsafe fn spawn_forked(child: &Child) -> Result<pid_t> {
+    let mut pipe = [0; 2];
+    Sys::pipe2(Out::from_mut(&mut pipe), O_CLOEXEC)?;
+    let [read_fd, write_fd] = pipe;
+
+    let pid = match unsafe { Sys::fork() } {
+        Ok(0) => {
+            let _ = Sys::close(read_fd);
+            let Errno(err) = unsafe { child.run() };
+            let _ = Sys::write(write_fd, &err.to_ne_bytes());
+            Sys::exit(127);
+        }
+        Ok(pid) => pid,
+        Err(err) => {
+            let _ = Sys::close(read_fd);
+            let _ = Sys::close(write_fd);
+            return Err(err);
+        }
+    };
+    let _ = Sys::close(write_fd);
+
+    let mut err = [0; mem::size_of::<c_int>()];
+    let read = loop {
+        match Sys::read(read_fd, &mut err) {
+            Err(Errno(EINTR)) => continue,
+            read => break read,
+        }
+    };
+    let _ = Sys::close(read_fd);
+
+    match read {
+        Ok(n) if n == err.len() => {
+            let _ = Sys::waitpid(pid, None, 0);
+            Err(Errno(c_int::from_ne_bytes(err)))
+        }
+        _ => Ok(pid),
+    }
+}
+
+/// Opens the first of `paths` that exists, following `execvp`.
+fn open_image<'a>(paths: &'a [CString]) -> Result<(File, CStr<'a>)> {
+    let mut error = Errno(ENOENT);
+    for path in paths {
+        let path = CStr::borrow(path);
+        // Closed on exec in our file table, while the loader keeps it open in the child's.
+        match File::open(path, O_RDONLY | O_CLOEXEC) {
+            Ok(file) => return Ok((file, path)),
+            Err(Errno(ENOENT | ENOTDIR)) => (),
+            Err(err @ Errno(EACCES)) => error = err,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(error)
+}
+
+/// Applies the attributes that can be set from the parent.
+unsafe fn setup(child: &Child, pid: pid_t) -> Result<()> {
+    let Some(attr) = child.attr else {
+        return Ok(());
+    };
+    if attr.__flags & POSIX_SPAWN_SETPGROUP != 0 {
+        Sys::setpgid(pid, attr.__pgroup)?;
+    }
+    if attr.__flags & POSIX_SPAWN_SETSCHEDULER != 0 {
+        Sys::sched_setscheduler(pid, attr.__schedpolicy, &attr.__schedparam)?;
+    } else if attr.__flags & POSIX_SPAWN_SETSCHEDPARAM != 0 {
+        Sys::sched_setparam(pid, &attr.__schedparam)?;
+    }
+    Ok(())
+}
+
+/// Gives the child a copy of our file table, and runs `f` with the calling thread using that
+/// copy, so that file actions and close-on-exec apply to the child only.
+///
+/// File tables are selected per thread, and all signals are blocked while spawning, so nothing
+/// else runs on the child's file table meanwhile.
+fn with_child_file_table<T>(
+    child_thr_fd: &FdGuardUpper,
+    f: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let thr_fd = RtTcb::current().thread_fd();
+    let ours = thr_fd.dup(b"filetable")?.to_upper()?;
+    let select = thr_fd.dup(b"current-filetable")?.to_upper()?;
+    let copy = ours.dup(b"copy")?.to_upper()?;
+
+    child_thr_fd
+        .dup(b"current-filetable")?
+        .write(&usize::to_ne_bytes(copy.as_raw_fd()))?;
+
+    select.write(&usize::to_ne_bytes(copy.as_raw_fd()))?;
+    let result = f();
+    // Both handles were copied along with the table, so they are still valid in it.
+    select
+        .write(&usize::to_ne_bytes(ours.as_raw_fd()))
+        .expect("failed to switch back to our file table");
+
+    result
+}
@@ -0,0 +1,221 @@
+//! Streams backed by callbacks: GNU `fopencookie` and BSD `funopen`.
+
+use alloc::boxed::Box;
+use core::ptr;
+
+use crate::{
+    c_str::CStr,
+    error::ResultExtPtrMut,
+    header::errno::{EBADF, EINVAL, ESPIPE},
+    io::{self, Read, Seek, SeekFrom, Write},
+    platform::{self, types::*},
+};
+
+use super::{Backend, FILE, constants::*, helpers};
+
+fn error(err: c_int) -> io::Error {
+    platform::ERRNO.set(err);
+    io::Error::from_raw_os_error(err)
+}
+
+/// The `lseek` arguments for `pos`.
+fn whence(pos: SeekFrom) -> (off_t, c_int) {
+    match pos {
+        SeekFrom::Start(off) => (off as off_t, SEEK_SET),
+        SeekFrom::Current(off) => (off, SEEK_CUR),
+        SeekFrom::End(off) => (off, SEEK_END),
+    }
+}
+
+/// The callbacks of a stream opened by [`fopencookie`], any of which may be
+/// NULL. cbindgen cannot declare function types, so the
+/// `cookie_*_function_t` typedefs are written out in `cbindgen.toml`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct cookie_io_functions_t {
+    pub read: Option<
+        unsafe extern "C" fn(cookie: *mut c_void, buf: *mut c_char, size: size_t) -> ssize_t,
+    >,
+    pub write: Option<
+        unsafe extern "C" fn(cookie: *mut c_void, buf: *const c_char, size: size_t) -> ssize_t,
+    >,
+    /// Seeks to `*offset` from `whence`, and stores the new position in
+    /// `*offset`.
+    pub seek: Option<
+        unsafe extern "C" fn(cookie: *mut c_void, offset: *mut off_t, whence: c_int) -> c_int,
+    >,
+    pub close: Option<unsafe extern "C" fn(cookie: *mut c_void) -> c_int>,
+}
+
+struct Cookie {
+    cookie: *mut c_void,
+    io: cookie_io_functions_t,
+}
+
+// The cookie is only ever handed to the callbacks, from the stream that owns
+// it
+unsafe impl Send for Cookie {}
+
+impl Read for Cookie {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Without a read function, the stream is always at its end
+        let Some(read) = self.io.read else {
+            return Ok(0);
+        };
+        match unsafe { read(self.cookie, buf.as_mut_ptr() as *mut c_char, buf.len()) } {
+            n if n < 0 => Err(io::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+}
+impl Write for Cookie {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Without a write function, output is discarded
+        let Some(write) = self.io.write else {
+            return Ok(buf.len());
+        };
+        match unsafe { write(self.cookie, buf.as_ptr() as *const c_char, buf.len()) } {
+            n if n < 0 => Err(io::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Seek for Cookie {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let Some(seek) = self.io.seek else {
+            return Err(error(ESPIPE));
+        };
+        let (mut off, whence) = whence(pos);
+        if unsafe { seek(self.cookie, &mut off, whence) } < 0 {
+            return Err(io::last_os_error());
+        }
+        Ok(off as u64)
+    }
+}
+impl Backend for Cookie {
+    fn close(&mut self) -> io::Result<()> {
+        let Some(close) = self.io.close else {
+            return Ok(());
+        };
+        if unsafe { close(self.cookie) } < 0 {
+            return Err(io::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// See <https://man7.org/linux/man-pages/man3/fopencookie.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fopencookie(
+    cookie: *mut c_void,
+    mode: *const c_char,
+    io_funcs: cookie_io_functions_t,
+) -> *mut FILE {
+    let mode = unsafe { CStr::from_ptr(mode) };
+    helpers::_fstream_flags(mode)
+        .map(|flags| {
+            let backend = Cookie {
+                cookie,
+                io: io_funcs,
+            };
+            Box::into_raw(helpers::_fbackend(Box::new(backend), flags))
+        })
+        .or_errno_null_mut()
+}
+
+struct FunCookie {
+    cookie: *mut c_void,
+    read: Option<unsafe extern "C" fn(*mut c_void, *mut c_char, c_int) -> c_int>,
+    write: Option<unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_int>,
+    seek: Option<unsafe extern "C" fn(*mut c_void, fpos_t, c_int) -> fpos_t>,
+    close: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+}
+
+// The cookie is only ever handed to the callbacks, from the stream that owns
+// it
+unsafe impl Send for FunCookie {}
+
+impl Read for FunCookie {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(read) = self.read else {
+            return Err(error(EBADF));
+        };
+        let len = buf.len().min(c_int::MAX as usize) as c_int;
+        match unsafe { read(self.cookie, buf.as_mut_ptr() as *mut c_char, len) } {
+            n if n < 0 => Err(io::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+}
+impl Write for FunCookie {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(write) = self.write else {
+            return Err(error(EBADF));
+        };
+        let len = buf.len().min(c_int::MAX as usize) as c_int;
+        match unsafe { write(self.cookie, buf.as_ptr() as *const c_char, len) } {
+            n if n < 0 => Err(io::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Seek for FunCookie {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let Some(seek) = self.seek else {
+            return Err(error(ESPIPE));
+        };
+        let (off, whence) = whence(pos);
+        match unsafe { seek(self.cookie, off, whence) } {
+            pos if pos < 0 => Err(io::last_os_error()),
+            pos => Ok(pos as u64),
+        }
+    }
+}
+impl Backend for FunCookie {
+    fn close(&mut self) -> io::Result<()> {
+        let Some(close) = self.close else {
+            return Ok(());
+        };
+        if unsafe { close(self.cookie) } < 0 {
+            return Err(io::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// See <https://man.freebsd.org/cgi/man.cgi?query=funopen&sektion=3>.
+///
+/// The stream is readable if `readfn` is given, and writable if `writefn`
+/// is.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn funopen(
+    cookie: *const c_void,
+    readfn: Option<unsafe extern "C" fn(*mut c_void, *mut c_char, c_int) -> c_int>,
+    writefn: Option<unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_int>,
+    seekfn: Option<unsafe extern "C" fn(*mut c_void, fpos_t, c_int) -> fpos_t>,
+    closefn: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+) -> *mut FILE {
+    let flags = match (readfn, writefn) {
+        (None, None) => {
+            platform::ERRNO.set(EINVAL);
+            return ptr::null_mut();
+        }
+        (Some(_), None) => F_NOWR,
+        (None, Some(_)) => F_NORD,
+        (Some(_), Some(_)) => 0,
+    };
+    let backend = FunCookie {
+        cookie: cookie as *mut c_void,
+        read: readfn,
+        write: writefn,
+        seek: seekfn,
+        close: closefn,
+    };
+    Box::into_raw(helpers::_fbackend(Box::new(backend), flags))
+}
@@ -1,6 +1,6 @@
 use alloc::boxed::Box;
 
-use super::{Buffer, FILE, constants::*};
+use super::{Backend, BackendWriter, Buffer, FILE, constants::*};
 use crate::{
     c_str::CStr,
     error::Errno,
@@ -42,8 +42,8 @@ pub fn parse_mode_flags(mode_str: CStr) -> i32 {
     flags
 }
 
-/// Open a file with the file descriptor `fd` in the mode `mode`
-pub fn _fdopen(fd: c_int, mode: CStr) -> Result<Box<FILE>, Errno> {
+/// Parse a mode string as the `F_*` flags of a stream
+pub fn _fstream_flags(mode: CStr) -> Result<c_int, Errno> {
     if mode.first() != b'r' && mode.first() != b'w' && mode.first() != b'a' {
         return Err(Errno(EINVAL));
     }
@@ -52,6 +52,15 @@ pub fn _fdopen(fd: c_int, mode: CStr) -> Result<Box<FILE>, Errno> {
     if !mode.contains(b'+') {
         flags |= if mode.first() == b'r' { F_NOWR } else { F_NORD };
     }
+    if mode.first() == b'a' {
+        flags |= F_APP;
+    }
+    Ok(flags)
+}
+
+/// Open a file with the file descriptor `fd` in the mode `mode`
+pub fn _fdopen(fd: c_int, mode: CStr) -> Result<Box<FILE>, Errno> {
+    let flags = _fstream_flags(mode)?;
 
     if mode.contains(b'e') {
         unsafe {
@@ -64,7 +73,6 @@ pub fn _fdopen(fd: c_int, mode: CStr) -> Result<Box<FILE>, Errno> {
         if (f & O_APPEND) == 0 {
             unsafe { fcntl(fd, F_SETFL, (f | O_APPEND) as c_ulonglong) };
         }
-        flags |= F_APP;
     }
 
     let file = File::new(fd);
@@ -86,3 +94,26 @@ pub fn _fdopen(fd: c_int, mode: CStr) -> Result<Box<FILE>, Errno> {
         orientation: 0,
     }))
 }
+
+/// Open a stream that reads and writes `backend` instead of a file descriptor
+pub fn _fbackend(backend: Box<dyn Backend>, flags: c_int) -> Box<FILE> {
+    Box::new(FILE {
+        lock: Mutex::new(()),
+
+        // Never used, and never closed
+        file: File {
+            fd: -1,
+            reference: true,
+        },
+        flags,
+        read_buf: Buffer::Owned(vec![0; BUFSIZ as usize]),
+        read_pos: 0,
+        read_size: 0,
+        unget: Vec::new(),
+        writer: Box::new(BackendWriter::new(backend)),
+
+        pid: None,
+
+        orientation: 0,
+    })
+}
@@ -0,0 +1,304 @@
+//! Streams on memory instead of a file descriptor: the fixed buffer of
+//! `fmemopen`, and the growing buffers of `open_memstream` and
+//! `open_wmemstream`.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{mem, ptr, slice};
+
+use crate::{
+    c_str::CStr,
+    header::{
+        errno::{EBADF, EILSEQ, EINVAL, ENOMEM, ENOSPC},
+        stdlib,
+        wchar::{self, mbstate_t},
+    },
+    io::{self, Read, Seek, SeekFrom, Write},
+    platform::{self, types::*},
+};
+
+use super::{Backend, Buffer, FILE, constants::*, helpers};
+
+fn error(err: c_int) -> io::Error {
+    platform::ERRNO.set(err);
+    io::Error::from_raw_os_error(err)
+}
+
+/// Resolves `pos` for a stream at `cur` that ends at `end`, allowing
+/// positions up to `max`.
+fn seek(pos: SeekFrom, cur: usize, end: usize, max: usize) -> io::Result<usize> {
+    let new = match pos {
+        SeekFrom::Start(off) => usize::try_from(off).ok(),
+        SeekFrom::Current(off) => cur.checked_add_signed(off as isize),
+        SeekFrom::End(off) => end.checked_add_signed(off as isize),
+    };
+    match new {
+        Some(new) if new <= max => Ok(new),
+        _ => Err(error(EINVAL)),
+    }
+}
+
+/// The caller's buffer given to `fmemopen`, or one of its own if that was
+/// NULL.
+struct FixedBuffer {
+    buf: Buffer<'static>,
+    /// Where the contents end. Reads stop here, and writes past it move it.
+    len: usize,
+    pos: usize,
+    append: bool,
+}
+
+impl Read for FixedBuffer {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = out.len().min(self.len.saturating_sub(self.pos));
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+impl Write for FixedBuffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.append {
+            self.pos = self.len;
+        }
+        let n = data.len().min(self.buf.len() - self.pos);
+        if n == 0 && !data.is_empty() {
+            return Err(error(ENOSPC));
+        }
+        self.buf[self.pos..self.pos + n].copy_from_slice(&data[..n]);
+        self.pos += n;
+
+        // Keep the contents a string as long as there is room for the nul
+        if self.pos > self.len {
+            self.len = self.pos;
+            if self.len < self.buf.len() {
+                self.buf[self.len] = 0;
+            }
+        }
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Seek for FixedBuffer {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = seek(pos, self.pos, self.len, self.buf.len())?;
+        Ok(self.pos as u64)
+    }
+}
+impl Backend for FixedBuffer {}
+
+/// The character type of a dynamic memory stream.
+pub(crate) trait MemChar: Copy + Send + 'static {
+    /// Decodes the bytes written so far of one character, or returns `None`
+    /// if it needs more of them.
+    fn decode(bytes: &[u8]) -> io::Result<Option<Self>>;
+}
+
+impl MemChar for c_char {
+    fn decode(bytes: &[u8]) -> io::Result<Option<Self>> {
+        Ok(Some(bytes[0] as c_char))
+    }
+}
+
+impl MemChar for wchar_t {
+    fn decode(bytes: &[u8]) -> io::Result<Option<Self>> {
+        let mut state = mbstate_t {
+            __count: 0,
+            __value: 0,
+        };
+        let mut wc = 0;
+        match unsafe {
+            wchar::mbrtowc(
+                &mut wc,
+                bytes.as_ptr() as *const c_char,
+                bytes.len(),
+                &mut state,
+            )
+        } {
+            n if n == -2isize as usize => Ok(None),
+            n if n == -1isize as usize => Err(error(EILSEQ)),
+            _ => Ok(Some(wc)),
+        }
+    }
+}
+
+/// The buffer of `open_memstream` and `open_wmemstream`, which grows to fit
+/// what is written and is handed to the caller on every flush.
+struct MemStream<T: MemChar> {
+    bufp: *mut *mut T,
+    sizep: *mut size_t,
+    /// Allocated with `malloc`, so that the caller can `free` it.
+    buf: *mut T,
+    capacity: usize,
+    len: usize,
+    pos: usize,
+    /// Bytes of a character that is not complete yet.
+    pending: Vec<u8>,
+}
+
+// The pointers are only ever reached through the stream that owns them
+unsafe impl<T: MemChar> Send for MemStream<T> {}
+
+impl<T: MemChar> MemStream<T> {
+    /// Makes room for `n` characters and the nul after them.
+    fn reserve(&mut self, n: usize) -> io::Result<()> {
+        let Some(needed) = n.checked_add(1) else {
+            return Err(error(ENOMEM));
+        };
+        if needed <= self.capacity {
+            return Ok(());
+        }
+        let capacity = needed.max(self.capacity.saturating_mul(2));
+        let Some(bytes) = capacity.checked_mul(mem::size_of::<T>()) else {
+            return Err(error(ENOMEM));
+        };
+        let buf = unsafe { stdlib::realloc(self.buf as *mut c_void, bytes) } as *mut T;
+        if buf.is_null() {
+            return Err(io::last_os_error());
+        }
+        self.buf = buf;
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    fn put(&mut self, c: T) -> io::Result<()> {
+        self.reserve(self.pos.max(self.len) + 1)?;
+        unsafe {
+            // A seek past the end leaves a gap, which reads as nuls
+            if self.pos > self.len {
+                ptr::write_bytes(self.buf.add(self.len), 0, self.pos - self.len);
+            }
+            self.buf.add(self.pos).write(c);
+            self.pos += 1;
+            if self.pos > self.len {
+                self.len = self.pos;
+                ptr::write_bytes(self.buf.add(self.len), 0, 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Hands the buffer and the size up to the current position to the
+    /// caller.
+    fn publish(&self) {
+        unsafe {
+            *self.bufp = self.buf;
+            *self.sizep = self.len.min(self.pos);
+        }
+    }
+}
+
+impl<T: MemChar> Read for MemStream<T> {
+    fn read(&mut self, _out: &mut [u8]) -> io::Result<usize> {
+        Err(error(EBADF))
+    }
+}
+impl<T: MemChar> Write for MemStream<T> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        for &b in data {
+            self.pending.push(b);
+            match T::decode(&self.pending) {
+                Ok(Some(c)) => {
+                    self.pending.clear();
+                    self.put(c)?;
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    self.pending.clear();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(data.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.publish();
+        Ok(())
+    }
+}
+impl<T: MemChar> Seek for MemStream<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let max = isize::MAX as usize / mem::size_of::<T>() - 1;
+        self.pos = seek(pos, self.pos, self.len, max)?;
+        self.pending.clear();
+        Ok(self.pos as u64)
+    }
+}
+impl<T: MemChar> Backend for MemStream<T> {
+    fn close(&mut self) -> io::Result<()> {
+        self.publish();
+        Ok(())
+    }
+}
+
+/// Opens a stream on a growing buffer of `T`, for `open_memstream` and
+/// `open_wmemstream`.
+pub(crate) unsafe fn open<T: MemChar>(bufp: *mut *mut T, sizep: *mut size_t) -> *mut FILE {
+    if bufp.is_null() || sizep.is_null() {
+        platform::ERRNO.set(EINVAL);
+        return ptr::null_mut();
+    }
+    let buf = unsafe { stdlib::calloc(1, mem::size_of::<T>()) } as *mut T;
+    if buf.is_null() {
+        return ptr::null_mut();
+    }
+
+    let stream = MemStream {
+        bufp,
+        sizep,
+        buf,
+        capacity: 1,
+        len: 0,
+        pos: 0,
+        pending: Vec::new(),
+    };
+    stream.publish();
+    Box::into_raw(helpers::_fbackend(Box::new(stream), F_NORD))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/fmemopen.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fmemopen(
+    buf: *mut c_void,
+    size: size_t,
+    mode: *const c_char,
+) -> *mut FILE {
+    let mode = unsafe { CStr::from_ptr(mode) };
+    let flags = match helpers::_fstream_flags(mode) {
+        Ok(flags) if size != 0 => flags,
+        _ => {
+            platform::ERRNO.set(EINVAL);
+            return ptr::null_mut();
+        }
+    };
+    let first = mode.first();
+
+    let mut buf = if buf.is_null() {
+        Buffer::Owned(vec![0; size])
+    } else {
+        Buffer::Borrowed(unsafe { slice::from_raw_parts_mut(buf as *mut u8, size) })
+    };
+    let len = match first {
+        b'r' => size,
+        b'w' => {
+            buf[0] = 0;
+            0
+        }
+        _ => buf.iter().position(|&b| b == 0).unwrap_or(size),
+    };
+
+    let backend = FixedBuffer {
+        buf,
+        len,
+        pos: if first == b'a' { len } else { 0 },
+        append: first == b'a',
+    };
+    Box::into_raw(helpers::_fbackend(Box::new(backend), flags))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/open_memstream.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn open_memstream(bufp: *mut *mut c_char, sizep: *mut size_t) -> *mut FILE {
+    unsafe { open(bufp, sizep) }
+}
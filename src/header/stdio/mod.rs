@@ -28,7 +28,7 @@ use crate::{
         string::{self, strlen, strncpy},
         unistd,
     },
-    io::{self, BufRead, BufWriter, LineWriter, Read, Write},
+    io::{self, BufRead, BufWriter, LineWriter, Read, Seek, SeekFrom, Write},
     out::Out,
     platform::{self, ERRNO, Pal, Sys, WriteByte, types::*},
     sync::Mutex,
@@ -43,9 +43,11 @@ mod default;
 pub use self::getdelim::*;
 mod getdelim;
 
+mod cookie;
 mod ext;
 mod helpers;
 mod lookaheadreader;
+pub(crate) mod memstream;
 pub mod printf;
 mod scanf;
 use lookaheadreader::LookAheadReader;
@@ -92,6 +94,11 @@ impl<W: crate::io::Write> Pending for LineWriter<W> {
 
 pub trait Writer: Write + Pending {
     fn purge(&mut self);
+
+    /// The backend of streams that are not backed by a file descriptor.
+    fn backend(&mut self) -> Option<&mut dyn Backend> {
+        None
+    }
 }
 
 impl<W: crate::io::Write> Writer for BufWriter<W> {
@@ -105,6 +112,56 @@ impl<W: crate::io::Write> Writer for LineWriter<W> {
     }
 }
 
+/// Storage behind a stream that is not a file descriptor, such as the buffer
+/// given to `fmemopen`. Seeking works like `lseek`, and flushing tells the
+/// backend that every buffered write has reached it.
+pub trait Backend: Read + Write + Seek + Send {
+    /// Called by `fclose`, after the final flush.
+    fn close(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Buffers writes to a [`Backend`], and lets the rest of the stream reach it.
+pub struct BackendWriter(BufWriter<Box<dyn Backend>>);
+
+impl BackendWriter {
+    pub fn new(backend: Box<dyn Backend>) -> Self {
+        Self(BufWriter::new(backend))
+    }
+}
+impl Write for BackendWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+impl Pending for BackendWriter {
+    fn pending(&self) -> size_t {
+        self.0.pending()
+    }
+}
+impl Writer for BackendWriter {
+    fn purge(&mut self) {
+        self.0.purge();
+    }
+    fn backend(&mut self) -> Option<&mut dyn Backend> {
+        Some(&mut **self.0.get_mut())
+    }
+}
+
+/// Converts the arguments of `fseek` for a [`Backend`].
+fn seek_from(off: off_t, whence: c_int) -> Option<SeekFrom> {
+    match whence {
+        SEEK_SET => u64::try_from(off).ok().map(SeekFrom::Start),
+        SEEK_CUR => Some(SeekFrom::Current(off)),
+        SEEK_END => Some(SeekFrom::End(off)),
+        _ => None,
+    }
+}
+
 /// This struct gets exposed to the C API.
 pub struct FILE {
     lock: Mutex<()>,
@@ -118,7 +175,8 @@ pub struct FILE {
 
     read_pos: usize,
     read_size: usize,
-    unget: Vec<u8>,
+    // pub for ungetwc
+    pub(crate) unget: Vec<u8>,
     // pub for stdio_ext
 
     // TODO: To support const fn initialization, use static dispatch (perhaps partially)?
@@ -155,7 +213,11 @@ impl Read for FILE {
 impl BufRead for FILE {
     fn fill_buf(&mut self) -> io::Result<&[u8]> {
         if self.read_pos == self.read_size {
-            self.read_size = match self.file.read(&mut self.read_buf) {
+            let read = match self.writer.backend() {
+                Some(backend) => backend.read(&mut self.read_buf),
+                None => self.file.read(&mut self.read_buf),
+            };
+            self.read_size = match read {
                 Ok(0) => {
                     self.flags |= F_EOF;
                     0
@@ -332,7 +394,16 @@ pub unsafe extern "C" fn fclose(stream: *mut FILE) -> c_int {
 
     let mut r = stream.flush().is_err();
     // TODO: better error handling
-    let close = Sys::close(*stream.file).map(|()| 0).or_minus_one_errno() == -1;
+    let close = match stream.writer.backend() {
+        Some(backend) => {
+            let close = backend.close().is_err();
+            // Anything a failed flush left behind must not reach a closed
+            // backend when the writer is dropped
+            stream.writer.purge();
+            close
+        }
+        None => Sys::close(*stream.file).map(|()| 0).or_minus_one_errno() == -1,
+    };
     r = r || close;
 
     if stream.flags & constants::F_PERM == 0 {
@@ -487,7 +558,11 @@ pub unsafe extern "C" fn fgets(
 /// Get the underlying file descriptor
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fileno(stream: *mut FILE) -> c_int {
-    let stream = (*stream).lock();
+    let mut stream = (*stream).lock();
+    if stream.writer.backend().is_some() {
+        platform::ERRNO.set(errno::EBADF);
+        return -1;
+    }
     *stream.file
 }
 
@@ -685,9 +760,19 @@ pub unsafe fn fseek_locked(stream: &mut FILE, mut off: off_t, whence: c_int) ->
         return -1;
     }
 
-    let err = Sys::lseek(*stream.file, off, whence).or_minus_one_errno();
-    if err < 0 {
-        return err as c_int;
+    if let Some(backend) = stream.writer.backend() {
+        let Some(pos) = seek_from(off, whence) else {
+            platform::ERRNO.set(errno::EINVAL);
+            return -1;
+        };
+        if backend.seek(pos).is_err() {
+            return -1;
+        }
+    } else {
+        let err = Sys::lseek(*stream.file, off, whence).or_minus_one_errno();
+        if err < 0 {
+            return err as c_int;
+        }
     }
 
     stream.flags &= !(F_EOF | F_ERR);
@@ -716,7 +801,19 @@ pub unsafe extern "C" fn ftello(stream: *mut FILE) -> off_t {
     ftell_locked(&mut *stream)
 }
 pub unsafe extern "C" fn ftell_locked(stream: &mut FILE) -> off_t {
-    let pos = Sys::lseek(*stream.file, 0, SEEK_CUR).or_minus_one_errno();
+    let pos = if stream.writer.backend().is_some() {
+        // Buffered writes move the position of a backend, so they have to
+        // reach it first
+        if stream.flush().is_err() {
+            return -1;
+        }
+        match stream.writer.backend().unwrap().seek(SeekFrom::Current(0)) {
+            Ok(pos) => pos as off_t,
+            Err(_) => return -1,
+        }
+    } else {
+        Sys::lseek(*stream.file, 0, SEEK_CUR).or_minus_one_errno()
+    };
     if pos < 0 {
         return -1;
     }
@@ -23,6 +23,7 @@ use crate::{
         errno::{self, *},
         fcntl::*,
         limits,
+        locale::{self, locale_t},
         stdio::flush_io_streams,
         string::*,
         sys_ioctl::*,
@@ -52,13 +53,21 @@ pub const EXIT_SUCCESS: c_int = 0;
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/stdlib.h.html>.
 pub const RAND_MAX: c_int = 2_147_483_647;
 
-/// See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/stdlib.h.html>.
-//Maximum number of bytes in a multibyte character for the current locale
-pub const MB_CUR_MAX: c_int = 4;
 /// Actually specified for `limits.h`?
 //Maximum number of bytes in a multibyte characters for any locale
 pub const MB_LEN_MAX: c_int = 4;
 
+/// Returns the maximum number of bytes in a multibyte character in the
+/// current locale, backing the `MB_CUR_MAX` macro.
+#[unsafe(no_mangle)]
+pub extern "C" fn __ctype_get_mb_cur_max() -> size_t {
+    if locale::current().is_utf8() {
+        MB_LEN_MAX as size_t
+    } else {
+        1
+    }
+}
+
 static ATEXIT_FUNCS: RawCell<[Option<extern "C" fn()>; 32]> = RawCell::new([None; 32]);
 static AT_QUICK_EXIT_FUNCS: RawCell<[Option<extern "C" fn()>; 32]> = RawCell::new([None; 32]);
 static L64A_BUFFER: RawCell<[c_char; 7]> = RawCell::new([0; 7]); // up to 6 digits plus null terminator
@@ -386,8 +395,6 @@ pub unsafe extern "C" fn exit(status: c_int) -> ! {
 
     ld_so::fini();
 
-    crate::pthread::terminate_from_main_thread();
-
     flush_io_streams();
 
     Sys::exit(status);
@@ -1468,13 +1475,33 @@ pub unsafe fn convert_integer(s: *const c_char, base: c_int) -> Option<(c_ulong,
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strtod.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn strtod(s: *const c_char, endptr: *mut *mut c_char) -> c_double {
-    strto_float_impl!(c_double, s, endptr)
+    strto_float_impl!(c_double, s, endptr, locale::current())
+}
+
+/// Non-POSIX, see <https://www.man7.org/linux/man-pages/man3/strtod.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strtod_l(
+    s: *const c_char,
+    endptr: *mut *mut c_char,
+    loc: locale_t,
+) -> c_double {
+    strto_float_impl!(c_double, s, endptr, locale::get(loc))
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strtod.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn strtof(s: *const c_char, endptr: *mut *mut c_char) -> c_float {
-    strto_float_impl!(c_float, s, endptr)
+    strto_float_impl!(c_float, s, endptr, locale::current())
+}
+
+/// Non-POSIX, see <https://www.man7.org/linux/man-pages/man3/strtod.3.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strtof_l(
+    s: *const c_char,
+    endptr: *mut *mut c_char,
+    loc: locale_t,
+) -> c_float {
+    strto_float_impl!(c_float, s, endptr, locale::get(loc))
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strtol.html>.
@@ -1491,7 +1518,7 @@ pub unsafe extern "C" fn strtol(s: *const c_char, endptr: *mut *mut c_char, base
     )
 }
 
-// TODO: strtold(), when long double is available
+// TODO: strtold() and strtold_l(), when long double is available
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strtol.html>.
 #[unsafe(no_mangle)]
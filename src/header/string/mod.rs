@@ -14,7 +14,11 @@ use core::{
 use cbitset::BitSet256;
 
 use crate::{
-    header::{errno::*, signal},
+    header::{
+        errno::*,
+        locale::{locale_t, uselocale},
+        signal,
+    },
     iter::{NulTerminated, NulTerminatedInclusive, SrcDstPtrIter},
     platform::{self, types::*},
     raw_cell::RawCell,
@@ -250,11 +254,15 @@ pub unsafe extern "C" fn strcmp(s1: *const c_char, s2: *const c_char) -> c_int {
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcoll.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn strcoll(s1: *const c_char, s2: *const c_char) -> c_int {
-    // relibc has no locale stuff (yet)
-    strcmp(s1, s2)
+    strcoll_l(s1, s2, uselocale(ptr::null_mut()))
 }
 
-// TODO: strcoll_l
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcoll.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strcoll_l(s1: *const c_char, s2: *const c_char, _loc: locale_t) -> c_int {
+    // Every LC_COLLATE we support sorts by byte value
+    strcmp(s1, s2)
+}
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcpy.html>.
 #[unsafe(no_mangle)]
@@ -326,7 +334,12 @@ pub unsafe extern "C" fn strerror(errnum: c_int) -> *mut c_char {
     strerror_buf.unsafe_mut().as_mut_ptr() as *mut c_char
 }
 
-// TODO: strerror_l
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strerror.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strerror_l(errnum: c_int, _loc: locale_t) -> *mut c_char {
+    // Messages are not translated, so LC_MESSAGES does not matter
+    strerror(errnum)
+}
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strerror.html>.
 #[unsafe(no_mangle)]
@@ -590,12 +603,21 @@ pub unsafe extern "C" fn strtok_r(
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strxfrm.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn strxfrm(s1: *mut c_char, s2: *const c_char, n: size_t) -> size_t {
-    // relibc has no locale stuff (yet)
+    strxfrm_l(s1, s2, n, uselocale(ptr::null_mut()))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strxfrm.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strxfrm_l(
+    s1: *mut c_char,
+    s2: *const c_char,
+    n: size_t,
+    _loc: locale_t,
+) -> size_t {
+    // Collation is by byte value, see strcoll_l
     let len = strlen(s2);
     if len < n {
         strcpy(s1, s2);
     }
     len
 }
-
-// TODO: strxfrm_l
@@ -16,7 +16,7 @@ use core::{
 };
 
 use crate::{
-    header::{ctype, string},
+    header::{ctype, locale::locale_t, string},
     iter::NulTerminated,
     platform::types::*,
 };
@@ -131,12 +131,16 @@ pub unsafe extern "C" fn strcasecmp(s1: *const c_char, s2: *const c_char) -> c_i
     inner_casecmp(zipped)
 }
 
-// TODO: needs locale_t
-// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcasecmp.html>.
-// #[unsafe(no_mangle)]
-/*pub extern "C" fn strcasecmp_l(s1: *const c_char, s2: *const c_char, locale: locale_t) -> c_int {
-    unimplemented!();
-}*/
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcasecmp.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strcasecmp_l(
+    s1: *const c_char,
+    s2: *const c_char,
+    _loc: locale_t,
+) -> c_int {
+    // Case mapping of single bytes is the same in every locale we support
+    unsafe { strcasecmp(s1, s2) }
+}
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcasecmp.html>.
 #[unsafe(no_mangle)]
@@ -149,12 +153,16 @@ pub unsafe extern "C" fn strncasecmp(s1: *const c_char, s2: *const c_char, n: si
     inner_casecmp(zipped)
 }
 
-// TODO: needs locale_t
-// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcasecmp.html>.
-// #[unsafe(no_mangle)]
-/*pub extern "C" fn strncasecmp_l(s1: *const c_char, s2: *const c_char, n: size_t, locale: locale_t) -> c_int {
-    unimplemented!();
-}*/
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strcasecmp.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strncasecmp_l(
+    s1: *const c_char,
+    s2: *const c_char,
+    n: size_t,
+    _loc: locale_t,
+) -> c_int {
+    unsafe { strncasecmp(s1, s2, n) }
+}
 
 /// Given two zipped `&c_char` iterators, either find the first comparison != 0, or return 0.
 fn inner_casecmp<'a>(iterator: impl Iterator<Item = (&'a c_char, &'a c_char)>) -> c_int {
@@ -5,7 +5,8 @@ use core::ptr;
 use crate::{
     error::ResultExt,
     header::signal::sigset_t,
-    platform::{PalEpoll, Sys, types::*},
+    platform::{Pal, PalEpoll, Sys, types::*},
+    pthread,
 };
 
 pub use self::sys::*;
@@ -91,6 +92,12 @@ pub unsafe extern "C" fn epoll_ctl(
     )
 }
 
+/// Closes `epfd` as a cleanup handler, for the temporary epoll instances `poll` and `select` wait
+/// on not to leak when the thread is cancelled.
+pub(crate) extern "C" fn close_epoll(epfd: *mut c_void) {
+    let _ = Sys::close(epfd as c_int);
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn epoll_wait(
     epfd: c_int,
@@ -110,7 +117,7 @@ pub unsafe extern "C" fn epoll_pwait(
     sigmask: *const sigset_t,
 ) -> c_int {
     trace_expr!(
-        Sys::epoll_pwait(epfd, events, maxevents, timeout, sigmask)
+        pthread::cancellation_point(|| Sys::epoll_pwait(epfd, events, maxevents, timeout, sigmask))
             .map(|e| e as c_int)
             .or_minus_one_errno(),
         "epoll_pwait({}, {:p}, {}, {}, {:p})",
@@ -117,7 +117,7 @@ pub unsafe extern "C" fn mremap(
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/msync.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn msync(addr: *mut c_void, len: size_t, flags: c_int) -> c_int {
-    Sys::msync(addr, len, flags)
+    crate::pthread::cancellation_point(|| Sys::msync(addr, len, flags))
         .map(|()| 0)
         .or_minus_one_errno()
 }
@@ -146,7 +146,7 @@ static SHM_PATH: &'static [u8] = b"/dev/shm/";
 #[cfg(target_os = "redox")]
 static SHM_PATH: &'static [u8] = b"/scheme/shm/";
 
-unsafe fn shm_path(name: *const c_char) -> CString {
+pub(crate) unsafe fn shm_path(name: *const c_char) -> CString {
     let name_c = CStr::from_ptr(name);
 
     let mut path = SHM_PATH.to_vec();
@@ -8,9 +8,10 @@ use crate::{
     fs::File,
     header::{
         errno,
+        pthread::with_cleanup,
         sys_epoll::{
-            EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLLERR, EPOLLIN, EPOLLOUT, epoll_create1, epoll_ctl,
-            epoll_data, epoll_event, epoll_wait,
+            EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLLERR, EPOLLIN, EPOLLOUT, close_epoll, epoll_create1,
+            epoll_ctl, epoll_data, epoll_event, epoll_wait,
         },
         sys_time::timeval,
     },
@@ -121,14 +122,14 @@ pub fn select_epoll(
             None => -1,
         }
     };
-    let res = unsafe {
+    let res = with_cleanup(close_epoll, *ep as *mut c_void, || unsafe {
         epoll_wait(
             *ep,
             events.as_mut_ptr(),
             events.len() as c_int,
             epoll_timeout,
         )
-    };
+    });
     if res < 0 {
         return -1;
     }
@@ -50,6 +50,9 @@ pub const MSG_PEEK: c_int = 2;
 pub const MSG_TRUNC: c_int = 32;
 pub const MSG_DONTWAIT: c_int = 64;
 pub const MSG_WAITALL: c_int = 256;
+pub const MSG_NOSIGNAL: c_int = 0x4000;
+pub const MSG_WAITFORONE: c_int = 0x1_0000;
+pub const MSG_CMSG_CLOEXEC: c_int = 0x4000_0000;
 
 pub const IP_ADD_SOURCE_MEMBERSHIP: c_int = 70;
 pub const IP_DROP_SOURCE_MEMBERSHIP: c_int = 71;
@@ -59,12 +62,16 @@ pub const MCAST_LEAVE_SOURCE_GROUP: c_int = 47;
 pub const AF_INET: c_int = 2;
 pub const AF_INET6: c_int = 10;
 pub const AF_LOCAL: c_int = AF_UNIX;
+pub const AF_NETLINK: c_int = 16;
+pub const AF_PACKET: c_int = 17;
 pub const AF_UNIX: c_int = 1;
 pub const AF_UNSPEC: c_int = 0;
 
 pub const PF_INET: c_int = 2;
 pub const PF_INET6: c_int = 10;
 pub const PF_LOCAL: c_int = PF_UNIX;
+pub const PF_NETLINK: c_int = 16;
+pub const PF_PACKET: c_int = 17;
 pub const PF_UNIX: c_int = 1;
 pub const PF_UNSPEC: c_int = 0;
 
@@ -8,8 +8,9 @@ use core::{mem, ptr};
 
 use crate::{
     error::ResultExt,
-    header::sys_uio::iovec,
+    header::{sys_uio::iovec, time::timespec},
     platform::{PalSocket, Sys, types::*},
+    pthread,
 };
 
 pub mod constants;
@@ -39,6 +40,16 @@ pub struct msghdr {
     pub msg_flags: c_int,
 }
 
+/// A message of [`recvmmsg`] or [`sendmmsg`], with the number of bytes
+/// received or sent.
+#[repr(C)]
+#[derive(Debug)]
+// FIXME: CheckVsLibcCrate
+pub struct mmsghdr {
+    pub msg_hdr: msghdr,
+    pub msg_len: c_uint,
+}
+
 #[repr(C)]
 #[derive(Debug, CheckVsLibcCrate)]
 pub struct cmsghdr {
@@ -113,14 +124,13 @@ pub unsafe extern "C" fn CMSG_NXTHDR(mhdr: *const msghdr, cmsg: *const cmsghdr)
     };
 
     unsafe {
-        let next = cmsg as usize
-            + CMSG_ALIGN((*cmsg).cmsg_len as usize)
-            + CMSG_ALIGN(mem::size_of::<cmsghdr>());
-        let max = (*mhdr).msg_control as usize + (*mhdr).msg_controllen as usize;
-        if next > max {
+        let left = (__MHDR_END(mhdr) as usize).wrapping_sub(cmsg as usize);
+        if (*cmsg).cmsg_len < mem::size_of::<cmsghdr>()
+            || __CMSG_LEN(cmsg) as usize + mem::size_of::<cmsghdr>() >= left
+        {
             0 as *mut cmsghdr
         } else {
-            (cmsg as usize + CMSG_ALIGN((*cmsg).cmsg_len as usize)) as *mut cmsghdr
+            __CMSG_NEXT(cmsg) as *mut cmsghdr
         }
     }
 }
@@ -159,7 +169,8 @@ pub unsafe extern "C" fn accept(
     address_len: *mut socklen_t,
 ) -> c_int {
     trace_expr!(
-        Sys::accept(socket, address, address_len).or_minus_one_errno(),
+        pthread::cancellation_point(|| Sys::accept(socket, address, address_len))
+            .or_minus_one_errno(),
         "accept({}, {:p}, {:p})",
         socket,
         address,
@@ -167,6 +178,28 @@ pub unsafe extern "C" fn accept(
     )
 }
 
+/// See <https://man7.org/linux/man-pages/man2/accept4.2.html>.
+///
+/// Non-POSIX, like [`accept`], with `flags` of `SOCK_CLOEXEC` and
+/// `SOCK_NONBLOCK` set on the accepted socket.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn accept4(
+    socket: c_int,
+    address: *mut sockaddr,
+    address_len: *mut socklen_t,
+    flags: c_int,
+) -> c_int {
+    trace_expr!(
+        pthread::cancellation_point(|| Sys::accept4(socket, address, address_len, flags))
+            .or_minus_one_errno(),
+        "accept4({}, {:p}, {:p}, {:#x})",
+        socket,
+        address,
+        address_len,
+        flags
+    )
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn bind(
     socket: c_int,
@@ -191,7 +224,8 @@ pub unsafe extern "C" fn connect(
     address_len: socklen_t,
 ) -> c_int {
     trace_expr!(
-        Sys::connect(socket, address, address_len).or_minus_one_errno(),
+        pthread::cancellation_point(|| Sys::connect(socket, address, address_len))
+            .or_minus_one_errno(),
         "connect({}, {:p}, {})",
         socket,
         address,
@@ -288,9 +322,16 @@ pub unsafe extern "C" fn recvfrom(
     address_len: *mut socklen_t,
 ) -> ssize_t {
     trace_expr!(
-        Sys::recvfrom(socket, buffer, length, flags, address, address_len)
-            .map(|r| r as ssize_t)
-            .or_minus_one_errno(),
+        pthread::cancellation_point(|| Sys::recvfrom(
+            socket,
+            buffer,
+            length,
+            flags,
+            address,
+            address_len
+        ))
+        .map(|r| r as ssize_t)
+        .or_minus_one_errno(),
         "recvfrom({}, {:p}, {}, {:#x}, {:p}, {:p})",
         socket,
         buffer,
@@ -301,9 +342,33 @@ pub unsafe extern "C" fn recvfrom(
     )
 }
 
+/// See <https://man7.org/linux/man-pages/man2/recvmmsg.2.html>.
+///
+/// Non-POSIX, receives up to `vlen` messages, returning how many were.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn recvmmsg(
+    socket: c_int,
+    msgvec: *mut mmsghdr,
+    vlen: c_uint,
+    flags: c_int,
+    timeout: *mut timespec,
+) -> c_int {
+    trace_expr!(
+        pthread::cancellation_point(|| Sys::recvmmsg(socket, msgvec, vlen, flags, timeout))
+            .map(|r| r as c_int)
+            .or_minus_one_errno(),
+        "recvmmsg({}, {:p}, {}, {:#x}, {:p})",
+        socket,
+        msgvec,
+        vlen,
+        flags,
+        timeout
+    )
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn recvmsg(socket: c_int, msg: *mut msghdr, flags: c_int) -> ssize_t {
-    Sys::recvmsg(socket, msg, flags)
+    pthread::cancellation_point(|| Sys::recvmsg(socket, msg, flags))
         .map(|r| r as ssize_t)
         .or_minus_one_errno()
 }
@@ -318,9 +383,31 @@ pub unsafe extern "C" fn send(
     sendto(socket, message, length, flags, ptr::null(), 0)
 }
 
+/// See <https://man7.org/linux/man-pages/man2/sendmmsg.2.html>.
+///
+/// Non-POSIX, sends up to `vlen` messages, returning how many were.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sendmmsg(
+    socket: c_int,
+    msgvec: *mut mmsghdr,
+    vlen: c_uint,
+    flags: c_int,
+) -> c_int {
+    trace_expr!(
+        pthread::cancellation_point(|| Sys::sendmmsg(socket, msgvec, vlen, flags))
+            .map(|w| w as c_int)
+            .or_minus_one_errno(),
+        "sendmmsg({}, {:p}, {}, {:#x})",
+        socket,
+        msgvec,
+        vlen,
+        flags
+    )
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sendmsg(socket: c_int, msg: *const msghdr, flags: c_int) -> ssize_t {
-    Sys::sendmsg(socket, msg, flags)
+    pthread::cancellation_point(|| Sys::sendmsg(socket, msg, flags))
         .map(|w| w as ssize_t)
         .or_minus_one_errno()
 }
@@ -335,9 +422,11 @@ pub unsafe extern "C" fn sendto(
     dest_len: socklen_t,
 ) -> ssize_t {
     trace_expr!(
-        Sys::sendto(socket, message, length, flags, dest_addr, dest_len)
-            .map(|w| w as ssize_t)
-            .or_minus_one_errno(),
+        pthread::cancellation_point(|| Sys::sendto(
+            socket, message, length, flags, dest_addr, dest_len
+        ))
+        .map(|w| w as ssize_t)
+        .or_minus_one_errno(),
         "sendto({}, {:p}, {}, {:#x}, {:p}, {})",
         socket,
         message,
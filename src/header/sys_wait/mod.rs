@@ -48,5 +48,6 @@ pub unsafe extern "C" fn wait(stat_loc: *mut c_int) -> pid_t {
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn waitpid(pid: pid_t, stat_loc: *mut c_int, options: c_int) -> pid_t {
-    Sys::waitpid(pid, Out::nullable(stat_loc), options).or_minus_one_errno()
+    crate::pthread::cancellation_point(|| Sys::waitpid(pid, Out::nullable(stat_loc), options))
+        .or_minus_one_errno()
 }
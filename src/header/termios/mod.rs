@@ -164,7 +164,7 @@ pub unsafe extern "C" fn tcflush(fd: c_int, queue: c_int) -> c_int {
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn tcdrain(fd: c_int) -> c_int {
-    sys_ioctl::ioctl(fd, sys_ioctl::TCSBRK, 1 as *mut _)
+    crate::pthread::cancellation_point(|| sys_ioctl::ioctl(fd, sys_ioctl::TCSBRK, 1 as *mut _))
 }
 
 #[unsafe(no_mangle)]
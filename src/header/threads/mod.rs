@@ -0,0 +1,273 @@
+//! `threads.h` implementation.
+//!
+//! See <https://pubs.opengroup.org/onlinepubs/9799919799/basedefs/threads.h.html>.
+
+use alloc::boxed::Box;
+use core::ptr;
+
+use crate::{
+    error::Errno,
+    header::{
+        errno::{EAGAIN, EBUSY, EINTR, ENOMEM, ETIMEDOUT},
+        pthread::{
+            PTHREAD_MUTEX_DEFAULT, PTHREAD_MUTEX_RECURSIVE, RlctCond, RlctMutex, RlctMutexAttr, e,
+            pthread_getspecific, pthread_key_create, pthread_key_delete, pthread_once,
+            pthread_setspecific,
+        },
+        time::timespec,
+    },
+    platform::{Pal, Sys, types::*},
+    pthread::{self, Retval},
+};
+
+pub type thrd_t = pthread_t;
+pub type thrd_start_t = extern "C" fn(arg: *mut c_void) -> c_int;
+pub type mtx_t = pthread_mutex_t;
+pub type cnd_t = pthread_cond_t;
+pub type tss_t = pthread_key_t;
+pub type tss_dtor_t = Option<extern "C" fn(value: *mut c_void)>;
+pub type once_flag = pthread_once_t;
+
+// ONCE_FLAG_INIT is defined in cbindgen.toml
+
+pub const TSS_DTOR_ITERATIONS: c_int = 4;
+
+pub const thrd_success: c_int = 0;
+pub const thrd_busy: c_int = 1;
+pub const thrd_error: c_int = 2;
+pub const thrd_nomem: c_int = 3;
+pub const thrd_timedout: c_int = 4;
+
+pub const mtx_plain: c_int = 0;
+pub const mtx_recursive: c_int = 1;
+pub const mtx_timed: c_int = 2;
+
+/// Maps an error number returned by the pthread functions to the corresponding `thrd_*` status.
+fn thrd_status(error: c_int) -> c_int {
+    match error {
+        0 => thrd_success,
+        EBUSY => thrd_busy,
+        // Short of resources other than memory, for thrd_create.
+        ENOMEM | EAGAIN => thrd_nomem,
+        ETIMEDOUT => thrd_timedout,
+        _ => thrd_error,
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/call_once.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn call_once(flag: *mut once_flag, func: extern "C" fn()) {
+    pthread_once(flag, func);
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/cnd_broadcast.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cnd_broadcast(cond: *mut cnd_t) -> c_int {
+    thrd_status(e((&*cond.cast::<RlctCond>()).broadcast()))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/cnd_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cnd_destroy(cond: *mut cnd_t) {
+    ptr::drop_in_place(cond.cast::<RlctCond>());
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/cnd_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cnd_init(cond: *mut cnd_t) -> c_int {
+    cond.cast::<RlctCond>().write(RlctCond::new());
+    thrd_success
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/cnd_broadcast.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cnd_signal(cond: *mut cnd_t) -> c_int {
+    thrd_status(e((&*cond.cast::<RlctCond>()).signal()))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/cnd_timedwait.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cnd_timedwait(
+    cond: *mut cnd_t,
+    mtx: *mut mtx_t,
+    ts: *const timespec,
+) -> c_int {
+    thrd_status(e(
+        (&*cond.cast::<RlctCond>()).timedwait(&*mtx.cast::<RlctMutex>(), &*ts)
+    ))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/cnd_timedwait.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cnd_wait(cond: *mut cnd_t, mtx: *mut mtx_t) -> c_int {
+    thrd_status(e(
+        (&*cond.cast::<RlctCond>()).wait(&*mtx.cast::<RlctMutex>())
+    ))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/mtx_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtx_destroy(mtx: *mut mtx_t) {
+    ptr::drop_in_place(mtx.cast::<RlctMutex>());
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/mtx_destroy.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtx_init(mtx: *mut mtx_t, ty: c_int) -> c_int {
+    // Every mutex can be waited on with a timeout, so mtx_timed changes nothing.
+    if ty & !mtx_recursive != mtx_plain && ty & !mtx_recursive != mtx_timed {
+        return thrd_error;
+    }
+    let attr = RlctMutexAttr {
+        ty: if ty & mtx_recursive != 0 {
+            PTHREAD_MUTEX_RECURSIVE
+        } else {
+            PTHREAD_MUTEX_DEFAULT
+        },
+        ..RlctMutexAttr::default()
+    };
+
+    match RlctMutex::new(&attr) {
+        Ok(new) => {
+            mtx.cast::<RlctMutex>().write(new);
+            thrd_success
+        }
+        Err(Errno(error)) => thrd_status(error),
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/mtx_lock.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtx_lock(mtx: *mut mtx_t) -> c_int {
+    thrd_status(e((&*mtx.cast::<RlctMutex>()).lock()))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/mtx_lock.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtx_timedlock(mtx: *mut mtx_t, ts: *const timespec) -> c_int {
+    thrd_status(e((&*mtx.cast::<RlctMutex>()).lock_with_timeout(&*ts)))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/mtx_lock.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtx_trylock(mtx: *mut mtx_t) -> c_int {
+    thrd_status(e((&*mtx.cast::<RlctMutex>()).try_lock()))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/mtx_lock.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtx_unlock(mtx: *mut mtx_t) -> c_int {
+    thrd_status(e((&*mtx.cast::<RlctMutex>()).unlock()))
+}
+
+struct ThrdStart {
+    func: thrd_start_t,
+    arg: *mut c_void,
+}
+
+/// Runs a thread created by `thrd_create`, whose `int` result becomes the pthread return value.
+extern "C" fn thrd_start_shim(start: *mut c_void) -> *mut c_void {
+    // Freed before running, as the thread may end with thrd_exit.
+    let ThrdStart { func, arg } = *unsafe { Box::from_raw(start.cast::<ThrdStart>()) };
+
+    func(arg) as isize as *mut c_void
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_create.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn thrd_create(
+    thr: *mut thrd_t,
+    func: thrd_start_t,
+    arg: *mut c_void,
+) -> c_int {
+    let start = Box::into_raw(Box::new(ThrdStart { func, arg }));
+
+    match pthread::create(None, thrd_start_shim, start.cast()) {
+        Ok(thread) => {
+            thr.write(thread);
+            thrd_success
+        }
+        Err(Errno(error)) => {
+            drop(Box::from_raw(start));
+            thrd_status(error)
+        }
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_current.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn thrd_current() -> thrd_t {
+    pthread::current_thread().unwrap_unchecked() as *const _ as *mut _
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_detach.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn thrd_detach(thr: thrd_t) -> c_int {
+    thrd_status(e(pthread::detach(&*thr.cast())))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_equal.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn thrd_equal(thr0: thrd_t, thr1: thrd_t) -> c_int {
+    ptr::eq(thr0, thr1).into()
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_exit.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn thrd_exit(res: c_int) -> ! {
+    pthread::exit_current_thread(Retval(res as isize as *mut c_void))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_join.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn thrd_join(thr: thrd_t, res: *mut c_int) -> c_int {
+    match pthread::join(&*thr.cast()) {
+        Ok(Retval(ret)) => {
+            if !res.is_null() {
+                res.write(ret as isize as c_int);
+            }
+            thrd_success
+        }
+        Err(Errno(error)) => thrd_status(error),
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_sleep.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn thrd_sleep(duration: *const timespec, remaining: *mut timespec) -> c_int {
+    match pthread::cancellation_point(|| Sys::nanosleep(duration, remaining)) {
+        Ok(()) => 0,
+        Err(Errno(EINTR)) => -1,
+        Err(_) => -2,
+    }
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/thrd_yield.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn thrd_yield() {
+    let _ = Sys::sched_yield();
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/tss_create.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tss_create(key: *mut tss_t, dtor: tss_dtor_t) -> c_int {
+    thrd_status(pthread_key_create(key, dtor))
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/tss_delete.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tss_delete(key: tss_t) {
+    pthread_key_delete(key);
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/tss_get.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tss_get(key: tss_t) -> *mut c_void {
+    pthread_getspecific(key)
+}
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/tss_get.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tss_set(key: tss_t, val: *mut c_void) -> c_int {
+    thrd_status(pthread_setspecific(key, val))
+}
@@ -12,6 +12,7 @@ use crate::{
     header::{
         errno::{EFAULT, EOVERFLOW},
         fcntl::O_RDONLY,
+        locale::{self, locale_t},
         signal::sigevent,
         stdlib::getenv,
         unistd::readlink,
@@ -466,7 +467,9 @@ pub unsafe extern "C" fn mktime(timeptr: *mut tm) -> time_t {
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/nanosleep.html>.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn nanosleep(rqtp: *const timespec, rmtp: *mut timespec) -> c_int {
-    Sys::nanosleep(rqtp, rmtp).map(|()| 0).or_minus_one_errno()
+    crate::pthread::cancellation_point(|| Sys::nanosleep(rqtp, rmtp))
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strftime.html>.
@@ -481,16 +484,28 @@ pub unsafe extern "C" fn strftime(
         &mut platform::StringWriter(s as *mut u8, maxsize),
         format,
         timeptr,
+        locale::current(),
     );
     if ret < maxsize { ret } else { 0 }
 }
 
-// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strftime.html>.
-// TODO: needs locale_t
-// #[unsafe(no_mangle)]
-/*pub extern "C" fn strftime_l(s: *mut char, maxsize: size_t, format: *const c_char, timeptr: *const tm, locale: locale_t) -> size_t {
-    unimplemented!();
-}*/
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/strftime.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn strftime_l(
+    s: *mut c_char,
+    maxsize: size_t,
+    format: *const c_char,
+    timeptr: *const tm,
+    loc: locale_t,
+) -> size_t {
+    let ret = strftime::strftime(
+        &mut platform::StringWriter(s as *mut u8, maxsize),
+        format,
+        timeptr,
+        locale::get(loc),
+    );
+    if ret < maxsize { ret } else { 0 }
+}
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/time.html>.
 #[unsafe(no_mangle)]
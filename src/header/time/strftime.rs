@@ -5,6 +5,7 @@ use alloc::string::String;
 use super::{get_offset, tm};
 use crate::{
     c_str::CStr,
+    header::locale::__locale_struct,
     platform::{self, WriteByte, types::*},
 };
 
@@ -13,20 +14,24 @@ use crate::header::langinfo::{
     ABDAY_1,
     ABMON_1,
     AM_STR,
+    D_FMT,
+    D_T_FMT,
     DAY_1,
     MON_1,
     PM_STR,
+    T_FMT,
+    T_FMT_AMPM,
     // TODO : other constants if needed
+    langinfo,
     nl_item,
-    nl_langinfo,
 };
 
-/// A helper that calls `nl_langinfo(item)` and converts the returned pointer
+/// A helper that looks up `item` in `loc` and converts the returned pointer
 /// into a `&str`. If it fails or is null, returns an empty string "".
-unsafe fn langinfo_to_str(item: nl_item) -> &'static str {
+unsafe fn langinfo_to_str(item: nl_item, loc: &__locale_struct) -> &str {
     use core::ffi::CStr;
 
-    let ptr = nl_langinfo(item);
+    let ptr = langinfo(item, loc);
     if ptr.is_null() {
         return "";
     }
@@ -41,12 +46,18 @@ unsafe fn langinfo_to_str(item: nl_item) -> &'static str {
 /// Use `langinfo` for locale-based day/month names,
 /// but still hard-codes other aspects of the "C" locale (like numeric/date
 /// formats) and ignores `%E` / `%O` variations.
-pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const tm) -> size_t {
+pub unsafe fn strftime<W: WriteByte>(
+    w: &mut W,
+    format: *const c_char,
+    t: *const tm,
+    loc: &__locale_struct,
+) -> size_t {
     /// Helper that actually parses the format string and writes output.
     pub unsafe fn inner_strftime<W: WriteByte>(
         w: &mut W,
         mut format: *const c_char,
         t: *const tm,
+        loc: &__locale_struct,
     ) -> bool {
         macro_rules! w {
             (byte $b:expr) => {{
@@ -64,7 +75,7 @@ pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const
                 tmp.push_str($fmt);
                 tmp.push('\0');
 
-                if !inner_strftime(w, tmp.as_ptr() as *mut c_char, t) {
+                if !inner_strftime(w, tmp.as_ptr() as *mut c_char, t, loc) {
                     return false;
                 }
             }};
@@ -109,26 +120,26 @@ pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const
                 // Abbreviated weekday name: %a
                 b'a' => {
                     // `ABDAY_1 + tm_wday` is the correct langinfo ID for abbreviated weekdays
-                    let s = langinfo_to_str(ABDAY_1 + (*t).tm_wday as i32);
+                    let s = langinfo_to_str(ABDAY_1 + (*t).tm_wday as i32, loc);
                     w!(s);
                 }
 
                 // Full weekday name: %A
                 b'A' => {
                     // `DAY_1 + tm_wday` is the correct langinfo ID for full weekdays
-                    let s = langinfo_to_str(DAY_1 + (*t).tm_wday as i32);
+                    let s = langinfo_to_str(DAY_1 + (*t).tm_wday as i32, loc);
                     w!(s);
                 }
 
                 // Abbreviated month name: %b or %h
                 b'b' | b'h' => {
-                    let s = langinfo_to_str(ABMON_1 + (*t).tm_mon as i32);
+                    let s = langinfo_to_str(ABMON_1 + (*t).tm_mon as i32, loc);
                     w!(s);
                 }
 
                 // Full month name: %B
                 b'B' => {
-                    let s = langinfo_to_str(MON_1 + (*t).tm_mon as i32);
+                    let s = langinfo_to_str(MON_1 + (*t).tm_mon as i32, loc);
                     w!(s);
                 }
 
@@ -141,6 +152,9 @@ pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const
                     w!("{:02}", year + 19);
                 }
 
+                // Locale's date and time: %c
+                b'c' => w!(recurse langinfo_to_str(D_T_FMT, loc)),
+
                 // Day of month: %d
                 b'd' => w!("{:02}", (*t).tm_mday),
 
@@ -172,9 +186,9 @@ pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const
                 b'p' => {
                     // Get "AM" / "PM" from langinfo
                     if (*t).tm_hour < 12 {
-                        w!(langinfo_to_str(AM_STR));
+                        w!(langinfo_to_str(AM_STR, loc));
                     } else {
-                        w!(langinfo_to_str(PM_STR));
+                        w!(langinfo_to_str(PM_STR, loc));
                     }
                 }
 
@@ -182,16 +196,16 @@ pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const
                 b'P' => {
                     // Convert the AM_STR / PM_STR to lowercase
                     if (*t).tm_hour < 12 {
-                        let am = langinfo_to_str(AM_STR).to_ascii_lowercase();
+                        let am = langinfo_to_str(AM_STR, loc).to_ascii_lowercase();
                         w!(&am);
                     } else {
-                        let pm = langinfo_to_str(PM_STR).to_ascii_lowercase();
+                        let pm = langinfo_to_str(PM_STR, loc).to_ascii_lowercase();
                         w!(&pm);
                     }
                 }
 
-                // 12-hour clock with seconds + AM/PM: %r => %I:%M:%S %p
-                b'r' => w!(recurse "%I:%M:%S %p"),
+                // 12-hour clock with seconds + AM/PM: %r, %I:%M:%S %p in the C locale
+                b'r' => w!(recurse langinfo_to_str(T_FMT_AMPM, loc)),
 
                 // 24-hour clock without seconds: %R => %H:%M
                 b'R' => w!(recurse "%H:%M"),
@@ -220,6 +234,12 @@ pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const
                 // Monday-based week of year: %W
                 b'W' => w!("{}", ((*t).tm_yday + 7 - ((*t).tm_wday + 6) % 7) / 7),
 
+                // Locale's date: %x
+                b'x' => w!(recurse langinfo_to_str(D_FMT, loc)),
+
+                // Locale's time: %X
+                b'X' => w!(recurse langinfo_to_str(T_FMT, loc)),
+
                 // Last two digits of year: %y
                 b'y' => w!("{:02}", (*t).tm_year % 100),
 
@@ -258,7 +278,7 @@ pub unsafe fn strftime<W: WriteByte>(w: &mut W, format: *const c_char, t: *const
 
     // Wrap the writer in a CountingWriter to return how many bytes were written.
     let mut cw = platform::CountingWriter::new(w);
-    if !inner_strftime(&mut cw, format, t) {
+    if !inner_strftime(&mut cw, format, t, loc) {
         return 0;
     }
     cw.written
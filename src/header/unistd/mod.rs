@@ -25,6 +25,7 @@ use crate::{
     },
     out::Out,
     platform::{self, ERRNO, Pal, Sys, types::*},
+    pthread,
 };
 
 pub use self::{brk::*, getopt::*, getpass::getpass, pathconf::*, sysconf::*};
@@ -196,7 +197,9 @@ pub unsafe extern "C" fn chroot(path: *const c_char) -> c_int {
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/close.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn close(fildes: c_int) -> c_int {
-    Sys::close(fildes).map(|()| 0).or_minus_one_errno()
+    pthread::cancellation_point(|| Sys::close(fildes))
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/confstr.html>.
@@ -419,7 +422,9 @@ pub extern "C" fn fchown(fildes: c_int, owner: uid_t, group: gid_t) -> c_int {
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/fdatasync.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn fdatasync(fildes: c_int) -> c_int {
-    Sys::fdatasync(fildes).map(|()| 0).or_minus_one_errno()
+    pthread::cancellation_point(|| Sys::fdatasync(fildes))
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/fork.html>.
@@ -430,6 +435,7 @@ pub unsafe extern "C" fn fork() -> pid_t {
     }
     let pid = Sys::fork().or_minus_one_errno();
     if pid == 0 {
+        crate::pthread::init_after_fork();
         for child in &fork_hooks[2] {
             child();
         }
@@ -444,7 +450,9 @@ pub unsafe extern "C" fn fork() -> pid_t {
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/fsync.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn fsync(fildes: c_int) -> c_int {
-    Sys::fsync(fildes).map(|()| 0).or_minus_one_errno()
+    pthread::cancellation_point(|| Sys::fsync(fildes))
+        .map(|()| 0)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/ftruncate.html>.
@@ -846,13 +854,10 @@ pub unsafe extern "C" fn pread(
     nbyte: size_t,
     offset: off_t,
 ) -> ssize_t {
-    Sys::pread(
-        fildes,
-        slice::from_raw_parts_mut(buf.cast::<u8>(), nbyte),
-        offset,
-    )
-    .map(|read| read as ssize_t)
-    .or_minus_one_errno()
+    let buf = slice::from_raw_parts_mut(buf.cast::<u8>(), nbyte);
+    pthread::cancellation_point(|| Sys::pread(fildes, buf, offset))
+        .map(|read| read as ssize_t)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/write.html>.
@@ -863,13 +868,10 @@ pub unsafe extern "C" fn pwrite(
     nbyte: size_t,
     offset: off_t,
 ) -> ssize_t {
-    Sys::pwrite(
-        fildes,
-        slice::from_raw_parts(buf.cast::<u8>(), nbyte),
-        offset,
-    )
-    .map(|read| read as ssize_t)
-    .or_minus_one_errno()
+    let buf = slice::from_raw_parts(buf.cast::<u8>(), nbyte);
+    pthread::cancellation_point(|| Sys::pwrite(fildes, buf, offset))
+        .map(|read| read as ssize_t)
+        .or_minus_one_errno()
 }
 
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/read.html>.
@@ -877,7 +879,7 @@ pub unsafe extern "C" fn pwrite(
 pub unsafe extern "C" fn read(fildes: c_int, buf: *const c_void, nbyte: size_t) -> ssize_t {
     let buf = unsafe { slice::from_raw_parts_mut(buf as *mut u8, nbyte as usize) };
     trace_expr!(
-        Sys::read(fildes, buf)
+        pthread::cancellation_point(|| Sys::read(fildes, buf))
             .map(|read| read as ssize_t)
             .or_minus_one_errno(),
         "read({}, {:p}, {})",
@@ -1041,7 +1043,7 @@ pub extern "C" fn sleep(seconds: c_uint) -> c_uint {
     // If sleep() returns because the requested time has elapsed, the value returned shall be 0.
     // If sleep() returns due to delivery of a signal, the return value shall be the "unslept" amount
     // (the requested time minus the time actually slept) in seconds.
-    match unsafe { Sys::nanosleep(&rqtp, &mut rmtp) } {
+    match pthread::cancellation_point(|| unsafe { Sys::nanosleep(&rqtp, &mut rmtp) }) {
         Err(Errno(EINTR)) => rmtp.tv_sec as c_uint,
         r => 0,
     }
@@ -1195,7 +1197,7 @@ pub extern "C" fn usleep(useconds: useconds_t) -> c_int {
         tv_nsec: ((useconds % 1_000_000) * 1000) as c_long,
     };
     let rmtp = ptr::null_mut();
-    unsafe { Sys::nanosleep(&rqtp, rmtp) }
+    pthread::cancellation_point(|| unsafe { Sys::nanosleep(&rqtp, rmtp) })
         .map(|()| 0)
         .or_minus_one_errno()
 }
@@ -1260,7 +1262,7 @@ unsafe fn with_argv(
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn write(fildes: c_int, buf: *const c_void, nbyte: size_t) -> ssize_t {
     let buf = slice::from_raw_parts(buf as *const u8, nbyte as usize);
-    Sys::write(fildes, buf)
+    pthread::cancellation_point(|| Sys::write(fildes, buf))
         .map(|bytes| bytes as ssize_t)
         .or_minus_one_errno()
 }
@@ -2,7 +2,7 @@ use super::{FILE, SEEK_SET, fseek_locked, ftell_locked};
 use crate::{
     header::{
         errno::EILSEQ,
-        wchar::{MB_CUR_MAX, fgetwc, get_char_encoded_length, mbrtowc},
+        wchar::{MB_LEN_MAX, fgetwc, get_char_encoded_length, mbrtowc},
         wctype::WEOF,
     },
     io::Read,
@@ -52,7 +52,7 @@ struct LookAheadFile<'a> {
 
 impl<'a> LookAheadFile<'a> {
     fn look_ahead(&mut self) -> Result<Option<wint_t>, i32> {
-        let buf = &mut [0; MB_CUR_MAX as usize];
+        let buf = &mut [0; MB_LEN_MAX as usize];
         let seek = unsafe { ftell_locked(self.f) };
         unsafe { fseek_locked(self.f, self.look_ahead as off_t, SEEK_SET) };
 
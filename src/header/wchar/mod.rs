@@ -10,8 +10,9 @@ use crate::{
     header::{
         ctype::isspace,
         errno::{EILSEQ, ENOMEM, ERANGE},
-        stdio::*,
-        stdlib::{malloc, MB_CUR_MAX, MB_LEN_MAX},
+        locale,
+        stdio::{memstream, *},
+        stdlib::{malloc, MB_LEN_MAX},
         string,
         time::*,
         wchar::{lookaheadreader::LookAheadReader, utf8::get_char_encoded_length},
@@ -23,7 +24,8 @@ use crate::{
 };
 
 mod lookaheadreader;
-mod utf8;
+mod single_byte;
+pub(crate) mod utf8;
 mod wcsftime;
 mod wprintf;
 mod wscanf;
@@ -129,31 +131,34 @@ pub unsafe extern "C" fn btowc(c: c_int) -> wint_t {
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fgetwc(stream: *mut FILE) -> wint_t {
-    let mut c = fgetc(stream);
-    if c == EOF {
+    let mut stream = (*stream).lock();
+    if let Err(_) = (*stream).try_set_wide_orientation_unlocked() {
         return WEOF;
     }
+
     let mut ps = mbstate_t {
         __count: 0,
         __value: 0,
     };
     let mut wc = 0;
-    let mut c_char = c as c_char;
-    let mut n = mbrtowc(&mut wc, &c_char, 1, &mut ps);
-    while n == usize::MAX - 2 {
-        c = fgetc(stream);
-        if c == EOF {
-            ERRNO.set(EILSEQ);
-            return WEOF;
+    loop {
+        let mut c = [0];
+        match io::Read::read(&mut *stream, &mut c) {
+            Ok(1) => (),
+            Ok(_) | Err(_) => {
+                // Running out in the middle of a character is an error
+                if mbsinit(&ps) == 0 {
+                    ERRNO.set(EILSEQ);
+                }
+                return WEOF;
+            }
+        }
+        match mbrtowc(&mut wc, c.as_ptr() as *const c_char, 1, &mut ps) {
+            n if n == usize::MAX - 1 => continue,
+            n if n == usize::MAX => return WEOF,
+            _ => return wc as wint_t,
         }
-        c_char = c as c_char;
-        n = mbrtowc(&mut wc, &c_char, 1, &mut ps);
-    }
-    if n == usize::MAX - 1 {
-        ERRNO.set(EILSEQ);
-        return WEOF;
     }
-    wc as wint_t
 }
 
 #[unsafe(no_mangle)]
@@ -182,17 +187,24 @@ pub unsafe extern "C" fn fgetws(ws: *mut wchar_t, n: c_int, stream: *mut FILE) -
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fputwc(wc: wchar_t, stream: *mut FILE) -> wint_t {
+    let mut stream = (*stream).lock();
+    if let Err(_) = (*stream).try_set_wide_orientation_unlocked() {
+        return WEOF;
+    }
+
     //Convert wchar_t to multibytes first
-    static mut INTERNAL: mbstate_t = mbstate_t {
+    let mut ps = mbstate_t {
         __count: 0,
         __value: 0,
     };
-    let mut bytes: [c_char; MB_CUR_MAX as usize] = [0; MB_CUR_MAX as usize];
+    let mut bytes: [u8; MB_LEN_MAX as usize] = [0; MB_LEN_MAX as usize];
 
-    let amount = wcrtomb(bytes.as_mut_ptr(), wc, &raw mut INTERNAL);
-
-    for i in 0..amount {
-        fputc(bytes[i] as c_int, &mut *stream);
+    let amount = wcrtomb(bytes.as_mut_ptr() as *mut c_char, wc, &mut ps);
+    if amount == usize::MAX {
+        return WEOF;
+    }
+    if io::Write::write_all(&mut *stream, &bytes[..amount]).is_err() {
+        return WEOF;
     }
 
     wc as wint_t
@@ -247,7 +259,7 @@ pub unsafe extern "C" fn mbrlen(s: *const c_char, n: size_t, ps: *mut mbstate_t)
     mbrtowc(ptr::null_mut(), s, n, &raw mut INTERNAL)
 }
 
-//Only works for UTF8 at the moment
+//Only UTF-8 and single-byte codesets are supported
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn mbrtowc(
     pwc: *mut wchar_t,
@@ -260,14 +272,18 @@ pub unsafe extern "C" fn mbrtowc(
         __value: 0,
     };
 
-    if ps.is_null() {
-        let ps = &raw mut INTERNAL;
-    }
-    if s.is_null() {
-        let xs: [c_char; 1] = [0];
-        utf8::mbrtowc(pwc, &xs[0] as *const c_char, 1, ps)
+    let ps = if ps.is_null() { &raw mut INTERNAL } else { ps };
+    let xs: [c_char; 1] = [0];
+    let (s, n) = if s.is_null() {
+        (xs.as_ptr(), 1)
     } else {
+        (s, n)
+    };
+
+    if locale::current().is_utf8() {
         utf8::mbrtowc(pwc, s, n, ps)
+    } else {
+        single_byte::mbrtowc(pwc, s, n, ps)
     }
 }
 
@@ -345,6 +361,16 @@ pub unsafe extern "C" fn mbsrtowcs(
     mbsnrtowcs(dst, src, size_t::max_value(), len, ps)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/open_memstream.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn open_wmemstream(bufp: *mut *mut wchar_t, sizep: *mut size_t) -> *mut FILE {
+    let stream = memstream::open(bufp, sizep);
+    if !stream.is_null() {
+        (*stream).try_set_orientation(1);
+    }
+    stream
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn putwc(wc: wchar_t, stream: *mut FILE) -> wint_t {
     fputwc(wc, &mut *stream)
@@ -384,7 +410,7 @@ pub unsafe extern "C" fn ungetwc(wc: wint_t, stream: &mut FILE) -> wint_t {
         __count: 0,
         __value: 0,
     };
-    let mut bytes: [c_char; MB_CUR_MAX as usize] = [0; MB_CUR_MAX as usize];
+    let mut bytes: [c_char; MB_LEN_MAX as usize] = [0; MB_LEN_MAX as usize];
 
     let amount = wcrtomb(bytes.as_mut_ptr(), wc as wchar_t, &raw mut INTERNAL);
     if amount == usize::MAX {
@@ -397,8 +423,12 @@ pub unsafe extern "C" fn ungetwc(wc: wint_t, stream: &mut FILE) -> wint_t {
     When we do fgetwc, we pop from the Vec, getting the write order of bytes [195, 167].
     If we called ungetc in the non-reversed order, we would get [167, 195]
     */
+    let mut stream = stream.lock();
+    if let Err(_) = stream.try_set_wide_orientation_unlocked() {
+        return WEOF;
+    }
     for i in 0..amount {
-        ungetc(bytes[amount - 1 - i] as c_int, &mut *stream);
+        stream.unget.push(bytes[amount - 1 - i] as u8);
     }
 
     wc
@@ -497,14 +527,18 @@ pub unsafe extern "C" fn wcpncpy(d: *mut wchar_t, s: *const wchar_t, n: size_t)
 //widechar to multibyte
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn wcrtomb(s: *mut c_char, wc: wchar_t, ps: *mut mbstate_t) -> size_t {
-    let mut buffer: [c_char; MB_CUR_MAX as usize] = [0; MB_CUR_MAX as usize];
+    let mut buffer: [c_char; MB_LEN_MAX as usize] = [0; MB_LEN_MAX as usize];
     let (s_cpy, wc_cpy) = if s.is_null() {
         (buffer.as_mut_ptr(), 0)
     } else {
         (s, wc)
     };
 
-    utf8::wcrtomb(s_cpy, wc_cpy, ps)
+    if locale::current().is_utf8() {
+        utf8::wcrtomb(s_cpy, wc_cpy, ps)
+    } else {
+        single_byte::wcrtomb(s_cpy, wc_cpy, ps)
+    }
 }
 
 #[unsafe(no_mangle)]
@@ -1137,7 +1171,12 @@ pub unsafe extern "C" fn wcsxfrm(ws1: *mut wchar_t, ws2: *const wchar_t, n: size
 
 #[unsafe(no_mangle)]
 pub extern "C" fn wctob(c: wint_t) -> c_int {
-    if c <= 0x7F {
+    let max = if locale::current().is_utf8() {
+        0x7F
+    } else {
+        0xFF
+    };
+    if c <= max {
         c as c_int
     } else {
         EOF
@@ -0,0 +1,35 @@
+//Single-byte implementation parts for wchar.h, used when the LC_CTYPE
+//codeset is not UTF-8. Each byte maps to the wide character of the same value.
+
+use crate::{
+    header::errno,
+    platform::{self, types::*},
+};
+
+use super::mbstate_t;
+
+//It's guaranteed that we don't have any nullpointers here
+pub unsafe fn mbrtowc(pwc: *mut wchar_t, s: *const c_char, n: usize, ps: *mut mbstate_t) -> usize {
+    if n == 0 {
+        return -2isize as usize;
+    }
+
+    let b = *s as u8;
+    if !pwc.is_null() {
+        *pwc = wchar_t::from(b);
+    }
+    (*ps).__count = 0;
+    (*ps).__value = 0;
+    if b == 0 { 0 } else { 1 }
+}
+
+//It's guaranteed that we don't have any nullpointers here
+pub unsafe fn wcrtomb(s: *mut c_char, wc: wchar_t, _ps: *mut mbstate_t) -> usize {
+    if !(0..=0xff).contains(&wc) {
+        platform::ERRNO.set(errno::EILSEQ);
+        return -1isize as usize;
+    }
+
+    *s = wc as u8 as c_char;
+    1
+}
@@ -23,7 +23,6 @@ use crate::{
     c_str::CStr,
     header::{
         langinfo::{nl_item, nl_langinfo, ABDAY_1, ABMON_1, AM_STR, DAY_1, MON_1, PM_STR},
-        stdlib::MB_CUR_MAX,
         time::tm,
     },
     platform::{self, types::*},
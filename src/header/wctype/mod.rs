@@ -8,10 +8,15 @@
 // TODO: set this for entire crate when possible
 #![deny(unsafe_op_in_unsafe_fn)]
 
-// TODO: *_l functions
+// The *_l functions ignore their locale, since the classification tables
+// here are used by every locale we support.
 
 use self::casecmp::casemap;
-use crate::{c_str::CStr, header::ctype, platform::types::*};
+use crate::{
+    c_str::CStr,
+    header::{ctype, locale::locale_t},
+    platform::types::*,
+};
 
 mod alpha;
 mod casecmp;
@@ -44,18 +49,36 @@ pub extern "C" fn iswalnum(wc: wint_t) -> c_int {
     c_int::from(iswdigit(wc) != 0 || iswalpha(wc) != 0)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswalnum.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswalnum_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswalnum(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswalpha.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswalpha(wc: wint_t) -> c_int {
     c_int::from(alpha::is(wc as usize))
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswalpha.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswalpha_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswalpha(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswblank.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswblank(wc: wint_t) -> c_int {
     ctype::isblank(wc as c_int)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswblank.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswblank_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswblank(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswcntrl.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswcntrl(wc: wint_t) -> c_int {
@@ -67,6 +90,12 @@ pub extern "C" fn iswcntrl(wc: wint_t) -> c_int {
     )
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswcntrl.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswcntrl_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswcntrl(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswctype.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswctype(wc: wint_t, desc: wctype_t) -> c_int {
@@ -87,24 +116,48 @@ pub extern "C" fn iswctype(wc: wint_t, desc: wctype_t) -> c_int {
     }
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswctype.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswctype_l(wc: wint_t, desc: wctype_t, _loc: locale_t) -> c_int {
+    iswctype(wc, desc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswdigit.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswdigit(wc: wint_t) -> c_int {
     c_int::from(wc.wrapping_sub('0' as wint_t) < 10)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswdigit.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswdigit_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswdigit(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswgraph.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswgraph(wc: wint_t) -> c_int {
     c_int::from(iswspace(wc) == 0 && iswprint(wc) != 0)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswgraph.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswgraph_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswgraph(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswlower.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswlower(wc: wint_t) -> c_int {
     c_int::from(towupper(wc) != wc)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswlower.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswlower_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswlower(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswprint.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswprint(wc: wint_t) -> c_int {
@@ -122,12 +175,24 @@ pub extern "C" fn iswprint(wc: wint_t) -> c_int {
     }
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswprint.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswprint_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswprint(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswpunct.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswpunct(wc: wint_t) -> c_int {
     c_int::from(punct::is(wc as usize))
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswpunct.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswpunct_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswpunct(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswspace.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswspace(wc: wint_t) -> c_int {
@@ -159,18 +224,36 @@ pub extern "C" fn iswspace(wc: wint_t) -> c_int {
     )
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswspace.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswspace_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswspace(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswupper.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswupper(wc: wint_t) -> c_int {
     c_int::from(towlower(wc) != wc)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswupper.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswupper_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswupper(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswxdigit.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn iswxdigit(wc: wint_t) -> c_int {
     c_int::from(wc.wrapping_sub('0' as wint_t) < 10 || (wc | 32).wrapping_sub('a' as wint_t) < 6)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/iswxdigit.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn iswxdigit_l(wc: wint_t, _loc: locale_t) -> c_int {
+    iswxdigit(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/towctrans.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn towctrans(wc: wint_t, trans: wctrans_t) -> wint_t {
@@ -181,18 +264,36 @@ pub extern "C" fn towctrans(wc: wint_t, trans: wctrans_t) -> wint_t {
     }
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/towctrans.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn towctrans_l(wc: wint_t, trans: wctrans_t, _loc: locale_t) -> wint_t {
+    towctrans(wc, trans)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/towlower.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn towlower(wc: wint_t) -> wint_t {
     casemap(wc, 0)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/towlower.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn towlower_l(wc: wint_t, _loc: locale_t) -> wint_t {
+    towlower(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/towupper.html>.
 #[unsafe(no_mangle)]
 pub extern "C" fn towupper(wc: wint_t) -> wint_t {
     casemap(wc, 1)
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/towupper.html>.
+#[unsafe(no_mangle)]
+pub extern "C" fn towupper_l(wc: wint_t, _loc: locale_t) -> wint_t {
+    towupper(wc)
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/wctrans.html>.
 ///
 /// # Safety
@@ -208,6 +309,16 @@ pub unsafe extern "C" fn wctrans(class: *const c_char) -> wctrans_t {
     }
 }
 
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/wctrans.html>.
+///
+/// # Safety
+/// The caller must ensure that `class` is convertible to a slice reference, up
+/// to and including a terminating nul.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn wctrans_l(class: *const c_char, _loc: locale_t) -> wctrans_t {
+    unsafe { wctrans(class) }
+}
+
 /// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/wctype.html>.
 ///
 /// # Safety
@@ -232,3 +343,13 @@ pub unsafe extern "C" fn wctype(name: *const c_char) -> wctype_t {
         _ => 0,
     }
 }
+
+/// See <https://pubs.opengroup.org/onlinepubs/9799919799/functions/wctype.html>.
+///
+/// # Safety
+/// The caller must ensure that `name` is convertible to a slice reference, up
+/// to and including a terminating nul.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn wctype_l(name: *const c_char, _loc: locale_t) -> wctype_t {
+    unsafe { wctype(name) }
+}
@@ -10,7 +10,6 @@
 
 //! Buffering wrappers for I/O traits
 
-use crate::platform::types::c_void;
 use core::{cmp, fmt};
 
 use crate::io::{
@@ -472,12 +471,12 @@ impl<W: Write> Write for BufWriter<W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         if buf.len() >= self.buf.capacity() {
             self.flush_buf()?;
-            // If the buffer is larger than our capacity, we can map it directly
-            // and flush it to disk, which is faster than a regular write.
-            let ptr = buf.as_ptr() as *mut c_void;
-            let len = buf.len();
-            io::flush_mmap(ptr, len)?;
-            Ok(len)
+            // Too big to be worth buffering, so hand it straight to the inner
+            // writer
+            self.panicked = true;
+            let r = self.get_mut().write(buf);
+            self.panicked = false;
+            r
         } else {
             // If the incoming buffer won't fit in the remaining space of our
             // buffer, flush our buffer first.
@@ -291,29 +291,6 @@ pub fn last_os_error() -> Error {
     Error::last_os_error()
 }
 
-/// Synchronizes a memory-mapped file with the underlying storage.
-///
-/// This function is a safe wrapper around the `msync` syscall, ensuring that
-/// modifications to a memory-mapped region are flushed to the file.
-///
-/// # Arguments
-///
-/// * `addr`: A pointer to the starting address of the memory-mapped region.
-/// * `len`: The length of the memory region to be flushed.
-///
-/// # Returns
-///
-/// An `io::Result<()>` indicating the success or failure of the operation.
-pub fn flush_mmap(addr: *mut c_void, len: usize) -> Result<()> {
-    let result =
-        unsafe { crate::header::sys_mman::msync(addr, len, crate::header::sys_mman::MS_SYNC) };
-    if result == 0 {
-        Ok(())
-    } else {
-        Err(last_os_error())
-    }
-}
-
 struct Guard<'a> {
     buf: &'a mut Vec<u8>,
     len: usize,
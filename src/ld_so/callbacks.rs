@@ -1,12 +1,20 @@
-use super::linker::{Linker, ObjectHandle, Resolve, Result, ScopeKind};
+use super::linker::{
+    AddrInfo, Linker, LoadFlags, ObjectHandle, ObjectInfo, PhdrInfo, Resolve, Result, ScopeKind,
+};
 use crate::platform::types::c_void;
 use alloc::boxed::Box;
 
 pub struct LinkerCallbacks {
     pub unload: Box<dyn Fn(&mut Linker, ObjectHandle)>,
-    pub load_library:
-        Box<dyn Fn(&mut Linker, Option<&str>, Resolve, ScopeKind, bool) -> Result<ObjectHandle>>,
-    pub get_sym: Box<dyn Fn(&Linker, Option<ObjectHandle>, &str) -> Option<*mut c_void>>,
+    pub load_library: Box<
+        dyn Fn(&mut Linker, Option<&str>, Resolve, ScopeKind, LoadFlags) -> Result<ObjectHandle>,
+    >,
+    pub get_sym:
+        Box<dyn Fn(&Linker, Option<ObjectHandle>, &str, Option<&str>) -> Option<*mut c_void>>,
+    pub addr_info: Box<dyn Fn(&Linker, usize) -> Option<AddrInfo>>,
+    pub iterate_phdr: Box<dyn Fn(&mut Linker, &mut dyn FnMut(PhdrInfo))>,
+    pub end_iterate_phdr: Box<dyn Fn(&mut Linker)>,
+    pub object_info: Box<dyn Fn(&Linker, &ObjectHandle, &mut dyn FnMut(&ObjectInfo))>,
 }
 
 impl LinkerCallbacks {
@@ -15,6 +23,10 @@ impl LinkerCallbacks {
             unload: Box::new(unload),
             load_library: Box::new(load_library),
             get_sym: Box::new(get_sym),
+            addr_info: Box::new(addr_info),
+            iterate_phdr: Box::new(iterate_phdr),
+            end_iterate_phdr: Box::new(end_iterate_phdr),
+            object_info: Box::new(object_info),
         }
     }
 }
@@ -28,11 +40,32 @@ fn load_library(
     name: Option<&str>,
     resolve: Resolve,
     scope: ScopeKind,
-    noload: bool,
+    flags: LoadFlags,
 ) -> Result<ObjectHandle> {
-    linker.load_library(name, resolve, scope, noload)
+    linker.load_library(name, resolve, scope, flags)
 }
 
-fn get_sym(linker: &Linker, handle: Option<ObjectHandle>, name: &str) -> Option<*mut c_void> {
-    linker.get_sym(handle, name)
+fn get_sym(
+    linker: &Linker,
+    handle: Option<ObjectHandle>,
+    name: &str,
+    version: Option<&str>,
+) -> Option<*mut c_void> {
+    linker.get_sym(handle, name, version)
+}
+
+fn addr_info(linker: &Linker, addr: usize) -> Option<AddrInfo> {
+    linker.addr_info(addr)
+}
+
+fn iterate_phdr(linker: &mut Linker, f: &mut dyn FnMut(PhdrInfo)) {
+    linker.iterate_phdr(f)
+}
+
+fn end_iterate_phdr(linker: &mut Linker) {
+    linker.end_iterate_phdr()
+}
+
+fn object_info(linker: &Linker, handle: &ObjectHandle, f: &mut dyn FnMut(&ObjectInfo)) {
+    linker.object_info(handle, f)
 }
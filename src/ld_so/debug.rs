@@ -1,4 +1,4 @@
-use crate::{c_str::CString, platform::types::*};
+use crate::{c_str::CString, header::link::link_map as LinkMap, platform::types::*};
 use alloc::boxed::Box;
 use core::ptr;
 
@@ -61,6 +61,19 @@ impl RTLDDebug {
             unsafe { (*self.r_map).link(&mut *tmp) };
         }
     }
+
+    /// Returns the most recently added entry for the object loaded at `l_addr`.
+    pub fn find(&self, l_addr: usize) -> Option<*mut LinkMap> {
+        let mut found = None;
+        let mut map = self.r_map;
+        while let Some(entry) = unsafe { map.as_ref() } {
+            if entry.l_addr == l_addr {
+                found = Some(map);
+            }
+            map = entry.l_next;
+        }
+        found
+    }
 }
 
 /// SAFETY: safe as long as caller wraps the instance in a mutex,
@@ -72,21 +85,6 @@ unsafe impl Send for RTLDDebug {}
 /// Separate instances must not contain pointers to the same LinkMap instance.
 unsafe impl Sync for RTLDDebug {}
 
-#[repr(C)]
-struct LinkMap {
-    /* These members are part of the protocol with the debugger.
-    This is the same format used in SVR4.  */
-    /// Difference between the address in the ELF
-    /// file and the addresses in memory.
-    l_addr: usize,
-    /// Absolute file name object was found in.
-    l_name: *const c_char,
-    /// Dynamic section of the shared object.
-    l_ld: usize,
-    l_next: *mut LinkMap,
-    l_prev: *mut LinkMap,
-}
-
 impl LinkMap {
     fn new() -> *mut Self {
         let map = Box::new(LinkMap {
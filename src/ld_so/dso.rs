@@ -4,38 +4,32 @@
 
 use object::{
     elf,
-    read::elf::{
-        Dyn as _, FileHeader as _, GnuHashTable, HashTable as SysVHashTable, ProgramHeader as _,
-        Rel as _, Rela as _, SectionTable, Sym as _, SymbolTable, Version, VersionTable,
-    },
-    NativeEndian, Object, SectionIndex, StringTable, SymbolIndex,
+    read::elf::{Dyn as _, FileHeader as _, ProgramHeader as _, Rel as _, Rela as _, Sym as _},
+    NativeEndian, Object, StringTable, SymbolIndex,
 };
 
 use super::{
     debug::{RTLDDebug, _r_debug},
+    gnu_hash::{GnuHash, SysvHash},
     linker::{Resolve, Scope, Symbol, __plt_resolve_trampoline, GLOBAL_SCOPE},
     tcb::Master,
+    versioning::{VersionData, VersionReq},
 };
 use crate::{
     header::sys_mman,
     platform::{types::c_void, Pal, Sys},
 };
 use alloc::{
-    collections::BTreeMap,
     string::{String, ToString},
     sync::Arc,
     vec::Vec,
 };
-#[cfg(target_pointer_width = "32")]
-type match_elf = elf::FileHeader32<NativeEndian>;
-#[cfg(target_pointer_width = "64")]
-type match_elf = elf::FileHeader64<NativeEndian>;
-
 use core::{
     ffi::c_char,
     mem::size_of,
     ptr::{self, NonNull},
     slice,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
 pub const CHAR_BITS: usize = size_of::<c_char>() * 8;
@@ -68,51 +62,28 @@ mod shim {
 pub use shim::*;
 
 enum HashTable<'a> {
-    Gnu(
-        GnuHashTable<'a, FileHeader>,
-        SymbolTable<'a, FileHeader, &'a [u8]>,
-    ),
-    Sysv(
-        SysVHashTable<'a, FileHeader>,
-        SymbolTable<'a, FileHeader, &'a [u8]>,
-    ),
+    Gnu(GnuHash<'a>),
+    Sysv(SysvHash<'a>),
 }
 
 impl<'a> HashTable<'a> {
-    /// Use the hash table to find the symbol table entry with the given name, hash, and version.
+    /// Use the hash table to find the index of the first symbol named `name`
+    /// for which `matches` holds.
     #[inline]
-    pub fn find(
-        &self,
-        name: &str,
-        version: Option<&Version<'_>>,
-        versions: &VersionTable<'a, FileHeader>,
-    ) -> Option<(SymbolIndex, &'a Sym)> {
+    fn find(&self, name: &str, matches: impl FnMut(usize) -> bool) -> Option<SymbolIndex> {
         let name = name.as_bytes();
 
         match self {
-            Self::Gnu(hash_table, symbols) => {
-                let hash = elf::gnu_hash(name);
-                hash_table
-                    .find(NativeEndian, name, hash, version, symbols, versions)
-                    .map(|(idx, sym)| (SymbolIndex(idx), sym))
-            }
-
-            Self::Sysv(hash_table, symbols) => {
-                let hash = elf::hash(name);
-                hash_table
-                    .find(NativeEndian, name, hash, version, symbols, versions)
-                    .map(|(idx, sym)| (SymbolIndex(idx), sym))
-            }
+            Self::Gnu(hash_table) => hash_table.find(elf::gnu_hash(name), matches),
+            Self::Sysv(hash_table) => hash_table.find(elf::hash(name), matches),
         }
+        .map(SymbolIndex)
     }
 
     fn symbol_table_length(&self) -> usize {
         match self {
-            Self::Gnu(hash_table, _) => hash_table
-                .symbol_table_length(NativeEndian)
-                .expect("empty GNU symbol hash table")
-                as usize,
-            Self::Sysv(hash_table, _) => hash_table.symbol_table_length() as usize,
+            Self::Gnu(hash_table) => hash_table.symbol_table_length(),
+            Self::Sysv(hash_table) => hash_table.symbol_table_length(),
         }
     }
 }
@@ -134,6 +105,7 @@ pub(super) struct Dynamic<'data> {
     relr: &'data [Relr],
     rel: &'data [Rel],
     symbols: &'data [Sym],
+    versions: VersionData<'data>,
     explicit_addend: bool,
     pltrelsz: usize,
 }
@@ -151,6 +123,25 @@ impl<'data> Dynamic<'data> {
         Some(core::str::from_utf8(name).expect("non UTF-8 ELF symbol name"))
     }
 
+    /// Finds the definition of `name` that has `version`, or else the
+    /// default one.
+    fn find_symbol(&self, name: &str, version: Option<&VersionReq>) -> Option<&'data Sym> {
+        let index = self.hash_table.find(name, |index| {
+            let Some(sym) = self.symbols.get(index) else {
+                return false;
+            };
+            sym.st_shndx(NativeEndian) != elf::SHN_UNDEF
+                && sym.name(NativeEndian, self.dynstrtab) == Ok(name.as_bytes())
+                && self.versions.check(index, version)
+        })?;
+        self.symbol(index)
+    }
+
+    /// The version that the reference to the symbol at `index` requires.
+    fn required_version(&self, index: SymbolIndex) -> Option<VersionReq<'data>> {
+        self.versions.required(index.0)
+    }
+
     fn static_relocations(&self) -> impl Iterator<Item = Relocation> + '_ {
         self.rela
             .iter()
@@ -325,11 +316,18 @@ pub struct DSO {
     pub entry_point: usize,
     /// Loaded library in-memory data
     pub mmap: &'static [u8],
+    pub program_headers: Vec<ProgramHeader>,
+    /// Address of the program headers within the loaded image, if a segment maps them.
+    pub phdr_addr: Option<usize>,
     pub tls_module_id: usize,
     pub tls_offset: usize,
+    /// Look symbols up in the scope of the object before the global scope,
+    /// as with `RTLD_DEEPBIND`.
+    pub deepbind: bool,
+    /// Never unload the object, as with `RTLD_NODELETE`.
+    pub nodelete: AtomicBool,
 
     pub(super) dynamic: Dynamic<'static>,
-    symbol_map: BTreeMap<String, SymbolIndex>,
 
     pub scope: spin::Once<Scope>,
     /// Position Independent Executable.
@@ -342,6 +340,7 @@ impl DSO {
         data: &'a [u8],
         base_addr: Option<usize>,
         dlopened: bool,
+        deepbind: bool,
         id: usize,
         tls_module_id: usize,
         tls_offset: usize,
@@ -364,12 +363,19 @@ impl DSO {
             elf.entry() as usize
         };
 
-        let mut symbol_map = BTreeMap::new();
-        for (i, sym) in dynamic.symbols.iter().enumerate() {
-            if let Some(name) = dynamic.symbol_name(SymbolIndex(i)) {
-                symbol_map.insert(name.to_string(), SymbolIndex(i));
+        let ph_off = elf.raw_header().e_phoff(elf.endian()) as usize;
+        let program_headers = {
+            let ph_num = elf.raw_header().e_phnum(elf.endian());
+            let ph_ptr = unsafe { data.as_ptr().add(ph_off) as *const ProgramHeader };
+            unsafe { slice::from_raw_parts(ph_ptr, ph_num as usize).to_vec() }
+        };
+        let phdr_addr = Self::find_phdr(&program_headers, ph_off).map(|vaddr| {
+            if is_pie_enabled(&elf) {
+                mmap.as_ptr() as usize + vaddr
+            } else {
+                vaddr
             }
-        }
+        });
 
         let dso = DSO {
             name,
@@ -377,25 +383,23 @@ impl DSO {
             dlopened,
             entry_point,
             mmap,
+            program_headers: program_headers.clone(),
+            phdr_addr,
             tls_module_id: if tcb_master.is_some() {
                 tls_module_id
             } else {
                 0
             },
             tls_offset,
+            deepbind,
+            nodelete: AtomicBool::new(false),
 
             pie: is_pie_enabled(&elf),
             dynamic,
-            symbol_map,
             scope: spin::Once::new(),
         };
 
-        Ok((dso, tcb_master, {
-            let ph_num = elf.raw_header().e_phnum(elf.endian());
-            let ph_off = elf.raw_header().e_phoff(elf.endian());
-            let ph_ptr = unsafe { data.as_ptr().add(ph_off as usize) as *const ProgramHeader };
-            unsafe { slice::from_raw_parts(ph_ptr, ph_num as usize).to_vec() }
-        }))
+        Ok((dso, tcb_master, program_headers))
     }
 
     #[inline]
@@ -419,13 +423,23 @@ impl DSO {
         &self.dynamic.needed
     }
 
-    pub fn get_sym<'a>(&self, name: &'a str) -> Option<(Symbol<'a>, SymbolBinding)> {
-        let sym_idx = self.symbol_map.get(name)?;
-        let sym = self.dynamic.symbol(*sym_idx)?;
-
-        if sym.st_shndx(NativeEndian) == elf::SHN_UNDEF {
-            return None;
+    /// The scopes to look the symbols of the object up in, in order.
+    pub fn lookup_scopes<'a>(&'a self, global_scope: &'a Scope) -> [&'a Scope; 2] {
+        if self.deepbind {
+            [self.scope(), global_scope]
+        } else {
+            [global_scope, self.scope()]
         }
+    }
+
+    /// Finds the definition of `name`, of the given `version` if there is
+    /// one.
+    pub fn get_sym<'a>(
+        &self,
+        name: &'a str,
+        version: Option<&VersionReq>,
+    ) -> Option<(Symbol<'a>, SymbolBinding)> {
+        let sym = self.dynamic.find_symbol(name, version)?;
 
         Some((
             Symbol {
@@ -448,6 +462,87 @@ impl DSO {
         ))
     }
 
+    /// Finds the virtual address the program headers at file offset `ph_off` are loaded at,
+    /// either from `PT_PHDR` or from the `PT_LOAD` segment covering them.
+    fn find_phdr(program_headers: &[ProgramHeader], ph_off: usize) -> Option<usize> {
+        if let Some(ph) = program_headers
+            .iter()
+            .find(|ph| ph.p_type(NativeEndian) == elf::PT_PHDR)
+        {
+            return Some(ph.p_vaddr(NativeEndian) as usize);
+        }
+
+        let ph_end = ph_off + program_headers.len() * size_of::<ProgramHeader>();
+        program_headers
+            .iter()
+            .filter(|ph| ph.p_type(NativeEndian) == elf::PT_LOAD)
+            .find_map(|ph| {
+                let offset = ph.p_offset(NativeEndian) as usize;
+                let end = offset + ph.p_filesz(NativeEndian) as usize;
+                (offset <= ph_off && ph_end <= end)
+                    .then(|| ph.p_vaddr(NativeEndian) as usize + (ph_off - offset))
+            })
+    }
+
+    /// Returns whether `addr` lies within the memory mapped for this object.
+    pub fn contains(&self, addr: usize) -> bool {
+        let start = self.mmap.as_ptr() as usize;
+        (start..start + self.mmap.len()).contains(&addr)
+    }
+
+    /// Finds the defined symbol whose extent contains `addr`.
+    ///
+    /// Symbols without a size only match their exact address, so addresses in padding, PLT stubs
+    /// or other gaps between symbols are not attributed to a neighbour.
+    pub fn symbol_at(&self, addr: usize) -> Option<(Symbol<'static>, &'static Sym)> {
+        let base = if self.pie {
+            self.mmap.as_ptr() as usize
+        } else {
+            0
+        };
+        let offset = addr.checked_sub(base)?;
+        let symbols: &'static [Sym] = self.dynamic.symbols;
+
+        let mut nearest: Option<(SymbolIndex, &'static Sym)> = None;
+        // Symbol table entry for index 0 is reserved.
+        for (i, sym) in symbols.iter().enumerate().skip(1) {
+            let value = sym.st_value(NativeEndian) as usize;
+            let defined = !matches!(sym.st_shndx(NativeEndian), elf::SHN_UNDEF | elf::SHN_ABS);
+            let addressable = !matches!(
+                sym.st_type(),
+                elf::STT_TLS | elf::STT_SECTION | elf::STT_FILE
+            );
+
+            let size = sym.st_size(NativeEndian) as usize;
+            let covers = if size == 0 {
+                offset == value
+            } else {
+                (value..value.saturating_add(size)).contains(&offset)
+            };
+
+            if !defined || !addressable || !covers {
+                continue;
+            }
+            if nearest.map_or(true, |(_, best)| {
+                value > best.st_value(NativeEndian) as usize
+            }) {
+                nearest = Some((SymbolIndex(i), sym));
+            }
+        }
+
+        let (index, sym) = nearest?;
+        Some((
+            Symbol {
+                name: self.dynamic.symbol_name(index)?,
+                base,
+                value: sym.st_value(NativeEndian) as usize,
+                size: sym.st_size(NativeEndian) as usize,
+                sym_type: sym.st_type(),
+            },
+            sym,
+        ))
+    }
+
     pub fn run_init(&self) {
         for f in self.dynamic.init_array {
             unsafe { f() }
@@ -687,6 +782,9 @@ impl DSO {
         let (mut fini_array_ptr, mut fini_array_len) = (None, None);
         let mut fini = None;
         let (mut rela_offset, mut rela_len) = (None, None);
+        let mut versym_ptr = None;
+        let (mut verdef_ptr, mut verdef_num) = (ptr::null(), 0);
+        let (mut verneed_ptr, mut verneed_num) = (ptr::null(), 0);
 
         for (i, entry) in entries.iter().enumerate() {
             let val = entry.d_val(NativeEndian);
@@ -697,21 +795,12 @@ impl DSO {
             match tag {
                 elf::DT_DEBUG => debug = Some(i),
 
-                // {Gnu,SysV}HashTable::parse()
-                //
-                // > The header does not contain a length field, and so all of
-                // > `data` will be used as the hash table values. It does not
-                // > matter if this is longer than needed...
-                elf::DT_GNU_HASH => {
-                    let value = GnuHashTable::parse(NativeEndian, &mmap[relative_idx..])?;
-                    gnu_hash = Some(value);
-                }
+                elf::DT_GNU_HASH => gnu_hash = Some(unsafe { GnuHash::new(ptr.cast()) }),
 
                 // XXX: Both GNU_HASH and HASH may be present, we give priority
                 // to GNU_HASH as it is significantly faster.
                 elf::DT_HASH if gnu_hash.is_none() => {
-                    let value = SysVHashTable::parse(NativeEndian, &mmap[relative_idx..])?;
-                    sysv_hash = Some(value);
+                    sysv_hash = Some(unsafe { SysvHash::new(ptr.cast()) })
                 }
 
                 elf::DT_PLTGOT => {
@@ -762,6 +851,12 @@ impl DSO {
 
                 elf::DT_FINI if val != 0 => fini = Some(unsafe { core::mem::transmute(ptr) }),
 
+                elf::DT_VERSYM => versym_ptr = Some(ptr.cast::<u16>()),
+                elf::DT_VERDEF => verdef_ptr = ptr.cast(),
+                elf::DT_VERDEFNUM => verdef_num = val as usize,
+                elf::DT_VERNEED => verneed_ptr = ptr.cast(),
+                elf::DT_VERNEEDNUM => verneed_num = val as usize,
+
                 elf::DT_SYMTAB => symtab_ptr = Some(ptr as *const Sym),
                 elf::DT_SYMENT => {
                     assert_eq!(val as usize, size_of::<Sym>());
@@ -812,66 +907,10 @@ impl DSO {
 
         let jmprel = jmprel.unwrap_or_default();
 
-        // Estimate symbol table length.
-        // We need to construct SymbolTable to pass to HashTable, but SymbolTable needs size.
-        // We'll use a safe upper bound provided by the file size or section headers if available,
-        // but here we are in a segment iterator.
-        // For now, let's use a dummy length if we can't determine it, or trust `sysv_hash.nchain` if available.
-        // `GnuHashTable` is harder.
-
-        let nchain = if let Some(sysv) = &sysv_hash {
-            unsafe {
-                let ptr = (sysv as *const _ as *const u32).add(1);
-                *ptr as usize
-            }
-        } else {
-            0
-        };
-
-        let symtab_len = if nchain > 0 {
-            nchain
-        } else {
-            // For GNU hash, we can't easily know total symbols without walking it?
-            // Actually, the chain is implicit.
-            // Let's assume the symtab entry size (DT_SYMENT) allows us to calculate max symbols from file end?
-            // `symtab_ptr` is known.
-            if let Some(ptr) = symtab_ptr {
-                let start = ptr as usize;
-                let end = mmap.as_ptr() as usize + mmap.len();
-                (end - start) / size_of::<Sym>()
-            } else {
-                0
-            }
-        };
-
-        let symbols_slice = unsafe { get_array(symtab_ptr, Some(symtab_len)) };
-        // We need 'static lifetime for now? No, 'a.
-        // But `parse_dynamic` returns `Dynamic<'a>`.
-        // `symbols` field in `Dynamic` is `&'a [Sym]`.
-
-        // Wait, `SymbolTable` holds `&'a [Sym]`.
-        let symbols_data = unsafe {
-            slice::from_raw_parts(
-                symbols_slice.as_ptr() as *const u8,
-                size_of_val(symbols_slice),
-            )
-        };
-        let dummy_sections: SectionTable<match_elf> = unsafe { core::mem::zeroed() };
-        let dummy_section: <match_elf as object::read::elf::FileHeader>::SectionHeader =
-            unsafe { core::mem::zeroed() };
-        let symtab = SymbolTable::parse(
-            NativeEndian,
-            symbols_data,
-            &dummy_sections,
-            SectionIndex(0),
-            &dummy_section,
-        )
-        .unwrap();
-
         let hash_table = if let Some(gnu) = gnu_hash {
-            HashTable::Gnu(gnu, symtab)
+            HashTable::Gnu(gnu)
         } else if let Some(sysv) = sysv_hash {
-            HashTable::Sysv(sysv, symtab)
+            HashTable::Sysv(sysv)
         } else {
             panic!("either DT_GNU_HASH and/or DT_HASH must be present");
         };
@@ -882,9 +921,22 @@ impl DSO {
         let relr = unsafe { get_array(relr_ptr, relr_len) };
         let rel = unsafe { get_array(rel_ptr, rel_len) };
 
+        let symbol_count = hash_table.symbol_table_length();
+        // One version index for each symbol
+        let versym_len = versym_ptr.map(|_| symbol_count);
+        let versions = VersionData {
+            versym: unsafe { get_array(versym_ptr, versym_len) },
+            verneed: verneed_ptr,
+            verneed_num,
+            verdef: verdef_ptr,
+            verdef_num,
+            str_tab: &mmap[strtab_offset..strtab_offset + strtab_size as usize],
+        };
+
         Ok((
             Dynamic {
-                symbols: unsafe { get_array(symtab_ptr, Some(hash_table.symbol_table_length())) },
+                symbols: unsafe { get_array(symtab_ptr, Some(symbol_count)) },
+                versions,
                 runpath,
                 got,
                 needed,
@@ -910,14 +962,15 @@ impl DSO {
 
         let (sym, my_sym) = if reloc.sym.0 > 0 {
             let name = self.dynamic.symbol_name(reloc.sym).unwrap();
+            let version = self.dynamic.required_version(reloc.sym);
 
-            let lookup_scopes = [global_scope, self.scope()];
+            let lookup_scopes = self.lookup_scopes(global_scope);
             let sym = if matches!(reloc.kind, RelocationKind::COPY) {
                 lookup_scopes
                     .iter()
-                    .find_map(|scope| scope._get_sym(name, 1))
+                    .find_map(|scope| scope._get_sym(name, version.as_ref(), 1))
             } else {
-                resolve_sym(name, &lookup_scopes)
+                resolve_sym(name, version.as_ref(), &lookup_scopes)
             }
             .map(|(sym, _, obj)| (sym, obj));
 
@@ -928,7 +981,7 @@ impl DSO {
 
         let (s, t, tls_id) = sym
             .as_ref()
-            .map(|(sym, obj)| (sym.as_ptr() as usize, obj.tls_offset, obj.tls_module_id))
+            .map(|(sym, obj)| (sym.resolve() as usize, obj.tls_offset, obj.tls_module_id))
             //TODO: is self.tls_module_id the right fallback?
             .unwrap_or((0, 0, self.tls_module_id));
 
@@ -1013,9 +1066,17 @@ impl DSO {
         let jmprel = self.dynamic.jmprel;
         let pltrelsz = self.dynamic.pltrelsz;
 
+        // Where the PLT header looks for the object and the resolver.
+        let (obj_idx, resolver_idx) = if cfg!(target_arch = "riscv64") {
+            (1, 0)
+        } else {
+            (1, 2)
+        };
+
         unsafe {
-            got.add(1).write(core::ptr::addr_of!(*self) as usize);
-            got.add(2).write(__plt_resolve_trampoline as usize);
+            got.add(obj_idx).write(core::ptr::addr_of!(*self) as usize);
+            got.add(resolver_idx)
+                .write(__plt_resolve_trampoline as usize);
         }
 
         let relsz = if self.dynamic.explicit_addend {
@@ -1048,15 +1109,17 @@ impl DSO {
 
                 (RelocationKind::PLT, Resolve::Now) => {
                     let name = self.dynamic.symbol_name(reloc.sym).unwrap();
-
-                    let resolved = resolve_sym(name, &[global_scope, self.scope()])
-                        .map(|(sym, _, _)| sym.as_ptr() as usize)
-                        .unwrap_or_else(|| {
-                            panic!(
-                                "unresolved symbol: {name} for soname {:?}",
-                                self.dynamic.soname
-                            )
-                        });
+                    let version = self.dynamic.required_version(reloc.sym);
+
+                    let resolved =
+                        resolve_sym(name, version.as_ref(), &self.lookup_scopes(global_scope))
+                            .map(|(sym, _, _)| sym.resolve() as usize)
+                            .unwrap_or_else(|| {
+                                panic!(
+                                    "unresolved symbol: {name} for soname {:?}",
+                                    self.dynamic.soname
+                                )
+                            });
 
                     unsafe {
                         *ptr = resolved + reloc.addend.unwrap_or(0);
@@ -1076,6 +1139,47 @@ impl DSO {
         Ok(())
     }
 
+    /// Resolves the symbol of the `index`th relocation in the PLT, on the
+    /// first call through it, and returns its address.
+    pub(super) fn resolve_plt(&self, index: usize) -> *mut c_void {
+        let reloc: Relocation = if self.dynamic.explicit_addend {
+            unsafe { &*(self.dynamic.jmprel as *const Rela).add(index) }.into()
+        } else {
+            unsafe { &*(self.dynamic.jmprel as *const Rel).add(index) }.into()
+        };
+        assert!(
+            matches!(reloc.kind, RelocationKind::PLT),
+            "lazy relocation type {:?}",
+            reloc.kind
+        );
+
+        let name = self
+            .dynamic
+            .symbol_name(reloc.sym)
+            .expect("symbol not found");
+        let version = self.dynamic.required_version(reloc.sym);
+        let resolved = resolve_sym(
+            name,
+            version.as_ref(),
+            &self.lookup_scopes(&GLOBAL_SCOPE.read()),
+        )
+        .map(|(sym, _, _)| sym.resolve() as usize + reloc.addend.unwrap_or(0))
+        .unwrap_or_else(|| panic!("symbol '{name}' not found"));
+
+        let ptr = if self.pie {
+            (self.mmap.as_ptr() as usize + reloc.offset) as *const AtomicUsize
+        } else {
+            reloc.offset as *const AtomicUsize
+        };
+
+        trace!("@plt: {} -> *mut {:p}", name, ptr);
+
+        // Threads making the same first call at once all store the same
+        // address.
+        unsafe { (*ptr).store(resolved, Ordering::Release) };
+        resolved as *mut c_void
+    }
+
     pub fn relocate(&self, ph: &[ProgramHeader], resolve: Resolve) -> object::Result<()> {
         let global_scope = GLOBAL_SCOPE.read();
         let base = self.mmap.as_ptr();
@@ -1168,7 +1272,7 @@ fn basename(path: &str) -> String {
     path.split("/").last().unwrap_or(path).to_string()
 }
 
-fn dirname(path: &str) -> String {
+pub(super) fn dirname(path: &str) -> String {
     let mut parts: Vec<&str> = path.split("/").collect();
     parts.truncate(parts.len() - 1);
     parts.join("/")
@@ -1176,7 +1280,8 @@ fn dirname(path: &str) -> String {
 
 pub fn resolve_sym<'a>(
     name: &'a str,
+    version: Option<&VersionReq>,
     scopes: &[&'a Scope],
 ) -> Option<(Symbol<'a>, SymbolBinding, Arc<DSO>)> {
-    scopes.iter().find_map(|scope| scope.get_sym(name))
+    scopes.iter().find_map(|scope| scope.get_sym(name, version))
 }
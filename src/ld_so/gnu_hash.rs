@@ -1,137 +1,155 @@
-// src/ld_so/gnu_hash.rs
-//! Implementation of the GNU Hash mechanism for rapid symbol lookup.
-//! This allows loading binaries compiled with --hash-style=gnu.
-
-#![no_std]
+//! Hash tables of dynamic symbols.
+//!
+//! `DT_GNU_HASH` is what binaries linked with `--hash-style=gnu` have, and
+//! `DT_HASH` is looked at only for the objects without one. See
+//! <https://flapenguin.me/elf-dt-gnu-hash>.
 
 use core::slice;
 
-/// The GNU Hash header structure.
-#[repr(C)]
-pub struct GnuHashHeader {
-    /// Number of bloom filter words.
-    pub nbuckets: u32,
-    /// Index of the first symbol in the dynamic symbol table accessible via this hash.
-    pub symndx: u32,
-    /// The number of words in the bloom filter.
-    pub maskwords: u32,
-    /// The shift count used in the bloom filter.
-    pub shift2: u32,
-}
-
+/// A `DT_GNU_HASH` table.
 pub struct GnuHash<'a> {
-    header: &'a GnuHashHeader,
+    /// Index of the first symbol in the dynamic symbol table accessible via
+    /// this hash.
+    symoffset: u32,
+    /// The shift count used in the bloom filter.
+    bloom_shift: u32,
     bloom: &'a [usize],
     buckets: &'a [u32],
-    chain: &'a [u32],
+    /// Hashes of the symbols from `symoffset` on, each with its lowest bit
+    /// set at the end of a chain.
+    chains: &'a [u32],
 }
 
 impl<'a> GnuHash<'a> {
-    /// Parse the DT_GNU_HASH section.
+    /// Parses the table at `ptr`.
     ///
     /// # Safety
-    /// `ptr` must point to a valid DT_GNU_HASH section in memory.
+    /// `ptr` must point to a valid `DT_GNU_HASH` table in memory.
     pub unsafe fn new(ptr: *const u32) -> Self {
-        let header = &*(ptr as *const GnuHashHeader);
-        
-        // Bloom filter follows immediately after header
-        let bloom_ptr = ptr.add(4) as *const usize;
-        let bloom = slice::from_raw_parts(bloom_ptr, header.maskwords as usize);
-        
-        // Buckets follow bloom filter
-        let buckets_ptr = bloom_ptr.add(header.maskwords as usize) as *const u32;
-        let buckets = slice::from_raw_parts(buckets_ptr, header.nbuckets as usize);
-        
-        // Chain follows buckets. The length is indeterminate until we walk it, 
-        // but essentially extends to the end of the dynsym table.
-        // We create a slice purely for pointer arithmetic convenience, effectively unbounded safe access relies on symndx.
-        let chain_ptr = buckets_ptr.add(header.nbuckets as usize);
-        let chain = slice::from_raw_parts(chain_ptr, 0xFFFFFF); // Arbitrary large limit
-
-        Self {
-            header,
-            bloom,
-            buckets,
-            chain,
+        unsafe {
+            let nbuckets = *ptr as usize;
+            let symoffset = *ptr.add(1);
+            let bloom_size = *ptr.add(2) as usize;
+            let bloom_shift = *ptr.add(3);
+
+            // Bloom filter follows immediately after header
+            let bloom_ptr = ptr.add(4).cast::<usize>();
+            let bloom = slice::from_raw_parts(bloom_ptr, bloom_size);
+
+            // Buckets follow bloom filter
+            let buckets_ptr = bloom_ptr.add(bloom_size).cast::<u32>();
+            let buckets = slice::from_raw_parts(buckets_ptr, nbuckets);
+
+            // Chains follow buckets, up to the end of the one of the last
+            // bucket.
+            let chains_ptr = buckets_ptr.add(nbuckets);
+            let chains_len = match buckets.iter().max() {
+                Some(&last) if last >= symoffset => {
+                    let mut i = (last - symoffset) as usize;
+                    while *chains_ptr.add(i) & 1 == 0 {
+                        i += 1;
+                    }
+                    i + 1
+                }
+                _ => 0,
+            };
+            let chains = slice::from_raw_parts(chains_ptr, chains_len);
+
+            Self {
+                symoffset,
+                bloom_shift,
+                bloom,
+                buckets,
+                chains,
+            }
         }
     }
 
-    /// Standard GNU hash function.
-    pub fn hash(name: &str) -> u32 {
-        let mut h: u32 = 5381;
-        for b in name.bytes() {
-            h = (h << 5).wrapping_add(h).wrapping_add(b as u32);
-        }
-        h
+    /// Number of entries in the dynamic symbol table.
+    pub fn symbol_table_length(&self) -> usize {
+        self.symoffset as usize + self.chains.len()
     }
 
-    /// Lookup a symbol index. Returns the index in the dynamic symbol table if found.
-    pub fn lookup(&self, name: &str, hash: u32, sym_table: &[crate::header::elf::Sym], str_tab: &[u8]) -> Option<u32> {
-        // 1. Bloom Filter Check
-        let maskwords = self.header.maskwords as usize;
-        if maskwords == 0 {
+    /// Finds the index of the first symbol with the GNU hash `hash` for
+    /// which `matches` holds.
+    pub fn find(&self, hash: u32, mut matches: impl FnMut(usize) -> bool) -> Option<usize> {
+        if self.bloom.is_empty() || self.buckets.is_empty() {
             return None;
         }
-        
-        let word = self.bloom[(hash as usize / (8 * core::mem::size_of::<usize>())) % maskwords];
-        let mask = (1usize << (hash % (8 * core::mem::size_of::<usize>() as u32))) 
-                 | (1usize << ((hash >> self.header.shift2) % (8 * core::mem::size_of::<usize>() as u32)));
 
-        if (word & mask) != mask {
-            return None; // Definitely not present
+        // Bloom filter check
+        let bits = usize::BITS;
+        let word = self.bloom[(hash / bits) as usize % self.bloom.len()];
+        let mask = (1usize << (hash % bits)) | (1usize << ((hash >> self.bloom_shift) % bits));
+        if word & mask != mask {
+            // Definitely not present
+            return None;
         }
 
-        // 2. Bucket Lookup
-        let bucket = self.buckets[(hash % self.header.nbuckets) as usize];
-        if bucket < self.header.symndx {
+        let mut index = self.buckets[hash as usize % self.buckets.len()] as usize;
+        if index < self.symoffset as usize {
             return None;
         }
 
-        // 3. Chain Traversal
-        let chain_idx_base = bucket - self.header.symndx;
-        let mut i = 0;
-        
         loop {
-            let hash_entry = self.chain[(chain_idx_base + i) as usize];
-            let sym_idx = bucket + i;
-            
-            // Verify hash match (top 31 bits)
-            if (hash | 1) == (hash_entry | 1) {
-                // Verify string match
-                if let Some(sym) = sym_table.get(sym_idx as usize) {
-                    // String comparison logic (helper function assumed to exist in dso/lib)
-                    // In a real impl, we'd check symbol versioning here too.
-                    if symbol_matches(sym, name, str_tab) {
-                        return Some(sym_idx);
-                    }
-                }
+            let chain_hash = *self.chains.get(index - self.symoffset as usize)?;
+            // Hash match in the top 31 bits
+            if chain_hash | 1 == hash | 1 && matches(index) {
+                return Some(index);
             }
-
             // LSB indicates end of chain for this bucket
-            if (hash_entry & 1) != 0 {
-                break;
+            if chain_hash & 1 != 0 {
+                return None;
             }
-            i += 1;
+            index += 1;
         }
-        
-        None
     }
 }
 
-fn symbol_matches(sym: &crate::header::elf::Sym, target: &str, str_tab: &[u8]) -> bool {
-    if sym.st_name == 0 { return false; }
-    let start = sym.st_name as usize;
-    if start >= str_tab.len() { return false; }
-    
-    // Safe string comparison
-    let mut sym_name = &str_tab[start..];
-    // Truncate at null byte
-    if let Some(end) = sym_name.iter().position(|&c| c == 0) {
-        sym_name = &sym_name[..end];
+/// A `DT_HASH` table.
+pub struct SysvHash<'a> {
+    buckets: &'a [u32],
+    chains: &'a [u32],
+}
+
+impl<'a> SysvHash<'a> {
+    /// Parses the table at `ptr`.
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid `DT_HASH` table in memory.
+    pub unsafe fn new(ptr: *const u32) -> Self {
+        unsafe {
+            let nbuckets = *ptr as usize;
+            let nchains = *ptr.add(1) as usize;
+            let buckets = slice::from_raw_parts(ptr.add(2), nbuckets);
+            let chains = slice::from_raw_parts(ptr.add(2 + nbuckets), nchains);
+            Self { buckets, chains }
+        }
     }
-    
-    if let Ok(s) = core::str::from_utf8(sym_name) {
-        return s == target;
+
+    /// Number of entries in the dynamic symbol table.
+    pub fn symbol_table_length(&self) -> usize {
+        self.chains.len()
     }
-    false
-}
\ No newline at end of file
+
+    /// Finds the index of the first symbol with the SysV hash `hash` for
+    /// which `matches` holds.
+    pub fn find(&self, hash: u32, mut matches: impl FnMut(usize) -> bool) -> Option<usize> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+
+        let mut index = self.buckets[hash as usize % self.buckets.len()] as usize;
+        // Bounded, in case of a loop in the chain
+        for _ in 0..self.chains.len() {
+            if index == 0 {
+                break;
+            }
+            if matches(index) {
+                return Some(index);
+            }
+            index = *self.chains.get(index)? as usize;
+        }
+        None
+    }
+}
@@ -5,15 +5,13 @@ use alloc::{
     sync::{Arc, Weak},
     vec::Vec,
 };
-use object::{
-    elf,
-    read::elf::{Rela as _, Sym},
-    NativeEndian, SymbolIndex,
-};
+use object::elf;
 
 use core::{
     cell::RefCell,
+    mem,
     ptr::{self, NonNull},
+    sync::atomic::Ordering,
 };
 
 use crate::{
@@ -24,10 +22,10 @@ use crate::{
         fcntl, sys_mman,
         unistd::F_OK,
     },
-    ld_so::dso::{resolve_sym, SymbolBinding},
+    ld_so::dso::SymbolBinding,
     out::Out,
     platform::{
-        types::{c_int, c_uint, c_void},
+        types::{c_char, c_int, c_void},
         Pal, Sys,
     },
     sync::rwlock::RwLock,
@@ -37,8 +35,10 @@ use super::{
     access::accessible,
     callbacks::LinkerCallbacks,
     debug::{RTLDState, _dl_debug_state, _r_debug},
-    dso::{ProgramHeader, Rela, DSO},
+    dso::{dirname, ProgramHeader, DSO},
+    linux_parity::resolve_ifunc,
     tcb::{Master, Tcb},
+    versioning::VersionReq,
     PATH_SEP,
 };
 
@@ -133,16 +133,25 @@ impl Symbol<'_> {
     pub fn as_ptr(&self) -> *mut c_void {
         (self.base + self.value) as *mut c_void
     }
+
+    /// The address that references to the symbol bind to, which for
+    /// `STT_GNU_IFUNC` symbols is the one that their resolver returns.
+    pub fn resolve(&self) -> *mut c_void {
+        if self.sym_type == elf::STT_GNU_IFUNC {
+            unsafe { resolve_ifunc(self.as_ptr() as usize) as *mut c_void }
+        } else {
+            self.as_ptr()
+        }
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub enum Resolve {
     /// Resolve all undefined symbols immediately.
-    #[cfg_attr(not(target_arch = "x86_64"), default)]
     Now,
     /// Perform lazy binding (i.e. symbols will be resolved when they are first
     /// used).
-    #[cfg_attr(target_arch = "x86_64", default)]
+    #[default]
     Lazy,
 }
 
@@ -215,19 +224,21 @@ impl Scope {
     pub(super) fn get_sym<'a>(
         &self,
         name: &'a str,
+        version: Option<&VersionReq>,
     ) -> Option<(Symbol<'a>, SymbolBinding, Arc<DSO>)> {
-        self._get_sym(name, 0)
+        self._get_sym(name, version, 0)
     }
 
     pub(super) fn _get_sym<'a>(
         &self,
         name: &'a str,
+        version: Option<&VersionReq>,
         skip: usize,
     ) -> Option<(Symbol<'a>, SymbolBinding, Arc<DSO>)> {
         let mut res = None;
 
         let get_sym = |obj: Arc<DSO>| {
-            if let Some((sym, binding)) = obj.get_sym(name) {
+            if let Some((sym, binding)) = obj.get_sym(name, version) {
                 if binding.is_global() {
                     return Some((sym, binding, obj.clone()));
                 }
@@ -331,6 +342,64 @@ impl AsRef<DSO> for ObjectHandle {
     }
 }
 
+// Used by dladdr() and dladdr1()
+pub struct AddrInfo {
+    /// Path of the object containing the address.
+    pub fname: *const c_char,
+    /// Address the object is mapped at.
+    pub fbase: *mut c_void,
+    /// Name of the symbol containing the address, or null if there is none.
+    pub sname: *const c_char,
+    /// Address of the symbol containing the address, or null if there is none.
+    pub saddr: *mut c_void,
+    /// The `ElfW(Sym)` entry of the symbol containing the address, or null if there is none.
+    pub sym: *const c_void,
+    /// The object's `struct link_map` entry in `_r_debug`.
+    pub link_map: *mut c_void,
+}
+
+// Used by dl_iterate_phdr()
+pub struct PhdrInfo {
+    /// Difference between the addresses in the ELF file and in memory.
+    pub addr: usize,
+    pub name: *const c_char,
+    /// Program headers of the object, as an `ElfW(Phdr)` array.
+    pub phdr: *const c_void,
+    pub phnum: usize,
+    /// Number of objects loaded so far.
+    pub adds: u64,
+    /// Number of objects unloaded so far.
+    pub subs: u64,
+    /// TLS module ID, or 0 if the object has no TLS segment.
+    pub tls_modid: usize,
+}
+
+// Used by dlinfo()
+pub struct ObjectInfo<'a> {
+    /// The object's `struct link_map` entry in `_r_debug`.
+    pub link_map: *mut c_void,
+    /// Directory of the object, which `$ORIGIN` expands to.
+    pub origin: &'a str,
+    /// TLS module ID, or 0 if the object has no TLS segment.
+    pub tls_modid: usize,
+    /// Directories searched for the dependencies of the object, in order.
+    pub search_path: &'a [&'a str],
+}
+
+bitflags::bitflags! {
+    /// `dlopen(3)` flags besides the binding mode and the scope.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct LoadFlags: u32 {
+        /// Do not load the object, only return it if it is already loaded.
+        const NOLOAD = 1 << 0;
+        /// Never unload the object.
+        const NODELETE = 1 << 1;
+        /// Prefer the symbols of the object and its dependencies to the
+        /// global ones.
+        const DEEPBIND = 1 << 2;
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Debug, Default)]
     pub struct DebugFlags: u32 {
@@ -394,6 +463,13 @@ pub struct Linker {
     objects: BTreeMap<usize, Arc<DSO>>,
     name_to_object_id_map: BTreeMap<String, usize>,
     library_cache: BTreeMap<String, String>,
+    /// Number of objects ever loaded and unloaded, for `dl_iterate_phdr`.
+    adds: u64,
+    subs: u64,
+    /// Number of `dl_iterate_phdr` calls handing out pointers into the objects, during which
+    /// unloading is deferred.
+    iterating: usize,
+    deferred_unloads: Vec<ObjectHandle>,
     pub cbs: Rc<RefCell<LinkerCallbacks>>,
 }
 
@@ -409,6 +485,10 @@ impl Linker {
             objects: BTreeMap::new(),
             name_to_object_id_map: BTreeMap::new(),
             library_cache: BTreeMap::new(),
+            adds: 0,
+            subs: 0,
+            iterating: 0,
+            deferred_unloads: Vec::new(),
             cbs: Rc::new(RefCell::new(LinkerCallbacks::new())),
         }
     }
@@ -419,6 +499,7 @@ impl Linker {
             &None,
             base_addr,
             false,
+            false,
             if self.config.bind_now {
                 Resolve::Now
             } else {
@@ -434,24 +515,20 @@ impl Linker {
         name: Option<&str>,
         resolve: Resolve,
         scope: ScopeKind,
-        noload: bool,
+        flags: LoadFlags,
     ) -> Result<ObjectHandle> {
         trace!(
-            "[ld.so] load_library(name={:?}, resolve={:#?}, scope={:#?}, noload={})",
+            "[ld.so] load_library(name={:?}, resolve={:#?}, scope={:#?}, flags={:?})",
             name,
             resolve,
             scope,
-            noload
+            flags
         );
 
-        if noload && resolve == Resolve::Now {
-            // Do not perform lazy binding anymore.
-            // * Check if loaded with Resolve::Now and if so, early return.
-            // * If not, resolve all symbols now.
-            todo!("resolve symbols now!");
-        }
-
-        match name {
+        // An object that is already loaded keeps its binding mode: the lazy
+        // relocations left in it are resolved as they are used, whatever
+        // `resolve` is.
+        let obj = match name {
             Some(name) => {
                 if let Some(id) = self.name_to_object_id_map.get(name) {
                     let obj = self.objects.get(id).unwrap();
@@ -470,43 +547,52 @@ impl Linker {
                         self.scope_debug();
                     }
 
-                    Ok(ObjectHandle::new(obj.clone()))
-                } else if !noload {
+                    obj.clone()
+                } else if !flags.contains(LoadFlags::NOLOAD) {
                     let parent_runpath = &self
                         .objects
                         .get(&ROOT_ID)
                         .and_then(|parent| parent.runpath().map(|path| path.to_string()));
 
-                    Ok(ObjectHandle::new(self.load_object(
+                    self.load_object(
                         name,
                         parent_runpath,
                         None,
                         true,
+                        flags.contains(LoadFlags::DEEPBIND),
                         if self.config.bind_now {
                             Resolve::Now
                         } else {
                             resolve
                         },
                         scope,
-                    )?))
+                    )?
                 } else {
-                    // FIXME: LoadError?
-                    // Err(Error::Malformed(format!(
-                    //     "object '{}' has not yet been loaded",
-                    //     name
-                    // )))
-                    Ok(ObjectHandle(ptr::null()))
+                    // Not an error, dlopen() returns NULL without setting one.
+                    return Ok(ObjectHandle(ptr::null()));
                 }
             }
 
             None => match self.objects.get(&ROOT_ID) {
-                Some(obj) => Ok(ObjectHandle::new(obj.clone())),
-                None => Err(DlError::NotFound),
+                Some(obj) => obj.clone(),
+                None => return Err(DlError::NotFound),
             },
+        };
+
+        if flags.contains(LoadFlags::NODELETE) {
+            obj.nodelete.store(true, Ordering::Relaxed);
         }
+
+        Ok(ObjectHandle::new(obj))
     }
 
-    pub fn get_sym(&self, handle: Option<ObjectHandle>, name: &str) -> Option<*mut c_void> {
+    pub fn get_sym(
+        &self,
+        handle: Option<ObjectHandle>,
+        name: &str,
+        version: Option<&str>,
+    ) -> Option<*mut c_void> {
+        let version = version.map(VersionReq::new);
         let guard;
 
         if let Some(handle) = handle.as_ref() {
@@ -515,10 +601,10 @@ impl Linker {
             guard = GLOBAL_SCOPE.read();
             &guard
         }
-        .get_sym(name)
+        .get_sym(name, version.as_ref())
         .map(|(symbol, _, obj)| {
             if symbol.sym_type != elf::STT_TLS {
-                symbol.as_ptr()
+                symbol.resolve()
             } else {
                 let mut tls_index = dl_tls_index {
                     ti_module: obj.tls_module_id,
@@ -530,9 +616,103 @@ impl Linker {
         })
     }
 
+    pub fn addr_info(&self, addr: usize) -> Option<AddrInfo> {
+        let obj = self.objects.values().find(|obj| obj.contains(addr))?;
+        let link_map = _r_debug.lock().find(obj.mmap.as_ptr() as usize);
+
+        let (sname, saddr, sym) = match obj.symbol_at(addr) {
+            Some((symbol, sym)) => (
+                symbol.name.as_ptr().cast::<c_char>(),
+                symbol.as_ptr(),
+                ptr::from_ref(sym).cast::<c_void>(),
+            ),
+            None => (ptr::null(), ptr::null_mut(), ptr::null()),
+        };
+
+        Some(AddrInfo {
+            fname: link_map.map_or(ptr::null(), |map| unsafe { (*map).l_name }),
+            fbase: obj.mmap.as_ptr().cast_mut().cast(),
+            sname,
+            saddr,
+            sym,
+            link_map: link_map.map_or(ptr::null_mut(), |map| map.cast()),
+        })
+    }
+
+    /// Describes every loaded object to `f`, which must be followed by a call to
+    /// `end_iterate_phdr` once the pointers handed out are no longer used.
+    pub fn iterate_phdr(&mut self, f: &mut dyn FnMut(PhdrInfo)) {
+        self.iterating += 1;
+
+        let debug = _r_debug.lock();
+        for obj in self.objects.values() {
+            let name = if obj.id == ROOT_ID {
+                // By convention, the main program is reported without a name.
+                c"".as_ptr()
+            } else {
+                debug
+                    .find(obj.mmap.as_ptr() as usize)
+                    .map_or(c"".as_ptr(), |map| unsafe { (*map).l_name })
+            };
+
+            f(PhdrInfo {
+                addr: if obj.pie {
+                    obj.mmap.as_ptr() as usize
+                } else {
+                    0
+                },
+                name,
+                phdr: obj
+                    .phdr_addr
+                    .map_or(obj.program_headers.as_ptr().cast(), |addr| addr as *const _),
+                phnum: obj.program_headers.len(),
+                adds: self.adds,
+                subs: self.subs,
+                tls_modid: obj.tls_module_id,
+            });
+        }
+    }
+
+    /// Unloads the objects closed while `dl_iterate_phdr` was running.
+    pub fn end_iterate_phdr(&mut self) {
+        self.iterating -= 1;
+        if self.iterating == 0 {
+            for handle in mem::take(&mut self.deferred_unloads) {
+                self.unload(handle);
+            }
+        }
+    }
+
+    pub fn object_info(&self, handle: &ObjectHandle, f: &mut dyn FnMut(&ObjectInfo)) {
+        let obj = handle.as_ref();
+        let link_map = _r_debug.lock().find(obj.mmap.as_ptr() as usize);
+        let path = link_map
+            .and_then(|map| unsafe { CStr::from_nullable_ptr((*map).l_name) })
+            .and_then(|name| name.to_str().ok())
+            .unwrap_or(&obj.name);
+        let origin = dirname(path);
+        let runpath = obj.runpath().map(|path| path.as_str());
+
+        f(&ObjectInfo {
+            link_map: link_map.map_or(ptr::null_mut(), |map| map.cast()),
+            origin: if origin.is_empty() {
+                "."
+            } else {
+                origin.as_str()
+            },
+            tls_modid: obj.tls_module_id,
+            search_path: &self.search_path(runpath),
+        });
+    }
+
     pub fn unload(&mut self, handle: ObjectHandle) {
+        if self.iterating != 0 {
+            self.deferred_unloads.push(handle);
+            return;
+        }
+
         let obj = handle.into_inner();
-        if !obj.dlopened {
+        if !obj.dlopened || obj.nodelete.load(Ordering::Relaxed) {
             return;
         }
 
@@ -556,6 +736,7 @@ impl Linker {
             }
 
             let _ = self.objects.remove(&obj.id).unwrap();
+            self.subs += 1;
             for dep in obj.dependencies() {
                 self.unload(ObjectHandle::new(
                     self.objects
@@ -584,16 +765,10 @@ impl Linker {
         runpath: &Option<String>,
         base_addr: Option<usize>,
         dlopened: bool,
+        deepbind: bool,
         resolve: Resolve,
         scope: ScopeKind,
     ) -> Result<Arc<DSO>> {
-        let resolve = if cfg!(target_arch = "x86_64") {
-            resolve
-        } else {
-            // Lazy binding is not currently supported on non-x86_64 architectures.
-            Resolve::Now
-        };
-
         _r_debug.lock().state = RTLDState::RT_ADD;
         _dl_debug_state();
 
@@ -605,13 +780,16 @@ impl Linker {
             runpath,
             base_addr,
             dlopened,
+            deepbind,
             &mut new_objects,
             &mut objects_data,
             &mut tcb_masters,
             scope,
         )?;
 
-        for (i, obj) in new_objects.iter().enumerate() {
+        // Dependencies first, for the IFUNC resolvers in them to be relocated
+        // by the time they are called.
+        for (i, obj) in new_objects.iter().enumerate().rev() {
             obj.relocate(&objects_data[i], resolve).unwrap();
         }
 
@@ -733,6 +911,7 @@ impl Linker {
     fn register_object(&mut self, obj: Arc<DSO>) {
         self.name_to_object_id_map.insert(obj.name.clone(), obj.id);
         self.objects.insert(obj.id, obj);
+        self.adds += 1;
     }
 
     fn load_objects_bfs<'a>(
@@ -741,6 +920,7 @@ impl Linker {
         parent_runpath: &Option<String>,
         base_addr: Option<usize>,
         dlopened: bool,
+        deepbind: bool,
         new_objects: &mut Vec<Arc<DSO>>,
         objects_data: &mut Vec<Vec<ProgramHeader>>,
         tcb_masters: &mut Vec<Master>,
@@ -766,6 +946,7 @@ impl Linker {
                 data,
                 base_addr,
                 dlopened,
+                deepbind,
                 self.next_object_id,
                 self.next_tls_module_id,
                 // Ensure TLS is aligned to 16 bytes for SSE
@@ -841,44 +1022,47 @@ impl Linker {
             eprintln!("[ld.so]: looking for '{}'", name);
         }
 
-        let mut full_path = name.to_string();
-        if accessible(&full_path, F_OK).is_ok() {
-            if debug {
-                eprintln!("[ld.so]: found at '{}'!", full_path);
-            }
-            self.library_cache
-                .insert(name.to_string(), full_path.clone());
-            return Ok(full_path);
+        let found = if accessible(name, F_OK).is_ok() {
+            Some(name.to_string())
         } else {
-            let mut search_paths = Vec::new();
-            if let Some(runpath) = parent_runpath {
-                search_paths.extend(runpath.split(PATH_SEP));
-            }
-            if let Some(ld_path) = self.config.library_path.as_ref() {
-                search_paths.extend(ld_path.split(PATH_SEP));
-            }
-            search_paths.push("/lib");
-            for part in search_paths.iter() {
-                full_path = format!("{}/{}", part, name);
-                if debug {
-                    eprintln!("[ld.so]: trying path '{}'", full_path);
-                }
-                if accessible(&full_path, F_OK).is_ok() {
+            self.search_path(parent_runpath.as_deref())
+                .iter()
+                .map(|part| format!("{}/{}", part, name))
+                .find(|full_path| {
                     if debug {
-                        eprintln!("[ld.so]: found at '{}'!", full_path);
+                        eprintln!("[ld.so]: trying path '{}'", full_path);
                     }
-                    self.library_cache
-                        .insert(name.to_string(), full_path.clone());
-                    return Ok(full_path);
-                }
+                    accessible(full_path, F_OK).is_ok()
+                })
+        };
+
+        let Some(full_path) = found else {
+            if debug {
+                eprintln!("[ld.so]: failed to locate '{}'", name);
             }
-        }
+            return Err(DlError::NotFound);
+        };
 
         if debug {
-            eprintln!("[ld.so]: failed to locate '{}'", name);
+            eprintln!("[ld.so]: found at '{}'!", full_path);
         }
+        self.library_cache
+            .insert(name.to_string(), full_path.clone());
+        Ok(full_path)
+    }
 
-        Err(DlError::NotFound)
+    /// The directories that objects are looked for in, for an object with
+    /// the given `DT_RUNPATH`.
+    fn search_path<'a>(&'a self, runpath: Option<&'a str>) -> Vec<&'a str> {
+        let mut search_path = Vec::new();
+        if let Some(runpath) = runpath {
+            search_path.extend(runpath.split(PATH_SEP));
+        }
+        if let Some(ld_path) = self.config.library_path.as_ref() {
+            search_path.extend(ld_path.split(PATH_SEP));
+        }
+        search_path.push("/lib");
+        search_path
     }
 
     fn read_file(&self, path: &str) -> Result<MmapFile> {
@@ -907,7 +1091,7 @@ impl Linker {
     fn run_init(&self, obj: &DSO) {
         use crate::platform::{self, types::*};
 
-        if let Some((symbol, SymbolBinding::Global)) = obj.get_sym("__relibc_init_environ") {
+        if let Some((symbol, SymbolBinding::Global)) = obj.get_sym("__relibc_init_environ", None) {
             unsafe {
                 symbol
                     .as_ptr()
@@ -931,53 +1115,19 @@ impl Linker {
     }
 }
 
-// GOT[1] = object_id
+// GOT[1] = object pointer
 // GOT[2] = __plt_resolve_trampoline
 //
-// The stubs in .plt will push the relocation index and the object pointer onto
-// the stack and jump to [`__plt_resolve_trampoline`]. The trampoline will then
-// call this function to resolve the symbol and update the respective GOT entry.
-// The trampoline will then jump to the resolved symbol.
+// (GOT[0] and GOT[1] respectively on riscv64)
 //
-// FIXME(andypython): 32-bit
-#[cfg(target_pointer_width = "64")]
-extern "C" fn __plt_resolve_inner(obj: *const DSO, relocation_index: c_uint) -> *mut c_void {
+// The stubs in .plt will pass the object pointer and the relocation to the
+// trampoline, each architecture in its own way. The trampoline will then save
+// the argument registers and call this function to resolve the symbol and
+// update the respective GOT entry. The trampoline will then restore the
+// argument registers and jump to the resolved symbol.
+extern "C" fn __plt_resolve_inner(obj: *const DSO, relocation_index: usize) -> *mut c_void {
     let obj = unsafe { &*obj };
-    let obj_base = obj.mmap.as_ptr() as usize;
-    let jmprel = obj.dynamic.jmprel;
-
-    let rela = unsafe { &*(jmprel as *const Rela).add(relocation_index as usize) };
-    assert_eq!(rela.r_type(NativeEndian, false), elf::R_X86_64_JUMP_SLOT);
-
-    let sym = obj
-        .dynamic
-        .symbol(SymbolIndex(rela.r_sym(NativeEndian, false) as usize))
-        .expect("symbol not found");
-    assert_ne!(sym.st_name(NativeEndian), 0);
-
-    let name = core::str::from_utf8(
-        obj.dynamic
-            .dynstrtab
-            .get(sym.st_name(NativeEndian))
-            .unwrap(),
-    )
-    .expect("non utf8 symbol name");
-
-    let resolved = resolve_sym(name, &[&GLOBAL_SCOPE.read(), obj.scope()])
-        .map(|(sym, _, _)| sym)
-        .unwrap_or_else(|| panic!("symbol '{name}' not found"))
-        .as_ptr();
-
-    let ptr = if obj.pie {
-        (obj_base as u64 + rela.r_offset(NativeEndian)) as *mut u64
-    } else {
-        rela.r_offset(NativeEndian) as *mut u64
-    };
-
-    trace!("@plt: {} -> *mut {:p}", name, ptr);
-
-    unsafe { *ptr = resolved as u64 }
-    resolved
+    obj.resolve_plt(relocation_index)
 }
 
 unsafe extern "C" {
@@ -1057,9 +1207,36 @@ core::arch::global_asm!(
 .global __plt_resolve_trampoline
 .hidden __plt_resolve_trampoline
 __plt_resolve_trampoline:
+    // [esp] = object pointer, [esp + 4] = byte offset of the relocation
+    push    eax
+    push    ecx
+    push    edx
+
+    push    ebp
+    mov     ebp, esp
+    and     esp, 0xfffffff0
+    sub     esp, 8
+    // Elf32_Rel entries are 8 bytes each
+    mov     eax, dword ptr [ebp + 20]
+    shr     eax, 3
+    push    eax
+    push    dword ptr [ebp + 16]
+    call    {__plt_resolve_inner}
+    mov     esp, ebp
+    pop     ebp
+
+    // Return to the resolved symbol in place of the object pointer, and drop
+    // the relocation offset
+    mov     dword ptr [esp + 12], eax
+    pop     edx
+    pop     ecx
+    pop     eax
+    ret     4
+
     ud2
 .size __plt_resolve_trampoline, . - __plt_resolve_trampoline
-    "
+",
+    __plt_resolve_inner = sym __plt_resolve_inner
 );
 
 #[cfg(target_arch = "aarch64")]
@@ -1068,9 +1245,50 @@ core::arch::global_asm!(
 .global __plt_resolve_trampoline
 .hidden __plt_resolve_trampoline
 __plt_resolve_trampoline:
-    udf #0
+    // [sp] = &GOT[3 + relocation index], [sp + 8] = lr, x16 = &GOT[2]
+    stp     x29, x30, [sp, #-16]!
+    mov     x29, sp
+
+    // stash the argument registers, x8 included for the indirect results
+    sub     sp, sp, #208
+    stp     x0, x1, [sp, #0]
+    stp     x2, x3, [sp, #16]
+    stp     x4, x5, [sp, #32]
+    stp     x6, x7, [sp, #48]
+    str     x8, [sp, #64]
+    stp     q0, q1, [sp, #80]
+    stp     q2, q3, [sp, #112]
+    stp     q4, q5, [sp, #144]
+    stp     q6, q7, [sp, #176]
+
+    ldr     x0, [x16, #-8]
+    ldr     x1, [x29, #16]
+    sub     x1, x1, x16
+    sub     x1, x1, #8
+    lsr     x1, x1, #3
+    bl      {__plt_resolve_inner}
+    mov     x16, x0
+
+    ldp     q6, q7, [sp, #176]
+    ldp     q4, q5, [sp, #144]
+    ldp     q2, q3, [sp, #112]
+    ldp     q0, q1, [sp, #80]
+    ldr     x8, [sp, #64]
+    ldp     x6, x7, [sp, #48]
+    ldp     x4, x5, [sp, #32]
+    ldp     x2, x3, [sp, #16]
+    ldp     x0, x1, [sp, #0]
+
+    mov     sp, x29
+    ldp     x29, x30, [sp], #16
+    // drop what the PLT header pushed
+    add     sp, sp, #16
+    br      x16
+
+    udf     #0
 .size __plt_resolve_trampoline, . - __plt_resolve_trampoline
-    "
+",
+    __plt_resolve_inner = sym __plt_resolve_inner
 );
 
 #[cfg(target_arch = "riscv64")]
@@ -1079,7 +1297,53 @@ core::arch::global_asm!(
 .global __plt_resolve_trampoline
 .hidden __plt_resolve_trampoline
 __plt_resolve_trampoline:
+    // t0 = object pointer, t1 = byte offset of the GOT entry past the header
+    addi    sp, sp, -144
+    sd      ra, 0(sp)
+    sd      a0, 8(sp)
+    sd      a1, 16(sp)
+    sd      a2, 24(sp)
+    sd      a3, 32(sp)
+    sd      a4, 40(sp)
+    sd      a5, 48(sp)
+    sd      a6, 56(sp)
+    sd      a7, 64(sp)
+    fsd     fa0, 72(sp)
+    fsd     fa1, 80(sp)
+    fsd     fa2, 88(sp)
+    fsd     fa3, 96(sp)
+    fsd     fa4, 104(sp)
+    fsd     fa5, 112(sp)
+    fsd     fa6, 120(sp)
+    fsd     fa7, 128(sp)
+
+    mv      a0, t0
+    srli    a1, t1, 3
+    call    {__plt_resolve_inner}
+    mv      t1, a0
+
+    fld     fa7, 128(sp)
+    fld     fa6, 120(sp)
+    fld     fa5, 112(sp)
+    fld     fa4, 104(sp)
+    fld     fa3, 96(sp)
+    fld     fa2, 88(sp)
+    fld     fa1, 80(sp)
+    fld     fa0, 72(sp)
+    ld      a7, 64(sp)
+    ld      a6, 56(sp)
+    ld      a5, 48(sp)
+    ld      a4, 40(sp)
+    ld      a3, 32(sp)
+    ld      a2, 24(sp)
+    ld      a1, 16(sp)
+    ld      a0, 8(sp)
+    ld      ra, 0(sp)
+    addi    sp, sp, 144
+    jr      t1
+
     unimp
 .size __plt_resolve_trampoline, . - __plt_resolve_trampoline
-    "
+",
+    __plt_resolve_inner = sym __plt_resolve_inner
 );
@@ -1,98 +1,13 @@
-// src/ld_so/linux_parity.rs
-//! Linux Parity layer.
-//! Integrates GNU Hash, Versioning, and IFUNC resolution into the DSO lifecycle.
+//! Behaviour of the Linux dynamic linkers that binaries rely on.
 
-#![no_std]
-
-use crate::header::elf;
-use crate::ld_so::gnu_hash::GnuHash;
-use crate::ld_so::versioning::{VersionData, VersionReq};
-
-/// Resolve an IFUNC symbol.
-/// 
+/// Calls the resolver of an `STT_GNU_IFUNC` symbol, returning the address
+/// of the implementation it picked.
+///
 /// # Safety
-/// This function calls arbitrary foreign code (the resolver). 
-/// The resolver must be valid and adhere to the C calling convention.
-/// We assume the resolver takes no arguments (common for libc dispatchers on x86_64/aarch64).
+/// This function calls arbitrary foreign code (the resolver), which must be
+/// relocated already. The resolver is assumed to take no arguments, which
+/// is what the libc dispatchers on x86_64 and aarch64 commonly do.
 pub unsafe fn resolve_ifunc(resolver_addr: usize) -> usize {
-    // Transmute address to a function pointer
-    let resolver: extern "C" fn() -> usize = core::mem::transmute(resolver_addr);
-    resolver()
-}
-
-/// Extended symbol lookup result
-pub struct LookupResult {
-    pub value: usize,
-    pub size: usize,
-    pub sym_type: u8,
-    pub weak: bool,
-    pub protected: bool,
+    let resolver: unsafe extern "C" fn() -> usize = unsafe { core::mem::transmute(resolver_addr) };
+    unsafe { resolver() }
 }
-
-/// Advanced symbol lookup implementing GNU_HASH and Versioning.
-pub unsafe fn find_symbol_linux_style<'a>(
-    name: &str,
-    ver_req: Option<&VersionReq>,
-    sym_tab: &[elf::Sym],
-    str_tab: &[u8],
-    gnu_hash: Option<&GnuHash<'a>>,
-    sysv_hash: Option<&[u32]>, // Legacy DT_HASH
-    version_data: Option<&VersionData<'a>>,
-    load_base: usize,
-) -> Option<LookupResult> {
-    
-    let mut found_sym_idx = None;
-
-    // 1. Try GNU Hash first (faster)
-    if let Some(hash_table) = gnu_hash {
-        let hash = GnuHash::hash(name);
-        if let Some(idx) = hash_table.lookup(name, hash, sym_tab, str_tab) {
-            found_sym_idx = Some(idx as usize);
-        }
-    } 
-    // 2. Fallback to SysV Hash
-    else if let Some(hash_table) = sysv_hash {
-        // Implementation of SysV chain walking (omitted for brevity, existing relibc has this)
-        // found_sym_idx = sysv_lookup(...);
-    }
-
-    // 3. Validate Versioning
-    if let Some(idx) = found_sym_idx {
-        let sym = &sym_tab[idx];
-        
-        // Check if symbol is undefined
-        if sym.st_shndx == elf::SHN_UNDEF {
-            return None;
-        }
-
-        // Check Version
-        if let Some(vdata) = version_data {
-            if !vdata.check(idx, ver_req) {
-                // Symbol found but version mismatch. 
-                // In a real linker, we might continue searching, but strict versioning usually stops here.
-                return None; 
-            }
-        }
-
-        // 4. Handle IFUNC
-        // If the symbol is an IFUNC, st_value is the address of the resolver, not the function.
-        // We must resolve it *now* if we are doing eager binding, or set up a trampoline if lazy.
-        // Relibc tends to be eager.
-        let mut sym_value = load_base + sym.st_value as usize;
-        let sym_type = (sym.st_info & 0xf) as u8;
-
-        if sym_type == elf::STT_GNU_IFUNC {
-            sym_value = resolve_ifunc(sym_value);
-        }
-
-        return Some(LookupResult {
-            value: sym_value,
-            size: sym.st_size as usize,
-            sym_type,
-            weak: (sym.st_info >> 4) == elf::STB_WEAK as u8,
-            protected: (sym.st_other & 0x3) == elf::STV_PROTECTED,
-        });
-    }
-
-    None
-}
\ No newline at end of file
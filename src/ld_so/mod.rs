@@ -29,9 +29,12 @@ mod access;
 pub mod callbacks;
 pub mod debug;
 mod dso;
+mod gnu_hash;
 pub mod linker;
+mod linux_parity;
 pub mod start;
 pub mod tcb;
+mod versioning;
 
 pub use generic_rt::{ExpectTlsFree, panic_notls};
 
@@ -14,8 +14,8 @@ use crate::{
     header::sys_mman,
     ld_so::linker::Linker,
     platform::{Dlmalloc, Pal, Sys},
-    pthread::{OsTid, Pthread},
-    sync::{mutex::Mutex, waitval::Waitval},
+    pthread::{OsTid, PrioCeilings, Pthread},
+    sync::{mutex::Mutex, pthread_mutex::RobustListHead, waitval::Waitval},
 };
 
 use super::linker::DlError;
@@ -107,11 +107,15 @@ impl Tcb {
                 pthread: Pthread {
                     waitval: Waitval::new(),
                     flags: Default::default(),
-                    has_enabled_cancelation: AtomicBool::new(false),
+                    has_enabled_cancelation: AtomicBool::new(true),
+                    has_async_cancelation: AtomicBool::new(false),
                     has_queued_cancelation: AtomicBool::new(false),
+                    in_cancelation_point: AtomicBool::new(false),
                     stack_base: core::ptr::null_mut(),
                     stack_size: 0,
                     os_tid: UnsafeCell::new(OsTid::default()),
+                    robust_list: UnsafeCell::new(RobustListHead::new()),
+                    prio_ceilings: UnsafeCell::new(PrioCeilings::new()),
                 },
 
                 dtv_ptr: ptr::null_mut(),
@@ -1,10 +1,10 @@
-// src/ld_so/versioning.rs
 //! Support for ELF Symbol Versioning.
 //! Handles .gnu.version, .gnu.version_r, and .gnu.version_d.
 
-#![no_std]
-
-use crate::header::elf;
+use object::{
+    NativeEndian,
+    elf::{self, Verdaux, Verdef, Vernaux, Verneed},
+};
 
 /// Represents a specific version requirement for a symbol.
 #[derive(Debug, Clone, Copy)]
@@ -19,14 +19,27 @@ pub struct VersionReq<'a> {
     pub hidden: bool,
 }
 
+impl<'a> VersionReq<'a> {
+    /// A requirement for `version` in whichever file defines it, as for
+    /// `dlvsym`.
+    pub fn new(version: &'a str) -> Self {
+        Self {
+            filename: "",
+            version,
+            hash: elf::hash(version.as_bytes()),
+            hidden: false,
+        }
+    }
+}
+
 pub struct VersionData<'a> {
     /// Pointer to .gnu.version section (array of u16 indices).
     pub versym: &'a [u16],
     /// Pointer to .gnu.version_r section.
-    pub verneed: *const elf::Verneed,
+    pub verneed: *const Verneed<NativeEndian>,
     pub verneed_num: usize,
     /// Pointer to .gnu.version_d section.
-    pub verdef: *const elf::Verdef,
+    pub verdef: *const Verdef<NativeEndian>,
     pub verdef_num: usize,
     /// String table for version strings.
     pub str_tab: &'a [u8],
@@ -36,12 +49,11 @@ impl<'a> VersionData<'a> {
     /// Check if a symbol definition matches a requirement.
     pub fn check(&self, sym_idx: usize, req: Option<&VersionReq>) -> bool {
         // 1. Get version index from .gnu.version table
-        if self.versym.is_empty() {
+        let Some(&ver_idx) = self.versym.get(sym_idx) else {
             // If no versioning data, assume match if req is None or global.
-            return true; 
-        }
-        
-        let ver_idx = self.versym[sym_idx];
+            return true;
+        };
+
         let is_hidden = (ver_idx & 0x8000) != 0;
         let idx = ver_idx & 0x7FFF;
 
@@ -53,11 +65,11 @@ impl<'a> VersionData<'a> {
         if let Some(requirement) = req {
             // We are looking for a specific version definition.
             // We need to walk .gnu.version_d to find the name associated with `idx`.
-            if let Some(def_name) = unsafe { self.get_def_name(idx) } {
+            if let Some((def_hash, def_name)) = unsafe { self.get_def(idx) } {
                 // Standard Linux ld.so comparison:
                 // Hash match && String match
                 // (Optimized: check hash first)
-                return def_name == requirement.version;
+                return def_hash == requirement.hash && def_name == requirement.version;
             }
         } else {
             // If no specific requirement provided, we take the default (non-hidden) one.
@@ -69,29 +81,68 @@ impl<'a> VersionData<'a> {
         false
     }
 
-    /// Retrieve the version name for a given definition index from .gnu.version_d
-    unsafe fn get_def_name(&self, ndx: u16) -> Option<&'a str> {
-        if self.verdef.is_null() { return None; }
+    /// The version that the undefined symbol at `sym_idx` requires, from
+    /// .gnu.version_r
+    pub fn required(&self, sym_idx: usize) -> Option<VersionReq<'a>> {
+        let ver_idx = self.versym.get(sym_idx)? & 0x7FFF;
+        // Unversioned references
+        if ver_idx <= 1 || self.verneed.is_null() {
+            return None;
+        }
+
+        let mut need_ptr = self.verneed as *const u8;
+        for _ in 0..self.verneed_num {
+            let need = unsafe { &*(need_ptr as *const Verneed<NativeEndian>) };
+            let mut aux_ptr = unsafe { need_ptr.add(need.vn_aux.get(NativeEndian) as usize) };
+            for _ in 0..need.vn_cnt.get(NativeEndian) {
+                let aux = unsafe { &*(aux_ptr as *const Vernaux<NativeEndian>) };
+                let other = aux.vna_other.get(NativeEndian);
+                if (other & 0x7FFF) == ver_idx {
+                    return Some(VersionReq {
+                        filename: self.get_string(need.vn_file.get(NativeEndian) as usize)?,
+                        version: self.get_string(aux.vna_name.get(NativeEndian) as usize)?,
+                        hash: aux.vna_hash.get(NativeEndian),
+                        hidden: (other & 0x8000) != 0,
+                    });
+                }
+                aux_ptr = unsafe { aux_ptr.add(aux.vna_next.get(NativeEndian) as usize) };
+            }
+            need_ptr = unsafe { need_ptr.add(need.vn_next.get(NativeEndian) as usize) };
+        }
+        None
+    }
+
+    /// Retrieve the hash and version name for a given definition index from
+    /// .gnu.version_d
+    unsafe fn get_def(&self, ndx: u16) -> Option<(u32, &'a str)> {
+        if self.verdef.is_null() {
+            return None;
+        }
 
         let mut ptr = self.verdef as *const u8;
         for _ in 0..self.verdef_num {
-            let def = &*(ptr as *const elf::Verdef);
-            if (def.vd_ndx & 0x7FFF) == ndx {
+            let def = unsafe { &*(ptr as *const Verdef<NativeEndian>) };
+            if (def.vd_ndx.get(NativeEndian) & 0x7FFF) == ndx {
                 // Found the definition, get the first aux entry for the string
-                let aux_ptr = ptr.add(def.vd_aux as usize);
-                let aux = &*(aux_ptr as *const elf::Verdaux);
-                return self.get_string(aux.vda_name as usize);
+                let aux_ptr = unsafe { ptr.add(def.vd_aux.get(NativeEndian) as usize) };
+                let aux = unsafe { &*(aux_ptr as *const Verdaux<NativeEndian>) };
+                let name = self.get_string(aux.vda_name.get(NativeEndian) as usize)?;
+                return Some((def.vd_hash.get(NativeEndian), name));
+            }
+            if def.vd_next.get(NativeEndian) == 0 {
+                break;
             }
-            if def.vd_next == 0 { break; }
-            ptr = ptr.add(def.vd_next as usize);
+            ptr = unsafe { ptr.add(def.vd_next.get(NativeEndian) as usize) };
         }
         None
     }
 
     fn get_string(&self, offset: usize) -> Option<&'a str> {
-        if offset >= self.str_tab.len() { return None; }
+        if offset >= self.str_tab.len() {
+            return None;
+        }
         let slice = &self.str_tab[offset..];
         let end = slice.iter().position(|&c| c == 0)?;
         core::str::from_utf8(&slice[..end]).ok()
     }
-}
\ No newline at end of file
+}
@@ -94,6 +94,7 @@ pub mod header;
 pub mod io;
 pub mod iter;
 pub mod ld_so;
+pub mod nss;
 pub mod out;
 pub mod platform;
 pub mod pthread;
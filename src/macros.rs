@@ -221,9 +221,10 @@ macro_rules! strto_impl {
 
 #[macro_export]
 macro_rules! strto_float_impl {
-    ($type:ident, $s:expr, $endptr:expr) => {{
+    ($type:ident, $s:expr, $endptr:expr, $locale:expr) => {{
         let mut s = $s;
         let endptr = $endptr;
+        let decimal_point = *$locale.lconv.decimal_point as u8;
 
         while ctype::isspace(*s as c_int) != 0 {
             s = s.offset(1);
@@ -268,7 +269,7 @@ macro_rules! strto_float_impl {
                 s = s.offset(1);
             }
 
-            if *s as u8 == b'.' {
+            if *s as u8 == decimal_point {
                 s = s.offset(1);
 
                 let mut i = 1.0;
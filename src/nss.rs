@@ -0,0 +1,507 @@
+//! The name service switch, which picks the sources that users, groups,
+//! hosts and so on are looked up in.
+//!
+//! See <https://man7.org/linux/man-pages/man5/nsswitch.conf.5.html>. Sources
+//! other than `files` and `dns` are modules loaded from `libnss_<name>.so.2`,
+//! with the functions of the glibc interface.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use crate::{
+    c_str::CStr,
+    fs::File,
+    header::{
+        dlfcn::{RTLD_NOW, dlclose, dlerror, dlopen, dlsym},
+        errno::{EINVAL, ERANGE},
+        fcntl,
+        sys_stat::stat,
+    },
+    io::Read,
+    out::Out,
+    platform::{self, Pal, Sys, types::*},
+    sync::Mutex,
+};
+
+/// The databases that lookups go through the switch for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Database {
+    Passwd,
+    Group,
+    Shadow,
+    Hosts,
+    Services,
+    Protocols,
+}
+
+impl Database {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "passwd" => Database::Passwd,
+            "group" => Database::Group,
+            "shadow" => Database::Shadow,
+            "hosts" => Database::Hosts,
+            "services" => Database::Services,
+            "protocols" => Database::Protocols,
+            _ => return None,
+        })
+    }
+
+    /// The sources of the database when `nsswitch.conf` leaves it out.
+    fn default_sources(self) -> &'static str {
+        match self {
+            Database::Hosts => "files dns",
+            _ => "files",
+        }
+    }
+}
+
+/// The outcome of asking a source, with the values of `enum nss_status`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    TryAgain = -2,
+    Unavail = -1,
+    NotFound = 0,
+    Success = 1,
+}
+
+impl Status {
+    pub fn from_raw(status: c_int) -> Self {
+        match status {
+            -2 => Status::TryAgain,
+            -1 => Status::Unavail,
+            1 => Status::Success,
+            // Including NSS_STATUS_RETURN, which is internal to glibc
+            _ => Status::NotFound,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "success" => Status::Success,
+            "notfound" => Status::NotFound,
+            "unavail" => Status::Unavail,
+            "tryagain" => Status::TryAgain,
+            _ => return None,
+        })
+    }
+
+    fn index(self) -> usize {
+        (self as isize + 2) as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Return,
+    Continue,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    Files,
+    Dns,
+    Module(String),
+}
+
+/// A source, and what to do after each outcome of asking it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Service {
+    pub source: Source,
+    /// Indexed by [`Status::index`].
+    actions: [Action; 4],
+}
+
+impl Service {
+    fn new(name: &str) -> Self {
+        let source = match name {
+            // `compat` adds NIS to the files, which is not supported
+            "files" | "compat" => Source::Files,
+            "dns" => Source::Dns,
+            name => Source::Module(String::from(name)),
+        };
+        let mut actions = [Action::Continue; 4];
+        actions[Status::Success.index()] = Action::Return;
+        Service { source, actions }
+    }
+
+    pub fn action(&self, status: Status) -> Action {
+        self.actions[status.index()]
+    }
+
+    /// Applies the criteria between the brackets of `[NOTFOUND=return]`.
+    fn criteria(&mut self, criteria: &str) {
+        for criterion in criteria.split_whitespace() {
+            let (negated, criterion) = match criterion.strip_prefix('!') {
+                Some(criterion) => (true, criterion),
+                None => (false, criterion),
+            };
+            let Some((status, action)) = criterion.split_once('=') else {
+                continue;
+            };
+            let action = match action.to_ascii_lowercase().as_str() {
+                "return" => Action::Return,
+                "continue" => Action::Continue,
+                _ => continue,
+            };
+            let Some(status) = Status::from_name(status) else {
+                continue;
+            };
+            for other in [
+                Status::TryAgain,
+                Status::Unavail,
+                Status::NotFound,
+                Status::Success,
+            ] {
+                if (other == status) != negated {
+                    self.actions[other.index()] = action;
+                }
+            }
+        }
+    }
+}
+
+/// Parses the sources of a database, as in `files [NOTFOUND=return] dns`.
+fn parse_services(text: &str) -> Vec<Service> {
+    let mut services: Vec<Service> = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        if let Some(criteria) = rest.strip_prefix('[') {
+            let (criteria, after) = criteria.split_once(']').unwrap_or((criteria, ""));
+            // Criteria before any source mean nothing
+            if let Some(service) = services.last_mut() {
+                service.criteria(criteria);
+            }
+            rest = after.trim_start();
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '[')
+                .unwrap_or(rest.len());
+            services.push(Service::new(&rest[..end]));
+            rest = rest[end..].trim_start();
+        }
+    }
+    services
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NsswitchConf {
+    databases: Vec<(Database, Vec<Service>)>,
+}
+
+impl NsswitchConf {
+    const fn new() -> Self {
+        NsswitchConf {
+            databases: Vec::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut conf = NsswitchConf::default();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            let Some((name, sources)) = line.split_once(':') else {
+                continue;
+            };
+            if let Some(database) = Database::from_name(name.trim()) {
+                conf.set(database, parse_services(sources));
+            }
+        }
+        conf
+    }
+
+    /// Replaces the sources of `database`, as a later line for it does.
+    fn set(&mut self, database: Database, services: Vec<Service>) {
+        self.databases.retain(|(other, _)| *other != database);
+        self.databases.push((database, services));
+    }
+
+    /// The sources given for `database`, if there are any.
+    fn get(&self, database: Database) -> Option<&[Service]> {
+        match self.databases.iter().find(|(other, _)| *other == database) {
+            Some((_, services)) if !services.is_empty() => Some(services),
+            _ => None,
+        }
+    }
+
+    /// The sources to look up `database` in, in order.
+    pub fn services(&self, database: Database) -> Vec<Service> {
+        match self.get(database) {
+            Some(services) => services.to_vec(),
+            None => parse_services(database.default_sources()),
+        }
+    }
+}
+
+const NSSWITCH_CONF: &core::ffi::CStr = c"/etc/nsswitch.conf";
+
+/// `/etc/nsswitch.conf` as last read, with the modification time it had
+/// then, or `None` for the time if it was missing.
+static FILE_CONF: Mutex<Option<(Option<(time_t, c_long)>, NsswitchConf)>> = Mutex::new(None);
+
+/// The sources set with `__nss_configure_lookup`, which take precedence over
+/// the file.
+static CONFIGURED: Mutex<NsswitchConf> = Mutex::new(NsswitchConf::new());
+
+/// The modification time of `/etc/nsswitch.conf`, to tell whether it
+/// changed, or `None` if it is missing.
+fn mtime() -> Option<(time_t, c_long)> {
+    let mut st = stat::default();
+    Sys::fstatat(
+        fcntl::AT_FDCWD,
+        Some(NSSWITCH_CONF.into()),
+        Out::from_mut(&mut st),
+        0,
+    )
+    .ok()?;
+    Some((st.st_mtim.tv_sec, st.st_mtim.tv_nsec))
+}
+
+/// The sources to look up `database` in, in order. `/etc/nsswitch.conf` is
+/// only read again once it has changed.
+fn services(database: Database) -> Vec<Service> {
+    if let Some(services) = CONFIGURED.lock().get(database) {
+        return services.to_vec();
+    }
+
+    let modified = mtime();
+    let mut cached = FILE_CONF.lock();
+    match &*cached {
+        Some((read, conf)) if *read == modified => conf.services(database),
+        _ => {
+            let mut string = String::new();
+            // Without the file, the defaults are used
+            if let Ok(mut file) = File::open(NSSWITCH_CONF.into(), fcntl::O_RDONLY) {
+                let _ = file.read_to_string(&mut string);
+            }
+            let conf = NsswitchConf::parse(&string);
+            let services = conf.services(database);
+            *cached = Some((modified, conf));
+            services
+        }
+    }
+}
+
+/// Sets the sources of the database named `db` to `service_line`, as a line
+/// of `nsswitch.conf` would, for the rest of the process. An empty line goes
+/// back to the sources of the file.
+///
+/// See <https://sourceware.org/glibc/manual/latest/html_node/NSS-Configuration-File.html>.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn __nss_configure_lookup(
+    db: *const c_char,
+    service_line: *const c_char,
+) -> c_int {
+    let db = unsafe { CStr::from_ptr(db) }.to_str().ok();
+    let line = unsafe { CStr::from_ptr(service_line) }.to_str().ok();
+    let (Some(database), Some(line)) = (db.and_then(Database::from_name), line) else {
+        platform::ERRNO.set(EINVAL);
+        return -1;
+    };
+    CONFIGURED.lock().set(database, parse_services(line));
+    0
+}
+
+/// An error of a lookup in one source, and what it means to the switch.
+pub trait LookupError {
+    fn from_status(status: Status) -> Self;
+    fn status(&self) -> Status;
+}
+
+impl LookupError for Status {
+    fn from_status(status: Status) -> Self {
+        status
+    }
+
+    fn status(&self) -> Status {
+        *self
+    }
+}
+
+/// Asks the sources of `database` in turn with `lookup`, until one of them
+/// has the answer or the criteria of `nsswitch.conf` say to stop.
+///
+/// Returns the error of the last source asked if none had the answer. A
+/// buffer that is too small, which is [`Status::TryAgain`] with `errno` set
+/// to `ERANGE`, stops the lookup for the caller to retry.
+pub fn dispatch<T, E: LookupError>(
+    database: Database,
+    mut lookup: impl FnMut(&Source) -> Result<T, E>,
+) -> Result<T, E> {
+    let mut last = E::from_status(Status::NotFound);
+    for service in services(database) {
+        match lookup(&service.source) {
+            Ok(found) => return Ok(found),
+            Err(err) => {
+                let status = err.status();
+                if status == Status::TryAgain && platform::ERRNO.get() == ERANGE {
+                    return Err(err);
+                }
+                last = err;
+                if service.action(status) == Action::Return {
+                    break;
+                }
+            }
+        }
+    }
+    Err(last)
+}
+
+/// A module of the switch, loaded with `dlopen`. Modules stay loaded.
+struct Module {
+    name: String,
+    handle: *mut c_void,
+}
+
+// The handle is only passed to `dlsym`, which can be called from any thread
+unsafe impl Send for Module {}
+
+/// The modules that were asked for so far, by name, with `None` for those
+/// that failed to load, so that each is only opened once.
+static MODULES: Mutex<Vec<(String, Option<Module>)>> = Mutex::new(Vec::new());
+
+impl Module {
+    /// Loads `libnss_<name>.so.2`, which fails in static programs.
+    fn open(name: &str) -> Option<Self> {
+        let path = format!("libnss_{}.so.2\0", name);
+        let handle = unsafe { dlopen(path.as_ptr() as *const c_char, RTLD_NOW) };
+        if handle.is_null() {
+            // A missing module is not an error of whatever is being looked up
+            dlerror();
+            return None;
+        }
+        Some(Module {
+            name: String::from(name),
+            handle,
+        })
+    }
+
+    /// Finds `_nss_<name>_<function>` in the module called `name`, loading it
+    /// the first time it is asked for.
+    fn find_symbol(name: &str, function: &str) -> Option<*mut c_void> {
+        if let Some((_, module)) = MODULES.lock().iter().find(|(other, _)| other == name) {
+            return module.as_ref()?.symbol(function);
+        }
+
+        // Not holding the lock, in case the constructors of the module look
+        // something up themselves
+        let opened = Module::open(name);
+        let mut modules = MODULES.lock();
+        if let Some((_, module)) = modules.iter().find(|(other, _)| other == name) {
+            // Another thread got there first
+            if let Some(opened) = opened {
+                unsafe { dlclose(opened.handle) };
+            }
+            return module.as_ref()?.symbol(function);
+        }
+        let symbol = opened.as_ref().and_then(|module| module.symbol(function));
+        modules.push((String::from(name), opened));
+        symbol
+    }
+
+    /// Finds `_nss_<name>_<function>`, for the caller to cast to its type.
+    fn symbol(&self, function: &str) -> Option<*mut c_void> {
+        let symbol = format!("_nss_{}_{}\0", self.name, function);
+        let address = unsafe { dlsym(self.handle, symbol.as_ptr() as *const c_char) };
+        if address.is_null() {
+            dlerror();
+            return None;
+        }
+        Some(address)
+    }
+}
+
+/// Calls the reentrant function of a module with a buffer for the strings of
+/// the entry: `dest` if there is one, or else a buffer that is grown for as
+/// long as it is too small. Returns the buffer if it was not `dest`.
+///
+/// The second argument of `call` is for the `errnop` of the function.
+fn with_buffer(
+    dest: Option<&mut [u8]>,
+    mut call: impl FnMut(&mut [u8], &mut c_int) -> Status,
+) -> Result<Option<Box<[u8]>>, Status> {
+    let mut errnop = 0;
+    if let Some(dest) = dest {
+        return match call(dest, &mut errnop) {
+            Status::Success => Ok(None),
+            status => {
+                if status == Status::TryAgain && errnop == ERANGE {
+                    platform::ERRNO.set(ERANGE);
+                }
+                Err(status)
+            }
+        };
+    }
+
+    let mut size = 1024;
+    loop {
+        let mut buffer = vec![0u8; size].into_boxed_slice();
+        match call(&mut buffer, &mut errnop) {
+            Status::Success => return Ok(Some(buffer)),
+            Status::TryAgain if errnop == ERANGE && size < 1 << 20 => size *= 2,
+            status => return Err(status),
+        }
+    }
+}
+
+/// Looks up an entry with `_nss_<module>_<function>`, which `call` casts to
+/// its type and calls with the buffer for the strings of the entry and
+/// `errnop`. The buffer is `dest`, or else one that is returned.
+pub fn module_lookup(
+    module: &str,
+    function: &str,
+    dest: Option<&mut [u8]>,
+    mut call: impl FnMut(*mut c_void, &mut [u8], &mut c_int) -> c_int,
+) -> Result<Option<Box<[u8]>>, Status> {
+    let symbol = Module::find_symbol(module, function).ok_or(Status::Unavail)?;
+    with_buffer(dest, |buffer, errnop| {
+        Status::from_raw(call(symbol, buffer, errnop))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Database, NsswitchConf, Source, Status, parse_services};
+
+    #[test]
+    fn defaults() {
+        let conf = NsswitchConf::parse("# nothing here\npasswd:\n");
+        let hosts = conf.services(Database::Hosts);
+        assert_eq!(2, hosts.len());
+        assert_eq!(Source::Files, hosts[0].source);
+        assert_eq!(Source::Dns, hosts[1].source);
+        let passwd = conf.services(Database::Passwd);
+        assert_eq!(1, passwd.len());
+        assert_eq!(Source::Files, passwd[0].source);
+    }
+
+    #[test]
+    fn sources() {
+        let conf = NsswitchConf::parse(
+            "passwd: compat ldap # comment\n\
+             hosts: dns\n\
+             hosts:\tfiles mdns4_minimal [NOTFOUND=return] dns\n\
+             netgroup: nis\n",
+        );
+        let passwd = conf.services(Database::Passwd);
+        assert_eq!(Source::Files, passwd[0].source);
+        assert_eq!(Source::Module("ldap".into()), passwd[1].source);
+
+        let hosts = conf.services(Database::Hosts);
+        assert_eq!(3, hosts.len());
+        assert_eq!(Source::Module("mdns4_minimal".into()), hosts[1].source);
+        assert_eq!(Action::Return, hosts[1].action(Status::NotFound));
+        assert_eq!(Action::Continue, hosts[1].action(Status::Unavail));
+        assert_eq!(Action::Continue, hosts[0].action(Status::NotFound));
+        assert_eq!(Action::Return, hosts[2].action(Status::Success));
+    }
+
+    #[test]
+    fn criteria() {
+        let services = parse_services("files[!UNAVAIL=return success=continue] dns");
+        assert_eq!(2, services.len());
+        assert_eq!(Action::Continue, services[0].action(Status::Unavail));
+        assert_eq!(Action::Return, services[0].action(Status::NotFound));
+        assert_eq!(Action::Return, services[0].action(Status::TryAgain));
+        assert_eq!(Action::Continue, services[0].action(Status::Success));
+    }
+}
@@ -0,0 +1,260 @@
+//! System calls of cancellation points, following `__syscall_cp` of musl.
+//!
+//! A cancellation request interrupting one of these calls is only acted upon while the call has
+//! had no effect, which is from checking for requests until the instruction making the system
+//! call. A thread blocked in a call that is restarted after the signal handler is back at that
+//! instruction when interrupted. Once the call has completed, the request waits for the next
+//! cancellation point, so that no descriptor or data that the call returned is lost.
+
+use core::{
+    arch::global_asm,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{header::errno::EINTR, platform::types::*, pthread};
+
+// The offset of the instruction pointer in the ucontext_t of signal handlers.
+#[cfg(target_arch = "x86")]
+const UCONTEXT_PC: usize = 76;
+#[cfg(target_arch = "x86_64")]
+const UCONTEXT_PC: usize = 168;
+#[cfg(target_arch = "aarch64")]
+const UCONTEXT_PC: usize = 440;
+#[cfg(target_arch = "riscv64")]
+const UCONTEXT_PC: usize = 176;
+
+#[cfg(target_arch = "x86")]
+global_asm!(
+    "
+    .p2align 4
+    .globl __relibc_syscall_cp_asm
+    .hidden __relibc_syscall_cp_asm
+    .type __relibc_syscall_cp_asm, @function
+__relibc_syscall_cp_asm:
+    mov ecx, [esp + 4]
+    push ebx
+    push esi
+    push edi
+    push ebp
+    .globl __relibc_cp_begin
+    .hidden __relibc_cp_begin
+__relibc_cp_begin:
+    movzx eax, byte ptr [ecx]
+    test eax, eax
+    jnz __relibc_cp_cancel
+    mov eax, [esp + 24]
+    mov ebx, [esp + 28]
+    mov ecx, [esp + 32]
+    mov edx, [esp + 36]
+    mov esi, [esp + 40]
+    mov edi, [esp + 44]
+    mov ebp, [esp + 48]
+    int 0x80
+    .globl __relibc_cp_end
+    .hidden __relibc_cp_end
+__relibc_cp_end:
+    pop ebp
+    pop edi
+    pop esi
+    pop ebx
+    ret
+    .globl __relibc_cp_cancel
+    .hidden __relibc_cp_cancel
+__relibc_cp_cancel:
+    pop ebp
+    pop edi
+    pop esi
+    pop ebx
+    jmp {cancel}
+    .size __relibc_syscall_cp_asm, . - __relibc_syscall_cp_asm
+    ",
+    cancel = sym cancel,
+);
+
+#[cfg(target_arch = "x86_64")]
+global_asm!(
+    "
+    .p2align 4
+    .globl __relibc_syscall_cp_asm
+    .hidden __relibc_syscall_cp_asm
+    .type __relibc_syscall_cp_asm, @function
+__relibc_syscall_cp_asm:
+    .globl __relibc_cp_begin
+    .hidden __relibc_cp_begin
+__relibc_cp_begin:
+    movzx eax, byte ptr [rdi]
+    test eax, eax
+    jnz __relibc_cp_cancel
+    mov rax, rsi
+    mov rdi, rdx
+    mov rsi, rcx
+    mov rdx, r8
+    mov r10, r9
+    mov r8, [rsp + 8]
+    mov r9, [rsp + 16]
+    syscall
+    .globl __relibc_cp_end
+    .hidden __relibc_cp_end
+__relibc_cp_end:
+    ret
+    .globl __relibc_cp_cancel
+    .hidden __relibc_cp_cancel
+__relibc_cp_cancel:
+    jmp {cancel}
+    .size __relibc_syscall_cp_asm, . - __relibc_syscall_cp_asm
+    ",
+    cancel = sym cancel,
+);
+
+#[cfg(target_arch = "aarch64")]
+global_asm!(
+    "
+    .p2align 4
+    .globl __relibc_syscall_cp_asm
+    .hidden __relibc_syscall_cp_asm
+    .type __relibc_syscall_cp_asm, @function
+__relibc_syscall_cp_asm:
+    .globl __relibc_cp_begin
+    .hidden __relibc_cp_begin
+__relibc_cp_begin:
+    ldrb w9, [x0]
+    cbnz w9, __relibc_cp_cancel
+    mov x8, x1
+    mov x0, x2
+    mov x1, x3
+    mov x2, x4
+    mov x3, x5
+    mov x4, x6
+    mov x5, x7
+    svc 0
+    .globl __relibc_cp_end
+    .hidden __relibc_cp_end
+__relibc_cp_end:
+    ret
+    .globl __relibc_cp_cancel
+    .hidden __relibc_cp_cancel
+__relibc_cp_cancel:
+    b {cancel}
+    .size __relibc_syscall_cp_asm, . - __relibc_syscall_cp_asm
+    ",
+    cancel = sym cancel,
+);
+
+#[cfg(target_arch = "riscv64")]
+global_asm!(
+    "
+    .p2align 4
+    .globl __relibc_syscall_cp_asm
+    .hidden __relibc_syscall_cp_asm
+    .type __relibc_syscall_cp_asm, @function
+__relibc_syscall_cp_asm:
+    .globl __relibc_cp_begin
+    .hidden __relibc_cp_begin
+__relibc_cp_begin:
+    lbu t0, 0(a0)
+    bnez t0, __relibc_cp_cancel
+    mv t0, a1
+    mv a0, a2
+    mv a1, a3
+    mv a2, a4
+    mv a3, a5
+    mv a4, a6
+    mv a5, a7
+    mv a7, t0
+    ecall
+    .globl __relibc_cp_end
+    .hidden __relibc_cp_end
+__relibc_cp_end:
+    ret
+    .globl __relibc_cp_cancel
+    .hidden __relibc_cp_cancel
+__relibc_cp_cancel:
+    tail {cancel}
+    .size __relibc_syscall_cp_asm, . - __relibc_syscall_cp_asm
+    ",
+    cancel = sym cancel,
+);
+
+unsafe extern "C" {
+    /// Makes the system call `nr`, unless `canceled` is set, in which case the thread is
+    /// cancelled instead.
+    fn __relibc_syscall_cp_asm(
+        canceled: *const AtomicBool,
+        nr: usize,
+        a1: usize,
+        a2: usize,
+        a3: usize,
+        a4: usize,
+        a5: usize,
+        a6: usize,
+    ) -> usize;
+
+    static __relibc_cp_begin: u8;
+    static __relibc_cp_end: u8;
+    static __relibc_cp_cancel: u8;
+}
+
+/// Where a thread goes when it is cancelled in a system call.
+unsafe extern "C" fn cancel() -> ! {
+    unsafe { pthread::cancel_current_thread() }
+}
+
+/// Like `syscall!`, for the system calls that are made by cancellation points.
+macro_rules! syscall_cp {
+    ($nr:ident $(, $arg:expr)* $(,)?) => {
+        $crate::platform::sys::cancel::syscall_cp(::sc::nr::$nr, &[$($arg as usize),*])
+    };
+}
+
+/// Makes the system call `nr` with up to six arguments, checking for cancellation requests if
+/// the calling thread is in a cancellation point with cancellation enabled.
+pub unsafe fn syscall_cp(nr: usize, args: &[usize]) -> usize {
+    let mut a = [0; 6];
+    a[..args.len()].copy_from_slice(args);
+
+    let this = pthread::threads_ready()
+        .then(pthread::current_thread)
+        .flatten()
+        .filter(|this| {
+            this.in_cancelation_point.load(Ordering::SeqCst)
+                && this.has_enabled_cancelation.load(Ordering::SeqCst)
+        });
+    let Some(this) = this else {
+        return unsafe { sc::syscall6(nr, a[0], a[1], a[2], a[3], a[4], a[5]) };
+    };
+
+    let ret = unsafe {
+        __relibc_syscall_cp_asm(
+            &this.has_queued_cancelation,
+            nr,
+            a[0],
+            a[1],
+            a[2],
+            a[3],
+            a[4],
+            a[5],
+        )
+    };
+
+    // Calls that are never restarted, like poll, fail with EINTR instead, having had no effect.
+    // A close failing this way has closed the descriptor all the same.
+    if ret == (EINTR as usize).wrapping_neg()
+        && nr != sc::nr::CLOSE
+        && this.has_queued_cancelation.load(Ordering::SeqCst)
+    {
+        unsafe { pthread::cancel_current_thread() };
+    }
+    ret
+}
+
+/// Makes the thread interrupted with the signal handler `context` cancel itself once back from
+/// the handler, if it was in the system call of a cancellation point without it having had an
+/// effect yet.
+pub unsafe fn cancel_interrupted_syscall(context: *mut c_void) {
+    let pc = unsafe { &mut *context.cast::<u8>().add(UCONTEXT_PC).cast::<usize>() };
+    let begin = &raw const __relibc_cp_begin as usize;
+    let end = &raw const __relibc_cp_end as usize;
+    if (begin..end).contains(pc) {
+        *pc = &raw const __relibc_cp_cancel as usize;
+    }
+}
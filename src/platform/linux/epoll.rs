@@ -25,7 +25,7 @@ impl PalEpoll for Sys {
         sigmask: *const sigset_t,
     ) -> Result<usize> {
         unsafe {
-            e_raw(syscall!(
+            e_raw(syscall_cp!(
                 EPOLL_PWAIT,
                 epfd,
                 events,
@@ -7,6 +7,7 @@ use crate::{
         dirent::dirent,
         errno::{EINVAL, EIO, EOPNOTSUPP},
         fcntl::{AT_EMPTY_PATH, AT_FDCWD, AT_REMOVEDIR, AT_SYMLINK_NOFOLLOW},
+        sched::sched_param,
         signal::{SIGCHLD, sigevent},
         sys_resource::{rlimit, rusage},
         sys_stat::{S_IFIFO, stat},
@@ -24,18 +25,193 @@ use crate::{
     header::{sys_utsname::utsname, time::timespec},
 };
 
+#[macro_use]
+pub(crate) mod cancel;
 mod epoll;
 mod ptrace;
 mod signal;
 mod socket;
 
+#[cfg(target_arch = "x86")]
+const SYS_CLONE: usize = 120;
+#[cfg(target_arch = "x86_64")]
 const SYS_CLONE: usize = 56;
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+const SYS_CLONE: usize = 220;
 const CLONE_VM: usize = 0x0100;
 const CLONE_FS: usize = 0x0200;
 const CLONE_FILES: usize = 0x0400;
 const CLONE_SIGHAND: usize = 0x0800;
+const CLONE_VFORK: usize = 0x4000;
 const CLONE_THREAD: usize = 0x00010000;
 
+/// Creates a child process sharing our address space, and suspends the calling
+/// thread until the child has called `execve` or exited, like `vfork`.
+///
+/// The child starts on `stack`, from which it pops an entry point and the
+/// argument to call it with. The entry point must not return.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn clone_vfork(stack: *mut usize) -> Result<pid_t> {
+    let flags = CLONE_VM | CLONE_VFORK | SIGCHLD;
+    let pid;
+    unsafe {
+        asm!("
+            # Call clone syscall
+            syscall
+
+            # Check if child or parent
+            test rax, rax
+            jnz 2f
+
+            # Load entry point and argument
+            pop rax
+            pop rdi
+
+            # Call entry point
+            call rax
+
+            # Invalid instruction if the entry point returns
+            ud2
+
+            # Return PID if parent
+            2:
+            ",
+            inout("rax") SYS_CLONE => pid,
+            inout("rdi") flags => _,
+            inout("rsi") stack => _,
+            inout("rdx") 0 => _,
+            inout("r10") 0 => _,
+            inout("r8") 0 => _,
+            out("rcx") _,
+            out("r11") _,
+        );
+    }
+    Ok(e_raw(pid)? as pid_t)
+}
+
+#[cfg(target_arch = "x86")]
+pub unsafe fn clone_vfork(stack: *mut usize) -> Result<pid_t> {
+    let flags = CLONE_VM | CLONE_VFORK | SIGCHLD;
+    let pid;
+    // The thread ID and TLS arguments in edx, esi and edi are ignored without
+    // the flags asking for them.
+    unsafe {
+        asm!("
+            # Call clone syscall
+            int 0x80
+
+            # Check if child or parent
+            test eax, eax
+            jnz 2f
+
+            # Load entry point and argument
+            pop eax
+            pop ecx
+
+            # Pass the argument on a 16-byte aligned stack
+            and esp, -16
+            sub esp, 12
+            push ecx
+
+            # Call entry point
+            call eax
+
+            # Invalid instruction if the entry point returns
+            ud2
+
+            # Return PID if parent
+            2:
+            ",
+            inout("eax") SYS_CLONE => pid,
+            in("ebx") flags,
+            inout("ecx") stack => _,
+            in("edx") 0,
+            in("edi") 0,
+        );
+    }
+    Ok(e_raw(pid)? as pid_t)
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn clone_vfork(stack: *mut usize) -> Result<pid_t> {
+    let flags = CLONE_VM | CLONE_VFORK | SIGCHLD;
+    let pid;
+    unsafe {
+        asm!("
+            // Call clone syscall
+            svc 0
+
+            // Check if child or parent
+            cbnz x0, 2f
+
+            // Load entry point and argument
+            ldp x1, x0, [sp], #16
+
+            // Call entry point
+            blr x1
+
+            // Invalid instruction if the entry point returns
+            udf #0
+
+            // Return PID if parent
+            2:
+            ",
+            inout("x0") flags => pid,
+            inout("x1") stack => _,
+            in("x2") 0,
+            in("x3") 0,
+            in("x4") 0,
+            in("x8") SYS_CLONE,
+        );
+    }
+    Ok(e_raw(pid)? as pid_t)
+}
+
+#[cfg(target_arch = "riscv64")]
+pub unsafe fn clone_vfork(stack: *mut usize) -> Result<pid_t> {
+    let flags = CLONE_VM | CLONE_VFORK | SIGCHLD;
+    let pid;
+    unsafe {
+        asm!("
+            # Call clone syscall
+            ecall
+
+            # Check if child or parent
+            bnez a0, 2f
+
+            # Load entry point and argument
+            ld t0, 0(sp)
+            ld a0, 8(sp)
+            addi sp, sp, 16
+
+            # Call entry point
+            jalr t0
+
+            # Invalid instruction if the entry point returns
+            unimp
+
+            # Return PID if parent
+            2:
+            ",
+            inout("a0") flags => pid,
+            inout("a1") stack => _,
+            in("a2") 0,
+            in("a3") 0,
+            in("a4") 0,
+            in("a7") SYS_CLONE,
+            out("t0") _,
+        );
+    }
+    Ok(e_raw(pid)? as pid_t)
+}
+
+/// Tells the kernel where the list of robust mutexes held by the calling
+/// thread is, for it to mark them as having a dead owner when the thread
+/// exits.
+pub unsafe fn set_robust_list(head: *mut c_void, len: usize) -> Result<()> {
+    e_raw(unsafe { syscall!(SET_ROBUST_LIST, head, len) }).map(|_| ())
+}
+
 #[repr(C)]
 #[derive(Default)]
 struct linux_statfs {
@@ -70,7 +246,7 @@ pub struct Sys;
 impl Sys {
     pub unsafe fn ioctl(fd: c_int, request: c_ulong, out: *mut c_void) -> Result<c_int> {
         // TODO: Somehow support varargs to syscall??
-        Ok(e_raw(syscall!(IOCTL, fd, request, out))? as c_int)
+        Ok(e_raw(syscall_cp!(IOCTL, fd, request, out))? as c_int)
     }
 
     // fn times(out: *mut tms) -> clock_t {
@@ -131,7 +307,7 @@ impl Pal for Sys {
     }
 
     fn close(fildes: c_int) -> Result<()> {
-        e_raw(unsafe { syscall!(CLOSE, fildes) }).map(|_| ())
+        e_raw(unsafe { syscall_cp!(CLOSE, fildes) }).map(|_| ())
     }
 
     fn dup(fildes: c_int) -> Result<c_int> {
@@ -195,7 +371,7 @@ impl Pal for Sys {
     }
 
     fn fdatasync(fildes: c_int) -> Result<()> {
-        e_raw(unsafe { syscall!(FDATASYNC, fildes) }).map(|_| ())
+        e_raw(unsafe { syscall_cp!(FDATASYNC, fildes) }).map(|_| ())
     }
 
     fn flock(fd: c_int, operation: c_int) -> Result<()> {
@@ -260,7 +436,7 @@ impl Pal for Sys {
     }
 
     fn fcntl(fildes: c_int, cmd: c_int, arg: c_ulonglong) -> Result<c_int> {
-        Ok(e_raw(unsafe { syscall!(FCNTL, fildes, cmd, arg) })? as c_int)
+        Ok(e_raw(unsafe { syscall_cp!(FCNTL, fildes, cmd, arg) })? as c_int)
     }
 
     unsafe fn fork() -> Result<pid_t> {
@@ -273,7 +449,7 @@ impl Pal for Sys {
     }
 
     fn fsync(fildes: c_int) -> Result<()> {
-        e_raw(unsafe { syscall!(FSYNC, fildes) }).map(|_| ())
+        e_raw(unsafe { syscall_cp!(FSYNC, fildes) }).map(|_| ())
     }
 
     fn ftruncate(fildes: c_int, length: off_t) -> Result<()> {
@@ -284,7 +460,7 @@ impl Pal for Sys {
     unsafe fn futex_wait(addr: *mut u32, val: u32, deadline: Option<&timespec>) -> Result<()> {
         let deadline = deadline.map_or(0, |d| d as *const _ as usize);
         e_raw(unsafe {
-            syscall!(
+            syscall_cp!(
                 FUTEX, addr,       // uaddr
                 9,          // futex_op: FUTEX_WAIT_BITSET
                 val,        // val
@@ -302,6 +478,26 @@ impl Pal for Sys {
         })
         .map(|n| n as u32)
     }
+    #[inline]
+    unsafe fn futex_lock_pi(addr: *mut u32, deadline: Option<&timespec>) -> Result<()> {
+        let deadline = deadline.map_or(0, |d| d as *const _ as usize);
+        e_raw(unsafe {
+            syscall!(
+                FUTEX, addr,     // uaddr
+                6,        // futex_op: FUTEX_LOCK_PI
+                0,        // val: ignored
+                deadline  // timeout: deadline on CLOCK_REALTIME
+            )
+        })
+        .map(|_| ())
+    }
+    #[inline]
+    unsafe fn futex_unlock_pi(addr: *mut u32) -> Result<()> {
+        e_raw(unsafe {
+            syscall!(FUTEX, addr, 7 /* FUTEX_UNLOCK_PI */)
+        })
+        .map(|_| ())
+    }
 
     unsafe fn futimens(fd: c_int, times: *const timespec) -> Result<()> {
         e_raw(unsafe { syscall!(UTIMENSAT, fd, ptr::null::<c_char>(), times, 0) }).map(|_| ())
@@ -532,7 +728,7 @@ impl Pal for Sys {
     }
 
     unsafe fn msync(addr: *mut c_void, len: usize, flags: c_int) -> Result<()> {
-        e_raw(syscall!(MSYNC, addr, len, flags)).map(|_| ())
+        e_raw(syscall_cp!(MSYNC, addr, len, flags)).map(|_| ())
     }
 
     unsafe fn munlock(addr: *const c_void, len: usize) -> Result<()> {
@@ -552,11 +748,11 @@ impl Pal for Sys {
     }
 
     unsafe fn nanosleep(rqtp: *const timespec, rmtp: *mut timespec) -> Result<()> {
-        e_raw(unsafe { syscall!(NANOSLEEP, rqtp, rmtp) }).map(|_| ())
+        e_raw(unsafe { syscall_cp!(NANOSLEEP, rqtp, rmtp) }).map(|_| ())
     }
 
     fn open(path: CStr, oflag: c_int, mode: mode_t) -> Result<c_int> {
-        e_raw(unsafe { syscall!(OPENAT, AT_FDCWD, path.as_ptr(), oflag, mode) })
+        e_raw(unsafe { syscall_cp!(OPENAT, AT_FDCWD, path.as_ptr(), oflag, mode) })
             .map(|fd| fd as c_int)
     }
 
@@ -655,10 +851,10 @@ impl Pal for Sys {
     }
 
     fn read(fildes: c_int, buf: &mut [u8]) -> Result<usize> {
-        e_raw(unsafe { syscall!(READ, fildes, buf.as_mut_ptr(), buf.len()) })
+        e_raw(unsafe { syscall_cp!(READ, fildes, buf.as_mut_ptr(), buf.len()) })
     }
     fn pread(fildes: c_int, buf: &mut [u8], off: off_t) -> Result<usize> {
-        e_raw(unsafe { syscall!(PREAD64, fildes, buf.as_mut_ptr(), buf.len(), off) })
+        e_raw(unsafe { syscall_cp!(PREAD64, fildes, buf.as_mut_ptr(), buf.len(), off) })
     }
 
     fn readlink(pathname: CStr, out: &mut [u8]) -> Result<usize> {
@@ -727,6 +923,56 @@ impl Pal for Sys {
         e_raw(unsafe { syscall!(UNLINKAT, AT_FDCWD, path.as_ptr(), AT_REMOVEDIR) }).map(|_| ())
     }
 
+    fn sched_get_priority_max(policy: c_int) -> Result<c_int> {
+        e_raw(unsafe { syscall!(SCHED_GET_PRIORITY_MAX, policy) }).map(|prio| prio as c_int)
+    }
+
+    fn sched_get_priority_min(policy: c_int) -> Result<c_int> {
+        e_raw(unsafe { syscall!(SCHED_GET_PRIORITY_MIN, policy) }).map(|prio| prio as c_int)
+    }
+
+    fn sched_getaffinity(pid: pid_t, mut mask: Out<[u8]>) -> Result<usize> {
+        e_raw(unsafe {
+            syscall!(
+                SCHED_GETAFFINITY,
+                pid,
+                mask.as_mut_ptr().len(),
+                mask.as_mut_ptr().as_mut_ptr()
+            )
+        })
+    }
+
+    fn sched_getcpu() -> Result<c_int> {
+        let mut cpu: c_uint = 0;
+        e_raw(unsafe { syscall!(GETCPU, &mut cpu as *mut c_uint, 0, 0) })?;
+        Ok(cpu as c_int)
+    }
+
+    fn sched_getparam(pid: pid_t, mut param: Out<sched_param>) -> Result<()> {
+        e_raw(unsafe { syscall!(SCHED_GETPARAM, pid, param.as_mut_ptr()) }).map(|_| ())
+    }
+
+    fn sched_getscheduler(pid: pid_t) -> Result<c_int> {
+        e_raw(unsafe { syscall!(SCHED_GETSCHEDULER, pid) }).map(|policy| policy as c_int)
+    }
+
+    fn sched_rr_get_interval(pid: pid_t, mut interval: Out<timespec>) -> Result<()> {
+        e_raw(unsafe { syscall!(SCHED_RR_GET_INTERVAL, pid, interval.as_mut_ptr()) }).map(|_| ())
+    }
+
+    fn sched_setaffinity(pid: pid_t, mask: &[u8]) -> Result<()> {
+        e_raw(unsafe { syscall!(SCHED_SETAFFINITY, pid, mask.len(), mask.as_ptr()) }).map(|_| ())
+    }
+
+    fn sched_setparam(pid: pid_t, param: &sched_param) -> Result<()> {
+        e_raw(unsafe { syscall!(SCHED_SETPARAM, pid, param as *const sched_param) }).map(|_| ())
+    }
+
+    fn sched_setscheduler(pid: pid_t, policy: c_int, param: &sched_param) -> Result<()> {
+        e_raw(unsafe { syscall!(SCHED_SETSCHEDULER, pid, policy, param as *const sched_param) })
+            .map(|_| ())
+    }
+
     fn sched_yield() -> Result<()> {
         e_raw(unsafe { syscall!(SCHED_YIELD) }).map(|_| ())
     }
@@ -818,7 +1064,7 @@ impl Pal for Sys {
 
     fn waitpid(pid: pid_t, stat_loc: Option<Out<c_int>>, options: c_int) -> Result<pid_t> {
         e_raw(unsafe {
-            syscall!(
+            syscall_cp!(
                 WAIT4,
                 pid,
                 stat_loc.map_or(core::ptr::null_mut(), |mut o| o.as_mut_ptr()),
@@ -830,10 +1076,10 @@ impl Pal for Sys {
     }
 
     fn write(fildes: c_int, buf: &[u8]) -> Result<usize> {
-        e_raw(unsafe { syscall!(WRITE, fildes, buf.as_ptr(), buf.len()) })
+        e_raw(unsafe { syscall_cp!(WRITE, fildes, buf.as_ptr(), buf.len()) })
     }
     fn pwrite(fildes: c_int, buf: &[u8], off: off_t) -> Result<usize> {
-        e_raw(unsafe { syscall!(PWRITE64, fildes, buf.as_ptr(), buf.len(), off) })
+        e_raw(unsafe { syscall_cp!(PWRITE64, fildes, buf.as_ptr(), buf.len(), off) })
     }
 
     fn verify() -> bool {
@@ -124,7 +124,7 @@ impl PalSignal for Sys {
 
     fn sigsuspend(mask: &sigset_t) -> Errno {
         unsafe {
-            e_raw(syscall!(
+            e_raw(syscall_cp!(
                 RT_SIGSUSPEND,
                 mask as *const sigset_t,
                 size_of::<sigset_t>()
@@ -139,7 +139,7 @@ impl PalSignal for Sys {
         tp: Option<&timespec>,
     ) -> Result<()> {
         unsafe {
-            e_raw(syscall!(
+            e_raw(syscall_cp!(
                 RT_SIGTIMEDWAIT,
                 set as *const _,
                 sig.map_or_else(core::ptr::null_mut, |s| s as *mut _),
@@ -1,7 +1,10 @@
 use super::{Sys, e_raw};
 use crate::{
     error::Result,
-    header::sys_socket::{msghdr, sockaddr, socklen_t},
+    header::{
+        sys_socket::{mmsghdr, msghdr, sockaddr, socklen_t},
+        time::timespec,
+    },
     platform::{PalSocket, types::*},
 };
 
@@ -11,7 +14,16 @@ impl PalSocket for Sys {
         address: *mut sockaddr,
         address_len: *mut socklen_t,
     ) -> Result<c_int> {
-        Ok(e_raw(syscall!(ACCEPT, socket, address, address_len))? as c_int)
+        Ok(e_raw(syscall_cp!(ACCEPT, socket, address, address_len))? as c_int)
+    }
+
+    unsafe fn accept4(
+        socket: c_int,
+        address: *mut sockaddr,
+        address_len: *mut socklen_t,
+        flags: c_int,
+    ) -> Result<c_int> {
+        Ok(e_raw(syscall_cp!(ACCEPT4, socket, address, address_len, flags))? as c_int)
     }
 
     unsafe fn bind(socket: c_int, address: *const sockaddr, address_len: socklen_t) -> Result<()> {
@@ -24,7 +36,7 @@ impl PalSocket for Sys {
         address: *const sockaddr,
         address_len: socklen_t,
     ) -> Result<c_int> {
-        Ok(e_raw(syscall!(CONNECT, socket, address, address_len))? as c_int)
+        Ok(e_raw(syscall_cp!(CONNECT, socket, address, address_len))? as c_int)
     }
 
     unsafe fn getpeername(
@@ -78,7 +90,7 @@ impl PalSocket for Sys {
         address: *mut sockaddr,
         address_len: *mut socklen_t,
     ) -> Result<usize> {
-        e_raw(syscall!(
+        e_raw(syscall_cp!(
             RECVFROM,
             socket,
             buf,
@@ -89,12 +101,31 @@ impl PalSocket for Sys {
         ))
     }
 
+    unsafe fn recvmmsg(
+        socket: c_int,
+        msgvec: *mut mmsghdr,
+        vlen: c_uint,
+        flags: c_int,
+        timeout: *mut timespec,
+    ) -> Result<usize> {
+        e_raw(syscall_cp!(RECVMMSG, socket, msgvec, vlen, flags, timeout))
+    }
+
     unsafe fn recvmsg(socket: c_int, msg: *mut msghdr, flags: c_int) -> Result<usize> {
-        e_raw(syscall!(RECVMSG, socket, msg, flags))
+        e_raw(syscall_cp!(RECVMSG, socket, msg, flags))
+    }
+
+    unsafe fn sendmmsg(
+        socket: c_int,
+        msgvec: *mut mmsghdr,
+        vlen: c_uint,
+        flags: c_int,
+    ) -> Result<usize> {
+        e_raw(syscall_cp!(SENDMMSG, socket, msgvec, vlen, flags))
     }
 
     unsafe fn sendmsg(socket: c_int, msg: *const msghdr, flags: c_int) -> Result<usize> {
-        e_raw(syscall!(SENDMSG, socket, msg, flags))
+        e_raw(syscall_cp!(SENDMSG, socket, msg, flags))
     }
 
     unsafe fn sendto(
@@ -105,7 +136,7 @@ impl PalSocket for Sys {
         dest_addr: *const sockaddr,
         dest_len: socklen_t,
     ) -> Result<usize> {
-        e_raw(syscall!(
+        e_raw(syscall_cp!(
             SENDTO, socket, buf, len, flags, dest_addr, dest_len
         ))
     }
@@ -3,6 +3,7 @@ use crate::{
     c_str::CStr,
     error::{Errno, Result},
     header::{
+        sched::sched_param,
         signal::sigevent,
         sys_resource::{rlimit, rusage},
         sys_stat::stat,
@@ -100,6 +101,8 @@ pub trait Pal {
 
     unsafe fn futex_wait(addr: *mut u32, val: u32, deadline: Option<&timespec>) -> Result<()>;
     unsafe fn futex_wake(addr: *mut u32, num: u32) -> Result<u32>;
+    unsafe fn futex_lock_pi(addr: *mut u32, deadline: Option<&timespec>) -> Result<()>;
+    unsafe fn futex_unlock_pi(addr: *mut u32) -> Result<()>;
 
     unsafe fn futimens(fd: c_int, times: *const timespec) -> Result<()>;
 
@@ -249,6 +252,27 @@ pub trait Pal {
 
     fn rmdir(path: CStr) -> Result<()>;
 
+    fn sched_get_priority_max(policy: c_int) -> Result<c_int>;
+
+    fn sched_get_priority_min(policy: c_int) -> Result<c_int>;
+
+    /// Returns the number of bytes of the mask the kernel wrote.
+    fn sched_getaffinity(pid: pid_t, mask: Out<[u8]>) -> Result<usize>;
+
+    fn sched_getcpu() -> Result<c_int>;
+
+    fn sched_getparam(pid: pid_t, param: Out<sched_param>) -> Result<()>;
+
+    fn sched_getscheduler(pid: pid_t) -> Result<c_int>;
+
+    fn sched_rr_get_interval(pid: pid_t, interval: Out<timespec>) -> Result<()>;
+
+    fn sched_setaffinity(pid: pid_t, mask: &[u8]) -> Result<()>;
+
+    fn sched_setparam(pid: pid_t, param: &sched_param) -> Result<()>;
+
+    fn sched_setscheduler(pid: pid_t, policy: c_int, param: &sched_param) -> Result<()>;
+
     fn sched_yield() -> Result<()>;
 
     unsafe fn setgroups(size: size_t, list: *const gid_t) -> Result<()>;
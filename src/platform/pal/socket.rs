@@ -1,6 +1,9 @@
 use crate::{
     error::Result,
-    header::sys_socket::{msghdr, sockaddr, socklen_t},
+    header::{
+        sys_socket::{mmsghdr, msghdr, sockaddr, socklen_t},
+        time::timespec,
+    },
     platform::{Pal, types::*},
 };
 
@@ -11,6 +14,13 @@ pub trait PalSocket: Pal {
         address_len: *mut socklen_t,
     ) -> Result<c_int>;
 
+    unsafe fn accept4(
+        socket: c_int,
+        address: *mut sockaddr,
+        address_len: *mut socklen_t,
+        flags: c_int,
+    ) -> Result<c_int>;
+
     unsafe fn bind(socket: c_int, address: *const sockaddr, address_len: socklen_t) -> Result<()>;
 
     unsafe fn connect(
@@ -50,8 +60,23 @@ pub trait PalSocket: Pal {
         address_len: *mut socklen_t,
     ) -> Result<usize>;
 
+    unsafe fn recvmmsg(
+        socket: c_int,
+        msgvec: *mut mmsghdr,
+        vlen: c_uint,
+        flags: c_int,
+        timeout: *mut timespec,
+    ) -> Result<usize>;
+
     unsafe fn recvmsg(socket: c_int, msg: *mut msghdr, flags: c_int) -> Result<usize>;
 
+    unsafe fn sendmmsg(
+        socket: c_int,
+        msgvec: *mut mmsghdr,
+        vlen: c_uint,
+        flags: c_int,
+    ) -> Result<usize>;
+
     unsafe fn sendmsg(socket: c_int, msg: *const msghdr, flags: c_int) -> Result<usize>;
 
     unsafe fn sendto(
@@ -21,6 +21,14 @@ use redox_rt::{
 };
 use syscall::{data::Stat, error::*, flag::*};
 
+/// The process an image is loaded into, along with the signal state it starts with.
+pub struct ExecTarget<'a> {
+    pub thread_fd: &'a FdGuardUpper,
+    pub proc_fd: &'a FdGuardUpper,
+    pub sigprocmask: u64,
+    pub sigignmask: u64,
+}
+
 fn fexec_impl(
     exec_file: FdGuardUpper,
     path: &[u8],
@@ -29,13 +37,14 @@ fn fexec_impl(
     total_args_envs_size: usize,
     extrainfo: &ExtraInfo,
     interp_override: Option<InterpOverride>,
-) -> Result<Infallible> {
+    target: &ExecTarget,
+) -> Result<()> {
     let memory = FdGuard::open("/scheme/memory", 0)?.to_upper()?;
 
     let addrspace_selection_fd = match redox_rt::proc::fexec_impl(
         exec_file,
-        &RtTcb::current().thread_fd(),
-        redox_rt::current_proc_fd(),
+        target.thread_fd,
+        target.proc_fd,
         &memory,
         path,
         args.iter().rev(),
@@ -57,7 +66,7 @@ fn fexec_impl(
             // null-terminated. Violating this should therefore give the "format error" ENOEXEC.
             let path_cstr = CStr::from_bytes_with_nul(&path).map_err(|_| Error::new(ENOEXEC))?;
 
-            return execve(
+            return load(
                 Executable::AtPath(path_cstr),
                 ArgEnv::Parsed {
                     total_args_envs_size,
@@ -65,15 +74,17 @@ fn fexec_impl(
                     envs,
                 },
                 Some(new_interp_override),
+                target,
             );
         }
     };
     drop(memory);
 
-    // Dropping this FD will cause the address space switch.
+    // Dropping this FD will cause the address space switch, so this only returns when loading
+    // into another process.
     drop(addrspace_selection_fd);
 
-    unreachable!();
+    Ok(())
 }
 pub enum ArgEnv<'a> {
     C {
@@ -97,6 +108,27 @@ pub fn execve(
     arg_env: ArgEnv,
     interp_override: Option<InterpOverride>,
 ) -> Result<Infallible> {
+    let target = ExecTarget {
+        thread_fd: RtTcb::current().thread_fd(),
+        proc_fd: redox_rt::current_proc_fd(),
+        sigprocmask: redox_rt::signal::get_sigmask().unwrap(),
+        sigignmask: redox_rt::signal::get_sigignmask_to_inherit(),
+    };
+    load(exec, arg_env, interp_override, &target)?;
+    unreachable!();
+}
+
+/// Loads an image into `target`, which is either the calling process, replacing the current
+/// image, or a new process that has not been started yet, as for `posix_spawn`.
+///
+/// File descriptors are closed on exec in the file table of the calling thread, which must be
+/// the one `target` uses.
+pub fn load(
+    exec: Executable<'_>,
+    arg_env: ArgEnv,
+    interp_override: Option<InterpOverride>,
+    target: &ExecTarget,
+) -> Result<()> {
     // NOTE: We must omit O_CLOEXEC and close manually, otherwise it will be closed before we
     // have even read it!
     let (mut image_file, arg0) = match exec {
@@ -248,6 +280,11 @@ pub fn execve(
                 Err(_) => continue,
             };
 
+            // The image itself is closed once it has been loaded.
+            if fd == image_file.fd as usize {
+                continue;
+            }
+
             let flags = syscall::fcntl(fd, F_GETFD, 0)?;
 
             if flags & O_CLOEXEC == O_CLOEXEC {
@@ -260,16 +297,14 @@ pub fn execve(
     let exec_fd_guard = FdGuard::new(image_file.fd as usize).to_upper().unwrap();
     core::mem::forget(image_file);
 
-    let sigprocmask = redox_rt::signal::get_sigmask().unwrap();
-
     let extrainfo = ExtraInfo {
         cwd: Some(&cwd),
         default_scheme: Some(&default_scheme),
-        sigignmask: redox_rt::signal::get_sigignmask_to_inherit(),
-        sigprocmask,
+        sigignmask: target.sigignmask,
+        sigprocmask: target.sigprocmask,
         umask: redox_rt::sys::get_umask(),
-        thr_fd: RtTcb::current().thread_fd().as_raw_fd(),
-        proc_fd: redox_rt::current_proc_fd().as_raw_fd(),
+        thr_fd: target.thread_fd.as_raw_fd(),
+        proc_fd: target.proc_fd.as_raw_fd(),
     };
     fexec_impl(
         exec_fd_guard,
@@ -279,6 +314,7 @@ pub fn execve(
         total_args_envs_size,
         &extrainfo,
         interp_override,
+        target,
     )
 }
 
@@ -32,6 +32,7 @@ use crate::{
         fcntl::{self, AT_FDCWD, AT_SYMLINK_NOFOLLOW, O_CREAT, O_RDONLY, O_RDWR},
         limits,
         pthread::{pthread_cancel, pthread_create},
+        sched::sched_param,
         signal::{NSIG, SIGEV_NONE, SIGEV_SIGNAL, SIGEV_THREAD, SIGRTMIN, sigevent},
         stdio::RENAME_NOREPLACE,
         sys_mman::{MAP_ANONYMOUS, MAP_FAILED, PROT_READ, PROT_WRITE},
@@ -75,7 +76,7 @@ fn cvt_uid(id: c_int) -> Result<Option<u32>> {
 mod clone;
 mod epoll;
 mod event;
-mod exec;
+pub(crate) mod exec;
 mod extra;
 mod libcscheme;
 mod libredox;
@@ -358,6 +359,13 @@ impl Pal for Sys {
     unsafe fn futex_wake(addr: *mut u32, num: u32) -> Result<u32> {
         Ok(redox_rt::sys::sys_futex_wake(addr, num)?)
     }
+    unsafe fn futex_lock_pi(_addr: *mut u32, _deadline: Option<&timespec>) -> Result<()> {
+        // TODO: Redox has no priority-inheriting futexes yet.
+        Err(Errno(ENOSYS))
+    }
+    unsafe fn futex_unlock_pi(_addr: *mut u32) -> Result<()> {
+        Err(Errno(ENOSYS))
+    }
 
     unsafe fn futimens(fd: c_int, times: *const timespec) -> Result<()> {
         libredox::futimens(fd as usize, times)?;
@@ -983,6 +991,56 @@ impl Pal for Sys {
         Ok(())
     }
 
+    fn sched_get_priority_max(_policy: c_int) -> Result<c_int> {
+        // TODO: Redox does not expose scheduling priorities yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_get_priority_min(_policy: c_int) -> Result<c_int> {
+        // TODO: Redox does not expose scheduling priorities yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_getaffinity(_pid: pid_t, _mask: Out<[u8]>) -> Result<usize> {
+        // TODO: Redox does not support pinning threads to CPUs yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_getcpu() -> Result<c_int> {
+        // TODO: Redox does not tell which CPU a thread runs on yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_getparam(_pid: pid_t, _param: Out<sched_param>) -> Result<()> {
+        // TODO: Redox does not expose scheduling priorities yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_getscheduler(_pid: pid_t) -> Result<c_int> {
+        // TODO: Redox does not expose scheduling policies yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_rr_get_interval(_pid: pid_t, _interval: Out<timespec>) -> Result<()> {
+        // TODO: Redox does not expose scheduling policies yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_setaffinity(_pid: pid_t, _mask: &[u8]) -> Result<()> {
+        // TODO: Redox does not support pinning threads to CPUs yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_setparam(_pid: pid_t, _param: &sched_param) -> Result<()> {
+        // TODO: Redox does not expose scheduling priorities yet.
+        Err(Errno(ENOSYS))
+    }
+
+    fn sched_setscheduler(_pid: pid_t, _policy: c_int, _param: &sched_param) -> Result<()> {
+        // TODO: Redox does not expose scheduling policies yet.
+        Err(Errno(ENOSYS))
+    }
+
     fn sched_yield() -> Result<()> {
         syscall::sched_yield()?;
         Ok(())
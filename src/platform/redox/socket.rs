@@ -23,12 +23,14 @@ use crate::{
         string::strnlen,
         sys_socket::{
             CMSG_ALIGN, CMSG_DATA, CMSG_FIRSTHDR, CMSG_LEN, CMSG_NXTHDR, CMSG_SPACE, cmsghdr,
-            constants::*, msghdr, sa_family_t, sockaddr, socklen_t, ucred,
+            constants::*, mmsghdr, msghdr, sa_family_t, sockaddr, socklen_t, ucred,
         },
         sys_time::timeval,
         sys_uio::iovec,
         sys_un::sockaddr_un,
+        time::{CLOCK_MONOTONIC, timespec},
     },
+    out::Out,
 };
 
 macro_rules! bind_or_connect {
@@ -223,6 +225,20 @@ fn socket_kind(mut kind: c_int) -> (c_int, usize) {
     (kind, flags)
 }
 
+/// Applies the `SOCK_CLOEXEC` and `SOCK_NONBLOCK` bits of `flags` to `fd`,
+/// as opening it with them would have.
+fn set_socket_flags(fd: &FdGuard, flags: c_int) -> Result<()> {
+    let (_, flags) = socket_kind(flags);
+    if flags & O_CLOEXEC == O_CLOEXEC {
+        fd.fcntl(F_SETFD, O_CLOEXEC)?;
+    }
+    if flags & O_NONBLOCK == O_NONBLOCK {
+        let status = fd.fcntl(F_GETFL, 0)?;
+        fd.fcntl(F_SETFL, status | O_NONBLOCK)?;
+    }
+    Ok(())
+}
+
 unsafe fn serialize_payload_to_stream(
     msg_stream: &mut Vec<u8>,
     iovs: &[iovec],
@@ -398,6 +414,7 @@ unsafe fn deserialize_ancillary_data_from_stream(
     msg_stream: &[u8],
     cursor: &mut usize,
     cmsg_space_provided: usize,
+    cloexec: bool,
 ) -> Result<()> {
     let mut current_cmsg_ptr_in_user_buf = if !mhdr.msg_control.is_null() && cmsg_space_provided > 0
     {
@@ -446,8 +463,12 @@ unsafe fn deserialize_ancillary_data_from_stream(
 
                 for _ in 0..fd_count {
                     // Call syscall::dup to duplicate the fd
-                    let new_fd = syscall::dup(socket as usize, b"recvfd")?;
-                    temp_posix_cmsg_data_buf.extend_from_slice(&(new_fd as c_int).to_le_bytes());
+                    let new_fd = FdGuard::new(syscall::dup(socket as usize, b"recvfd")?);
+                    if cloexec {
+                        new_fd.fcntl(F_SETFD, O_CLOEXEC)?;
+                    }
+                    temp_posix_cmsg_data_buf
+                        .extend_from_slice(&(new_fd.take() as c_int).to_le_bytes());
                 }
                 actual_posix_cmsg_data_len = temp_posix_cmsg_data_buf.len();
             }
@@ -524,6 +545,20 @@ impl PalSocket for Sys {
         Ok(stream as c_int)
     }
 
+    unsafe fn accept4(
+        socket: c_int,
+        address: *mut sockaddr,
+        address_len: *mut socklen_t,
+        flags: c_int,
+    ) -> Result<c_int> {
+        if flags & !(SOCK_CLOEXEC | SOCK_NONBLOCK) != 0 {
+            return Err(Errno(EINVAL));
+        }
+        let stream = FdGuard::new(Self::accept(socket, address, address_len)? as usize);
+        set_socket_flags(&stream, flags)?;
+        Ok(stream.take() as c_int)
+    }
+
     unsafe fn bind(socket: c_int, address: *const sockaddr, address_len: socklen_t) -> Result<()> {
         match (*address).sa_family as c_int {
             AF_INET => {
@@ -817,6 +852,48 @@ impl PalSocket for Sys {
         }
     }
 
+    unsafe fn recvmmsg(
+        socket: c_int,
+        msgvec: *mut mmsghdr,
+        vlen: c_uint,
+        flags: c_int,
+        timeout: *mut timespec,
+    ) -> Result<usize> {
+        // The timeout is only checked after each message, as on Linux
+        let deadline = if timeout.is_null() {
+            None
+        } else {
+            let mut now = timespec::default();
+            Sys::clock_gettime(CLOCK_MONOTONIC, Out::from_mut(&mut now))?;
+            Some(timespec::add(now, *timeout).ok_or(Errno(EINVAL))?)
+        };
+
+        let mut flags = flags;
+        let mut count = 0;
+        while count < vlen as usize {
+            let entry = &mut *msgvec.add(count);
+            match Self::recvmsg(socket, &mut entry.msg_hdr, flags & !MSG_WAITFORONE) {
+                Ok(len) => entry.msg_len = len as c_uint,
+                // The error is left for the next call to report
+                Err(_) if count > 0 => break,
+                Err(err) => return Err(err),
+            }
+            count += 1;
+
+            if flags & MSG_WAITFORONE == MSG_WAITFORONE {
+                flags |= MSG_DONTWAIT;
+            }
+            if let Some(deadline) = &deadline {
+                let mut now = timespec::default();
+                Sys::clock_gettime(CLOCK_MONOTONIC, Out::from_mut(&mut now))?;
+                if (now.tv_sec, now.tv_nsec) >= (deadline.tv_sec, deadline.tv_nsec) {
+                    break;
+                }
+            }
+        }
+        Ok(count)
+    }
+
     unsafe fn recvmsg(socket: c_int, msg: *mut msghdr, flags: c_int) -> Result<usize> {
         if msg.is_null() {
             return Err(Errno(EINVAL));
@@ -859,8 +936,13 @@ impl PalSocket for Sys {
         msg_stream[cursor..cursor + mem::size_of::<usize>()]
             .copy_from_slice(&(mhdr.msg_controllen as usize).to_le_bytes());
 
-        // Read the message stream.
-        let metadata = [SocketCall::RecvMsg as u64, flags as u64];
+        // Read the message stream. Received fds are marked close-on-exec
+        // here, rather than by the scheme.
+        let cloexec = flags & MSG_CMSG_CLOEXEC == MSG_CMSG_CLOEXEC;
+        let metadata = [
+            SocketCall::RecvMsg as u64,
+            (flags & !MSG_CMSG_CLOEXEC) as u64,
+        ];
         let call_flags = CallFlags::empty();
         let actual_read_len =
             redox_rt::sys::sys_call(socket as usize, &mut msg_stream, call_flags, &metadata)?;
@@ -893,6 +975,7 @@ impl PalSocket for Sys {
                 &msg_stream,
                 &mut cursor,
                 cmsg_space_provided_by_user as usize,
+                cloexec,
             )?;
         } else {
             mhdr.msg_controllen = 0; // No ancillary data
@@ -900,6 +983,26 @@ impl PalSocket for Sys {
         Ok(actual_payload_bytes_written_to_iov)
     }
 
+    unsafe fn sendmmsg(
+        socket: c_int,
+        msgvec: *mut mmsghdr,
+        vlen: c_uint,
+        flags: c_int,
+    ) -> Result<usize> {
+        let mut count = 0;
+        while count < vlen as usize {
+            let entry = &mut *msgvec.add(count);
+            match Self::sendmsg(socket, &entry.msg_hdr, flags) {
+                Ok(len) => entry.msg_len = len as c_uint,
+                // The error is left for the next call to report
+                Err(_) if count > 0 => break,
+                Err(err) => return Err(err),
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
     unsafe fn sendmsg(socket: c_int, msg: *const msghdr, flags: c_int) -> Result<usize> {
         if msg.is_null() {
             return Err(Errno(EINVAL));
@@ -2,32 +2,41 @@
 
 use core::{
     cell::{Cell, UnsafeCell},
-    mem::{MaybeUninit, offset_of},
+    mem::{self, MaybeUninit, offset_of},
     ptr::{self, NonNull, addr_of},
     sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
-use alloc::{boxed::Box, collections::BTreeMap};
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
 
 use crate::{
     error::Errno,
-    header::{errno::*, pthread as header, sched::sched_param, sys_mman},
+    header::{
+        errno::*,
+        pthread as header,
+        sched::{self, sched_param},
+        signal::{SA_RESTART, SA_SIGINFO, sigaction, siginfo_t},
+        sys_mman,
+    },
     ld_so::{
         ExpectTlsFree,
         linker::Linker,
         tcb::{Master, Tcb},
     },
-    platform::{Pal, Sys, types::*},
+    out::Out,
+    platform::{Pal, PalSignal, Sys, types::*},
 };
 
-use crate::sync::{Mutex, waitval::Waitval};
+use crate::sync::{Mutex, Once, pthread_mutex::RobustListHead, waitval::Waitval};
 
 /// Called only by the main thread, as part of relibc_start.
 pub unsafe fn init() {
     let mut thread = Pthread {
         waitval: Waitval::new(),
-        has_enabled_cancelation: AtomicBool::new(false),
+        has_enabled_cancelation: AtomicBool::new(true),
+        has_async_cancelation: AtomicBool::new(false),
         has_queued_cancelation: AtomicBool::new(false),
+        in_cancelation_point: AtomicBool::new(false),
         flags: PthreadFlags::empty().bits().into(),
 
         //index: FIRST_THREAD_IDX,
@@ -37,6 +46,8 @@ pub unsafe fn init() {
         stack_size: 0,
 
         os_tid: UnsafeCell::new(Sys::current_os_tid()),
+        robust_list: UnsafeCell::new(RobustListHead::new()),
+        prio_ceilings: UnsafeCell::new(PrioCeilings::new()),
     };
 
     #[cfg(target_os = "redox")]
@@ -47,20 +58,48 @@ pub unsafe fn init() {
         thread.stack_size = STACK_SIZE;
     }
 
-    Tcb::current()
-        .expect_notls("no TCB present for main thread")
-        .pthread = thread;
+    let tcb = Tcb::current().expect_notls("no TCB present for main thread");
+    tcb.pthread = thread;
+    init_robust_list(&tcb.pthread);
+
+    THREADS_READY.store(true, Ordering::Release);
 }
 
-//static NEXT_INDEX: AtomicU32 = AtomicU32::new(FIRST_THREAD_IDX + 1);
-//const FIRST_THREAD_IDX: usize = 1;
+/// Set once the main thread has its thread state, from which point every thread has one.
+static THREADS_READY: AtomicBool = AtomicBool::new(false);
+
+/// Whether `current_thread` can be called, which is not the case in the dynamic linker, before
+/// it has set up TLS.
+pub fn threads_ready() -> bool {
+    THREADS_READY.load(Ordering::Acquire)
+}
 
-pub unsafe fn terminate_from_main_thread() {
-    for (_, tcb) in OS_TID_TO_PTHREAD.lock().iter() {
-        let _ = cancel(&(*tcb.0).pthread);
+/// Called in the child after `fork`, whose only thread is a copy of the calling one.
+pub unsafe fn init_after_fork() {
+    crate::sync::pthread_mutex::forget_os_tid();
+
+    // The kernel forgets the robust list, and the child doesn't own the mutexes in it anyway.
+    init_robust_list(current_thread().expect("current thread not present"));
+}
+
+/// Empties the list of robust mutexes held by the calling thread, and registers it with the
+/// kernel for them to be marked as having a dead owner however the thread dies.
+unsafe fn init_robust_list(thread: &Pthread) {
+    let head = thread.robust_list.get();
+    RobustListHead::reset(head);
+
+    #[cfg(target_os = "linux")]
+    {
+        let _ = crate::platform::sys::set_robust_list(
+            head.cast(),
+            core::mem::size_of::<RobustListHead>(),
+        );
     }
 }
 
+//static NEXT_INDEX: AtomicU32 = AtomicU32::new(FIRST_THREAD_IDX + 1);
+//const FIRST_THREAD_IDX: usize = 1;
+
 bitflags::bitflags! {
     pub struct PthreadFlags: usize {
         const DETACHED = 1;
@@ -72,12 +111,39 @@ pub struct Pthread {
     pub(crate) waitval: Waitval<Retval>,
     pub(crate) has_queued_cancelation: AtomicBool,
     pub(crate) has_enabled_cancelation: AtomicBool,
+    pub(crate) has_async_cancelation: AtomicBool,
+    // Whether the thread is within a cancellation point, where it acts on cancellation requests
+    // as they arrive.
+    pub(crate) in_cancelation_point: AtomicBool,
     pub(crate) flags: AtomicUsize,
 
     pub(crate) stack_base: *mut c_void,
     pub(crate) stack_size: usize,
 
     pub os_tid: UnsafeCell<OsTid>,
+
+    pub(crate) robust_list: UnsafeCell<RobustListHead>,
+    pub(crate) prio_ceilings: UnsafeCell<PrioCeilings>,
+}
+
+/// Priority ceilings of the `PTHREAD_PRIO_PROTECT` mutexes held by a thread.
+#[derive(Debug)]
+pub(crate) struct PrioCeilings {
+    // Priority of the thread when it holds none.
+    base: c_int,
+    held: Vec<c_int>,
+}
+
+impl PrioCeilings {
+    pub(crate) const fn new() -> Self {
+        Self {
+            base: 0,
+            held: Vec::new(),
+        }
+    }
+    fn priority(&self) -> c_int {
+        self.held.iter().copied().fold(self.base, c_int::max)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, Ord, Eq, PartialOrd, PartialEq)]
@@ -124,7 +190,7 @@ pub(crate) unsafe fn create(
     let synchronization_mutex = Mutex::locked(current_sigmask);
     let synchronization_mutex = &synchronization_mutex;
 
-    let tid_mutex = Mutex::<MaybeUninit<OsTid>>::new(MaybeUninit::uninit());
+    let tid_mutex = Mutex::<MaybeUninit<Start>>::new(MaybeUninit::uninit());
     let mut tid_guard = tid_mutex.lock();
 
     let stack_size = attrs.stacksize.next_multiple_of(Sys::getpagesize());
@@ -202,9 +268,29 @@ pub(crate) unsafe fn create(
     };
     core::mem::forget(stack_raii);
 
-    tid_guard.write(os_tid);
+    // The thread waits for its ID before running anything, so it only runs the start routine
+    // with the scheduling it was asked for.
+    let sched = if i32::from(attrs.inheritsched) == header::PTHREAD_EXPLICIT_SCHED {
+        os_tid_sched_id(os_tid)
+            .and_then(|id| Sys::sched_setscheduler(id, attrs.schedpolicy.into(), &attrs.param))
+    } else {
+        Ok(())
+    };
+    if sched.is_err() {
+        // Nothing can join it, so it cleans up after itself.
+        new_tcb
+            .pthread
+            .flags
+            .fetch_or(PthreadFlags::DETACHED.bits(), Ordering::AcqRel);
+    }
+
+    tid_guard.write(Start {
+        os_tid,
+        run: sched.is_ok(),
+    });
     drop(tid_guard);
     let _ = synchronization_mutex.lock();
+    sched?;
 
     OS_TID_TO_PTHREAD
         .lock()
@@ -212,15 +298,24 @@ pub(crate) unsafe fn create(
 
     Ok((&new_tcb.pthread) as *const _ as *mut _)
 }
+/// What a new thread is told by the thread creating it.
+#[derive(Clone, Copy)]
+struct Start {
+    os_tid: OsTid,
+    // Whether to run the start routine, or to exit as the thread could not be set up.
+    run: bool,
+}
+
 /// A shim to wrap thread entry points in logic to set up TLS, for example
 unsafe extern "C" fn new_thread_shim(
     entry_point: unsafe extern "C" fn(*mut c_void) -> *mut c_void,
     arg: *mut c_void,
     tcb: *mut Tcb,
-    mutex1: *const Mutex<MaybeUninit<OsTid>>,
+    mutex1: *const Mutex<MaybeUninit<Start>>,
     mutex2: *const Mutex<u64>,
 ) -> ! {
-    let tid = (*(&*mutex1).lock()).assume_init();
+    let start = (*(&*mutex1).lock()).assume_init();
+    let tid = start.os_tid;
 
     if let Some(tcb) = tcb.as_mut() {
         #[cfg(not(target_os = "redox"))]
@@ -245,6 +340,7 @@ unsafe extern "C" fn new_thread_shim(
     }
 
     (*tcb).pthread.os_tid.get().write(Sys::current_os_tid());
+    init_robust_list(&(*tcb).pthread);
 
     (&*mutex2).manual_unlock();
 
@@ -254,6 +350,10 @@ unsafe extern "C" fn new_thread_shim(
             .expect("failed to set procmask in child thread");
     }
 
+    if !start.run {
+        exit_current_thread(Retval(ptr::null_mut()));
+    }
+
     let retval = entry_point(arg);
 
     exit_current_thread(Retval(retval))
@@ -269,7 +369,7 @@ pub unsafe fn join(thread: &Pthread) -> Result<Retval, Errno> {
     }
 
     // Waitval starts locked, and is unlocked when the thread finishes.
-    let retval = *thread.waitval.wait();
+    let retval = *cancellation_point(|| thread.waitval.wait());
 
     // We have now awaited the thread and received its return value. POSIX states that the
     // pthread_t of this thread, will no longer be valid. In practice, we can thus deallocate the
@@ -294,20 +394,47 @@ pub fn current_thread() -> Option<&'static Pthread> {
 pub unsafe fn testcancel() {
     let this_thread = current_thread().expect("current thread not present");
 
-    if this_thread.has_queued_cancelation.load(Ordering::Acquire)
-        && this_thread.has_enabled_cancelation.load(Ordering::Acquire)
+    if this_thread.has_queued_cancelation.load(Ordering::SeqCst)
+        && this_thread.has_enabled_cancelation.load(Ordering::SeqCst)
     {
         cancel_current_thread();
     }
 }
 
+/// Runs `f`, which blocks in a way POSIX makes a cancellation point. A pending cancellation
+/// request is acted upon before `f` starts, or when interrupting a system call of `f` that has not
+/// had any effect yet. Otherwise, the request is left for the next cancellation point, so that
+/// nothing `f` did is lost, e.g. a descriptor it opened or data it read.
+pub fn cancellation_point<T>(f: impl FnOnce() -> T) -> T {
+    let Some(this) = current_thread() else {
+        return f();
+    };
+
+    // Cancellation points can nest, e.g. when one is implemented in terms of another.
+    let was_in_cancelation_point = this.in_cancelation_point.swap(true, Ordering::SeqCst);
+    unsafe { testcancel() };
+
+    let ret = f();
+
+    this.in_cancelation_point
+        .store(was_in_cancelation_point, Ordering::SeqCst);
+    ret
+}
+
 pub unsafe fn exit_current_thread(retval: Retval) -> ! {
+    let this = current_thread().expect("failed to obtain current thread when exiting");
+
+    // Cleanup handlers and destructors may call cancellation points themselves.
+    this.has_enabled_cancelation.store(false, Ordering::SeqCst);
+
     // Run pthread_cleanup_push/pthread_cleanup_pop destructors.
     header::run_destructor_stack();
 
     header::tls::run_all_destructors();
 
-    let this = current_thread().expect("failed to obtain current thread when exiting");
+    // Before the thread can be joined, which is earlier than when the kernel would do it.
+    RobustListHead::abandon(this.robust_list.get());
+
     let stack_base = this.stack_base;
     let stack_size = this.stack_size;
 
@@ -330,35 +457,138 @@ unsafe fn dealloc_thread(thread: &Pthread) {
 pub const SIGRT_RLCT_CANCEL: usize = 33;
 pub const SIGRT_RLCT_TIMER: usize = 34;
 
-unsafe extern "C" fn cancel_sighandler(_: c_int) {
-    cancel_current_thread();
+static CANCEL_SIGHANDLER: Once<()> = Once::new();
+
+/// Acts on a cancellation request interrupting the thread, unless it is deferred until the next
+/// cancellation point.
+extern "C" fn cancel_sighandler(_: c_int, _: *mut siginfo_t, context: *mut c_void) {
+    let this = current_thread().expect("current thread not present");
+
+    if !this.has_enabled_cancelation.load(Ordering::SeqCst) {
+        return;
+    }
+    if this.has_async_cancelation.load(Ordering::SeqCst) {
+        unsafe { cancel_current_thread() };
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe {
+        crate::platform::sys::cancel::cancel_interrupted_syscall(context)
+    };
+
+    // TODO: Redox system calls are not made from a known range of instructions, so whether the
+    // interrupted one has had an effect cannot be told.
+    #[cfg(target_os = "redox")]
+    if this.in_cancelation_point.load(Ordering::SeqCst) {
+        unsafe { cancel_current_thread() };
+    }
 }
-unsafe fn cancel_current_thread() {
+pub(crate) unsafe fn cancel_current_thread() -> ! {
     // Terminate the thread
     exit_current_thread(Retval(header::PTHREAD_CANCELED));
 }
 
 pub unsafe fn cancel(thread: &Pthread) -> Result<(), Errno> {
-    // TODO: What order should these atomic bools be accessed in?
-    thread.has_queued_cancelation.store(true, Ordering::Release);
+    CANCEL_SIGHANDLER.call_once(|| {
+        let action = sigaction {
+            sa_handler: Some(unsafe {
+                mem::transmute::<
+                    extern "C" fn(c_int, *mut siginfo_t, *mut c_void),
+                    extern "C" fn(c_int),
+                >(cancel_sighandler)
+            }),
+            sa_flags: (SA_SIGINFO | SA_RESTART) as c_ulong,
+            sa_restorer: None,
+            sa_mask: 0,
+        };
+        Sys::sigaction(SIGRT_RLCT_CANCEL as c_int, Some(&action), None)
+            .expect("failed to install cancellation handler");
+    });
+
+    // The target either sees the request when checking for it, or is interrupted after setting
+    // the state it checks in.
+    thread.has_queued_cancelation.store(true, Ordering::SeqCst);
 
-    if thread.has_enabled_cancelation.load(Ordering::Acquire) {
+    if thread.has_enabled_cancelation.load(Ordering::SeqCst) {
         Sys::rlct_kill(thread.os_tid.get().read(), SIGRT_RLCT_CANCEL)?;
     }
 
     Ok(())
 }
 
-pub fn set_sched_param(
-    _thread: &Pthread,
-    _policy: c_int,
-    _param: &sched_param,
-) -> Result<(), Errno> {
-    // TODO
-    Ok(())
+/// The ID the scheduling functions know `thread` by.
+fn sched_id(thread: &Pthread) -> Result<pid_t, Errno> {
+    os_tid_sched_id(unsafe { thread.os_tid.get().read() })
 }
-pub fn set_sched_priority(_thread: &Pthread, _prio: c_int) -> Result<(), Errno> {
-    // TODO
+fn os_tid_sched_id(os_tid: OsTid) -> Result<pid_t, Errno> {
+    #[cfg(target_os = "linux")]
+    {
+        Ok(os_tid.thread_id as pid_t)
+    }
+    #[cfg(target_os = "redox")]
+    {
+        // TODO: Redox can't schedule threads individually yet.
+        Err(Errno(ENOSYS))
+    }
+}
+pub fn set_sched_param(thread: &Pthread, policy: c_int, param: &sched_param) -> Result<(), Errno> {
+    Sys::sched_setscheduler(sched_id(thread)?, policy, param)
+}
+pub fn set_sched_priority(thread: &Pthread, prio: c_int) -> Result<(), Errno> {
+    let param = sched_param {
+        sched_priority: prio,
+    };
+    Sys::sched_setparam(sched_id(thread)?, &param)
+}
+pub fn get_affinity(thread: &Pthread, mask: Out<[u8]>) -> Result<(), Errno> {
+    sched::getaffinity(sched_id(thread)?, mask)
+}
+pub fn set_affinity(thread: &Pthread, mask: &[u8]) -> Result<(), Errno> {
+    Sys::sched_setaffinity(sched_id(thread)?, mask)
+}
+/// Accounts for the calling thread releasing a `PTHREAD_PRIO_PROTECT` mutex with the ceiling
+/// `old`, and taking one with the ceiling `new`. The thread runs at the highest ceiling it holds,
+/// or at its own priority if that is higher.
+///
+/// Threads under `SCHED_OTHER` only have the priority 0, so raising one to a ceiling above that
+/// fails with `EINVAL`, as it does with glibc. Such threads can then only lock mutexes with a
+/// ceiling of 0.
+pub(crate) fn change_prio_ceiling(old: Option<c_int>, new: Option<c_int>) -> Result<(), Errno> {
+    let this = current_thread().expect("current thread not present");
+    let ceilings = unsafe { &mut *this.prio_ceilings.get() };
+
+    if ceilings.held.is_empty() {
+        let mut param = MaybeUninit::uninit();
+        match Sys::sched_getparam(0, Out::from_uninit_mut(&mut param)) {
+            Ok(()) => ceilings.base = unsafe { param.assume_init() }.sched_priority,
+            // Without priorities, there is nothing to raise.
+            Err(Errno(ENOSYS)) => return Ok(()),
+            Err(error) => return Err(error),
+        }
+    }
+    if new.is_some_and(|new| new < ceilings.base) {
+        return Err(Errno(EINVAL));
+    }
+
+    let prev_priority = ceilings.priority();
+    let removed = old
+        .and_then(|old| ceilings.held.iter().position(|&held| held == old))
+        .map(|i| ceilings.held.swap_remove(i));
+    ceilings.held.extend(new);
+
+    let priority = ceilings.priority();
+    if priority != prev_priority {
+        let param = sched_param {
+            sched_priority: priority,
+        };
+        if let Err(error) = Sys::sched_setparam(0, &param) {
+            if new.is_some() {
+                ceilings.held.pop();
+            }
+            ceilings.held.extend(removed);
+            return Err(error);
+        }
+    }
     Ok(())
 }
 pub fn set_cancel_state(state: c_int) -> Result<c_int, Errno> {
@@ -368,18 +598,17 @@ pub fn set_cancel_state(state: c_int) -> Result<c_int, Errno> {
         header::PTHREAD_CANCEL_ENABLE => {
             let old = this_thread
                 .has_enabled_cancelation
-                .swap(true, Ordering::Release);
+                .swap(true, Ordering::SeqCst);
 
-            if this_thread.has_queued_cancelation.load(Ordering::Acquire) {
-                unsafe {
-                    cancel_current_thread();
-                }
+            // A deferred request waits for the next cancellation point.
+            if this_thread.has_async_cancelation.load(Ordering::SeqCst) {
+                unsafe { testcancel() };
             }
             old
         }
         header::PTHREAD_CANCEL_DISABLE => this_thread
             .has_enabled_cancelation
-            .swap(false, Ordering::Release),
+            .swap(false, Ordering::SeqCst),
 
         _ => return Err(Errno(EINVAL)),
     };
@@ -392,21 +621,39 @@ pub fn set_cancel_state(state: c_int) -> Result<c_int, Errno> {
 pub fn set_cancel_type(ty: c_int) -> Result<c_int, Errno> {
     let this_thread = current_thread().expect("current thread not present");
 
-    // TODO
-    match ty {
-        header::PTHREAD_CANCEL_DEFERRED => (),
-        header::PTHREAD_CANCEL_ASYNCHRONOUS => (),
+    let was_async = match ty {
+        header::PTHREAD_CANCEL_DEFERRED => this_thread
+            .has_async_cancelation
+            .swap(false, Ordering::SeqCst),
+        header::PTHREAD_CANCEL_ASYNCHRONOUS => {
+            let old = this_thread
+                .has_async_cancelation
+                .swap(true, Ordering::SeqCst);
+
+            unsafe { testcancel() };
+            old
+        }
 
         _ => return Err(Errno(EINVAL)),
-    }
-    Ok(header::PTHREAD_CANCEL_DEFERRED)
+    };
+
+    Ok(match was_async {
+        true => header::PTHREAD_CANCEL_ASYNCHRONOUS,
+        false => header::PTHREAD_CANCEL_DEFERRED,
+    })
 }
 pub fn get_cpu_clkid(thread: &Pthread) -> Result<clockid_t, Errno> {
     // TODO
     Err(Errno(ENOENT))
 }
-pub fn get_sched_param(thread: &Pthread) -> Result<(clockid_t, sched_param), Errno> {
-    todo!()
+pub fn get_sched_param(thread: &Pthread) -> Result<(c_int, sched_param), Errno> {
+    let id = sched_id(thread)?;
+    let policy = Sys::sched_getscheduler(id)?;
+
+    let mut param = MaybeUninit::uninit();
+    Sys::sched_getparam(id, Out::from_uninit_mut(&mut param))?;
+
+    Ok((policy, unsafe { param.assume_init() }))
 }
 
 // TODO: Hash map?
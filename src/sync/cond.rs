@@ -3,6 +3,7 @@
 use crate::{
     error::Errno,
     header::{errno::*, pthread::*, time::timespec},
+    platform::types::*,
 };
 
 use core::sync::atomic::{AtomicU32 as AtomicUint, Ordering};
@@ -33,12 +34,21 @@ impl Cond {
         self.wait_inner(mutex, Some(timeout))
     }
     fn wait_inner(&self, mutex: &RlctMutex, timeout: Option<&timespec>) -> Result<(), Errno> {
-        self.wait_inner_generic(
-            || mutex.unlock(),
-            || mutex.lock(),
-            |timeout| mutex.lock_with_timeout(timeout),
-            timeout,
-        )
+        // A cancellation point, where cleanup handlers run with the mutex locked again.
+        extern "C" fn relock(mutex: *mut c_void) {
+            let _ = unsafe { &*mutex.cast::<RlctMutex>() }.lock();
+        }
+        unsafe { crate::pthread::testcancel() };
+
+        with_cleanup(relock, mutex as *const RlctMutex as *mut c_void, || {
+            self.wait_inner_generic(
+                || mutex.unlock(),
+                || mutex.lock(),
+                |timeout| mutex.lock_with_timeout(timeout),
+                timeout,
+                true,
+            )
+        })
     }
     pub fn wait_inner_typedmutex<'lock, T>(
         &self,
@@ -57,6 +67,7 @@ impl Cond {
             },
             |_| unreachable!(),
             None,
+            false,
         )
         .unwrap();
         newguard.unwrap()
@@ -68,6 +79,7 @@ impl Cond {
         lock: impl FnOnce() -> Result<()>,
         lock_with_timeout: impl FnOnce(&timespec) -> Result<()>,
         deadline: Option<&timespec>,
+        cancelable: bool,
     ) -> Result<(), Errno> {
         // TODO: Error checking for certain types (i.e. robust and errorcheck) of mutexes, e.g. if the
         // mutex is not locked.
@@ -75,7 +87,12 @@ impl Cond {
 
         unlock()?;
 
-        let res = crate::sync::futex_wait(&self.cur, current, deadline);
+        let wait = || crate::sync::futex_wait(&self.cur, current, deadline);
+        let res = if cancelable {
+            crate::pthread::cancellation_point(wait)
+        } else {
+            wait()
+        };
 
         // Always re-acquire the lock, even if the wait timed out.
         if let Some(deadline) = deadline {
@@ -1,6 +1,10 @@
 use core::{
-    cell::Cell,
-    sync::atomic::{AtomicU32 as AtomicUint, Ordering},
+    cell::{Cell, UnsafeCell},
+    mem::offset_of,
+    ptr,
+    sync::atomic::{
+        AtomicBool, AtomicI32 as AtomicInt, AtomicU32 as AtomicUint, Ordering, compiler_fence,
+    },
 };
 
 use crate::{
@@ -11,20 +15,42 @@ use crate::{
 
 use crate::platform::{types::*, Pal, Sys};
 
-use super::FutexWaitResult;
+use super::{FutexAtomicTy, FutexWaitResult};
 
+#[repr(C)]
 pub struct RlctMutex {
+    // Link in the list of robust mutexes held by the owner.
+    robust_list: UnsafeCell<RobustList>,
+
     // Actual locking word.
     inner: AtomicUint,
     recursive_count: AtomicUint,
 
     ty: Ty,
     robust: bool,
+    protocol: Protocol,
+    // Only used by priority-inheriting mutexes, as the kernel owns their locking word while they
+    // have waiters. The others use STATE_NOTRECOVERABLE.
+    notrecoverable: AtomicBool,
+    prioceiling: AtomicInt,
+
+    #[cfg(target_pointer_width = "32")]
+    _pad: [u8; 8],
 }
 
+// The robust list is only ever accessed by the thread owning the mutex.
+unsafe impl Send for RlctMutex {}
+unsafe impl Sync for RlctMutex {}
+
+// The bits of the locking word are those the Linux kernel expects for robust futexes.
 const STATE_UNLOCKED: u32 = 0;
 const WAITING_BIT: u32 = 1 << 31;
-const INDEX_MASK: u32 = !WAITING_BIT;
+// Set by the kernel, or `RobustListHead::abandon`, when the owner of a robust mutex dies. Stays
+// set while the next owner holds it, until it is made consistent.
+const OWNER_DIED_BIT: u32 = 1 << 30;
+const INDEX_MASK: u32 = !(WAITING_BIT | OWNER_DIED_BIT);
+// Owner of a robust mutex unlocked without being made consistent, which can't be locked anymore.
+const STATE_NOTRECOVERABLE: u32 = INDEX_MASK;
 
 // TODO: Lower limit is probably better.
 const RECURSIVE_COUNT_MAX_INCLUSIVE: u32 = u32::MAX;
@@ -43,6 +69,10 @@ impl RlctMutex {
         } = *attr;
 
         Ok(Self {
+            robust_list: UnsafeCell::new(RobustList {
+                next: ptr::null_mut(),
+                prev: ptr::null_mut(),
+            }),
             inner: AtomicUint::new(STATE_UNLOCKED),
             recursive_count: AtomicUint::new(0),
             robust: match robust {
@@ -59,30 +89,181 @@ impl RlctMutex {
 
                 _ => return Err(Errno(EINVAL)),
             },
+            protocol: match protocol {
+                PTHREAD_PRIO_NONE => Protocol::None,
+                // Redox has no priority-inheriting futexes yet, so the owner just keeps its own
+                // priority there.
+                PTHREAD_PRIO_INHERIT if cfg!(target_os = "redox") => Protocol::None,
+                PTHREAD_PRIO_INHERIT => Protocol::Inherit,
+                PTHREAD_PRIO_PROTECT => Protocol::Protect,
+
+                _ => return Err(Errno(EINVAL)),
+            },
+            notrecoverable: AtomicBool::new(false),
+            prioceiling: AtomicInt::new(prioceiling),
+            #[cfg(target_pointer_width = "32")]
+            _pad: [0; 8],
         })
     }
     pub fn prioceiling(&self) -> Result<c_int, Errno> {
-        println!("TODO: Implement pthread_getprioceiling");
-        Ok(0)
+        if self.protocol != Protocol::Protect {
+            return Err(Errno(EINVAL));
+        }
+        Ok(self.prioceiling.load(Ordering::Relaxed))
     }
-    pub fn replace_prioceiling(&self, _: c_int) -> Result<c_int, Errno> {
-        println!("TODO: Implement pthread_setprioceiling");
-        Ok(0)
+    pub fn replace_prioceiling(&self, prioceiling: c_int) -> Result<c_int, Errno> {
+        if self.protocol != Protocol::Protect {
+            return Err(Errno(EINVAL));
+        }
+
+        if self.held_by_caller() {
+            // The priority of the caller depends on it.
+            let old = self.prioceiling.load(Ordering::Relaxed);
+            change_prio_ceiling(Some(old), Some(prioceiling))?;
+            self.prioceiling.store(prioceiling, Ordering::Relaxed);
+            return Ok(old);
+        }
+
+        // Owners rely on the ceiling not changing, but it needn't be followed when locking here.
+        match self.acquire(|| self.lock_raw(None)) {
+            Ok(()) | Err(Errno(EOWNERDEAD)) => (),
+            Err(error) => return Err(error),
+        }
+        if self.ty == Ty::Recursive {
+            self.recursive_count.store(0, Ordering::Relaxed);
+        }
+        let old = self.prioceiling.swap(prioceiling, Ordering::Relaxed);
+        // Left inconsistent for the next owner if it was.
+        self.release(|| self.release_raw());
+
+        Ok(old)
+    }
+    fn held_by_caller(&self) -> bool {
+        self.inner.load(Ordering::Relaxed) & INDEX_MASK == os_tid_invalid_after_fork()
     }
     pub fn make_consistent(&self) -> Result<(), Errno> {
         let this_thread = os_tid_invalid_after_fork();
         let current_state = self.inner.load(Ordering::Relaxed);
 
-        if current_state & INDEX_MASK != this_thread {
+        if !self.robust
+            || current_state & INDEX_MASK != this_thread
+            || current_state & OWNER_DIED_BIT == 0
+        {
             return Err(Errno(EINVAL));
         }
 
-        self.inner.fetch_and(!WAITING_BIT, Ordering::Relaxed);
+        self.inner.fetch_and(!OWNER_DIED_BIT, Ordering::Relaxed);
+        Ok(())
+    }
+    /// Runs `lock`, raising the priority of the calling thread to the ceiling of a
+    /// priority-protecting mutex for as long as it holds it.
+    fn with_ceiling(&self, lock: impl FnOnce() -> Result<(), Errno>) -> Result<(), Errno> {
+        // Relocking a recursive mutex doesn't raise it again.
+        if self.protocol != Protocol::Protect || self.held_by_caller() {
+            return lock();
+        }
+
+        let ceiling = self.prioceiling.load(Ordering::Relaxed);
+        change_prio_ceiling(None, Some(ceiling))?;
+
+        let result = lock();
+        if matches!(result, Ok(()) | Err(Errno(EOWNERDEAD))) {
+            // Changed by pthread_mutex_setprioceiling while waiting.
+            let current = self.prioceiling.load(Ordering::Relaxed);
+            if current != ceiling {
+                let _ = change_prio_ceiling(Some(ceiling), Some(current));
+            }
+        } else {
+            let _ = change_prio_ceiling(Some(ceiling), None);
+        }
+        result
+    }
+    /// Runs `lock`, and adds a robust mutex to the list of the calling thread if it is now held.
+    fn acquire(&self, lock: impl FnOnce() -> Result<(), Errno>) -> Result<(), Errno> {
+        if !self.robust {
+            return lock();
+        }
+
+        let this = current_thread().expect("current thread not present");
+        let head = this.robust_list.get();
+        let entry = self.robust_list_entry();
+
+        unsafe {
+            RobustListHead::set_pending(head, entry);
+
+            let result = lock();
+            let relocked =
+                self.ty == Ty::Recursive && self.recursive_count.load(Ordering::Relaxed) > 1;
+            if matches!(result, Ok(()) | Err(Errno(EOWNERDEAD))) && !relocked {
+                RobustListHead::link(head, entry);
+            }
+
+            RobustListHead::set_pending(head, ptr::null_mut());
+            result
+        }
+    }
+    /// Runs `unlock`, removing a robust mutex from the list of the calling thread beforehand.
+    fn release(&self, unlock: impl FnOnce()) {
+        if !self.robust {
+            return unlock();
+        }
+
+        let this = current_thread().expect("current thread not present");
+        let head = this.robust_list.get();
+        let entry = self.robust_list_entry();
+
+        unsafe {
+            RobustListHead::set_pending(head, entry);
+            RobustListHead::unlink(head, entry);
+            unlock();
+            RobustListHead::set_pending(head, ptr::null_mut());
+        }
+    }
+    /// The link of this mutex in robust lists, tagged in the lowest bit if priority-inheriting as
+    /// the kernel expects.
+    fn robust_list_entry(&self) -> *mut RobustList {
+        self.robust_list
+            .get()
+            .map_addr(|addr| addr | usize::from(self.protocol == Protocol::Inherit))
+    }
+    /// Finishes acquiring the mutex, whose locking word was `prev_state`.
+    fn acquired(&self, prev_state: u32) -> Result<(), Errno> {
+        if self.notrecoverable.load(Ordering::Relaxed) {
+            // Handed over by the kernel after becoming unrecoverable.
+            self.release_raw();
+            return Err(Errno(ENOTRECOVERABLE));
+        }
+
+        let owner_died = prev_state & OWNER_DIED_BIT != 0;
+        if owner_died {
+            // The count of the dead owner is meaningless.
+            self.recursive_count.store(0, Ordering::Relaxed);
+        }
+        if self.ty == Ty::Recursive {
+            self.increment_recursive_count()?;
+        }
+        if owner_died {
+            return Err(Errno(EOWNERDEAD));
+        }
         Ok(())
     }
     fn lock_inner(&self, deadline: Option<&timespec>) -> Result<(), Errno> {
+        self.with_ceiling(|| self.acquire(|| self.lock_raw(deadline)))
+    }
+    fn lock_raw(&self, deadline: Option<&timespec>) -> Result<(), Errno> {
         let this_thread = os_tid_invalid_after_fork();
 
+        if self.inner.load(Ordering::Relaxed) & INDEX_MASK == this_thread {
+            match self.ty {
+                Ty::Recursive => return self.increment_recursive_count(),
+                Ty::Errck => return Err(Errno(EDEADLK)),
+                _ => (),
+            }
+        }
+        if self.protocol == Protocol::Inherit {
+            return self.lock_pi(deadline);
+        }
+
         for _ in 0..SPIN_COUNT {
             if self
                 .inner
@@ -94,10 +275,7 @@ impl RlctMutex {
                 )
                 .is_ok()
             {
-                if self.ty == Ty::Recursive {
-                    self.increment_recursive_count()?;
-                }
-                return Ok(());
+                return self.acquired(STATE_UNLOCKED);
             }
             core::hint::spin_loop();
         }
@@ -107,18 +285,15 @@ impl RlctMutex {
             if current_state & INDEX_MASK == 0 {
                 match self.inner.compare_exchange_weak(
                     current_state,
-                    (current_state & WAITING_BIT) | this_thread,
+                    (current_state & !INDEX_MASK) | this_thread,
                     Ordering::Acquire,
                     Ordering::Relaxed,
                 ) {
-                    Ok(_) => {
-                        if self.ty == Ty::Recursive {
-                            self.increment_recursive_count()?;
-                        }
-                        return Ok(());
-                    }
+                    Ok(_) => return self.acquired(current_state),
                     Err(s) => current_state = s,
                 }
+            } else if current_state & INDEX_MASK == STATE_NOTRECOVERABLE {
+                return Err(Errno(ENOTRECOVERABLE));
             } else {
                 let new_state = current_state | WAITING_BIT;
                 if new_state != current_state {
@@ -138,15 +313,42 @@ impl RlctMutex {
 
                 let res = crate::sync::futex_wait(&self.inner, current_state, deadline);
 
-                if self.robust && res == FutexWaitResult::Stale {
-                    return Err(Errno(EOWNERDEAD));
-                }
                 if res == FutexWaitResult::TimedOut {
                     return Err(Errno(ETIMEDOUT));
                 }
             }
         }
     }
+    fn lock_pi(&self, deadline: Option<&timespec>) -> Result<(), Errno> {
+        let this_thread = os_tid_invalid_after_fork();
+
+        if self.notrecoverable.load(Ordering::Relaxed) {
+            return Err(Errno(ENOTRECOVERABLE));
+        }
+
+        loop {
+            let current_state = self.inner.load(Ordering::Relaxed);
+
+            // Once there are waiters, the kernel decides who gets the mutex next.
+            if current_state & (INDEX_MASK | WAITING_BIT) != 0 {
+                unsafe { Sys::futex_lock_pi(self.inner.ptr(), deadline)? };
+                return self.acquired(self.inner.load(Ordering::Relaxed));
+            }
+
+            if self
+                .inner
+                .compare_exchange(
+                    current_state,
+                    current_state | this_thread,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                return self.acquired(current_state);
+            }
+        }
+    }
     pub fn lock(&self) -> Result<(), Errno> {
         self.lock_inner(None)
     }
@@ -172,39 +374,53 @@ impl RlctMutex {
         Ok(())
     }
     pub fn try_lock(&self) -> Result<(), Errno> {
+        self.with_ceiling(|| self.acquire(|| self.try_lock_raw()))
+    }
+    fn try_lock_raw(&self) -> Result<(), Errno> {
         let this_thread = os_tid_invalid_after_fork();
 
-        // TODO: If recursive, omitting CAS may be faster if it is already owned by this thread.
-        let result = self.inner.compare_exchange(
-            STATE_UNLOCKED,
-            this_thread,
-            Ordering::Acquire,
-            Ordering::Relaxed,
-        );
+        let mut current_state = self.inner.load(Ordering::Relaxed);
+        loop {
+            let owner = current_state & INDEX_MASK;
+            // The kernel hands priority-inheriting mutexes with waiters over to one of them.
+            let free = owner == 0
+                && (self.protocol != Protocol::Inherit || current_state & WAITING_BIT == 0);
 
-        if self.ty == Ty::Recursive {
-            match result {
-                Err(index) if index & INDEX_MASK != this_thread => return Err(Errno(EBUSY)),
-                _ => (),
+            if free {
+                match self.inner.compare_exchange(
+                    current_state,
+                    (current_state & !INDEX_MASK) | this_thread,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return self.acquired(current_state),
+                    Err(s) => {
+                        current_state = s;
+                        continue;
+                    }
+                }
             }
 
-            self.increment_recursive_count()?;
-
-            return Ok(());
-        }
-
-        match result {
-            Ok(_) => Ok(()),
-            Err(index) if index & INDEX_MASK == this_thread && self.ty == Ty::Errck => {
-                Err(Errno(EDEADLK))
+            if owner == STATE_NOTRECOVERABLE || self.notrecoverable.load(Ordering::Relaxed) {
+                return Err(Errno(ENOTRECOVERABLE));
             }
-            Err(_) => Err(Errno(EBUSY)),
+            if owner == this_thread {
+                match self.ty {
+                    Ty::Recursive => return self.increment_recursive_count(),
+                    Ty::Errck => return Err(Errno(EDEADLK)),
+                    _ => (),
+                }
+            }
+            return Err(Errno(EBUSY));
         }
     }
     // Safe because we are not protecting any data.
     pub fn unlock(&self) -> Result<(), Errno> {
-        if self.robust || matches!(self.ty, Ty::Recursive | Ty::Errck) {
-            if self.inner.load(Ordering::Relaxed) & INDEX_MASK != os_tid_invalid_after_fork() {
+        if self.robust
+            || self.protocol != Protocol::None
+            || matches!(self.ty, Ty::Recursive | Ty::Errck)
+        {
+            if !self.held_by_caller() {
                 return Err(Errno(EPERM));
             }
 
@@ -221,6 +437,48 @@ impl RlctMutex {
             }
         }
 
+        let ceiling = self.prioceiling.load(Ordering::Relaxed);
+
+        self.release(|| {
+            if self.inner.load(Ordering::Relaxed) & OWNER_DIED_BIT == 0 {
+                self.release_raw();
+                return;
+            }
+
+            // Never made consistent, so whatever it protects can't be trusted anymore.
+            if self.protocol == Protocol::Inherit {
+                self.notrecoverable.store(true, Ordering::Relaxed);
+                self.release_raw();
+            } else {
+                self.inner.store(STATE_NOTRECOVERABLE, Ordering::Release);
+                let _ = crate::sync::futex_wake(&self.inner, i32::MAX);
+            }
+        });
+
+        if self.protocol == Protocol::Protect {
+            let _ = change_prio_ceiling(Some(ceiling), None);
+        }
+
+        Ok(())
+    }
+    fn release_raw(&self) {
+        if self.protocol == Protocol::Inherit {
+            // Unless there are waiters or the owner died bit, which the kernel deals with.
+            if self
+                .inner
+                .compare_exchange(
+                    os_tid_invalid_after_fork(),
+                    STATE_UNLOCKED,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                let _ = unsafe { Sys::futex_unlock_pi(self.inner.ptr()) };
+            }
+            return;
+        }
+
         if self.inner.fetch_sub(
             self.inner.load(Ordering::Relaxed) & INDEX_MASK,
             Ordering::Release,
@@ -229,9 +487,135 @@ impl RlctMutex {
         {
             let _ = crate::sync::futex_wake(&self.inner, 1);
         }
+    }
+}
+
+/// A link in the list of robust mutexes held by a thread. Starts like the Linux
+/// `struct robust_list`, with a back link to unlink it quickly.
+///
+/// Links to priority-inheriting mutexes are tagged in their lowest bit, but not back links.
+#[repr(C)]
+struct RobustList {
+    next: *mut RobustList,
+    prev: *mut RobustList,
+}
+
+/// The list of robust mutexes held by a thread, laid out as the Linux `struct robust_list_head`.
+///
+/// When the thread exits, the mutexes left in it are marked as having a dead owner, for the next
+/// thread locking them to get `EOWNERDEAD`. On Linux, the kernel also does so when the thread
+/// dies in any other way, like the whole process being killed.
+#[repr(C)]
+pub(crate) struct RobustListHead {
+    // Points back to the head itself when empty.
+    list: *mut RobustList,
+    // Offset of the locking word from the link in a mutex.
+    futex_offset: c_long,
+    // Mutex being locked or unlocked, which may or may not be in the list.
+    list_op_pending: *mut RobustList,
+}
+
+impl RobustListHead {
+    pub(crate) const fn new() -> Self {
+        Self {
+            list: ptr::null_mut(),
+            futex_offset: offset_of!(RlctMutex, inner) as c_long
+                - offset_of!(RlctMutex, robust_list) as c_long,
+            list_op_pending: ptr::null_mut(),
+        }
+    }
+    /// Empties the list, which points to where `head` is from then on.
+    pub(crate) unsafe fn reset(head: *mut Self) {
+        unsafe {
+            (*head).list = head.cast();
+            (*head).list_op_pending = ptr::null_mut();
+        }
+    }
+    unsafe fn set_pending(head: *mut Self, entry: *mut RobustList) {
+        // The thread can die at any point, and the list must be up-to-date when it does.
+        compiler_fence(Ordering::SeqCst);
+        unsafe { (*head).list_op_pending = entry };
+        compiler_fence(Ordering::SeqCst);
+    }
+    unsafe fn link(head: *mut Self, link: *mut RobustList) {
+        unsafe {
+            let entry = untag(link);
+            let first = (*head).list;
+            (*entry).next = first;
+            (*entry).prev = head.cast();
+            if untag(first) != head.cast() {
+                (*untag(first)).prev = entry;
+            }
+            (*head).list = link;
+        }
+    }
+    unsafe fn unlink(head: *mut Self, link: *mut RobustList) {
+        unsafe {
+            let RobustList { next, prev } = *untag(link);
+            // The head only has the next link, which is at the same offset.
+            (*prev).next = next;
+            if untag(next) != head.cast() {
+                (*untag(next)).prev = prev;
+            }
+        }
+    }
+    /// Marks the mutexes still held by the calling thread, which is exiting, as having a dead
+    /// owner, and removes them from the list.
+    pub(crate) unsafe fn abandon(head: *mut Self) {
+        let this_thread = os_tid_invalid_after_fork();
 
-        Ok(())
+        unsafe {
+            let mut link = (*head).list;
+            while !link.is_null() && untag(link) != head.cast() {
+                let next = (*untag(link)).next;
+                if Self::mark_owner_died(link, this_thread) {
+                    Self::unlink(head, link);
+                }
+                link = next;
+            }
+
+            let pending = (*head).list_op_pending;
+            if !pending.is_null() {
+                Self::mark_owner_died(pending, this_thread);
+            }
+        }
     }
+    /// Returns whether the mutex isn't held anymore. Priority-inheriting mutexes with waiters
+    /// are left to the kernel, which hands them over once the thread has exited.
+    unsafe fn mark_owner_died(link: *mut RobustList, this_thread: u32) -> bool {
+        let pi = link.addr() & 1 != 0;
+        let mutex = unsafe {
+            &*untag(link)
+                .byte_sub(offset_of!(RlctMutex, robust_list))
+                .cast::<RlctMutex>()
+        };
+
+        let mut current_state = mutex.inner.load(Ordering::Relaxed);
+        while current_state & INDEX_MASK == this_thread {
+            if pi && current_state & WAITING_BIT != 0 {
+                return false;
+            }
+            match mutex.inner.compare_exchange_weak(
+                current_state,
+                (current_state & WAITING_BIT) | OWNER_DIED_BIT,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    if current_state & WAITING_BIT != 0 {
+                        let _ = crate::sync::futex_wake(&mutex.inner, 1);
+                    }
+                    break;
+                }
+                Err(s) => current_state = s,
+            }
+        }
+        true
+    }
+}
+
+fn untag(link: *mut RobustList) -> *mut RobustList {
+    link.map_addr(|addr| addr & !1)
 }
 
 #[repr(u8)]
@@ -247,10 +631,26 @@ enum Ty {
     Recursive,
 }
 
-// Children after fork can only call async-signal-safe functions until they exec.
+#[repr(u8)]
+#[derive(PartialEq)]
+enum Protocol {
+    None,
+    // The kernel lends the priority of the waiters to the owner.
+    Inherit,
+    // The owner runs at the priority ceiling at least.
+    Protect,
+}
+
+// Children after fork can only call async-signal-safe functions until they exec, or
+// `forget_os_tid` is called.
 #[thread_local]
 static CACHED_OS_TID_INVALID_AFTER_FORK: Cell<u32> = Cell::new(0);
 
+/// Called in the child after `fork`, which has another thread ID.
+pub(crate) fn forget_os_tid() {
+    CACHED_OS_TID_INVALID_AFTER_FORK.set(0);
+}
+
 // Assumes TIDs are unique between processes, which I only know is true for Redox.
 fn os_tid_invalid_after_fork() -> u32 {
     // TODO: Coordinate better if using shared == PTHREAD_PROCESS_SHARED, with up to 2^32 separate
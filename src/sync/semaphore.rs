@@ -52,12 +52,41 @@ impl Semaphore {
     }
 
     pub fn wait(&self, timeout_opt: Option<&timespec>, clock_id: clockid_t) -> Result<(), ()> {
-        loop {
-            let value = self.try_wait();
+        self.wait_inner(timeout_opt, clock_id, false)
+    }
 
-            if value == 0 {
+    /// Like [`Self::wait`], but a cancellation point.
+    pub fn wait_cancelable(
+        &self,
+        timeout_opt: Option<&timespec>,
+        clock_id: clockid_t,
+    ) -> Result<(), ()> {
+        self.wait_inner(timeout_opt, clock_id, true)
+    }
+
+    fn wait_inner(
+        &self,
+        timeout_opt: Option<&timespec>,
+        clock_id: clockid_t,
+        cancelable: bool,
+    ) -> Result<(), ()> {
+        // Only the sleeping is interruptible, so that a cancelled thread never takes the count.
+        let futex_wait = |value: u32, timeout: Option<&timespec>| {
+            let wait = || crate::sync::futex_wait(&self.count, value, timeout);
+            if cancelable {
+                crate::pthread::cancellation_point(wait)
+            } else {
+                wait()
+            }
+        };
+
+        loop {
+            if self.try_wait() != 0 {
+                // Acquired
                 return Ok(());
             }
+            // The count was observed to be zero, so sleep until it changes.
+            let value = 0;
 
             if let Some(timeout) = timeout_opt {
                 let mut time = timespec::default();
@@ -80,11 +109,11 @@ impl Semaphore {
                     relative.tv_sec -= time.tv_sec;
                     relative.tv_nsec -= time.tv_nsec;
 
-                    crate::sync::futex_wait(&self.count, value, Some(&relative));
+                    futex_wait(value, Some(&relative));
                 }
             } else {
                 // Use futex to wait for the next change, without a timeout
-                crate::sync::futex_wait(&self.count, value, None);
+                futex_wait(value, None);
             }
         }
     }
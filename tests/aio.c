@@ -0,0 +1,87 @@
+#include <aio.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "test_helpers.h"
+
+static int wait_for(struct aiocb *cb) {
+    const struct aiocb *list[1] = { cb };
+    int status;
+    while ((status = aio_error(cb)) == EINPROGRESS) {
+        int ret = aio_suspend(list, 1, NULL);
+        ERROR_IF(aio_suspend, ret, == -1);
+    }
+    return status;
+}
+
+int main(void) {
+    char path[] = "/tmp/relibc-aio-XXXXXX";
+    int fd = mkstemp(path);
+    ERROR_IF(mkstemp, fd, == -1);
+    unlink(path);
+
+    char data[] = "Hello, asynchronous world!";
+    struct aiocb wcb;
+    memset(&wcb, 0, sizeof(wcb));
+    wcb.aio_fildes = fd;
+    wcb.aio_buf = data;
+    wcb.aio_nbytes = strlen(data);
+    wcb.aio_offset = 0;
+    wcb.aio_sigevent.sigev_notify = SIGEV_NONE;
+
+    int status = aio_write(&wcb);
+    ERROR_IF(aio_write, status, == -1);
+    printf("aio_write error: %d\n", wait_for(&wcb));
+    printf("aio_write return: %zd\n", aio_return(&wcb));
+
+    struct aiocb scb;
+    memset(&scb, 0, sizeof(scb));
+    scb.aio_fildes = fd;
+    scb.aio_sigevent.sigev_notify = SIGEV_NONE;
+    status = aio_fsync(O_SYNC, &scb);
+    ERROR_IF(aio_fsync, status, == -1);
+    printf("aio_fsync error: %d\n", wait_for(&scb));
+
+    char first[8] = { 0 };
+    char second[8] = { 0 };
+    struct aiocb rcbs[2];
+    memset(rcbs, 0, sizeof(rcbs));
+    rcbs[0].aio_fildes = fd;
+    rcbs[0].aio_buf = first;
+    rcbs[0].aio_nbytes = 5;
+    rcbs[0].aio_offset = 0;
+    rcbs[0].aio_lio_opcode = LIO_READ;
+    rcbs[1].aio_fildes = fd;
+    rcbs[1].aio_buf = second;
+    rcbs[1].aio_nbytes = 6;
+    rcbs[1].aio_offset = 20;
+    rcbs[1].aio_lio_opcode = LIO_READ;
+    struct aiocb *list[3] = { &rcbs[0], NULL, &rcbs[1] };
+
+    status = lio_listio(LIO_WAIT, list, 3, NULL);
+    ERROR_IF(lio_listio, status, == -1);
+    printf("lio_listio: %zd \"%s\", %zd \"%s\"\n",
+        aio_return(&rcbs[0]), first, aio_return(&rcbs[1]), second);
+
+    struct aiocb bad;
+    memset(&bad, 0, sizeof(bad));
+    bad.aio_fildes = fd;
+    bad.aio_offset = -1;
+    status = aio_read(&bad);
+    printf("aio_read with negative offset: %d (%s)\n", status, strerror(errno));
+
+    status = aio_cancel(fd, NULL);
+    printf("aio_cancel with nothing queued: %s\n", status == AIO_ALLDONE ? "AIO_ALLDONE" : "unexpected");
+
+    struct timespec timeout = { 0, 0 };
+    const struct aiocb *pending[1] = { NULL };
+    status = aio_suspend(pending, 1, &timeout);
+    printf("aio_suspend timeout: %d (%s)\n", status, strerror(errno));
+
+    close(fd);
+    return EXIT_SUCCESS;
+}
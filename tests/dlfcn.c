@@ -112,6 +112,43 @@ void test_dlunload(void) {
     assert(handle6 == NULL);
 }
 
+void test_dlnodelete(void) {
+    void *handle = dlopen(SHARED_LIB, RTLD_LAZY | RTLD_LOCAL | RTLD_NODELETE);
+    assert(handle);
+    assert(!dlclose(handle));
+    // Still loaded after the last dlclose()
+    void *handle2 = dlopen(SHARED_LIB, RTLD_LAZY | RTLD_NOLOAD);
+    assert(handle2 == handle);
+    assert(!dlclose(handle2));
+}
+
+void test_dladdr(void) {
+    Dl_info info;
+    if (!dladdr((char *)add + 1, &info)) {
+        printf("dladdr(add + 1) failed\n");
+        exit(1);
+    }
+    assert(info.dli_fname && info.dli_fbase);
+    assert(info.dli_saddr == (void *)add);
+    printf("dladdr(add + 1): %s\n", info.dli_sname);
+
+    const void *sym = NULL;
+    if (!dladdr1((void *)puts, &info, (void **)&sym, RTLD_DL_SYMENT)) {
+        printf("dladdr1(puts) failed\n");
+        exit(2);
+    }
+    assert(sym);
+    printf("dladdr1(puts): %s\n", info.dli_sname);
+
+    // The extra information needs somewhere to go
+    dlerror();
+    assert(dladdr1((void *)puts, &info, NULL, RTLD_DL_LINKMAP) == 0);
+    assert(dlerror() != NULL);
+
+    int local;
+    assert(dladdr(&local, &info) == 0);
+}
+
 int main() {
     test_dlopen_null();
     test_dlopen_libc();
@@ -119,4 +156,6 @@ int main() {
     test_dlsym_global_var();
     test_dlsym_tls_var();
     test_dlunload();
+    test_dlnodelete();
+    test_dladdr();
 }
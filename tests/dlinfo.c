@@ -0,0 +1,105 @@
+#include <assert.h>
+#include <dlfcn.h>
+#include <link.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// Interposes the definition in the libraries, unless they are opened with
+// RTLD_DEEPBIND.
+const char *whoami(void) { return "main"; }
+
+void test_dlvsym(void) {
+    void *handle = dlopen("libversioned.so", RTLD_NOW | RTLD_LOCAL);
+    if (!handle) {
+        printf("dlopen(libversioned.so): %s\n", dlerror());
+        exit(1);
+    }
+
+    int (*value)(void);
+    *(void **)(&value) = dlsym(handle, "value");
+    assert(value);
+    printf("value: %d\n", value());
+
+    *(void **)(&value) = dlvsym(handle, "value", "VERS_1");
+    assert(value);
+    printf("value@VERS_1: %d\n", value());
+
+    *(void **)(&value) = dlvsym(handle, "value", "VERS_2");
+    assert(value);
+    printf("value@VERS_2: %d\n", value());
+
+    assert(dlvsym(handle, "value", "VERS_3") == NULL);
+    assert(dlerror() != NULL);
+
+    assert(!dlclose(handle));
+}
+
+void test_dlinfo(void) {
+    void *handle = dlopen("libversioned.so", RTLD_NOW | RTLD_LOCAL);
+    assert(handle);
+
+    struct link_map *map = NULL;
+    assert(dlinfo(handle, RTLD_DI_LINKMAP, &map) == 0);
+    assert(map && map->l_name);
+    const char *name = strrchr(map->l_name, '/');
+    printf("link map: %s\n", name ? name + 1 : map->l_name);
+
+    // The origin is the directory of the object.
+    char origin[4096];
+    assert(dlinfo(handle, RTLD_DI_ORIGIN, origin) == 0);
+    assert(name && strncmp(map->l_name, origin, name - map->l_name) == 0);
+    assert(strlen(origin) == (size_t)(name - map->l_name));
+
+    size_t modid = 1;
+    assert(dlinfo(handle, RTLD_DI_TLS_MODID, &modid) == 0);
+    printf("TLS module ID: %zu\n", modid);
+
+    Dl_serinfo size;
+    assert(dlinfo(handle, RTLD_DI_SERINFOSIZE, &size) == 0);
+    assert(size.dls_cnt > 0);
+    assert(size.dls_size >= sizeof(Dl_serinfo));
+    Dl_serinfo *info = malloc(size.dls_size);
+    assert(info);
+    assert(dlinfo(handle, RTLD_DI_SERINFOSIZE, info) == 0);
+    assert(dlinfo(handle, RTLD_DI_SERINFO, info) == 0);
+    assert(info->dls_cnt == size.dls_cnt);
+    for (unsigned i = 0; i < info->dls_cnt; i++) {
+        char *dir = info->dls_serpath[i].dls_name;
+        assert(dir >= (char *)&info->dls_serpath[info->dls_cnt]);
+        assert(dir + strlen(dir) < (char *)info + info->dls_size);
+    }
+    free(info);
+
+    assert(!dlclose(handle));
+}
+
+void test_deepbind(void) {
+    void *handle = dlopen("libdeepbind.so", RTLD_NOW | RTLD_LOCAL | RTLD_DEEPBIND);
+    if (!handle) {
+        printf("dlopen(libdeepbind.so): %s\n", dlerror());
+        exit(1);
+    }
+    const char *(*call_whoami)(void);
+    *(void **)(&call_whoami) = dlsym(handle, "call_whoami");
+    assert(call_whoami);
+    printf("RTLD_DEEPBIND: %s\n", call_whoami());
+
+    void *shallow = dlopen("libshallowbind.so", RTLD_NOW | RTLD_LOCAL);
+    if (!shallow) {
+        printf("dlopen(libshallowbind.so): %s\n", dlerror());
+        exit(1);
+    }
+    *(void **)(&call_whoami) = dlsym(shallow, "call_whoami");
+    assert(call_whoami);
+    printf("no RTLD_DEEPBIND: %s\n", call_whoami());
+
+    assert(!dlclose(shallow));
+    assert(!dlclose(handle));
+}
+
+int main(void) {
+    test_dlvsym();
+    test_dlinfo();
+    test_deepbind();
+}
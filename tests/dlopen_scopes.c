@@ -29,7 +29,7 @@ int main(void) {
     assert(dlclose(self) == 0);
 
     // Promote the library to the global scope.
-    assert(dlopen("libfoobar.so", /* RTLD_NOLOAD |*/ RTLD_NOW | RTLD_GLOBAL));
+    assert(dlopen("libfoobar.so", RTLD_NOLOAD | RTLD_NOW | RTLD_GLOBAL));
 
     assert(dlsym(RTLD_DEFAULT, "FOO") != NULL);
     assert(dlsym(RTLD_DEFAULT, "BAR") != NULL);
@@ -39,4 +39,14 @@ void test_getgrnam_r(const char *gr_name) {
 int main(void) {
     test_getgrnam("lcake");
     test_getgrnam_r("lcake");
+
+    // A missing group is not an error
+    char buf[100];
+    struct group grp;
+    struct group *out = &grp;
+    int status = getgrnam_r("no such group", &grp, buf, sizeof(buf), &out);
+    if (status != 0 || out != NULL) {
+        printf("getgrnam_r of a missing group: %d %p\n", status, (void *)out);
+        return EXIT_FAILURE;
+    }
 }
@@ -0,0 +1,39 @@
+#include <grp.h>
+#include <nss.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "test_helpers.h"
+
+int main(void) {
+    int status = __nss_configure_lookup("group", "files");
+    ERROR_IF(__nss_configure_lookup, status, == -1);
+    struct group *grp = getgrnam("root");
+    ERROR_IF(getgrnam, grp, == NULL);
+    UNEXP_IF(getgrnam, grp->gr_gid, != 0);
+
+    // A module that fails to load is unavailable, which goes on to the next
+    // source unless told otherwise
+    status = __nss_configure_lookup("group", "no_such_module files");
+    ERROR_IF(__nss_configure_lookup, status, == -1);
+    grp = getgrnam("root");
+    ERROR_IF(getgrnam, grp, == NULL);
+
+    status = __nss_configure_lookup("group", "no_such_module [UNAVAIL=return] files");
+    ERROR_IF(__nss_configure_lookup, status, == -1);
+    if (getgrnam("root") != NULL) {
+        fprintf(stderr, "getgrnam: found root past [UNAVAIL=return]\n");
+        exit(EXIT_FAILURE);
+    }
+
+    // Looking for the module again gives the same answer
+    if (getgrgid(0) != NULL) {
+        fprintf(stderr, "getgrgid: found root past [UNAVAIL=return]\n");
+        exit(EXIT_FAILURE);
+    }
+
+    status = __nss_configure_lookup("no_such_database", "files");
+    UNEXP_IF(__nss_configure_lookup, status, != -1);
+
+    return EXIT_SUCCESS;
+}
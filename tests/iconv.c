@@ -0,0 +1,99 @@
+#include <assert.h>
+#include <errno.h>
+#include <iconv.h>
+#include <stdio.h>
+#include <string.h>
+#include <wchar.h>
+
+static const char *error_name(size_t ret) {
+    if (ret != (size_t)-1) {
+        return "none";
+    }
+    switch (errno) {
+    case E2BIG:
+        return "E2BIG";
+    case EILSEQ:
+        return "EILSEQ";
+    case EINVAL:
+        return "EINVAL";
+    default:
+        return "other";
+    }
+}
+
+// Converts all of `in`, printing the result as hex along with the return
+// value and errno.
+static void convert(const char *to, const char *from, const char *in,
+                    size_t in_len) {
+    iconv_t cd = iconv_open(to, from);
+    assert(cd != (iconv_t)-1);
+
+    char out[64];
+    char *inp = (char *)in;
+    char *outp = out;
+    size_t inleft = in_len;
+    size_t outleft = sizeof(out);
+    errno = 0;
+    size_t ret = iconv(cd, &inp, &inleft, &outp, &outleft);
+
+    printf("%s -> %s: ret %d, error %s, left %zu, out", from, to, (int)ret,
+           error_name(ret), inleft);
+    for (char *p = out; p < outp; p++) {
+        printf(" %02x", (unsigned char)*p);
+    }
+    putchar('\n');
+    assert(iconv_close(cd) == 0);
+}
+
+int main(void) {
+    errno = 0;
+    assert(iconv_open("NO-SUCH-CHARSET", "UTF-8") == (iconv_t)-1);
+    assert(errno == EINVAL);
+
+    convert("ISO-8859-1", "UTF-8", "caf\xc3\xa9", 5);
+    convert("utf-16le", "utf8", "a\xe2\x82\xac\xf0\x9f\x8d\x8c", 8);
+    convert("UTF-8", "UTF-16", "\xff\xfe" "a\0\xac\x20", 6);
+    convert("UTF-8", "UTF-16", "\0a\x20\xac", 4);
+    convert("UTF-32BE", "UCS-4LE", "\x4c\xf3\x01\0", 4);
+    convert("CP1252", "UTF-8", "\xe2\x82\xac\xe2\x80\x9c", 6);
+    convert("ISO_8859-15", "WINDOWS-1252", "\x80\xa4", 2);
+    convert("UTF-8", "ISO-8859-7", "\xe1\xe2\xe3", 3);
+
+    // Errors stop before the offending input
+    convert("ASCII", "UTF-8", "ok\xc3\xa9!", 5);
+    convert("UTF-16BE", "UTF-8", "\xff", 1);
+    convert("UTF-8", "UTF-8", "ab\xe2\x82", 4);
+    convert("UTF-8", "UTF-16LE", "a\0\x3d", 3);
+
+    convert("ASCII//TRANSLIT", "UTF-8",
+            "\xc3\xa9t\xc3\xa9 \xe2\x80\x9cok\xe2\x80\x9d \xe2\x82\xac" "5 \xe6\xb0\xb4",
+            23);
+    convert("ASCII//IGNORE", "UTF-8", "a\xc3\xa9\xff" "b", 5);
+    convert("ISO-8859-1//TRANSLIT//IGNORE", "UTF-8", "\xc5\x93\xe6\xb0\xb4", 5);
+
+    // Output that does not fit
+    iconv_t cd = iconv_open("UTF-16", "UTF-8");
+    assert(cd != (iconv_t)-1);
+    char out[5];
+    char *inp = "abc";
+    char *outp = out;
+    size_t inleft = 3, outleft = sizeof(out);
+    size_t ret = iconv(cd, &inp, &inleft, &outp, &outleft);
+    printf("error %s, in left %zu, out used %zu\n", error_name(ret), inleft,
+           sizeof(out) - outleft);
+    assert(iconv(cd, NULL, NULL, NULL, NULL) == 0);
+    assert(iconv_close(cd) == 0);
+
+    // wchar_t matches what mbstowcs produces
+    wchar_t wide[4] = {0};
+    cd = iconv_open("WCHAR_T", "UTF-8");
+    assert(cd != (iconv_t)-1);
+    inp = "x\xc3\x9f";
+    inleft = 3;
+    outp = (char *)wide;
+    outleft = sizeof(wide);
+    assert(iconv(cd, &inp, &inleft, &outp, &outleft) == 0);
+    printf("WCHAR_T: %x %x %x\n", (unsigned)wide[0], (unsigned)wide[1],
+           (unsigned)wide[2]);
+    assert(iconv_close(cd) == 0);
+}
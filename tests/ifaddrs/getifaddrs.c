@@ -0,0 +1,69 @@
+#include <arpa/inet.h>
+#include <ifaddrs.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+
+#include "../test_helpers.h"
+
+static void print_addr(const char *what, const struct sockaddr *addr) {
+    char text[INET6_ADDRSTRLEN];
+    const void *src;
+    if (addr->sa_family == AF_INET) {
+        src = &((const struct sockaddr_in *)addr)->sin_addr;
+    } else {
+        src = &((const struct sockaddr_in6 *)addr)->sin6_addr;
+    }
+    const char *result = inet_ntop(addr->sa_family, src, text, sizeof(text));
+    ERROR_IF(inet_ntop, result, == NULL);
+    printf(" %s %s", what, text);
+}
+
+int main(void) {
+    struct ifaddrs *list;
+    int status = getifaddrs(&list);
+    ERROR_IF(getifaddrs, status, == -1);
+
+    for (struct ifaddrs *ifa = list; ifa != NULL; ifa = ifa->ifa_next) {
+        printf("%s: flags %#x", ifa->ifa_name, ifa->ifa_flags);
+        if (ifa->ifa_addr == NULL) {
+            printf(" no address\n");
+            continue;
+        }
+
+        int family = ifa->ifa_addr->sa_family;
+        if (family == AF_INET || family == AF_INET6) {
+            print_addr("address", ifa->ifa_addr);
+            if (ifa->ifa_netmask == NULL || ifa->ifa_netmask->sa_family != family) {
+                fprintf(stderr, "%s: netmask of another family\n", ifa->ifa_name);
+                exit(EXIT_FAILURE);
+            }
+            print_addr("netmask", ifa->ifa_netmask);
+            if (ifa->ifa_flags & IFF_POINTOPOINT && ifa->ifa_dstaddr != NULL) {
+                print_addr("peer", ifa->ifa_dstaddr);
+            } else if (ifa->ifa_flags & IFF_BROADCAST && ifa->ifa_broadaddr != NULL) {
+                print_addr("broadcast", ifa->ifa_broadaddr);
+            }
+        } else {
+            printf(" family %d", family);
+        }
+        printf("\n");
+
+        // Aliases have a name of their own, after the one of the interface
+        char name[IF_NAMESIZE];
+        strncpy(name, ifa->ifa_name, sizeof(name) - 1);
+        name[sizeof(name) - 1] = 0;
+        char *colon = strchr(name, ':');
+        if (colon != NULL) {
+            *colon = 0;
+        }
+        if (if_nametoindex(name) == 0) {
+            fprintf(stderr, "%s: no index\n", name);
+            exit(EXIT_FAILURE);
+        }
+    }
+
+    freeifaddrs(list);
+}
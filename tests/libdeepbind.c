@@ -0,0 +1,3 @@
+const char *whoami(void) { return "library"; }
+
+const char *call_whoami(void) { return whoami(); }
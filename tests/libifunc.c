@@ -0,0 +1,6 @@
+static int answer_impl(void) { return 42; }
+
+// Picks the implementation of `answer` when it is bound
+static int (*resolve_answer(void))(void) { return answer_impl; }
+
+int answer(void) __attribute__((ifunc("resolve_answer")));
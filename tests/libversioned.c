@@ -0,0 +1,12 @@
+// Two versions of `value`, see libversioned.map
+int value_v1(void) { return 1; }
+
+#ifdef VERS_1_ONLY
+// As built before VERS_2, for binaries to require VERS_1
+__asm__(".symver value_v1, value@@VERS_1");
+#else
+int value_v2(void) { return 2; }
+
+__asm__(".symver value_v1, value@VERS_1");
+__asm__(".symver value_v2, value@@VERS_2");
+#endif
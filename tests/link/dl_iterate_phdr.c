@@ -0,0 +1,49 @@
+#include <assert.h>
+#include <link.h>
+#include <stdint.h>
+#include <stdio.h>
+
+static int found_main = 0;
+static int objects = 0;
+
+static int callback(struct dl_phdr_info *info, size_t size, void *data) {
+    assert(size >= sizeof(*info));
+    assert(data == &objects);
+    assert(info->dlpi_name);
+    assert(info->dlpi_phdr && info->dlpi_phnum > 0);
+    assert(info->dlpi_adds >= 1);
+
+    objects++;
+
+    uintptr_t addr = (uintptr_t)&main;
+    for (int i = 0; i < info->dlpi_phnum; i++) {
+        const ElfW(Phdr) *ph = &info->dlpi_phdr[i];
+        if (ph->p_type != PT_LOAD) {
+            continue;
+        }
+        uintptr_t start = info->dlpi_addr + ph->p_vaddr;
+        if (addr >= start && addr < start + ph->p_memsz) {
+            found_main = 1;
+            printf("main found in object %d, name \"%s\"\n", objects,
+                   info->dlpi_name);
+        }
+    }
+    return 0;
+}
+
+static int stop(struct dl_phdr_info *info, size_t size, void *data) {
+    (void)info;
+    (void)size;
+    (*(int *)data)++;
+    return 42;
+}
+
+int main(void) {
+    assert(dl_iterate_phdr(callback, &objects) == 0);
+    assert(found_main);
+
+    int calls = 0;
+    int ret = dl_iterate_phdr(stop, &calls);
+    printf("stop: returned %d after %d call(s)\n", ret, calls);
+    return 0;
+}
@@ -0,0 +1,64 @@
+#include <assert.h>
+#include <ctype.h>
+#include <errno.h>
+#include <langinfo.h>
+#include <locale.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+
+int main(void) {
+    setenv("LOCPATH", "locale/data", 1);
+
+    errno = 0;
+    assert(newlocale(LC_ALL_MASK, "no_SUCH.locale", NULL) == NULL);
+    assert(errno == ENOENT);
+    errno = 0;
+    assert(newlocale(1 << 30, "C", NULL) == NULL);
+    assert(errno == EINVAL);
+
+    locale_t loc = newlocale(LC_ALL_MASK, "POSIX", NULL);
+    assert(loc != NULL);
+    printf("LC_CTYPE: %s\n", getlocalename_l(LC_CTYPE, loc));
+
+    // Modifying only some categories reuses the base object
+    locale_t utf8 = newlocale(LC_CTYPE_MASK, "C.UTF-8", loc);
+    assert(utf8 == loc);
+    printf("LC_CTYPE: %s, LC_NUMERIC: %s\n", getlocalename_l(LC_CTYPE, utf8),
+           getlocalename_l(LC_NUMERIC, utf8));
+
+    locale_t copy = duplocale(utf8);
+    assert(copy != NULL && copy != utf8);
+    freelocale(utf8);
+    printf("copy LC_CTYPE: %s\n", getlocalename_l(LC_CTYPE, copy));
+
+    assert(uselocale(NULL) == LC_GLOBAL_LOCALE);
+    assert(uselocale(copy) == LC_GLOBAL_LOCALE);
+    assert(uselocale(NULL) == copy);
+
+    printf("isalpha_l('a'): %d, toupper_l('q'): %c\n", isalpha_l('a', copy) != 0,
+           toupper_l('q', copy));
+
+    locale_t latin1 = newlocale(LC_CTYPE_MASK, "en_US.ISO-8859-1", NULL);
+    assert(latin1 != NULL);
+    printf("isalpha_l(0xe9): %d %d, toupper_l(0xe9): 0x%x\n",
+           isalpha_l(0xe9, latin1) != 0, isalpha_l(0xe9, copy) != 0,
+           toupper_l(0xe9, latin1));
+    freelocale(latin1);
+
+    printf("strcoll_l: %d\n", strcoll_l("abc", "abd", copy) < 0);
+    printf("strtod_l: %g\n", strtod_l("2.5", NULL, copy));
+    printf("RADIXCHAR: %s\n", nl_langinfo_l(RADIXCHAR, copy));
+
+    struct tm tm = {0};
+    tm.tm_wday = 3;
+    tm.tm_mon = 6;
+    char buf[64];
+    size_t len = strftime_l(buf, sizeof(buf), "%A %B", &tm, copy);
+    printf("strftime_l: %s (%zu)\n", buf, len);
+
+    assert(uselocale(LC_GLOBAL_LOCALE) == copy);
+    assert(uselocale(NULL) == LC_GLOBAL_LOCALE);
+    freelocale(copy);
+}
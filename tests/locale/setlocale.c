@@ -0,0 +1,85 @@
+#include <assert.h>
+#include <ctype.h>
+#include <langinfo.h>
+#include <locale.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <wchar.h>
+
+int main(void) {
+    setenv("LOCPATH", "locale/data", 1);
+    unsetenv("LC_ALL");
+    unsetenv("LC_TIME");
+    setenv("LANG", "C", 1);
+    printf("initial: %s\n", setlocale(LC_ALL, NULL));
+
+    assert(setlocale(LC_ALL, "xx_XX") == NULL);
+    assert(setlocale(LC_ALL, "../locale/data/de_DE.UTF-8") == NULL);
+    assert(setlocale(-1, "C") == NULL);
+    printf("after failures: %s\n", setlocale(LC_ALL, NULL));
+
+    printf("LC_NUMERIC: %s\n", setlocale(LC_NUMERIC, "de_DE.UTF-8"));
+    char *mixed = strdup(setlocale(LC_ALL, NULL));
+    printf("LC_ALL: %s\n", mixed);
+
+    struct lconv *lc = localeconv();
+    printf("decimal_point: %s, thousands_sep: %s, grouping: %d;%d\n",
+           lc->decimal_point, lc->thousands_sep, lc->grouping[0],
+           lc->grouping[1]);
+    printf("strtod: %g\n", strtod("1,5", NULL));
+
+    printf("LC_ALL: %s\n", setlocale(LC_ALL, "de_DE.UTF-8"));
+    lc = localeconv();
+    printf("currency_symbol: %s, int_curr_symbol: %s, frac_digits: %d, "
+           "p_cs_precedes: %d\n",
+           lc->currency_symbol, lc->int_curr_symbol, lc->frac_digits,
+           lc->p_cs_precedes);
+    printf("CODESET: %s, CRNCYSTR: %s, YESEXPR: %s\n", nl_langinfo(CODESET),
+           nl_langinfo(CRNCYSTR), nl_langinfo(YESEXPR));
+    printf("DAY_1: %s, ABMON_3: %s, D_FMT: %s\n", nl_langinfo(DAY_1),
+           nl_langinfo(ABMON_3), nl_langinfo(D_FMT));
+
+    struct tm tm = {0};
+    tm.tm_year = 124;
+    tm.tm_mon = 2;
+    tm.tm_mday = 3;
+    tm.tm_wday = 0;
+    tm.tm_hour = 14;
+    tm.tm_min = 5;
+    char buf[64];
+    strftime(buf, sizeof(buf), "%A %B|%c|%x|%X", &tm);
+    printf("strftime: %s\n", buf);
+
+    printf("MB_CUR_MAX: %zu\n", MB_CUR_MAX);
+    printf("LC_CTYPE: %s\n", setlocale(LC_CTYPE, "en_US.ISO-8859-1"));
+    printf("MB_CUR_MAX: %zu, CODESET: %s\n", MB_CUR_MAX, nl_langinfo(CODESET));
+    wchar_t wc = 0;
+    size_t len = mbrtowc(&wc, "\xe4", 1, NULL);
+    printf("mbrtowc: %zu 0x%x, wctob: 0x%x\n", len, (unsigned)wc, wctob(0xe4));
+
+    // Bytes above ASCII are ISO-8859-1 characters
+    printf("isalpha: %d, isupper: %d, ispunct: %d, toupper: 0x%x, tolower: 0x%x\n",
+           isalpha(0xe4) != 0, isupper(0xc4) != 0, ispunct(0xd7) != 0,
+           toupper(0xe4), tolower(0xc4));
+
+    // Strings are stored in the codeset, even when it comes after them
+    printf("LC_TIME: %s\n", setlocale(LC_TIME, "fr_FR.ISO-8859-1"));
+    const char *feb = nl_langinfo(ABMON_2);
+    printf("ABMON_2: %zu bytes, 0x%x\n", strlen(feb), (unsigned char)feb[1]);
+    assert(setlocale(LC_ALL, "el_GR.ISO-8859-1") == NULL);
+
+    // Composite names restore every category
+    printf("restored: %s\n", setlocale(LC_ALL, mixed));
+    printf("MB_CUR_MAX: %zu, RADIXCHAR: %s\n", MB_CUR_MAX,
+           nl_langinfo(RADIXCHAR));
+    printf("isalpha: %d\n", isalpha(0xe4) != 0);
+    free(mixed);
+
+    // An empty name consults the environment
+    setenv("LC_TIME", "de_DE.UTF-8", 1);
+    printf("LC_ALL: %s\n", setlocale(LC_ALL, ""));
+    setenv("LC_ALL", "C", 1);
+    printf("LC_ALL: %s\n", setlocale(LC_ALL, ""));
+}
@@ -1,3 +1,4 @@
+#include <errno.h>
 #include <net/if.h>
 #include <string.h>
 
@@ -12,32 +13,38 @@
   }
 
 int main(void) {
-  const struct if_nameindex *list = if_nameindex();
-
-  // Currently always returning a stub
-  const struct if_nameindex *first = &(list[0]);
-  assert_eq(first->if_index, 1);
-  assert_eq(strcmp(first->if_name, "stub"), 0);
+  struct if_nameindex *list = if_nameindex();
+  ERROR_IF(if_nameindex, list, == NULL);
 
   // Last item with 0 values determines the end of the list
-  const struct if_nameindex *second = &(list[1]);
-  assert_eq(second->if_index, 0);
-  assert_eq(second->if_name, 0);
+  unsigned max_index = 0;
+  for (struct if_nameindex *item = list; item->if_index != 0; item++) {
+    printf("%u: %s\n", item->if_index, item->if_name);
+
+    // Names and indexes map to each other
+    assert_eq(if_nametoindex(item->if_name), item->if_index);
+    char buf[IF_NAMESIZE];
+    const char *name = if_indextoname(item->if_index, buf);
+    assert_eq(name, buf);
+    assert_eq(strcmp(name, item->if_name), 0);
+
+    if (item->if_index > max_index) {
+      max_index = item->if_index;
+    }
+  }
+  if_freenameindex(list);
 
   unsigned idx;
   idx = if_nametoindex(0);
   assert_eq(idx, 0);
-  idx = if_nametoindex("any");
+  idx = if_nametoindex("no such interface");
   assert_eq(idx, 0);
-  idx = if_nametoindex("stub");
-  assert_eq(idx, 1);
 
-  const char *name;
-  name = if_indextoname(0, 0);
+  char buf[IF_NAMESIZE];
+  errno = 0;
+  const char *name = if_indextoname(max_index + 1, buf);
   assert_eq(name, 0);
-
-  name = if_indextoname(1, 0);
-  assert_eq(strcmp(name, "stub"), 0);
+  assert_eq(errno, ENXIO);
 
   printf("OK\n");
 }
@@ -0,0 +1,87 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/types.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netdb.h>
+#include <arpa/inet.h>
+
+#include "test_helpers.h"
+
+static const char *socktype_name(int socktype) {
+    switch (socktype) {
+        case SOCK_STREAM: return "SOCK_STREAM";
+        case SOCK_DGRAM: return "SOCK_DGRAM";
+        case SOCK_RAW: return "SOCK_RAW";
+        default: return "unknown";
+    }
+}
+
+static int lookup(const char *node, const char *service, int socktype, int protocol, int flags) {
+    struct addrinfo hints, *res;
+
+    memset(&hints, 0, sizeof(hints));
+    hints.ai_family = AF_INET;
+    hints.ai_socktype = socktype;
+    hints.ai_protocol = protocol;
+    hints.ai_flags = flags;
+
+    int status = getaddrinfo(node, service, &hints, &res);
+    if (status != 0) {
+        printf("%s %s: %s\n", node ? node : "(null)", service ? service : "(null)",
+               gai_strerror(status));
+        return status;
+    }
+    for (struct addrinfo *ai = res; ai; ai = ai->ai_next) {
+        struct sockaddr_in *sin = (struct sockaddr_in *)ai->ai_addr;
+        char addrstr[INET_ADDRSTRLEN];
+        inet_ntop(AF_INET, &sin->sin_addr, addrstr, sizeof(addrstr));
+        printf("%s %s: %s port %d %s protocol %d canonname %s\n",
+               node ? node : "(null)", service ? service : "(null)", addrstr,
+               ntohs(sin->sin_port), socktype_name(ai->ai_socktype), ai->ai_protocol,
+               ai->ai_canonname ? ai->ai_canonname : "(null)");
+    }
+    freeaddrinfo(res);
+    return 0;
+}
+
+int main(void) {
+    int status;
+
+    // One entry per socket type, unless told which
+    status = lookup("127.0.0.1", NULL, 0, 0, 0);
+    ERROR_IF(getaddrinfo, status, != 0);
+    status = lookup("127.0.0.1", "8080", 0, 0, 0);
+    ERROR_IF(getaddrinfo, status, != 0);
+    status = lookup("127.0.0.1", "8080", SOCK_DGRAM, 0, 0);
+    ERROR_IF(getaddrinfo, status, != 0);
+    status = lookup("127.0.0.1", "8080", 0, IPPROTO_TCP, 0);
+    ERROR_IF(getaddrinfo, status, != 0);
+
+    // Services by name come from /etc/services
+    status = lookup("127.0.0.1", "http", SOCK_STREAM, 0, AI_CANONNAME);
+    ERROR_IF(getaddrinfo, status, != 0);
+
+    status = lookup("127.0.0.1", "http", SOCK_STREAM, 0, AI_NUMERICSERV);
+    UNEXP_IF(getaddrinfo, status, != EAI_NONAME);
+    status = lookup("127.0.0.1", "no-such-service", SOCK_STREAM, 0, 0);
+    UNEXP_IF(getaddrinfo, status, != EAI_SERVICE);
+    status = lookup("127.0.0.1", "8080", 12345, 0, 0);
+    UNEXP_IF(getaddrinfo, status, != EAI_SOCKTYPE);
+    status = lookup(NULL, "8080", 0, 0, AI_CANONNAME);
+    UNEXP_IF(getaddrinfo, status, != EAI_BADFLAGS);
+    status = lookup(NULL, NULL, 0, 0, 0);
+    UNEXP_IF(getaddrinfo, status, != EAI_NONAME);
+
+    // Only dotted-decimal addresses are numeric hosts, not names with four
+    // labels
+    status = lookup("192.0.2.1", NULL, SOCK_STREAM, 0, AI_NUMERICHOST);
+    ERROR_IF(getaddrinfo, status, != 0);
+    status = lookup("www.mail.example.com", NULL, SOCK_STREAM, 0, AI_NUMERICHOST);
+    UNEXP_IF(getaddrinfo, status, != EAI_NONAME);
+    status = lookup("192.0.2.256", NULL, SOCK_STREAM, 0, AI_NUMERICHOST);
+    UNEXP_IF(getaddrinfo, status, != EAI_NONAME);
+
+    return EXIT_SUCCESS;
+}
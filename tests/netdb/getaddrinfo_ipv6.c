@@ -0,0 +1,104 @@
+#define _DEFAULT_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/types.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netdb.h>
+#include <arpa/inet.h>
+
+#include "test_helpers.h"
+
+static void print_addrinfo(const char *label, struct addrinfo *res) {
+    char addrstr[INET6_ADDRSTRLEN];
+
+    for (; res; res = res->ai_next) {
+        if (res->ai_family == AF_INET6) {
+            struct sockaddr_in6 *sin6 = (struct sockaddr_in6 *)res->ai_addr;
+            ERROR_IF(getaddrinfo_ai_addrlen, res->ai_addrlen, != sizeof(struct sockaddr_in6));
+            inet_ntop(AF_INET6, &sin6->sin6_addr, addrstr, sizeof(addrstr));
+            printf("%s: AF_INET6 %s port %d\n", label, addrstr, ntohs(sin6->sin6_port));
+        } else {
+            struct sockaddr_in *sin = (struct sockaddr_in *)res->ai_addr;
+            ERROR_IF(getaddrinfo_ai_addrlen, res->ai_addrlen, != sizeof(struct sockaddr_in));
+            inet_ntop(AF_INET, &sin->sin_addr, addrstr, sizeof(addrstr));
+            printf("%s: AF_INET %s port %d\n", label, addrstr, ntohs(sin->sin_port));
+        }
+    }
+}
+
+static int lookup(const char *label, const char *node, const char *service, int family, int flags) {
+    struct addrinfo hints, *res;
+
+    memset(&hints, 0, sizeof(hints));
+    hints.ai_family = family;
+    hints.ai_socktype = SOCK_STREAM;
+    hints.ai_flags = flags;
+
+    int status = getaddrinfo(node, service, &hints, &res);
+    if (status != 0) {
+        return status;
+    }
+    print_addrinfo(label, res);
+    freeaddrinfo(res);
+    return 0;
+}
+
+int main(void) {
+    int status;
+
+    status = lookup("numeric", "::1", "80", AF_UNSPEC, AI_NUMERICHOST);
+    ERROR_IF(getaddrinfo, status, != 0);
+
+    status = lookup("v4mapped", "192.0.2.1", NULL, AF_INET6, AI_V4MAPPED);
+    ERROR_IF(getaddrinfo, status, != 0);
+
+    status = lookup("passive", NULL, "8080", AF_INET6, AI_PASSIVE);
+    ERROR_IF(getaddrinfo, status, != 0);
+
+    // An IPv4 address is not one of the IPv6 family without AI_V4MAPPED
+    status = lookup("unmapped", "192.0.2.1", NULL, AF_INET6, AI_NUMERICHOST);
+    UNEXP_IF(getaddrinfo, status, == 0);
+
+    status = lookup("family", "::1", NULL, 12345, 0);
+    UNEXP_IF(getaddrinfo, status, != EAI_FAMILY);
+
+    // Without a node, both loopback addresses are given in some order
+    struct addrinfo hints, *res;
+    memset(&hints, 0, sizeof(hints));
+    hints.ai_socktype = SOCK_STREAM;
+    status = getaddrinfo(NULL, "8080", &hints, &res);
+    ERROR_IF(getaddrinfo, status, != 0);
+    int v4 = 0, v6 = 0;
+    for (struct addrinfo *ai = res; ai; ai = ai->ai_next) {
+        if (ai->ai_family == AF_INET) {
+            v4++;
+        } else if (ai->ai_family == AF_INET6) {
+            v6++;
+        }
+    }
+    freeaddrinfo(res);
+    printf("loopback: %d AF_INET, %d AF_INET6\n", v4, v6);
+
+    struct sockaddr_in6 sin6;
+    memset(&sin6, 0, sizeof(sin6));
+    sin6.sin6_family = AF_INET6;
+    sin6.sin6_port = htons(443);
+    inet_pton(AF_INET6, "2001:db8::1", &sin6.sin6_addr);
+    char host[NI_MAXHOST], serv[NI_MAXSERV];
+    status = getnameinfo((struct sockaddr *)&sin6, sizeof(sin6), host, sizeof(host),
+                         serv, sizeof(serv), NI_NUMERICHOST | NI_NUMERICSERV);
+    ERROR_IF(getnameinfo, status, != 0);
+    printf("getnameinfo: %s port %s\n", host, serv);
+
+    // A short length is not a whole sockaddr_in6
+    status = getnameinfo((struct sockaddr *)&sin6, sizeof(struct sockaddr_in), host,
+                         sizeof(host), serv, sizeof(serv), NI_NUMERICHOST | NI_NUMERICSERV);
+    UNEXP_IF(getnameinfo, status, != EAI_FAMILY);
+
+    struct hostent *he = gethostbyname2("192.0.2.1", AF_INET6);
+    UNEXP_IF(gethostbyname2, (void *)he, != NULL);
+
+    return EXIT_SUCCESS;
+}
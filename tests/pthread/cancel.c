@@ -0,0 +1,208 @@
+#include <errno.h>
+#include <pthread.h>
+#include <semaphore.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../test_helpers.h"
+
+static pthread_key_t key;
+static volatile bool cleaned_up;
+static volatile bool destructed;
+static volatile bool slept;
+
+static pthread_mutex_t mutex;
+static pthread_cond_t cond = PTHREAD_COND_INITIALIZER;
+static volatile int unlock_status = -1;
+
+static sem_t sem;
+
+void cleanup(void *arg) {
+  cleaned_up = true;
+}
+
+void destructor(void *arg) {
+  destructed = true;
+}
+
+void unlock(void *arg) {
+  unlock_status = pthread_mutex_unlock(arg);
+}
+
+void *blocked_read(void *arg) {
+  int *fds = arg;
+  char c;
+
+  int status = pthread_setspecific(key, &c);
+  ERROR_IF2(pthread_setspecific, status, != 0);
+
+  pthread_cleanup_push(cleanup, NULL);
+  read(fds[0], &c, 1);
+  pthread_cleanup_pop(false);
+
+  puts("read returned");
+  exit(EXIT_FAILURE);
+}
+
+void exit_cleanup(void *arg) {
+  puts("cleanup handler ran at exit");
+  _exit(EXIT_FAILURE);
+}
+
+void *blocked_at_exit(void *arg) {
+  int *fds = arg;
+  char c;
+
+  pthread_cleanup_push(exit_cleanup, NULL);
+  read(fds[0], &c, 1);
+  pthread_cleanup_pop(false);
+
+  return NULL;
+}
+
+void *cond_wait(void *arg) {
+  int status = pthread_mutex_lock(&mutex);
+  ERROR_IF2(pthread_mutex_lock, status, != 0);
+
+  pthread_cleanup_push(unlock, &mutex);
+  for (;;) {
+    pthread_cond_wait(&cond, &mutex);
+  }
+  pthread_cleanup_pop(false);
+
+  return NULL;
+}
+
+void *sem_wait_forever(void *arg) {
+  sem_wait(&sem);
+
+  puts("sem_wait returned");
+  exit(EXIT_FAILURE);
+}
+
+void *disabled(void *arg) {
+  int oldstate;
+  int status = pthread_setcancelstate(PTHREAD_CANCEL_DISABLE, &oldstate);
+  ERROR_IF2(pthread_setcancelstate, status, != 0);
+  UNEXP_IF(pthread_setcancelstate, oldstate, != PTHREAD_CANCEL_ENABLE);
+
+  struct timespec delay = {.tv_sec = 0, .tv_nsec = 200000000};
+  nanosleep(&delay, NULL);
+  slept = true;
+
+  // A deferred request is only acted upon at the next cancellation point.
+  status = pthread_setcancelstate(PTHREAD_CANCEL_ENABLE, NULL);
+  ERROR_IF2(pthread_setcancelstate, status, != 0);
+
+  pthread_testcancel();
+
+  puts("pthread_testcancel returned");
+  exit(EXIT_FAILURE);
+}
+
+void *asynchronous(void *arg) {
+  int oldtype;
+  int status = pthread_setcanceltype(PTHREAD_CANCEL_ASYNCHRONOUS, &oldtype);
+  ERROR_IF2(pthread_setcanceltype, status, != 0);
+  UNEXP_IF(pthread_setcanceltype, oldtype, != PTHREAD_CANCEL_DEFERRED);
+
+  status = pthread_setcanceltype(PTHREAD_CANCEL_ASYNCHRONOUS, &oldtype);
+  ERROR_IF2(pthread_setcanceltype, status, != 0);
+  UNEXP_IF(pthread_setcanceltype, oldtype, != PTHREAD_CANCEL_ASYNCHRONOUS);
+
+  for (;;) {
+  }
+}
+
+void cancel_and_join(void *(*routine)(void *), void *arg) {
+  pthread_t thread;
+  int status = pthread_create(&thread, NULL, routine, arg);
+  ERROR_IF2(pthread_create, status, != 0);
+
+  struct timespec delay = {.tv_sec = 0, .tv_nsec = 50000000};
+  nanosleep(&delay, NULL);
+
+  status = pthread_cancel(thread);
+  ERROR_IF2(pthread_cancel, status, != 0);
+
+  void *retval;
+  status = pthread_join(thread, &retval);
+  ERROR_IF2(pthread_join, status, != 0);
+  UNEXP_IF(pthread_join, retval, != PTHREAD_CANCELED);
+}
+
+int main(void) {
+  int status;
+
+  status = pthread_key_create(&key, destructor);
+  ERROR_IF2(pthread_key_create, status, != 0);
+
+  // Cancelled while blocked in read, running cleanup handlers and TLS
+  // destructors.
+  int fds[2];
+  status = pipe(fds);
+  ERROR_IF(pipe, status, == -1);
+
+  cancel_and_join(blocked_read, fds);
+  UNEXP_IF(cleanup, cleaned_up, != true);
+  UNEXP_IF(destructor, destructed, != true);
+
+  // Cancelled while waiting on a condition variable, with the mutex locked
+  // again for the cleanup handlers.
+  pthread_mutexattr_t attr;
+  status = pthread_mutexattr_init(&attr);
+  ERROR_IF2(pthread_mutexattr_init, status, != 0);
+
+  status = pthread_mutexattr_settype(&attr, PTHREAD_MUTEX_ERRORCHECK);
+  ERROR_IF2(pthread_mutexattr_settype, status, != 0);
+
+  status = pthread_mutex_init(&mutex, &attr);
+  ERROR_IF2(pthread_mutex_init, status, != 0);
+
+  cancel_and_join(cond_wait, NULL);
+  UNEXP_IF(pthread_mutex_unlock, unlock_status, != 0);
+
+  // Cancelled while waiting on a semaphore.
+  status = sem_init(&sem, 0, 0);
+  ERROR_IF(sem_init, status, == -1);
+
+  cancel_and_join(sem_wait_forever, NULL);
+
+  int value;
+  status = sem_getvalue(&sem, &value);
+  ERROR_IF(sem_getvalue, status, == -1);
+  UNEXP_IF(sem_getvalue, value, != 0);
+
+  // A request made while cancellation is disabled stays pending.
+  cancel_and_join(disabled, NULL);
+  UNEXP_IF(nanosleep, slept, != true);
+
+  // Asynchronous cancellation of a thread that never reaches a cancellation
+  // point.
+  cancel_and_join(asynchronous, NULL);
+
+  status = pthread_setcanceltype(42, NULL);
+  UNEXP_IF(pthread_setcanceltype, status, != EINVAL);
+
+  status = pthread_mutex_destroy(&mutex);
+  ERROR_IF2(pthread_mutex_destroy, status, != 0);
+
+  status = pthread_mutexattr_destroy(&attr);
+  ERROR_IF2(pthread_mutexattr_destroy, status, != 0);
+
+  status = sem_destroy(&sem);
+  ERROR_IF(sem_destroy, status, == -1);
+
+  // Exiting stops the other threads without running their cleanup handlers.
+  pthread_t thread;
+  status = pthread_create(&thread, NULL, blocked_at_exit, fds);
+  ERROR_IF2(pthread_create, status, != 0);
+
+  struct timespec delay = {.tv_sec = 0, .tv_nsec = 50000000};
+  nanosleep(&delay, NULL);
+
+  return 0;
+}
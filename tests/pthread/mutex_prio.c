@@ -0,0 +1,178 @@
+#include <errno.h>
+#include <pthread.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+
+#include "../test_helpers.h"
+
+void *contend(void *arg) {
+  pthread_mutex_t *mutex = arg;
+
+  int status = pthread_mutex_trylock(mutex);
+  UNEXP_IF(pthread_mutex_trylock, status, != EBUSY);
+
+  status = pthread_mutex_lock(mutex);
+  ERROR_IF2(pthread_mutex_lock, status, != 0);
+
+  status = pthread_mutex_unlock(mutex);
+  ERROR_IF2(pthread_mutex_unlock, status, != 0);
+
+  return NULL;
+}
+
+void *die_holding(void *arg) {
+  pthread_mutex_t *mutex = arg;
+
+  int status = pthread_mutex_lock(mutex);
+  ERROR_IF2(pthread_mutex_lock, status, != 0);
+
+  return NULL;
+}
+
+int main(void) {
+  int status;
+  int protocol;
+  int prioceiling;
+  pthread_mutexattr_t attr;
+
+  status = pthread_mutexattr_init(&attr);
+  ERROR_IF2(pthread_mutexattr_init, status, != 0);
+
+  status = pthread_mutexattr_getprotocol(&attr, &protocol);
+  ERROR_IF2(pthread_mutexattr_getprotocol, status, != 0);
+  UNEXP_IF(pthread_mutexattr_getprotocol, protocol, != PTHREAD_PRIO_NONE);
+
+  status = pthread_mutexattr_setprotocol(&attr, 42);
+  UNEXP_IF(pthread_mutexattr_setprotocol, status, != EINVAL);
+
+  // Priority inheritance.
+  status = pthread_mutexattr_setprotocol(&attr, PTHREAD_PRIO_INHERIT);
+  ERROR_IF2(pthread_mutexattr_setprotocol, status, != 0);
+
+  status = pthread_mutexattr_getprotocol(&attr, &protocol);
+  ERROR_IF2(pthread_mutexattr_getprotocol, status, != 0);
+  UNEXP_IF(pthread_mutexattr_getprotocol, protocol, != PTHREAD_PRIO_INHERIT);
+
+  pthread_mutex_t mutex;
+  status = pthread_mutex_init(&mutex, &attr);
+  ERROR_IF2(pthread_mutex_init, status, != 0);
+
+  status = pthread_mutex_getprioceiling(&mutex, &prioceiling);
+  UNEXP_IF(pthread_mutex_getprioceiling, status, != EINVAL);
+
+  status = pthread_mutex_lock(&mutex);
+  ERROR_IF2(pthread_mutex_lock, status, != 0);
+
+  pthread_t thread;
+  status = pthread_create(&thread, NULL, contend, &mutex);
+  ERROR_IF2(pthread_create, status, != 0);
+
+  struct timespec delay = {.tv_sec = 0, .tv_nsec = 50000000};
+  nanosleep(&delay, NULL);
+
+  status = pthread_mutex_unlock(&mutex);
+  ERROR_IF2(pthread_mutex_unlock, status, != 0);
+
+  status = pthread_join(thread, NULL);
+  ERROR_IF2(pthread_join, status, != 0);
+
+  status = pthread_mutex_destroy(&mutex);
+  ERROR_IF2(pthread_mutex_destroy, status, != 0);
+
+  // A robust priority-inheriting mutex whose owner exits.
+  status = pthread_mutexattr_setrobust(&attr, PTHREAD_MUTEX_ROBUST);
+  ERROR_IF2(pthread_mutexattr_setrobust, status, != 0);
+
+  status = pthread_mutex_init(&mutex, &attr);
+  ERROR_IF2(pthread_mutex_init, status, != 0);
+
+  status = pthread_create(&thread, NULL, die_holding, &mutex);
+  ERROR_IF2(pthread_create, status, != 0);
+
+  status = pthread_join(thread, NULL);
+  ERROR_IF2(pthread_join, status, != 0);
+
+  status = pthread_mutex_lock(&mutex);
+  UNEXP_IF(pthread_mutex_lock, status, != EOWNERDEAD);
+
+  status = pthread_mutex_consistent(&mutex);
+  ERROR_IF2(pthread_mutex_consistent, status, != 0);
+
+  status = pthread_mutex_unlock(&mutex);
+  ERROR_IF2(pthread_mutex_unlock, status, != 0);
+
+  status = pthread_mutex_destroy(&mutex);
+  ERROR_IF2(pthread_mutex_destroy, status, != 0);
+
+  status = pthread_mutexattr_setrobust(&attr, PTHREAD_MUTEX_STALLED);
+  ERROR_IF2(pthread_mutexattr_setrobust, status, != 0);
+
+  // Priority protection.
+  status = pthread_mutexattr_setprotocol(&attr, PTHREAD_PRIO_PROTECT);
+  ERROR_IF2(pthread_mutexattr_setprotocol, status, != 0);
+
+  status = pthread_mutexattr_setprioceiling(&attr, 5);
+  ERROR_IF2(pthread_mutexattr_setprioceiling, status, != 0);
+
+  status = pthread_mutex_init(&mutex, &attr);
+  ERROR_IF2(pthread_mutex_init, status, != 0);
+
+  status = pthread_mutex_getprioceiling(&mutex, &prioceiling);
+  ERROR_IF2(pthread_mutex_getprioceiling, status, != 0);
+  UNEXP_IF(pthread_mutex_getprioceiling, prioceiling, != 5);
+
+  int old_prioceiling;
+  status = pthread_mutex_setprioceiling(&mutex, 10, &old_prioceiling);
+  ERROR_IF2(pthread_mutex_setprioceiling, status, != 0);
+  UNEXP_IF(pthread_mutex_setprioceiling, old_prioceiling, != 5);
+
+  status = pthread_mutex_getprioceiling(&mutex, &prioceiling);
+  ERROR_IF2(pthread_mutex_getprioceiling, status, != 0);
+  UNEXP_IF(pthread_mutex_getprioceiling, prioceiling, != 10);
+
+  // Locking raises the thread to the ceiling. Only privileged threads may
+  // switch to a real-time policy to try it.
+  int min = sched_get_priority_min(SCHED_FIFO);
+  ERROR_IF(sched_get_priority_min, min, == -1);
+  struct sched_param param = {.sched_priority = min};
+  status = pthread_setschedparam(pthread_self(), SCHED_FIFO, &param);
+  UNEXP_IF(pthread_setschedparam, status, != 0 && status != EPERM);
+
+  if (status == 0) {
+    status = pthread_mutex_setprioceiling(&mutex, min + 1, &old_prioceiling);
+    ERROR_IF2(pthread_mutex_setprioceiling, status, != 0);
+
+    status = pthread_mutex_lock(&mutex);
+    ERROR_IF2(pthread_mutex_lock, status, != 0);
+
+    status = sched_getparam(0, &param);
+    ERROR_IF(sched_getparam, status, == -1);
+    UNEXP_IF(sched_getparam, param.sched_priority, != min + 1);
+
+    status = pthread_mutex_unlock(&mutex);
+    ERROR_IF2(pthread_mutex_unlock, status, != 0);
+
+    status = sched_getparam(0, &param);
+    ERROR_IF(sched_getparam, status, == -1);
+    UNEXP_IF(sched_getparam, param.sched_priority, != min);
+
+    param.sched_priority = 0;
+    status = pthread_setschedparam(pthread_self(), SCHED_OTHER, &param);
+    ERROR_IF2(pthread_setschedparam, status, != 0);
+  }
+
+  // SCHED_OTHER threads can't be raised to a ceiling above 0, their only
+  // priority.
+  status = pthread_mutex_lock(&mutex);
+  UNEXP_IF(pthread_mutex_lock, status, != EINVAL);
+
+  status = pthread_mutex_destroy(&mutex);
+  ERROR_IF2(pthread_mutex_destroy, status, != 0);
+
+  status = pthread_mutexattr_destroy(&attr);
+  ERROR_IF2(pthread_mutexattr_destroy, status, != 0);
+
+  return 0;
+}
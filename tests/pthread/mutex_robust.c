@@ -0,0 +1,113 @@
+#include <errno.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test_helpers.h"
+
+void *die_holding(void *arg) {
+  pthread_mutex_t *mutex = arg;
+
+  int status = pthread_mutex_lock(mutex);
+  ERROR_IF2(pthread_mutex_lock, status, != 0);
+
+  return NULL;
+}
+
+int main(void) {
+  int status;
+  pthread_mutexattr_t attr;
+
+  status = pthread_mutexattr_init(&attr);
+  ERROR_IF2(pthread_mutexattr_init, status, != 0);
+
+  status = pthread_mutexattr_setrobust(&attr, PTHREAD_MUTEX_ROBUST);
+  ERROR_IF2(pthread_mutexattr_setrobust, status, != 0);
+
+  status = pthread_mutexattr_setpshared(&attr, PTHREAD_PROCESS_SHARED);
+  ERROR_IF2(pthread_mutexattr_setpshared, status, != 0);
+
+  // A process dies holding a mutex in shared memory.
+  pthread_mutex_t *shared = mmap(NULL, sizeof(pthread_mutex_t),
+      PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+  ERROR_IF(mmap, shared, == MAP_FAILED);
+
+  status = pthread_mutex_init(shared, &attr);
+  ERROR_IF2(pthread_mutex_init, status, != 0);
+
+  pid_t pid = fork();
+  ERROR_IF(fork, pid, == -1);
+  if (pid == 0) {
+    status = pthread_mutex_lock(shared);
+    if (status != 0) {
+      _exit(EXIT_FAILURE);
+    }
+    _exit(EXIT_SUCCESS);
+  }
+
+  int wstatus;
+  status = waitpid(pid, &wstatus, 0);
+  ERROR_IF(waitpid, status, == -1);
+  UNEXP_IF(waitpid, WEXITSTATUS(wstatus), != EXIT_SUCCESS);
+
+  status = pthread_mutex_lock(shared);
+  UNEXP_IF(pthread_mutex_lock, status, != EOWNERDEAD);
+
+  status = pthread_mutex_consistent(shared);
+  ERROR_IF2(pthread_mutex_consistent, status, != 0);
+
+  status = pthread_mutex_consistent(shared);
+  UNEXP_IF(pthread_mutex_consistent, status, != EINVAL);
+
+  status = pthread_mutex_unlock(shared);
+  ERROR_IF2(pthread_mutex_unlock, status, != 0);
+
+  status = pthread_mutex_trylock(shared);
+  ERROR_IF2(pthread_mutex_trylock, status, != 0);
+
+  status = pthread_mutex_unlock(shared);
+  ERROR_IF2(pthread_mutex_unlock, status, != 0);
+
+  status = pthread_mutex_destroy(shared);
+  ERROR_IF2(pthread_mutex_destroy, status, != 0);
+
+  status = munmap(shared, sizeof(pthread_mutex_t));
+  ERROR_IF(munmap, status, != 0);
+
+  // A thread exits holding a mutex, and it is unlocked without being made
+  // consistent.
+  pthread_mutex_t mutex;
+
+  status = pthread_mutex_init(&mutex, &attr);
+  ERROR_IF2(pthread_mutex_init, status, != 0);
+
+  pthread_t thread;
+  status = pthread_create(&thread, NULL, die_holding, &mutex);
+  ERROR_IF2(pthread_create, status, != 0);
+
+  status = pthread_join(thread, NULL);
+  ERROR_IF2(pthread_join, status, != 0);
+
+  status = pthread_mutex_trylock(&mutex);
+  UNEXP_IF(pthread_mutex_trylock, status, != EOWNERDEAD);
+
+  status = pthread_mutex_unlock(&mutex);
+  ERROR_IF2(pthread_mutex_unlock, status, != 0);
+
+  status = pthread_mutex_lock(&mutex);
+  UNEXP_IF(pthread_mutex_lock, status, != ENOTRECOVERABLE);
+
+  status = pthread_mutex_trylock(&mutex);
+  UNEXP_IF(pthread_mutex_trylock, status, != ENOTRECOVERABLE);
+
+  status = pthread_mutex_destroy(&mutex);
+  ERROR_IF2(pthread_mutex_destroy, status, != 0);
+
+  status = pthread_mutexattr_destroy(&attr);
+  ERROR_IF2(pthread_mutexattr_destroy, status, != 0);
+
+  return 0;
+}
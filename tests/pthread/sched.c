@@ -0,0 +1,137 @@
+#include <errno.h>
+#include <pthread.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "../test_helpers.h"
+
+void *check_policy(void *arg) {
+  int policy;
+  struct sched_param param;
+
+  int status = pthread_getschedparam(pthread_self(), &policy, &param);
+  ERROR_IF2(pthread_getschedparam, status, != 0);
+  UNEXP_IF(pthread_getschedparam, policy, != SCHED_OTHER);
+
+  return NULL;
+}
+
+void *check_fifo(void *arg) {
+  int policy;
+  struct sched_param param;
+
+  int status = pthread_getschedparam(pthread_self(), &policy, &param);
+  ERROR_IF2(pthread_getschedparam, status, != 0);
+  UNEXP_IF(pthread_getschedparam, policy, != SCHED_FIFO);
+
+  return NULL;
+}
+
+int main(void) {
+  int status;
+  int policy;
+  struct sched_param param;
+
+  int min = sched_get_priority_min(SCHED_FIFO);
+  ERROR_IF(sched_get_priority_min, min, == -1);
+  int max = sched_get_priority_max(SCHED_FIFO);
+  ERROR_IF(sched_get_priority_max, max, == -1);
+  UNEXP_IF(sched_get_priority_max, max, < min);
+
+  status = sched_get_priority_max(42);
+  UNEXP_IF(sched_get_priority_max, status, != -1);
+  UNEXP_IF(sched_get_priority_max, errno, != EINVAL);
+
+  status = sched_getscheduler(0);
+  ERROR_IF(sched_getscheduler, status, == -1);
+  UNEXP_IF(sched_getscheduler, status, != SCHED_OTHER);
+
+  status = sched_getparam(0, &param);
+  ERROR_IF(sched_getparam, status, == -1);
+  UNEXP_IF(sched_getparam, param.sched_priority, != 0);
+
+  status = pthread_getschedparam(pthread_self(), &policy, &param);
+  ERROR_IF2(pthread_getschedparam, status, != 0);
+  UNEXP_IF(pthread_getschedparam, policy, != SCHED_OTHER);
+  UNEXP_IF(pthread_getschedparam, param.sched_priority, != 0);
+
+  // Only privileged threads may switch to a real-time policy.
+  param.sched_priority = min;
+  status = pthread_setschedparam(pthread_self(), SCHED_FIFO, &param);
+  UNEXP_IF(pthread_setschedparam, status, != 0 && status != EPERM);
+
+  if (status == 0) {
+    status = pthread_getschedparam(pthread_self(), &policy, &param);
+    ERROR_IF2(pthread_getschedparam, status, != 0);
+    UNEXP_IF(pthread_getschedparam, policy, != SCHED_FIFO);
+    UNEXP_IF(pthread_getschedparam, param.sched_priority, != min);
+
+    status = pthread_setschedprio(pthread_self(), max);
+    ERROR_IF2(pthread_setschedprio, status, != 0);
+
+    status = pthread_getschedparam(pthread_self(), &policy, &param);
+    ERROR_IF2(pthread_getschedparam, status, != 0);
+    UNEXP_IF(pthread_getschedparam, param.sched_priority, != max);
+
+    param.sched_priority = 0;
+    status = pthread_setschedparam(pthread_self(), SCHED_OTHER, &param);
+    ERROR_IF2(pthread_setschedparam, status, != 0);
+  }
+
+  // An invalid priority for the policy.
+  param.sched_priority = max + 1;
+  status = pthread_setschedparam(pthread_self(), SCHED_FIFO, &param);
+  UNEXP_IF(pthread_setschedparam, status, != EINVAL);
+
+  // Other threads start with the default policy.
+  pthread_t thread;
+  status = pthread_create(&thread, NULL, check_policy, NULL);
+  ERROR_IF2(pthread_create, status, != 0);
+
+  status = pthread_join(thread, NULL);
+  ERROR_IF2(pthread_join, status, != 0);
+
+  // Explicit scheduling is applied before the thread runs, and failing to
+  // apply it fails pthread_create.
+  pthread_attr_t attr;
+  status = pthread_attr_init(&attr);
+  ERROR_IF2(pthread_attr_init, status, != 0);
+
+  status = pthread_attr_setinheritsched(&attr, PTHREAD_EXPLICIT_SCHED);
+  ERROR_IF2(pthread_attr_setinheritsched, status, != 0);
+
+  status = pthread_attr_setschedpolicy(&attr, SCHED_OTHER);
+  ERROR_IF2(pthread_attr_setschedpolicy, status, != 0);
+
+  param.sched_priority = 0;
+  status = pthread_attr_setschedparam(&attr, &param);
+  ERROR_IF2(pthread_attr_setschedparam, status, != 0);
+
+  status = pthread_create(&thread, &attr, check_policy, NULL);
+  ERROR_IF2(pthread_create, status, != 0);
+
+  status = pthread_join(thread, NULL);
+  ERROR_IF2(pthread_join, status, != 0);
+
+  status = pthread_attr_setschedpolicy(&attr, SCHED_FIFO);
+  ERROR_IF2(pthread_attr_setschedpolicy, status, != 0);
+
+  param.sched_priority = min;
+  status = pthread_attr_setschedparam(&attr, &param);
+  ERROR_IF2(pthread_attr_setschedparam, status, != 0);
+
+  // Only privileged threads may switch to a real-time policy.
+  status = pthread_create(&thread, &attr, check_fifo, NULL);
+  UNEXP_IF(pthread_create, status, != 0 && status != EPERM);
+
+  if (status == 0) {
+    status = pthread_join(thread, NULL);
+    ERROR_IF2(pthread_join, status, != 0);
+  }
+
+  status = pthread_attr_destroy(&attr);
+  ERROR_IF2(pthread_attr_destroy, status, != 0);
+
+  return 0;
+}
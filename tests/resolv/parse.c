@@ -0,0 +1,116 @@
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/types.h>
+#include <netinet/in.h>
+#include <arpa/nameser.h>
+#include <resolv.h>
+
+#include "test_helpers.h"
+
+static void print_bytes(const unsigned char *data, int len) {
+    for (int i = 0; i < len; i++) {
+        printf("%02x", data[i]);
+    }
+    printf("\n");
+}
+
+static void expand(const unsigned char *msg, int msglen, const unsigned char *name) {
+    char text[MAXDNAME];
+
+    int len = dn_expand(msg, msg + msglen, name, text, sizeof(text));
+    ERROR_IF(dn_expand, len, < 0);
+    int skipped = dn_skipname(name, msg + msglen);
+    ERROR_IF(dn_skipname, skipped, != len);
+    printf("expanded \"%s\" from %d bytes\n", text, len);
+}
+
+int main(void) {
+    unsigned char buf[PACKETSZ];
+
+    // A query, without its random id
+    int len = res_mkquery(QUERY, "example.com", C_IN, T_MX, NULL, 0, NULL, buf, sizeof(buf));
+    ERROR_IF(res_mkquery, len, < 0);
+    printf("query of %d bytes: ", len);
+    print_bytes(buf + 2, len - 2);
+
+    len = res_mkquery(QUERY, "a..example.com", C_IN, T_MX, NULL, 0, NULL, buf, sizeof(buf));
+    printf("query for an empty label: %d\n", len);
+
+    // Names compressed against the ones before them
+    unsigned char msg[PACKETSZ] = {0};
+    unsigned char *dnptrs[8] = {msg, NULL};
+    unsigned char *end = msg + HFIXEDSZ;
+    const char *names[] = {"mail.example.com", "www.EXAMPLE.com.", "example.com", "a\\.b.example.com", "."};
+    const unsigned char *starts[5];
+    for (int i = 0; i < 5; i++) {
+        starts[i] = end;
+        len = dn_comp(names[i], end, msg + sizeof(msg) - end, dnptrs, dnptrs + 8);
+        ERROR_IF(dn_comp, len, < 0);
+        printf("compressed \"%s\" to %d bytes: ", names[i], len);
+        print_bytes(end, len);
+        end += len;
+    }
+    for (int i = 0; i < 5; i++) {
+        expand(msg, end - msg, starts[i]);
+    }
+
+    len = dn_comp("a..b", buf, sizeof(buf), NULL, NULL);
+    printf("compressed an empty label: %d\n", len);
+
+    // An answer with two MX records
+    static const unsigned char answer[] = {
+        0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+        // example.com MX IN
+        0x07, 'e', 'x', 'a', 'm', 'p', 'l', 'e', 0x03, 'c', 'o', 'm', 0x00,
+        0x00, 0x0f, 0x00, 0x01,
+        // example.com MX IN 3600 10 mail.example.com
+        0xc0, 0x0c, 0x00, 0x0f, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x09,
+        0x00, 0x0a, 0x04, 'm', 'a', 'i', 'l', 0xc0, 0x0c,
+        // example.com MX IN 300 20 backup.example.com
+        0xc0, 0x0c, 0x00, 0x0f, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x0b,
+        0x00, 0x14, 0x06, 'b', 'a', 'c', 'k', 'u', 'p', 0xc0, 0x0c,
+    };
+
+    ns_msg handle;
+    int status = ns_initparse(answer, sizeof(answer), &handle);
+    ERROR_IF(ns_initparse, status, < 0);
+    printf("id %04x, %d questions, %d answers, %d authority, %d additional\n",
+        ns_msg_id(handle),
+        ns_msg_count(handle, ns_s_qd),
+        ns_msg_count(handle, ns_s_an),
+        ns_msg_count(handle, ns_s_ns),
+        ns_msg_count(handle, ns_s_ar));
+
+    ns_rr rr;
+    status = ns_parserr(&handle, ns_s_qd, 0, &rr);
+    ERROR_IF(ns_parserr, status, < 0);
+    printf("question %s, type %d, class %d\n", ns_rr_name(rr), ns_rr_type(rr), ns_rr_class(rr));
+
+    // In reverse, to go back in the section
+    for (int i = ns_msg_count(handle, ns_s_an) - 1; i >= 0; i--) {
+        status = ns_parserr(&handle, ns_s_an, i, &rr);
+        ERROR_IF(ns_parserr, status, < 0);
+
+        char exchange[MAXDNAME];
+        const unsigned char *rdata = ns_rr_rdata(rr);
+        len = dn_expand(ns_msg_base(handle), ns_msg_end(handle), rdata + 2, exchange, sizeof(exchange));
+        ERROR_IF(dn_expand, len, < 0);
+        printf("answer %s, type %d, class %d, ttl %lu, %d bytes: %u %s\n",
+            ns_rr_name(rr),
+            ns_rr_type(rr),
+            ns_rr_class(rr),
+            (unsigned long)ns_rr_ttl(rr),
+            ns_rr_rdlen(rr),
+            ns_get16(rdata),
+            exchange);
+    }
+
+    errno = 0;
+    status = ns_parserr(&handle, ns_s_an, 2, &rr);
+    printf("record past the end: %d, %s\n", status, errno == ENODEV ? "ENODEV" : "other");
+
+    errno = 0;
+    status = ns_initparse(answer, sizeof(answer) - 1, &handle);
+    printf("truncated answer: %d, %s\n", status, errno == EMSGSIZE ? "EMSGSIZE" : "other");
+}
@@ -0,0 +1,104 @@
+#include <errno.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/types.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <arpa/nameser.h>
+#include <resolv.h>
+#include <unistd.h>
+
+#include "test_helpers.h"
+
+// Answers every query with a TXT record of this many bytes
+#define TXT_LEN 200
+
+static int server_fd;
+
+static void *serve(void *arg) {
+    unsigned char buf[PACKETSZ];
+
+    for (int i = 0; i < 2; i++) {
+        struct sockaddr_in client;
+        socklen_t client_len = sizeof(client);
+        ssize_t len = recvfrom(server_fd, buf, sizeof(buf), 0, (struct sockaddr *)&client,
+                               &client_len);
+        ERROR_IF(recvfrom, len, < HFIXEDSZ);
+
+        // The query with the answer after it, pointing back at the question
+        buf[2] |= 0x80;
+        buf[7] = 1;
+        unsigned char *rr = buf + len;
+        *rr++ = 0xc0;
+        *rr++ = HFIXEDSZ;
+        *rr++ = 0;
+        *rr++ = T_TXT;
+        *rr++ = 0;
+        *rr++ = C_IN;
+        memset(rr, 0, 4);
+        rr += 4;
+        *rr++ = 0;
+        *rr++ = TXT_LEN + 1;
+        *rr++ = TXT_LEN;
+        memset(rr, 'x', TXT_LEN);
+        rr += TXT_LEN;
+
+        ssize_t sent = sendto(server_fd, buf, rr - buf, 0, (struct sockaddr *)&client,
+                              client_len);
+        ERROR_IF(sendto, sent, == -1);
+    }
+    return NULL;
+}
+
+int main(void) {
+    server_fd = socket(AF_INET, SOCK_DGRAM, 0);
+    ERROR_IF(socket, server_fd, == -1);
+
+    struct sockaddr_in addr = {0};
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(NAMESERVER_PORT);
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (bind(server_fd, (struct sockaddr *)&addr, sizeof(addr)) == -1) {
+        // Needs privileges, and no nameserver running here already
+        printf("skipped: bind: %s\n", strerror(errno));
+        return EXIT_SUCCESS;
+    }
+
+    pthread_t thread;
+    int status = pthread_create(&thread, NULL, serve, NULL);
+    ERROR_IF2(pthread_create, status, != 0);
+
+    struct __res_state state = {0};
+    status = res_ninit(&state);
+    ERROR_IF(res_ninit, status, == -1);
+    state.nscount = 1;
+    state.nsaddr_list[0] = addr;
+    state.options &= ~RES_USE_EDNS0;
+
+    unsigned char query[PACKETSZ];
+    int query_len = res_nmkquery(&state, QUERY, "example.com", C_IN, T_TXT, NULL, 0, NULL,
+                                 query, sizeof(query));
+    ERROR_IF(res_nmkquery, query_len, < 0);
+
+    // Answers longer than the buffer are cut short, with the TC bit set
+    unsigned char answer[64];
+    int len = res_nsend(&state, query, query_len, answer, sizeof(answer));
+    ERROR_IF(res_nsend, len, < 0);
+    UNEXP_IF(res_nsend, len, != sizeof(answer));
+    UNEXP_IF(res_nsend, answer[2] & 0x02, == 0);
+
+    // The length can be used to parse what was stored
+    len = res_nquery(&state, "example.com", C_IN, T_TXT, answer, sizeof(answer));
+    ERROR_IF(res_nquery, len, < 0);
+    UNEXP_IF(res_nquery, len, > (int)sizeof(answer));
+
+    status = pthread_join(thread, NULL);
+    ERROR_IF2(pthread_join, status, != 0);
+
+    res_nclose(&state);
+    close(server_fd);
+    return EXIT_SUCCESS;
+}
@@ -0,0 +1,110 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "../test_helpers.h"
+
+int main(void) {
+  int status;
+
+  // The macros.
+  cpu_set_t a;
+  cpu_set_t b;
+  cpu_set_t c;
+
+  CPU_ZERO(&a);
+  UNEXP_IF(CPU_COUNT, CPU_COUNT(&a), != 0);
+
+  CPU_SET(1, &a);
+  CPU_SET(3, &a);
+  CPU_SET(100, &a);
+  CPU_SET(CPU_SETSIZE, &a);
+  UNEXP_IF(CPU_COUNT, CPU_COUNT(&a), != 3);
+  UNEXP_IF(CPU_ISSET, CPU_ISSET(3, &a), != 1);
+  UNEXP_IF(CPU_ISSET, CPU_ISSET(2, &a), != 0);
+
+  CPU_CLR(100, &a);
+  UNEXP_IF(CPU_ISSET, CPU_ISSET(100, &a), != 0);
+
+  CPU_ZERO(&b);
+  CPU_SET(3, &b);
+  CPU_SET(4, &b);
+
+  CPU_AND(&c, &a, &b);
+  UNEXP_IF(CPU_COUNT, CPU_COUNT(&c), != 1);
+  UNEXP_IF(CPU_ISSET, CPU_ISSET(3, &c), != 1);
+
+  CPU_OR(&c, &a, &b);
+  UNEXP_IF(CPU_COUNT, CPU_COUNT(&c), != 3);
+
+  CPU_XOR(&c, &a, &b);
+  UNEXP_IF(CPU_COUNT, CPU_COUNT(&c), != 2);
+  UNEXP_IF(CPU_EQUAL, CPU_EQUAL(&c, &a), != 0);
+
+  CPU_AND(&c, &a, &a);
+  UNEXP_IF(CPU_EQUAL, CPU_EQUAL(&c, &a), != 1);
+
+  cpu_set_t *big = CPU_ALLOC(2048);
+  ERROR_IF(CPU_ALLOC, big, == NULL);
+  int big_size = CPU_ALLOC_SIZE(2048);
+  UNEXP_IF(CPU_ALLOC_SIZE, big_size, != 256);
+
+  CPU_ZERO_S(big_size, big);
+  CPU_SET_S(2000, big_size, big);
+  CPU_SET_S(2048, big_size, big);
+  UNEXP_IF(CPU_COUNT_S, CPU_COUNT_S(big_size, big), != 1);
+  UNEXP_IF(CPU_ISSET_S, CPU_ISSET_S(2000, big_size, big), != 1);
+  CPU_FREE(big);
+
+  // The affinity of the process, which the CPU it runs on is part of.
+  cpu_set_t mask;
+  status = sched_getaffinity(0, sizeof(mask), &mask);
+  ERROR_IF(sched_getaffinity, status, == -1);
+  UNEXP_IF(sched_getaffinity, CPU_COUNT(&mask), < 1);
+
+  int cpu = sched_getcpu();
+  ERROR_IF(sched_getcpu, cpu, == -1);
+  UNEXP_IF(sched_getcpu, CPU_ISSET(cpu, &mask), != 1);
+
+  cpu_set_t thread_mask;
+  status = pthread_getaffinity_np(pthread_self(), sizeof(thread_mask),
+      &thread_mask);
+  ERROR_IF2(pthread_getaffinity_np, status, != 0);
+  UNEXP_IF(pthread_getaffinity_np, CPU_EQUAL(&thread_mask, &mask), != 1);
+
+  // Pinning the thread to the last CPU it may run on.
+  int last = -1;
+  for (int i = 0; i < CPU_SETSIZE; i++) {
+    if (CPU_ISSET(i, &mask)) {
+      last = i;
+    }
+  }
+
+  cpu_set_t pinned;
+  CPU_ZERO(&pinned);
+  CPU_SET(last, &pinned);
+  status = pthread_setaffinity_np(pthread_self(), sizeof(pinned), &pinned);
+  ERROR_IF2(pthread_setaffinity_np, status, != 0);
+
+  cpu = sched_getcpu();
+  ERROR_IF(sched_getcpu, cpu, == -1);
+  UNEXP_IF(sched_getcpu, cpu, != last);
+
+  status = sched_getaffinity(0, sizeof(thread_mask), &thread_mask);
+  ERROR_IF(sched_getaffinity, status, == -1);
+  UNEXP_IF(sched_getaffinity, CPU_EQUAL(&thread_mask, &pinned), != 1);
+
+  status = sched_setaffinity(0, sizeof(mask), &mask);
+  ERROR_IF(sched_setaffinity, status, == -1);
+
+  // No CPU at all.
+  CPU_ZERO(&pinned);
+  status = sched_setaffinity(0, sizeof(pinned), &pinned);
+  UNEXP_IF(sched_setaffinity, status, != -1);
+  UNEXP_IF(sched_setaffinity, errno, != EINVAL);
+
+  return 0;
+}
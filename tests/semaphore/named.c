@@ -0,0 +1,79 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <semaphore.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../test_helpers.h"
+
+#define NAME "/relibc-test-named-semaphore"
+
+int main(void) {
+    // Make sure a previous run does not interfere
+    sem_unlink(NAME);
+
+    sem_t *sem = sem_open(NAME, O_CREAT | O_EXCL, 0600, 1);
+    ERROR_IF(sem_open, sem, == SEM_FAILED);
+
+    sem_t *again = sem_open(NAME, 0);
+    ERROR_IF(sem_open, again, == SEM_FAILED);
+    printf("same address: %d\n", sem == again);
+
+    sem_t *excl = sem_open(NAME, O_CREAT | O_EXCL, 0600, 0);
+    printf("exclusive reopen: %s (%s)\n", excl == SEM_FAILED ? "failed" : "succeeded", strerror(errno));
+
+    int value;
+    int status = sem_getvalue(sem, &value);
+    ERROR_IF(sem_getvalue, status, == -1);
+    printf("initial value: %d\n", value);
+
+    status = sem_wait(sem);
+    ERROR_IF(sem_wait, status, == -1);
+
+    pid_t pid = fork();
+    ERROR_IF(fork, pid, == -1);
+    if (pid == 0) {
+        sem_t *child = sem_open(NAME, 0);
+        ERROR_IF(sem_open, child, == SEM_FAILED);
+        status = sem_post(child);
+        ERROR_IF(sem_post, status, == -1);
+        status = sem_close(child);
+        ERROR_IF(sem_close, status, == -1);
+        _exit(EXIT_SUCCESS);
+    }
+
+    // Blocks until the child posts
+    status = sem_wait(sem);
+    ERROR_IF(sem_wait, status, == -1);
+    puts("woken by child");
+
+    int wstatus;
+    status = waitpid(pid, &wstatus, 0);
+    ERROR_IF(waitpid, status, == -1);
+
+    status = sem_unlink(NAME);
+    ERROR_IF(sem_unlink, status, == -1);
+
+    // The semaphore stays usable after being unlinked
+    status = sem_post(again);
+    ERROR_IF(sem_post, status, == -1);
+    status = sem_getvalue(sem, &value);
+    ERROR_IF(sem_getvalue, status, == -1);
+    printf("value after unlink: %d\n", value);
+
+    status = sem_close(again);
+    ERROR_IF(sem_close, status, == -1);
+    status = sem_close(sem);
+    ERROR_IF(sem_close, status, == -1);
+
+    sem_t *gone = sem_open(NAME, 0);
+    printf("open after unlink: %s (%s)\n", gone == SEM_FAILED ? "failed" : "succeeded", strerror(errno));
+
+    status = sem_unlink(NAME);
+    printf("second unlink: %d (%s)\n", status, strerror(errno));
+
+    return EXIT_SUCCESS;
+}
@@ -0,0 +1,82 @@
+#include <errno.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "test_helpers.h"
+
+extern char **environ;
+
+static void wait_child(pid_t pid) {
+    int status;
+    pid_t waited = waitpid(pid, &status, 0);
+    ERROR_IF(waitpid, waited, == -1);
+    printf("exited: %d, status: %d\n", WIFEXITED(status), WEXITSTATUS(status));
+}
+
+int main(void) {
+    pid_t pid;
+    int ret;
+
+    // Plain spawn, inheriting stdout.
+    char *echo_args[] = {"sh", "-c", "echo spawned; exit 3", NULL};
+    fflush(stdout);
+    ret = posix_spawn(&pid, "/bin/sh", NULL, NULL, echo_args, environ);
+    if (ret != 0) {
+        printf("posix_spawn: %s\n", strerror(ret));
+        exit(EXIT_FAILURE);
+    }
+    wait_child(pid);
+
+    // Redirect the child's stdout into a pipe and change its directory.
+    int fds[2];
+    ret = pipe(fds);
+    ERROR_IF(pipe, ret, == -1);
+
+    posix_spawn_file_actions_t actions;
+    posix_spawn_file_actions_init(&actions);
+    posix_spawn_file_actions_adddup2(&actions, fds[1], STDOUT_FILENO);
+    posix_spawn_file_actions_addclose(&actions, fds[0]);
+    posix_spawn_file_actions_addclose(&actions, fds[1]);
+    posix_spawn_file_actions_addchdir_np(&actions, "/");
+
+    posix_spawnattr_t attr;
+    posix_spawnattr_init(&attr);
+    posix_spawnattr_setflags(&attr, POSIX_SPAWN_SETPGROUP | POSIX_SPAWN_SETSIGMASK);
+    posix_spawnattr_setpgroup(&attr, 0);
+    sigset_t mask;
+    sigemptyset(&mask);
+    posix_spawnattr_setsigmask(&attr, &mask);
+
+    char *pwd_args[] = {"sh", "-c", "pwd", NULL};
+    ret = posix_spawnp(&pid, "sh", &actions, &attr, pwd_args, environ);
+    if (ret != 0) {
+        printf("posix_spawnp: %s\n", strerror(ret));
+        exit(EXIT_FAILURE);
+    }
+    posix_spawn_file_actions_destroy(&actions);
+    posix_spawnattr_destroy(&attr);
+    close(fds[1]);
+
+    char buf[64] = {0};
+    ssize_t n = read(fds[0], buf, sizeof(buf) - 1);
+    ERROR_IF(read, n, == -1);
+    close(fds[0]);
+    printf("child cwd: %s", buf);
+    wait_child(pid);
+
+    // Failures to execute are reported by the spawn call itself.
+    char *missing_args[] = {"missing", NULL};
+    ret = posix_spawn(&pid, "/nonexistent/missing", NULL, NULL, missing_args, environ);
+    printf("missing program: %s\n", strerror(ret));
+
+    posix_spawnattr_init(&attr);
+    ret = posix_spawnattr_setflags(&attr, 0x4000);
+    printf("invalid flags: %s\n", strerror(ret));
+    posix_spawnattr_destroy(&attr);
+
+    return 0;
+}
@@ -0,0 +1,72 @@
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "test_helpers.h"
+
+int main(void) {
+    // Reading and seeking
+    char input[] = "42 apples and 7 pears";
+    FILE *f = fmemopen(input, strlen(input), "r");
+    ERROR_IF(fmemopen, f, == NULL);
+
+    int apples, pears;
+    char fruit[16];
+    int n = fscanf(f, "%d %15s and %d", &apples, fruit, &pears);
+    printf("fscanf: %d, %d %s, %d\n", n, apples, fruit, pears);
+    printf("ftell: %ld\n", ftell(f));
+
+    int status = fseek(f, -5, SEEK_END);
+    ERROR_IF(fseek, status, == -1);
+    char word[16];
+    printf("fgets: %s\n", fgets(word, sizeof(word), f));
+    printf("fgetc at end: %d, feof: %d\n", fgetc(f), feof(f) != 0);
+
+    status = fseek(f, 100, SEEK_SET);
+    printf("fseek past the end: %d, EINVAL: %d\n", status, errno == EINVAL);
+    fclose(f);
+
+    // Writing, which keeps the buffer a string while it fits
+    char buf[16];
+    memset(buf, 'x', sizeof(buf));
+    f = fmemopen(buf, sizeof(buf), "w");
+    ERROR_IF(fmemopen, f, == NULL);
+    printf("w empties: %d\n", buf[0] == '\0');
+    fprintf(f, "%s-%d", "abc", 123);
+    fflush(f);
+    printf("after fflush: %s, ftell: %ld\n", buf, ftell(f));
+    fseek(f, 2, SEEK_SET);
+    fputc('C', f);
+    fclose(f);
+    printf("after fclose: %s\n", buf);
+
+    // Writes past the end of the buffer fail
+    f = fmemopen(buf, 4, "w");
+    ERROR_IF(fmemopen, f, == NULL);
+    fputs("too long", f);
+    status = fflush(f);
+    printf("fflush: %d, ferror: %d, ENOSPC: %d\n", status, ferror(f) != 0, errno == ENOSPC);
+    fclose(f);
+    printf("truncated: [%.4s]\n", buf);
+
+    // Appending starts at the first nul
+    strcpy(buf, "one");
+    f = fmemopen(buf, sizeof(buf), "a+");
+    ERROR_IF(fmemopen, f, == NULL);
+    printf("a+ ftell: %ld\n", ftell(f));
+    fseek(f, 0, SEEK_SET);
+    fputs(",two", f);
+    fclose(f);
+    printf("appended: %s\n", buf);
+
+    // A NULL buffer gets one of the stream's own
+    f = fmemopen(NULL, 32, "w+");
+    ERROR_IF(fmemopen, f, == NULL);
+    fputs("scratch space", f);
+    rewind(f);
+    printf("own buffer: %s\n", fgets(word, sizeof(word), f));
+    fclose(f);
+
+    f = fmemopen(buf, 0, "r");
+    printf("zero size: %d, EINVAL: %d\n", f == NULL, errno == EINVAL);
+}
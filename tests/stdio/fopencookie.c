@@ -0,0 +1,136 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/types.h>
+
+#include "test_helpers.h"
+
+struct memory {
+    char data[64];
+    size_t len;
+    size_t pos;
+    int closed;
+};
+
+static ssize_t mem_read(void *cookie, char *buf, size_t size) {
+    struct memory *m = cookie;
+    size_t n = m->len - m->pos < size ? m->len - m->pos : size;
+    memcpy(buf, m->data + m->pos, n);
+    m->pos += n;
+    return n;
+}
+
+static ssize_t mem_write(void *cookie, const char *buf, size_t size) {
+    struct memory *m = cookie;
+    if (m->pos + size > sizeof(m->data)) {
+        errno = ENOSPC;
+        return -1;
+    }
+    memcpy(m->data + m->pos, buf, size);
+    m->pos += size;
+    if (m->pos > m->len) {
+        m->len = m->pos;
+    }
+    return size;
+}
+
+static int mem_seek(void *cookie, off_t *offset, int whence) {
+    struct memory *m = cookie;
+    off_t base = whence == SEEK_SET ? 0 : whence == SEEK_CUR ? (off_t)m->pos : (off_t)m->len;
+    if (base + *offset < 0 || base + *offset > (off_t)m->len) {
+        errno = EINVAL;
+        return -1;
+    }
+    m->pos = base + *offset;
+    *offset = m->pos;
+    return 0;
+}
+
+static int mem_close(void *cookie) {
+    struct memory *m = cookie;
+    m->closed = 1;
+    return 0;
+}
+
+static int fun_read(void *cookie, char *buf, int size) {
+    return mem_read(cookie, buf, size);
+}
+
+static int fun_write(void *cookie, const char *buf, int size) {
+    return mem_write(cookie, buf, size);
+}
+
+static fpos_t fun_seek(void *cookie, fpos_t offset, int whence) {
+    return mem_seek(cookie, &offset, whence) == 0 ? offset : -1;
+}
+
+int main(void) {
+    struct memory m = { 0 };
+    cookie_io_functions_t io = {
+        .read = mem_read,
+        .write = mem_write,
+        .seek = mem_seek,
+        .close = mem_close,
+    };
+
+    FILE *f = fopencookie(&m, "w+", io);
+    ERROR_IF(fopencookie, f, == NULL);
+    fprintf(f, "%d little %s\n", 3, "pigs");
+    printf("before fflush: %zu\n", m.len);
+    fflush(f);
+    printf("after fflush: %zu, %.*s", m.len, (int)m.len, m.data);
+    printf("ftell: %ld\n", ftell(f));
+
+    rewind(f);
+    int n;
+    char word[16];
+    int status = fscanf(f, "%d %15s", &n, word);
+    printf("fscanf: %d, %d %s\n", status, n, word);
+
+    status = fseek(f, 100, SEEK_SET);
+    printf("fseek past the end: %d, EINVAL: %d\n", status, errno == EINVAL);
+    printf("fileno: %d, EBADF: %d\n", fileno(f), errno == EBADF);
+
+    status = fclose(f);
+    printf("fclose: %d, closed: %d\n", status, m.closed);
+
+    // Without a write function, output is discarded
+    io.write = NULL;
+    f = fopencookie(&m, "w", io);
+    ERROR_IF(fopencookie, f, == NULL);
+    fputs("gone", f);
+    fclose(f);
+    printf("discarded: %zu\n", m.len);
+
+    // Without a seek function, the stream cannot seek
+    io.seek = NULL;
+    f = fopencookie(&m, "r", io);
+    ERROR_IF(fopencookie, f, == NULL);
+    status = fseek(f, 0, SEEK_SET);
+    printf("fseek: %d, ESPIPE: %d\n", status, errno == ESPIPE);
+    fclose(f);
+
+    f = fopencookie(&m, "q", io);
+    printf("bad mode: %d, EINVAL: %d\n", f == NULL, errno == EINVAL);
+
+    // funopen takes the callbacks directly, and the mode from which are given
+    m = (struct memory){ 0 };
+    f = funopen(&m, NULL, fun_write, fun_seek, mem_close);
+    ERROR_IF(funopen, f, == NULL);
+    fputs("written by funopen", f);
+    fseek(f, 11, SEEK_SET);
+    printf("funopen ftell: %ld\n", ftell(f));
+    printf("write-only fgetc: %d\n", fgetc(f));
+    fclose(f);
+    printf("funopen: %.*s, closed: %d\n", (int)m.len, m.data, m.closed);
+
+    m.pos = 0;
+    f = funopen(&m, fun_read, NULL, NULL, NULL);
+    ERROR_IF(funopen, f, == NULL);
+    printf("funopen fgets: %s\n", fgets(word, sizeof(word), f));
+    fclose(f);
+
+    f = funopen(&m, NULL, NULL, NULL, NULL);
+    printf("no functions: %d, EINVAL: %d\n", f == NULL, errno == EINVAL);
+}
@@ -0,0 +1,38 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "test_helpers.h"
+
+int main(void) {
+    char *buf;
+    size_t size;
+    FILE *f = open_memstream(&buf, &size);
+    ERROR_IF(open_memstream, f, == NULL);
+    printf("empty: \"%s\", %zu\n", buf, size);
+
+    fprintf(f, "hello %s", "world");
+    fflush(f);
+    printf("fflush: \"%s\", %zu\n", buf, size);
+
+    // Growing well past the first allocation
+    for (int i = 0; i < 1000; i++) {
+        fputc('a' + i % 26, f);
+    }
+    fflush(f);
+    printf("grown: %zu, %.5s, ftell: %ld\n", size, buf + 11, ftell(f));
+
+    // The size follows the position back, but the contents stay
+    int status = fseek(f, 5, SEEK_SET);
+    ERROR_IF(fseek, status, == -1);
+    fflush(f);
+    printf("fseek back: %zu, %.11s\n", size, buf);
+
+    // Writing after a seek past the end fills the gap with nuls
+    fseek(f, 2000, SEEK_SET);
+    fputc('!', f);
+    fclose(f);
+    printf("fclose: %zu, gap: %d, last: %c\n", size, buf[1500] == '\0', buf[2000]);
+    printf("terminated: %d\n", buf[size] == '\0');
+    free(buf);
+}
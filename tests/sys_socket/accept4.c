@@ -0,0 +1,68 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+#include "../test_helpers.h"
+
+static void print_flags(const char *name, int fd) {
+    int fd_flags = fcntl(fd, F_GETFD);
+    ERROR_IF(fcntl, fd_flags, == -1);
+    int status_flags = fcntl(fd, F_GETFL);
+    ERROR_IF(fcntl, status_flags, == -1);
+    printf("%s: cloexec %d, nonblock %d\n", name, (fd_flags & FD_CLOEXEC) != 0,
+           (status_flags & O_NONBLOCK) != 0);
+}
+
+int main(void) {
+    struct sockaddr_un addr = {.sun_family = AF_UNIX};
+    snprintf(addr.sun_path, sizeof(addr.sun_path), "/tmp/relibc-accept4-%d",
+             (int)getpid());
+    unlink(addr.sun_path);
+
+    int listener = socket(AF_UNIX, SOCK_STREAM, 0);
+    ERROR_IF(socket, listener, == -1);
+    int status = bind(listener, (struct sockaddr *)&addr, sizeof(addr));
+    ERROR_IF(bind, status, == -1);
+    status = listen(listener, 2);
+    ERROR_IF(listen, status, == -1);
+
+    int clients[2];
+    for (int i = 0; i < 2; i++) {
+        clients[i] = socket(AF_UNIX, SOCK_STREAM, 0);
+        ERROR_IF(socket, clients[i], == -1);
+        status = connect(clients[i], (struct sockaddr *)&addr, sizeof(addr));
+        ERROR_IF(connect, status, == -1);
+    }
+
+    int plain = accept4(listener, NULL, NULL, 0);
+    ERROR_IF(accept4, plain, == -1);
+    print_flags("plain", plain);
+
+    int flagged = accept4(listener, NULL, NULL, SOCK_CLOEXEC | SOCK_NONBLOCK);
+    ERROR_IF(accept4, flagged, == -1);
+    print_flags("flagged", flagged);
+
+    // With nothing to accept, the nonblocking socket does not block
+    char c;
+    ssize_t len = read(flagged, &c, 1);
+    UNEXP_IF(read, len, != -1);
+    UNEXP_IF(read, errno, != EAGAIN);
+
+    errno = 0;
+    int invalid = accept4(listener, NULL, NULL, 0x1);
+    UNEXP_IF(accept4, invalid, != -1);
+    UNEXP_IF(accept4, errno, != EINVAL);
+
+    close(plain);
+    close(flagged);
+    close(clients[0]);
+    close(clients[1]);
+    close(listener);
+    unlink(addr.sun_path);
+    printf("OK\n");
+}
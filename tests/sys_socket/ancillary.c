@@ -0,0 +1,106 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../test_helpers.h"
+
+int main(void) {
+    int sv[2];
+    int status = socketpair(AF_UNIX, SOCK_STREAM, 0, sv);
+    ERROR_IF(socketpair, status, == -1);
+
+    int on = 1;
+    status = setsockopt(sv[1], SOL_SOCKET, SO_PASSCRED, &on, sizeof(on));
+    ERROR_IF(setsockopt, status, == -1);
+
+    int pipefd[2];
+    status = pipe(pipefd);
+    ERROR_IF(pipe, status, == -1);
+
+    // Send the write end of the pipe along with our credentials
+    char data[] = "fd";
+    struct iovec iov = {.iov_base = data, .iov_len = sizeof(data)};
+    union {
+        struct cmsghdr align;
+        char buf[CMSG_SPACE(sizeof(int)) + CMSG_SPACE(sizeof(struct ucred))];
+    } control;
+    memset(&control, 0, sizeof(control));
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = control.buf,
+        .msg_controllen = sizeof(control.buf),
+    };
+
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(cmsg), &pipefd[1], sizeof(int));
+
+    cmsg = CMSG_NXTHDR(&msg, cmsg);
+    ERROR_IF(CMSG_NXTHDR, cmsg, == NULL);
+    struct ucred cred = {.pid = getpid(), .uid = getuid(), .gid = getgid()};
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_CREDENTIALS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(cred));
+    memcpy(CMSG_DATA(cmsg), &cred, sizeof(cred));
+    UNEXP_IF(CMSG_NXTHDR, (CMSG_NXTHDR(&msg, cmsg) == NULL), != 1);
+
+    ssize_t len = sendmsg(sv[0], &msg, 0);
+    ERROR_IF(sendmsg, len, == -1);
+    UNEXP_IF(sendmsg, len, != sizeof(data));
+    status = close(pipefd[1]);
+    ERROR_IF(close, status, == -1);
+
+    // Receive them on the other end
+    char received[sizeof(data)] = {0};
+    iov.iov_base = received;
+    memset(&control, 0, sizeof(control));
+    msg.msg_controllen = sizeof(control.buf);
+    len = recvmsg(sv[1], &msg, MSG_CMSG_CLOEXEC);
+    ERROR_IF(recvmsg, len, == -1);
+    UNEXP_IF(recvmsg, len, != sizeof(data));
+    UNEXP_IF(recvmsg, (msg.msg_flags & MSG_CTRUNC), != 0);
+    printf("data: %s\n", received);
+
+    int fd = -1;
+    struct ucred received_cred = {0};
+    for (cmsg = CMSG_FIRSTHDR(&msg); cmsg != NULL; cmsg = CMSG_NXTHDR(&msg, cmsg)) {
+        UNEXP_IF(cmsg_level, cmsg->cmsg_level, != SOL_SOCKET);
+        if (cmsg->cmsg_type == SCM_RIGHTS) {
+            UNEXP_IF(SCM_RIGHTS, (int)cmsg->cmsg_len, != (int)CMSG_LEN(sizeof(int)));
+            memcpy(&fd, CMSG_DATA(cmsg), sizeof(int));
+        } else if (cmsg->cmsg_type == SCM_CREDENTIALS) {
+            UNEXP_IF(SCM_CREDENTIALS, (int)cmsg->cmsg_len,
+                     != (int)CMSG_LEN(sizeof(struct ucred)));
+            memcpy(&received_cred, CMSG_DATA(cmsg), sizeof(received_cred));
+        }
+    }
+    UNEXP_IF(SCM_RIGHTS, fd, == -1);
+    printf("pid matches: %d\n", received_cred.pid == getpid());
+    printf("uid matches: %d\n", received_cred.uid == getuid());
+    printf("gid matches: %d\n", received_cred.gid == getgid());
+
+    int fd_flags = fcntl(fd, F_GETFD);
+    ERROR_IF(fcntl, fd_flags, == -1);
+    printf("cloexec: %d\n", (fd_flags & FD_CLOEXEC) != 0);
+
+    // The received fd is the write end of the pipe
+    len = write(fd, "pipe", 5);
+    ERROR_IF(write, len, == -1);
+    status = close(fd);
+    ERROR_IF(close, status, == -1);
+    char piped[5];
+    len = read(pipefd[0], piped, sizeof(piped));
+    ERROR_IF(read, len, == -1);
+    UNEXP_IF(read, len, != sizeof(piped));
+    printf("pipe: %s\n", piped);
+
+    close(pipefd[0]);
+    close(sv[0]);
+    close(sv[1]);
+}
@@ -0,0 +1,55 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../test_helpers.h"
+
+int main(void) {
+    int sv[2];
+    int status = socketpair(AF_UNIX, SOCK_DGRAM, 0, sv);
+    ERROR_IF(socketpair, status, == -1);
+
+    char *messages[] = {"one", "two", "three"};
+    struct iovec send_iov[3];
+    struct mmsghdr send_msgs[3];
+    memset(send_msgs, 0, sizeof(send_msgs));
+    for (int i = 0; i < 3; i++) {
+        send_iov[i].iov_base = messages[i];
+        send_iov[i].iov_len = strlen(messages[i]) + 1;
+        send_msgs[i].msg_hdr.msg_iov = &send_iov[i];
+        send_msgs[i].msg_hdr.msg_iovlen = 1;
+    }
+    int sent = sendmmsg(sv[0], send_msgs, 3, 0);
+    ERROR_IF(sendmmsg, sent, == -1);
+    printf("sent: %d\n", sent);
+    for (int i = 0; i < sent; i++) {
+        printf("  %u bytes\n", send_msgs[i].msg_len);
+    }
+
+    // Waits for the first message only, and returns what there is after it
+    char buffers[8][16];
+    struct iovec recv_iov[8];
+    struct mmsghdr recv_msgs[8];
+    memset(recv_msgs, 0, sizeof(recv_msgs));
+    for (int i = 0; i < 8; i++) {
+        recv_iov[i].iov_base = buffers[i];
+        recv_iov[i].iov_len = sizeof(buffers[i]);
+        recv_msgs[i].msg_hdr.msg_iov = &recv_iov[i];
+        recv_msgs[i].msg_hdr.msg_iovlen = 1;
+    }
+    int received = recvmmsg(sv[1], recv_msgs, 8, MSG_WAITFORONE, NULL);
+    ERROR_IF(recvmmsg, received, == -1);
+    printf("received: %d\n", received);
+    for (int i = 0; i < received; i++) {
+        printf("  %u bytes: %s\n", recv_msgs[i].msg_len, buffers[i]);
+    }
+
+    // Nothing is left
+    received = recvmmsg(sv[1], recv_msgs, 8, MSG_DONTWAIT, NULL);
+    UNEXP_IF(recvmmsg, received, != -1);
+
+    close(sv[0]);
+    close(sv[1]);
+}
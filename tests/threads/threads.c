@@ -0,0 +1,200 @@
+#include <errno.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <threads.h>
+#include <time.h>
+
+#include "../test_helpers.h"
+
+static once_flag once = ONCE_FLAG_INIT;
+static volatile int once_count;
+
+static tss_t key;
+static volatile bool destructed;
+
+static mtx_t mutex;
+static cnd_t cond;
+static volatile bool ready;
+
+void count_once(void) {
+  once_count++;
+}
+
+void destructor(void *value) {
+  destructed = true;
+}
+
+int returns_arg(void *arg) {
+  call_once(&once, count_once);
+  return (int)(intptr_t)arg;
+}
+
+int exits_early(void *arg) {
+  int status = tss_set(key, arg);
+  UNEXP_IF(tss_set, status, != thrd_success);
+  UNEXP_IF(tss_get, tss_get(key), != arg);
+
+  thrd_exit(42);
+}
+
+int try_locked(void *arg) {
+  int status = mtx_trylock(&mutex);
+  UNEXP_IF(mtx_trylock, status, != thrd_busy);
+
+  struct timespec deadline;
+  timespec_get(&deadline, TIME_UTC);
+  deadline.tv_nsec += 50000000;
+  if (deadline.tv_nsec >= 1000000000) {
+    deadline.tv_sec++;
+    deadline.tv_nsec -= 1000000000;
+  }
+
+  status = mtx_timedlock(&mutex, &deadline);
+  UNEXP_IF(mtx_timedlock, status, != thrd_timedout);
+
+  return 0;
+}
+
+int signals(void *arg) {
+  int status = mtx_lock(&mutex);
+  UNEXP_IF(mtx_lock, status, != thrd_success);
+
+  ready = true;
+
+  status = cnd_signal(&cond);
+  UNEXP_IF(cnd_signal, status, != thrd_success);
+
+  status = mtx_unlock(&mutex);
+  UNEXP_IF(mtx_unlock, status, != thrd_success);
+
+  return 0;
+}
+
+int main(void) {
+  int status;
+  thrd_t thread;
+  int res;
+
+  // Return values are passed to thrd_join, from the start function or
+  // thrd_exit.
+  status = thrd_create(&thread, returns_arg, (void *)7);
+  UNEXP_IF(thrd_create, status, != thrd_success);
+
+  status = thrd_join(thread, &res);
+  UNEXP_IF(thrd_join, status, != thrd_success);
+  UNEXP_IF(thrd_join, res, != 7);
+
+  status = thrd_create(&thread, returns_arg, (void *)8);
+  UNEXP_IF(thrd_create, status, != thrd_success);
+
+  status = thrd_join(thread, NULL);
+  UNEXP_IF(thrd_join, status, != thrd_success);
+  UNEXP_IF(call_once, once_count, != 1);
+
+  status = tss_create(&key, destructor);
+  UNEXP_IF(tss_create, status, != thrd_success);
+
+  status = thrd_create(&thread, exits_early, &res);
+  UNEXP_IF(thrd_create, status, != thrd_success);
+
+  status = thrd_join(thread, &res);
+  UNEXP_IF(thrd_join, status, != thrd_success);
+  UNEXP_IF(thrd_exit, res, != 42);
+  UNEXP_IF(destructor, destructed, != true);
+  UNEXP_IF(tss_get, tss_get(key), != NULL);
+
+  tss_delete(key);
+
+  UNEXP_IF(thrd_equal, thrd_equal(thrd_current(), thrd_current()), == 0);
+
+  thrd_yield();
+
+  struct timespec duration = {.tv_sec = 0, .tv_nsec = 1000000};
+  status = thrd_sleep(&duration, NULL);
+  UNEXP_IF(thrd_sleep, status, != 0);
+
+  // Timed mutexes.
+  status = mtx_init(&mutex, mtx_timed);
+  UNEXP_IF(mtx_init, status, != thrd_success);
+
+  status = mtx_lock(&mutex);
+  UNEXP_IF(mtx_lock, status, != thrd_success);
+
+  status = thrd_create(&thread, try_locked, NULL);
+  UNEXP_IF(thrd_create, status, != thrd_success);
+
+  UNEXP_IF(thrd_equal, thrd_equal(thread, thrd_current()), != 0);
+
+  status = thrd_join(thread, NULL);
+  UNEXP_IF(thrd_join, status, != thrd_success);
+
+  status = mtx_unlock(&mutex);
+  UNEXP_IF(mtx_unlock, status, != thrd_success);
+
+  mtx_destroy(&mutex);
+
+  // Recursive mutexes.
+  status = mtx_init(&mutex, mtx_plain | mtx_recursive);
+  UNEXP_IF(mtx_init, status, != thrd_success);
+
+  status = mtx_lock(&mutex);
+  UNEXP_IF(mtx_lock, status, != thrd_success);
+
+  status = mtx_trylock(&mutex);
+  UNEXP_IF(mtx_trylock, status, != thrd_success);
+
+  status = mtx_unlock(&mutex);
+  UNEXP_IF(mtx_unlock, status, != thrd_success);
+
+  status = mtx_unlock(&mutex);
+  UNEXP_IF(mtx_unlock, status, != thrd_success);
+
+  mtx_destroy(&mutex);
+
+  // Condition variables.
+  status = mtx_init(&mutex, mtx_plain);
+  UNEXP_IF(mtx_init, status, != thrd_success);
+
+  status = cnd_init(&cond);
+  UNEXP_IF(cnd_init, status, != thrd_success);
+
+  status = mtx_lock(&mutex);
+  UNEXP_IF(mtx_lock, status, != thrd_success);
+
+  struct timespec deadline;
+  timespec_get(&deadline, TIME_UTC);
+
+  status = cnd_timedwait(&cond, &mutex, &deadline);
+  UNEXP_IF(cnd_timedwait, status, != thrd_timedout);
+
+  status = thrd_create(&thread, signals, NULL);
+  UNEXP_IF(thrd_create, status, != thrd_success);
+
+  while (!ready) {
+    status = cnd_wait(&cond, &mutex);
+    UNEXP_IF(cnd_wait, status, != thrd_success);
+  }
+
+  status = mtx_unlock(&mutex);
+  UNEXP_IF(mtx_unlock, status, != thrd_success);
+
+  status = thrd_join(thread, NULL);
+  UNEXP_IF(thrd_join, status, != thrd_success);
+
+  status = cnd_broadcast(&cond);
+  UNEXP_IF(cnd_broadcast, status, != thrd_success);
+
+  cnd_destroy(&cond);
+  mtx_destroy(&mutex);
+
+  // Detached threads clean up after themselves.
+  status = thrd_create(&thread, returns_arg, NULL);
+  UNEXP_IF(thrd_create, status, != thrd_success);
+
+  status = thrd_detach(thread);
+  UNEXP_IF(thrd_detach, status, != thrd_success);
+
+  return 0;
+}
@@ -0,0 +1,22 @@
+#include <dlfcn.h>
+#include <stdio.h>
+
+// Linked against a libversioned.so that only had VERS_1, so bound to
+// value@VERS_1 rather than to the default version of the one loaded.
+int value(void);
+
+// An IFUNC, bound to the implementation that its resolver returns.
+int answer(void);
+
+int main(void) {
+    printf("value: %d\n", value());
+    printf("answer: %d\n", answer());
+
+    int (*f)(void);
+    *(void **)(&f) = dlsym(RTLD_DEFAULT, "answer");
+    if (!f) {
+        printf("dlsym(answer): %s\n", dlerror());
+        return 1;
+    }
+    printf("dlsym(answer): %d\n", f());
+}
@@ -0,0 +1,37 @@
+#include <locale.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <wchar.h>
+
+#include "test_helpers.h"
+
+int main(void) {
+    setlocale(LC_ALL, "C.UTF-8");
+
+    wchar_t *buf;
+    size_t size;
+    FILE *f = open_wmemstream(&buf, &size);
+    ERROR_IF(open_wmemstream, f, == NULL);
+    printf("fwide: %d\n", fwide(f, 0) > 0);
+
+    fwprintf(f, L"%ls %d", L"été", 2024);
+    fputwc(L'€', f);
+    fflush(f);
+    printf("size: %zu\n", size);
+    for (size_t i = 0; i < size; i++) {
+        printf("%lx ", (unsigned long)buf[i]);
+    }
+    printf("\n");
+
+    // Sizes and positions count wide characters
+    printf("ftell: %ld\n", ftell(f));
+    fseek(f, 1, SEEK_SET);
+    fputws(L"xy", f);
+    fclose(f);
+    printf("size after fclose: %zu, terminated: %d\n", size, buf[9] == L'\0');
+    for (size_t i = 0; i < 9; i++) {
+        printf("%lx ", (unsigned long)buf[i]);
+    }
+    printf("\n");
+    free(buf);
+}